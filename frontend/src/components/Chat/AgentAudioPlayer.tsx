@@ -2,6 +2,7 @@
 import React, { useState, useRef, useEffect } from 'react';
 import { motion } from 'framer-motion';
 import { FaPlay, FaPause } from 'react-icons/fa';
+import { getAudioUrl } from '../../services/api';
 
 interface AgentAudioPlayerProps {
   audioUrl: string;
@@ -77,7 +78,7 @@ export const AgentAudioPlayer: React.FC<AgentAudioPlayerProps> = ({ audioUrl })
   return (
     <div className="mt-3 flex items-center space-x-3 bg-gray-800 rounded-lg p-3 w-full">
       {/* Hidden audio element */}
-      <audio ref={audioRef} src={`${import.meta.env.VITE_API_BASE_URL}${audioUrl}`} preload="metadata" />
+      <audio ref={audioRef} src={getAudioUrl(audioUrl)} preload="metadata" />
 
       {/* Play/Pause Button */}
       <button
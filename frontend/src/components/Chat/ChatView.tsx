@@ -6,6 +6,15 @@ import { InputArea } from '../InputArea/InputArea';
 import { AgentAudioPlayer } from './AgentAudioPlayer';
 import { AgentSelector } from './AgentSelector';
 import { MintModal } from './MintModal';
+import {
+  type AuthSession,
+  RateLimitError,
+  authHeaders,
+  getSession,
+  rateLimitError,
+  signInWithEthereum,
+  signOut,
+} from '../../services/api';
 
 // Type definition for chat messages
 interface ChatMessage {
@@ -13,6 +22,8 @@ interface ChatMessage {
   role: 'user' | 'agent';
   text: string;
   audioUrl?: string;
+  // Index of the turn in the session, for minting it
+  turn?: number;
 }
 
 export const ChatView: React.FC = () => {
@@ -22,7 +33,11 @@ export const ChatView: React.FC = () => {
   const [selectedAgent, setSelectedAgent] = useState<string | null>(null);
   const [selectedAgentName, setSelectedAgentName] = useState<string | null>(null);
   const [isMintModalOpen, setIsMintModalOpen] = useState(false);
-  const [currentItemToMint, setCurrentItemToMint] = useState<string | null>(null);
+  const [currentItemToMint, setCurrentItemToMint] = useState<ChatMessage | null>(null);
+  const [sessionId, setSessionId] = useState<string | null>(null);
+  // Signed-in wallet; the API server requires it for chatting and minting
+  const [authSession, setAuthSession] = useState<AuthSession | null>(getSession);
+  const [signInError, setSignInError] = useState<string | null>(null);
   const messagesEndRef = useRef<HTMLDivElement>(null);
 
   // Auto-scroll to bottom when new messages arrive
@@ -30,6 +45,23 @@ export const ChatView: React.FC = () => {
     messagesEndRef.current?.scrollIntoView({ behavior: 'smooth' });
   }, [messages, isLoading]);
 
+  const handleSignIn = async () => {
+    setSignInError(null);
+    try {
+      setAuthSession(await signInWithEthereum());
+    } catch (error) {
+      console.error('Sign-in failed:', error);
+      setSignInError(error instanceof Error ? error.message : String(error));
+    }
+  };
+
+  const handleSignOut = () => {
+    signOut();
+    setAuthSession(null);
+    setSessionId(null);
+    setMessages([]);
+  };
+
   // Handle chat submission with real API calls
   const handleChatSubmit = async (prompt: string, file?: File) => {
     // Set loading state
@@ -55,12 +87,22 @@ export const ChatView: React.FC = () => {
         if (selectedAgent) {
           formData.append('agent_id', selectedAgent);
         }
+        if (sessionId) {
+          formData.append('session_id', sessionId);
+        }
 
         const response = await fetch(`${import.meta.env.VITE_API_BASE_URL}/input/audio`, {
           method: 'POST',
+          headers: authHeaders(),
           body: formData,
         });
 
+        if (response.status === 401) {
+          handleSignOut();
+        }
+        if (response.status === 429) {
+          throw await rateLimitError(response);
+        }
         if (!response.ok) {
           throw new Error('Failed to send audio');
         }
@@ -72,13 +114,21 @@ export const ChatView: React.FC = () => {
           method: 'POST',
           headers: {
             'Content-Type': 'application/json',
+            ...authHeaders(),
           },
           body: JSON.stringify({
             agent_id: selectedAgent,
             user_text: prompt,
+            session_id: sessionId ?? undefined,
           }),
         });
 
+        if (response.status === 401) {
+          handleSignOut();
+        }
+        if (response.status === 429) {
+          throw await rateLimitError(response);
+        }
         if (!response.ok) {
           throw new Error('Failed to send text');
         }
@@ -86,12 +136,18 @@ export const ChatView: React.FC = () => {
         data = await response.json();
       }
 
+      // Keep the server-side session so follow-up messages have context
+      if (data.session_id) {
+        setSessionId(data.session_id);
+      }
+
       // Create agent response with real data from API
       const agentMessage: ChatMessage = {
         id: (Date.now() + 1).toString(),
         role: 'agent',
         text: data.reply_text,
         audioUrl: data.audio_url,
+        turn: data.turn,
       };
 
       // Add agent response to the conversation
@@ -103,7 +159,9 @@ export const ChatView: React.FC = () => {
       const errorMessage: ChatMessage = {
         id: (Date.now() + 1).toString(),
         role: 'agent',
-        text: 'Sorry, there was an error processing your request. Please try again.',
+        text: error instanceof RateLimitError
+          ? error.message
+          : 'Sorry, there was an error processing your request. Please try again.',
       };
       
       setMessages((prev) => [...prev, errorMessage]);
@@ -119,11 +177,29 @@ export const ChatView: React.FC = () => {
       <MintModal
         show={isMintModalOpen}
         onClose={() => setIsMintModalOpen(false)}
-        chatResult={currentItemToMint || ''}
+        chatResult={currentItemToMint?.text || ''}
+        conversationId={sessionId || ''}
+        turn={currentItemToMint?.turn ?? 0}
       />
 
-      {/* Show agent selector if no agent is selected */}
-      {!selectedAgent ? (
+      {/* Sign in with Ethereum before anything else */}
+      {!authSession ? (
+        <div className="flex-1 flex flex-col items-center justify-center p-4 space-y-4">
+          <h1 className="text-2xl font-bold text-white">Welcome</h1>
+          <p className="text-gray-400 text-sm text-center max-w-md">
+            Sign in with your Ethereum wallet to chat with the agents and mint your conversations.
+          </p>
+          <button
+            onClick={handleSignIn}
+            className="px-6 py-3 bg-purple-600 hover:bg-purple-500 text-white font-medium rounded-lg transition-colors"
+          >
+            Sign in with Ethereum
+          </button>
+          {signInError && (
+            <p className="text-red-400 text-sm text-center max-w-md break-words">{signInError}</p>
+          )}
+        </div>
+      ) : !selectedAgent ? (
         <AgentSelector onAgentSelect={(id, name) => {
           setSelectedAgent(id);
           setSelectedAgentName(name);
@@ -136,7 +212,14 @@ export const ChatView: React.FC = () => {
             <h1 className="text-xl font-bold text-white text-center flex-1">
               {selectedAgentName || selectedAgent}
             </h1>
-            <div className="flex-1 flex justify-end">
+            <div className="flex-1 flex justify-end items-center space-x-3">
+              <button
+                onClick={handleSignOut}
+                title={authSession.address}
+                className="px-4 py-2 bg-gray-800 hover:bg-gray-700 text-gray-300 text-sm font-mono rounded-lg transition-colors"
+              >
+                {authSession.address.slice(0, 6)}…{authSession.address.slice(-4)} · Sign out
+              </button>
               <button
                 onClick={() => {
                   setSelectedAgent(null);
@@ -181,10 +264,10 @@ export const ChatView: React.FC = () => {
                 )}
 
                 {/* Mint Button */}
-                {message.role === 'agent' && (
+                {message.role === 'agent' && message.turn !== undefined && (
                   <button
                     onClick={() => {
-                      setCurrentItemToMint(message.text);
+                      setCurrentItemToMint(message);
                       setIsMintModalOpen(true);
                     }}
                     className="mt-3 w-full px-4 py-2 bg-purple-600 hover:bg-purple-500 text-white text-sm font-medium rounded-lg transition-colors"
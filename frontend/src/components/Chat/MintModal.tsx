@@ -1,53 +1,110 @@
 // src/components/Chat/MintModal.tsx
-import React, { useState, useEffect } from 'react';
+import React, { useState, useEffect, useRef } from 'react';
 import { motion, AnimatePresence } from 'framer-motion';
 import Confetti from 'react-confetti';
 import Lottie from 'react-lottie-player';
-import { FaTimes, FaCheck } from 'react-icons/fa';
+import { FaTimes, FaCheck, FaExclamationTriangle } from 'react-icons/fa';
+import {
+  type MintJobStatus,
+  getConversationMint,
+  getSession,
+  mintConversation,
+} from '../../services/api';
+
+// How often the mint job is polled
+const POLL_INTERVAL_MS = 2000;
+
+// What the modal shows while the mint job has each status
+const JOB_PROGRESS: Record<MintJobStatus, string> = {
+  pending: 'Waiting for the minting service...',
+  uploading: 'Uploading the metadata...',
+  submitted: 'Transaction sent, waiting for confirmation...',
+  confirmed: 'Confirmed',
+  failed: 'Failed',
+};
 
 interface MintModalProps {
   show: boolean;
   onClose: () => void;
   chatResult: string;
+  conversationId: string;
+  turn: number;
 }
 
 type MintingState = 'idle' | 'minting' | 'success' | 'error';
 
-export const MintModal: React.FC<MintModalProps> = ({ show, onClose, chatResult }) => {
+export const MintModal: React.FC<MintModalProps> = ({
+  show,
+  onClose,
+  chatResult,
+  conversationId,
+  turn,
+}) => {
   // State management
   const [walletAddress, setWalletAddress] = useState('');
   const [mintingState, setMintingState] = useState<MintingState>('idle');
   const [txHash, setTxHash] = useState<string | null>(null);
+  const [tokenId, setTokenId] = useState<string | null>(null);
+  const [explorerUrl, setExplorerUrl] = useState<string | null>(null);
+  const [errorMessage, setErrorMessage] = useState<string | null>(null);
+  const [progress, setProgress] = useState('Uploading the audio...');
+
+  // Incremented whenever the modal closes, so polling of a mint started before stops
+  const openCount = useRef(0);
 
   // Reset state when modal closes
   useEffect(() => {
     if (!show) {
+      openCount.current += 1;
       setWalletAddress('');
       setMintingState('idle');
       setTxHash(null);
+      setTokenId(null);
+      setExplorerUrl(null);
+      setErrorMessage(null);
     }
   }, [show]);
 
   // Handle mint confirmation
-  const handleConfirmMint = () => {
-    if (!walletAddress.trim()) {
-      alert('Please enter a wallet address');
-      return;
-    }
-
+  const handleConfirmMint = async () => {
     // Start minting
     setMintingState('minting');
+    setProgress('Uploading the audio...');
+    const opened = openCount.current;
+
+    try {
+      // The API server uploads the turn's audio and queues the mint
+      const { job_id } = await mintConversation(conversationId, {
+        turn,
+        // Empty means the signed-in wallet
+        recipient: walletAddress.trim() || undefined,
+      });
 
-    // Simulate minting process (2 seconds)
-    setTimeout(() => {
-      // Generate mock transaction hash
-      const mockTxHash = `0x${[...Array(64)]
-        .map(() => Math.floor(Math.random() * 16).toString(16))
-        .join('')}`;
-      
-      setTxHash(mockTxHash);
-      setMintingState('success');
-    }, 2000);
+      // Poll the job until it is confirmed or failed, or the modal closes
+      for (;;) {
+        if (openCount.current !== opened) return;
+        const job = await getConversationMint(conversationId, job_id);
+        if (openCount.current !== opened) return;
+
+        if (job.status === 'confirmed') {
+          setTxHash(job.tx_hash);
+          setTokenId(job.token_id);
+          setExplorerUrl(job.explorer_url);
+          setMintingState('success');
+          return;
+        }
+        if (job.status === 'failed') {
+          throw new Error(job.error || 'Mint job failed');
+        }
+        setProgress(JOB_PROGRESS[job.status]);
+        await new Promise((resolve) => setTimeout(resolve, POLL_INTERVAL_MS));
+      }
+    } catch (error) {
+      if (openCount.current !== opened) return;
+      console.error('Minting failed:', error);
+      setErrorMessage(error instanceof Error ? error.message : String(error));
+      setMintingState('error');
+    }
   };
 
   return (
@@ -70,8 +127,8 @@ export const MintModal: React.FC<MintModalProps> = ({ show, onClose, chatResult
               onClick={(e) => e.stopPropagation()}
               className="bg-gray-800 rounded-2xl shadow-2xl max-w-lg w-full p-6 relative"
             >
-              {/* Close button (only show in idle and success states) */}
-              {(mintingState === 'idle' || mintingState === 'success') && (
+              {/* Close button (hidden while minting) */}
+              {mintingState !== 'minting' && (
                 <button
                   onClick={onClose}
                   className="absolute top-4 right-4 text-gray-400 hover:text-white transition-colors"
@@ -109,7 +166,7 @@ export const MintModal: React.FC<MintModalProps> = ({ show, onClose, chatResult
                       type="text"
                       value={walletAddress}
                       onChange={(e) => setWalletAddress(e.target.value)}
-                      placeholder="0x..."
+                      placeholder="Your wallet, or 0x... / name.eth"
                       className="w-full px-4 py-3 bg-gray-700 text-white rounded-lg border border-gray-600 focus:border-purple-500 focus:outline-none transition-colors"
                     />
                   </div>
@@ -141,7 +198,7 @@ export const MintModal: React.FC<MintModalProps> = ({ show, onClose, chatResult
                     Minting in progress...
                   </p>
                   <p className="text-gray-400 text-sm mt-2">
-                    Please wait while we process your transaction
+                    {progress}
                   </p>
                 </motion.div>
               )}
@@ -186,13 +243,33 @@ export const MintModal: React.FC<MintModalProps> = ({ show, onClose, chatResult
                       <p className="text-xs text-green-400 font-mono break-all">
                         {txHash}
                       </p>
+                      {explorerUrl && (
+                        <a
+                          href={explorerUrl}
+                          target="_blank"
+                          rel="noopener noreferrer"
+                          className="text-sm text-purple-400 hover:text-purple-300 mt-2 inline-block"
+                        >
+                          View on block explorer
+                        </a>
+                      )}
                     </div>
 
+                    {/* Token ID */}
+                    {tokenId && (
+                      <div className="w-full bg-gray-700 rounded-lg p-4 mb-6">
+                        <p className="text-sm text-gray-400 mb-2">Token ID:</p>
+                        <p className="text-sm text-white font-mono break-all">
+                          {tokenId}
+                        </p>
+                      </div>
+                    )}
+
                     {/* Wallet Address */}
                     <div className="w-full bg-gray-700 rounded-lg p-4 mb-6">
                       <p className="text-sm text-gray-400 mb-2">Minted to:</p>
                       <p className="text-sm text-white font-mono break-all">
-                        {walletAddress}
+                        {walletAddress.trim() || getSession()?.address}
                       </p>
                     </div>
 
@@ -206,6 +283,35 @@ export const MintModal: React.FC<MintModalProps> = ({ show, onClose, chatResult
                   </motion.div>
                 </>
               )}
+
+              {/* State: Error */}
+              {mintingState === 'error' && (
+                <motion.div
+                  initial={{ opacity: 0, scale: 0.8 }}
+                  animate={{ opacity: 1, scale: 1 }}
+                  className="flex flex-col items-center justify-center py-8"
+                >
+                  <div className="w-20 h-20 bg-red-500 rounded-full flex items-center justify-center mb-6">
+                    <FaExclamationTriangle className="text-white text-3xl" />
+                  </div>
+
+                  <h3 className="text-2xl font-bold text-white mb-2">
+                    Mint Failed
+                  </h3>
+                  <div className="w-full bg-gray-700 rounded-lg p-4 mb-6">
+                    <p className="text-sm text-red-400 break-all">
+                      {errorMessage}
+                    </p>
+                  </div>
+
+                  <button
+                    onClick={() => setMintingState('idle')}
+                    className="w-full px-6 py-3 bg-purple-600 hover:bg-purple-500 text-white font-medium rounded-lg transition-colors"
+                  >
+                    Try Again
+                  </button>
+                </motion.div>
+              )}
             </motion.div>
           </motion.div>
         </>
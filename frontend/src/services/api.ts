@@ -2,6 +2,146 @@
 
 const API_BASE_URL = import.meta.env.VITE_API_BASE_URL || 'http://localhost:8000';
 
+const SESSION_STORAGE_KEY = 'siwe-session';
+
+/**
+ * The injected EIP-1193 wallet provider (e.g. MetaMask)
+ */
+interface EthereumProvider {
+  request(args: { method: string; params?: unknown[] }): Promise<any>;
+}
+
+declare global {
+  interface Window {
+    ethereum?: EthereumProvider;
+  }
+}
+
+/**
+ * A signed-in wallet session, as returned by POST /auth/verify
+ */
+export interface AuthSession {
+  token: string;
+  address: string;
+  expires_at: string;
+}
+
+/**
+ * The stored session, or null if not signed in or expired
+ */
+export function getSession(): AuthSession | null {
+  const stored = localStorage.getItem(SESSION_STORAGE_KEY);
+  if (!stored) {
+    return null;
+  }
+  const session: AuthSession = JSON.parse(stored);
+  if (new Date(session.expires_at).getTime() <= Date.now()) {
+    localStorage.removeItem(SESSION_STORAGE_KEY);
+    return null;
+  }
+  return session;
+}
+
+/**
+ * Forget the stored session
+ */
+export function signOut(): void {
+  localStorage.removeItem(SESSION_STORAGE_KEY);
+}
+
+/**
+ * Authorization header for the API server, if signed in
+ */
+export function authHeaders(): Record<string, string> {
+  const session = getSession();
+  return session ? { Authorization: `Bearer ${session.token}` } : {};
+}
+
+/**
+ * A request rejected with 429 by the API server's rate limits or daily quotas
+ */
+export class RateLimitError extends Error {
+  retryAfterSecs: number;
+
+  constructor(reason: string, retryAfterSecs: number) {
+    const wait = retryAfterSecs >= 3600
+      ? `${Math.ceil(retryAfterSecs / 3600)} h`
+      : retryAfterSecs >= 60
+        ? `${Math.ceil(retryAfterSecs / 60)} min`
+        : `${retryAfterSecs} s`;
+    super(`${reason}. Try again in ${wait}.`);
+    this.name = 'RateLimitError';
+    this.retryAfterSecs = retryAfterSecs;
+  }
+}
+
+/**
+ * Build a RateLimitError from a 429 response and its Retry-After header
+ */
+export async function rateLimitError(response: Response): Promise<RateLimitError> {
+  const reason = await response.json().catch(() => 'Too many requests');
+  return new RateLimitError(String(reason), Number(response.headers.get('Retry-After')) || 60);
+}
+
+/**
+ * Sign in with Ethereum (EIP-4361): the wallet signs a message with a nonce from the
+ * API server, which exchanges the signature for a session token
+ */
+export async function signInWithEthereum(): Promise<AuthSession> {
+  const ethereum = window.ethereum;
+  if (!ethereum) {
+    throw new Error('No Ethereum wallet found; install MetaMask or a compatible wallet');
+  }
+
+  const [address] = await ethereum.request({ method: 'eth_requestAccounts' });
+  const chainId = parseInt(await ethereum.request({ method: 'eth_chainId' }), 16);
+
+  const nonceResponse = await fetch(`${API_BASE_URL}/auth/nonce`);
+  if (!nonceResponse.ok) {
+    throw new Error(`Failed to fetch nonce: ${nonceResponse.statusText}`);
+  }
+  const { nonce, domain } = await nonceResponse.json();
+
+  const message = [
+    `${domain} wants you to sign in with your Ethereum account:`,
+    address,
+    '',
+    'Sign in to the Web3 concierge.',
+    '',
+    `URI: ${window.location.origin}`,
+    'Version: 1',
+    `Chain ID: ${chainId}`,
+    `Nonce: ${nonce}`,
+    `Issued At: ${new Date().toISOString()}`,
+  ].join('\n');
+  const hexMessage =
+    '0x' +
+    Array.from(new TextEncoder().encode(message))
+      .map((byte) => byte.toString(16).padStart(2, '0'))
+      .join('');
+  const signature = await ethereum.request({
+    method: 'personal_sign',
+    params: [hexMessage, address],
+  });
+
+  const response = await fetch(`${API_BASE_URL}/auth/verify`, {
+    method: 'POST',
+    headers: {
+      'Content-Type': 'application/json',
+    },
+    body: JSON.stringify({ message, signature }),
+  });
+
+  if (!response.ok) {
+    const errorText = await response.text();
+    throw new Error(`Failed to sign in: ${response.statusText} - ${errorText}`);
+  }
+
+  const session: AuthSession = await response.json();
+  localStorage.setItem(SESSION_STORAGE_KEY, JSON.stringify(session));
+  return session;
+}
+
 /**
  * Agent information structure
  */
@@ -17,6 +157,8 @@ export interface Agent {
 export interface AgentReplyResponse {
   reply_text: string;
   audio_url: string;
+  session_id: string;
+  turn: number;
 }
 
 /**
@@ -25,6 +167,7 @@ export interface AgentReplyResponse {
 export interface TextInputRequest {
   agent_id: string;
   user_text: string;
+  session_id?: string;
 }
 
 /**
@@ -58,17 +201,20 @@ export async function getAgents(): Promise<Agent[]> {
  */
 export async function sendTextInput(
   agentId: string,
-  userText: string
+  userText: string,
+  sessionId?: string
 ): Promise<AgentReplyResponse> {
   const payload: TextInputRequest = {
     agent_id: agentId,
     user_text: userText,
+    session_id: sessionId,
   };
 
   const response = await fetch(`${API_BASE_URL}/input/text`, {
     method: 'POST',
     headers: {
       'Content-Type': 'application/json',
+      ...authHeaders(),
     },
     body: JSON.stringify(payload),
   });
@@ -86,14 +232,19 @@ export async function sendTextInput(
  */
 export async function sendAudioInput(
   agentId: string,
-  audioFile: File
+  audioFile: File,
+  sessionId?: string
 ): Promise<AgentReplyResponse> {
   const formData = new FormData();
   formData.append('audio_file', audioFile);
   formData.append('agent_id', agentId);
+  if (sessionId) {
+    formData.append('session_id', sessionId);
+  }
 
   const response = await fetch(`${API_BASE_URL}/input/audio`, {
     method: 'POST',
+    headers: authHeaders(),
     body: formData,
   });
 
@@ -122,3 +273,86 @@ export function getAudioUrl(audioPath: string): string {
   // Otherwise, append with a slash
   return `${API_BASE_URL}/${audioPath}`;
 }
+
+/**
+ * Request payload for minting a conversation turn
+ */
+export interface MintConversationRequest {
+  turn?: number;
+  recipient?: string;
+  chain?: string;
+}
+
+/**
+ * A conversation turn's mint job, as started by mintConversation
+ */
+export interface MintConversationResponse {
+  job_id: string;
+  turn: number;
+}
+
+/**
+ * Progress of a mint job on the web3-minting service
+ */
+export type MintJobStatus = 'pending' | 'uploading' | 'submitted' | 'confirmed' | 'failed';
+
+/**
+ * A conversation turn's mint job, as returned by GET /conversations/{id}/mint/{job_id}
+ */
+export interface MintStatus {
+  job_id: string;
+  turn: number;
+  status: MintJobStatus;
+  audio_url: string;
+  metadata_url: string | null;
+  tx_hash: string | null;
+  token_id: string | null;
+  chain_id: number | null;
+  explorer_url: string | null;
+  error: string | null;
+}
+
+/**
+ * Start minting a conversation turn, with its audio, through the API server; poll the
+ * returned job with getConversationMint. A turn is minted once: minting it again returns
+ * the same job. Without a recipient, the token goes to the signed-in wallet
+ */
+export async function mintConversation(
+  conversationId: string,
+  request: MintConversationRequest
+): Promise<MintConversationResponse> {
+  const response = await fetch(`${API_BASE_URL}/conversations/${conversationId}/mint`, {
+    method: 'POST',
+    headers: {
+      'Content-Type': 'application/json',
+      ...authHeaders(),
+    },
+    body: JSON.stringify(request),
+  });
+
+  if (!response.ok) {
+    const errorText = await response.text();
+    throw new Error(`Failed to mint conversation: ${response.statusText} - ${errorText}`);
+  }
+
+  return response.json();
+}
+
+/**
+ * Fetch the current state of a conversation turn's mint job
+ */
+export async function getConversationMint(
+  conversationId: string,
+  jobId: string
+): Promise<MintStatus> {
+  const response = await fetch(`${API_BASE_URL}/conversations/${conversationId}/mint/${jobId}`, {
+    headers: authHeaders(),
+  });
+
+  if (!response.ok) {
+    const errorText = await response.text();
+    throw new Error(`Failed to fetch mint job: ${response.statusText} - ${errorText}`);
+  }
+
+  return response.json();
+}
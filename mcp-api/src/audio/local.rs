@@ -0,0 +1,415 @@
+//! Audio files in a local directory (`AUDIO_DIR`).
+//!
+//! Files are served by the API itself. [`AudioStore::cleanup`] deletes files that
+//! have not been used for `AUDIO_TTL_SECS`, and the least recently used files while
+//! the directory is larger than `AUDIO_MAX_BYTES`. Storing, reusing or serving a
+//! file counts as using it; its modification time records the last use across
+//! restarts.
+//!
+//! The directory belongs to one server: replicas behind a load balancer need the
+//! [`super::s3`] backend.
+
+use super::{AudioStore, URL_PREFIX, filename_of};
+use crate::env_number;
+use async_trait::async_trait;
+use axum::{body::Body, extract::Request, response::Response};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+use tower::ServiceExt;
+use tower_http::services::ServeFile;
+use uuid::Uuid;
+
+/// How often a file's modification time is updated while it is being used, so an
+/// audio player's range requests do not each touch the file.
+const TOUCH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Audio files in a local directory, with TTL and size limits.
+pub struct LocalStore {
+    dir: PathBuf,
+    /// Files unused for longer are deleted
+    ttl: Option<Duration>,
+    /// Least recently used files are deleted while the total size is larger
+    max_bytes: Option<u64>,
+    /// Stored files by key (file name without extension)
+    files: Mutex<HashMap<String, StoredFile>>,
+}
+
+struct StoredFile {
+    filename: String,
+    size: u64,
+    last_used: SystemTime,
+}
+
+impl LocalStore {
+    /// Opens the audio directory configured in the environment, creating it if needed.
+    ///
+    /// # Environment Variables
+    ///
+    /// * `AUDIO_DIR` - Directory of the audio files (default: `public/audio`)
+    /// * `AUDIO_TTL_SECS` - Delete files unused for this long (default: 604800, one week; 0 = never)
+    /// * `AUDIO_MAX_BYTES` - Evict least recently used files above this total size
+    ///   (default: 1073741824, 1 GiB; 0 = unlimited)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created or read, or presigned
+    /// URLs are asked for with `AUDIO_URL_MODE`.
+    pub fn from_env() -> Result<Self, String> {
+        if std::env::var("AUDIO_URL_MODE").is_ok_and(|mode| mode == "presigned") {
+            return Err("AUDIO_URL_MODE=presigned needs AUDIO_STORE=s3".to_string());
+        }
+        let dir = std::env::var("AUDIO_DIR").unwrap_or_else(|_| "public/audio".to_string());
+        let ttl = env_number("AUDIO_TTL_SECS", 7 * 24 * 60 * 60);
+        let max_bytes = env_number("AUDIO_MAX_BYTES", 1 << 30);
+        Self::open(
+            dir,
+            (ttl > 0).then(|| Duration::from_secs(ttl)),
+            (max_bytes > 0).then_some(max_bytes),
+        )
+    }
+
+    /// Opens an audio directory, creating it if needed, and indexes the files in it.
+    ///
+    /// Leftovers of interrupted writes are deleted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created or read.
+    pub fn open(
+        dir: impl Into<PathBuf>,
+        ttl: Option<Duration>,
+        max_bytes: Option<u64>,
+    ) -> Result<Self, String> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+
+        let mut files = HashMap::new();
+        for entry in std::fs::read_dir(&dir).map_err(|e| e.to_string())? {
+            let entry = entry.map_err(|e| e.to_string())?;
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let filename = entry.file_name().to_string_lossy().into_owned();
+            if !metadata.is_file() || filename.starts_with('.') {
+                continue;
+            }
+            if filename.ends_with(".tmp") {
+                let _ = std::fs::remove_file(entry.path());
+                continue;
+            }
+            let key = filename.split('.').next().unwrap_or_default().to_string();
+            files.insert(
+                key,
+                StoredFile {
+                    filename,
+                    size: metadata.len(),
+                    last_used: metadata.modified().unwrap_or_else(|_| SystemTime::now()),
+                },
+            );
+        }
+
+        Ok(Self {
+            dir,
+            ttl,
+            max_bytes,
+            files: Mutex::new(files),
+        })
+    }
+
+    /// Number of stored files and their total size in bytes.
+    pub fn usage(&self) -> (usize, u64) {
+        let files = self.files.lock().unwrap();
+        (files.len(), files.values().map(|f| f.size).sum())
+    }
+
+    /// Path of a stored file, counting it as used; `None` if no file of that name is stored.
+    fn path_of(&self, filename: &str) -> Option<PathBuf> {
+        let key = filename.split('.').next()?;
+        let filename = self.touch(|files| files.get_mut(key).filter(|f| f.filename == filename))?;
+        Some(self.dir.join(filename))
+    }
+
+    /// Marks a file as used and returns its name; the modification time is
+    /// updated at most every [`TOUCH_INTERVAL`].
+    fn touch(
+        &self,
+        lookup: impl FnOnce(&mut HashMap<String, StoredFile>) -> Option<&mut StoredFile>,
+    ) -> Option<String> {
+        let now = SystemTime::now();
+        let mut files = self.files.lock().unwrap();
+        let file = lookup(&mut files)?;
+        if now.duration_since(file.last_used).unwrap_or_default() >= TOUCH_INTERVAL {
+            let path = self.dir.join(&file.filename);
+            let touched = std::fs::File::options()
+                .append(true)
+                .open(&path)
+                .and_then(|f| f.set_modified(now));
+            if let Err(e) = touched {
+                tracing::warn!("Failed to touch audio file {}: {}", path.display(), e);
+            }
+        }
+        file.last_used = now;
+        Some(file.filename.clone())
+    }
+}
+
+#[async_trait]
+impl AudioStore for LocalStore {
+    fn location(&self) -> String {
+        let (files, bytes) = self.usage();
+        format!("{} ({} files, {} bytes)", self.dir.display(), files, bytes)
+    }
+
+    /// Looks up stored audio by key, counting it as used.
+    async fn find(&self, key: &str) -> Result<Option<String>, String> {
+        let filename = self.touch(|files| files.get_mut(key));
+        Ok(filename.map(|filename| format!("{}{}", URL_PREFIX, filename)))
+    }
+
+    /// Stores audio, then evicts files if the directory is over its size limit.
+    async fn put(&self, key: &str, extension: &str, bytes: Vec<u8>) -> Result<String, String> {
+        let filename = format!("{}.{}", key, extension);
+        let path = self.dir.join(&filename);
+        // Written under a temporary name, so a file is never served half-written
+        let tmp = self
+            .dir
+            .join(format!("{}.{}.tmp", filename, Uuid::new_v4().simple()));
+        tokio::fs::write(&tmp, &bytes)
+            .await
+            .map_err(|e| e.to_string())?;
+        if let Err(e) = tokio::fs::rename(&tmp, &path).await {
+            let _ = tokio::fs::remove_file(&tmp).await;
+            return Err(e.to_string());
+        }
+
+        self.files.lock().unwrap().insert(
+            key.to_string(),
+            StoredFile {
+                filename: filename.clone(),
+                size: bytes.len() as u64,
+                last_used: SystemTime::now(),
+            },
+        );
+        if self.max_bytes.is_some_and(|max| self.usage().1 > max) {
+            self.cleanup().await;
+        }
+        Ok(format!("{}{}", URL_PREFIX, filename))
+    }
+
+    async fn read(&self, url: &str) -> Result<Option<Vec<u8>>, String> {
+        let Some(path) = filename_of(url).and_then(|f| self.path_of(f)) else {
+            return Ok(None);
+        };
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => Ok(Some(bytes)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    async fn remove(&self, url: &str) -> Result<(), String> {
+        let Some(filename) = filename_of(url) else {
+            return Ok(());
+        };
+        let key = filename.split('.').next().unwrap_or_default();
+        let removed = {
+            let mut files = self.files.lock().unwrap();
+            match files.get(key) {
+                Some(file) if file.filename == filename => files.remove(key),
+                _ => None,
+            }
+        };
+        match removed {
+            Some(file) => remove_file(&self.dir.join(file.filename)).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Serves a stored file, counting it as used.
+    async fn serve(&self, filename: &str, request: Request) -> Result<Option<Response>, String> {
+        let Some(path) = self.path_of(filename) else {
+            return Ok(None);
+        };
+        let response = ServeFile::new(path)
+            .oneshot(request)
+            .await
+            .unwrap_or_else(|e| match e {});
+        Ok(Some(response.map(Body::new)))
+    }
+
+    /// Deletes files unused for longer than the TTL, then the least recently used
+    /// files while the total size is over the limit.
+    async fn cleanup(&self) -> usize {
+        let evicted: Vec<String> = {
+            let mut files = self.files.lock().unwrap();
+            let now = SystemTime::now();
+            let mut evicted: Vec<String> = match self.ttl {
+                Some(ttl) => files
+                    .iter()
+                    .filter(|(_, f)| now.duration_since(f.last_used).unwrap_or_default() > ttl)
+                    .map(|(key, _)| key.clone())
+                    .collect(),
+                None => Vec::new(),
+            };
+
+            if let Some(max_bytes) = self.max_bytes {
+                let mut total: u64 = files.values().map(|f| f.size).sum();
+                total -= evicted.iter().map(|key| files[key].size).sum::<u64>();
+                let mut by_last_use: Vec<(&String, &StoredFile)> = files
+                    .iter()
+                    .filter(|(key, _)| !evicted.contains(key))
+                    .collect();
+                by_last_use.sort_by_key(|(_, f)| f.last_used);
+                let mut lru = Vec::new();
+                for (key, file) in by_last_use {
+                    if total <= max_bytes {
+                        break;
+                    }
+                    total -= file.size;
+                    lru.push(key.clone());
+                }
+                evicted.extend(lru);
+            }
+
+            evicted
+                .into_iter()
+                .filter_map(|key| files.remove(&key))
+                .map(|f| f.filename)
+                .collect()
+        };
+
+        for filename in &evicted {
+            if let Err(e) = remove_file(&self.dir.join(filename)).await {
+                tracing::warn!("Failed to delete audio file {}: {}", filename, e);
+            }
+        }
+        evicted.len()
+    }
+}
+
+/// Deletes a file; one that is already gone is not an error.
+async fn remove_file(path: &Path) -> Result<(), String> {
+    match tokio::fs::remove_file(path).await {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.to_string()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A directory under the system temp dir, deleted when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            let name = format!("mcp-api-audio-{}", Uuid::new_v4().simple());
+            let dir = std::env::temp_dir().join(name);
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        /// Writes a file last modified `age` ago.
+        fn write(&self, filename: &str, bytes: &[u8], age: Duration) {
+            let path = self.0.join(filename);
+            std::fs::write(&path, bytes).unwrap();
+            let file = std::fs::File::options().append(true).open(&path).unwrap();
+            file.set_modified(SystemTime::now() - age).unwrap();
+        }
+
+        fn exists(&self, filename: &str) -> bool {
+            self.0.join(filename).exists()
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    const HOUR: Duration = Duration::from_secs(60 * 60);
+
+    #[tokio::test]
+    async fn open_indexes_files_and_deletes_interrupted_writes() {
+        let dir = TempDir::new();
+        dir.write("aaa.mp3", b"0123456789", HOUR);
+        dir.write("bbb.wav", b"01234", HOUR);
+        dir.write("ccc.mp3.0f1e2d.tmp", b"partial", Duration::ZERO);
+        dir.write(".keep", b"", Duration::ZERO);
+
+        let store = LocalStore::open(&dir.0, None, None).unwrap();
+        assert_eq!(store.usage(), (2, 15));
+        assert!(!dir.exists("ccc.mp3.0f1e2d.tmp"));
+        assert!(dir.exists(".keep"));
+
+        assert_eq!(
+            store.find("aaa").await.unwrap().as_deref(),
+            Some("/public/audio/aaa.mp3")
+        );
+        assert_eq!(store.find("ccc").await.unwrap(), None);
+        let bytes = store.read("/public/audio/bbb.wav").await.unwrap();
+        assert_eq!(bytes.as_deref(), Some(&b"01234"[..]));
+        // Only the stored extension is served
+        assert_eq!(store.read("/public/audio/bbb.mp3").await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn cleanup_deletes_files_unused_for_the_ttl() {
+        let dir = TempDir::new();
+        dir.write("old.mp3", b"old", 3 * HOUR);
+        dir.write("used.mp3", b"used", 3 * HOUR);
+        dir.write("new.mp3", b"new", Duration::ZERO);
+
+        let store = LocalStore::open(&dir.0, Some(2 * HOUR), None).unwrap();
+        // Finding a file uses it, which renews it on disk too
+        store.find("used").await.unwrap().unwrap();
+        assert_eq!(store.cleanup().await, 1);
+        assert!(!dir.exists("old.mp3"));
+        assert_eq!(store.usage(), (2, 7));
+
+        let reopened = LocalStore::open(&dir.0, Some(2 * HOUR), None).unwrap();
+        assert_eq!(reopened.cleanup().await, 0);
+        assert!(dir.exists("used.mp3") && dir.exists("new.mp3"));
+    }
+
+    #[tokio::test]
+    async fn cleanup_evicts_least_recently_used_files_over_the_size_limit() {
+        let dir = TempDir::new();
+        dir.write("a.mp3", b"0123456789", 3 * HOUR);
+        dir.write("b.mp3", b"0123456789", 2 * HOUR);
+        dir.write("c.mp3", b"0123456789", HOUR);
+
+        let store = LocalStore::open(&dir.0, None, Some(25)).unwrap();
+        store.read("/public/audio/a.mp3").await.unwrap().unwrap();
+        assert_eq!(store.cleanup().await, 1);
+        assert!(!dir.exists("b.mp3"));
+        assert!(dir.exists("a.mp3") && dir.exists("c.mp3"));
+        assert_eq!(store.usage(), (2, 20));
+    }
+
+    #[tokio::test]
+    async fn put_evicts_when_the_directory_is_full() {
+        let dir = TempDir::new();
+        dir.write("a.mp3", b"0123456789", HOUR);
+        let store = LocalStore::open(&dir.0, None, Some(25)).unwrap();
+
+        store.put("b", "mp3", b"0123456789".to_vec()).await.unwrap();
+        assert_eq!(store.usage(), (2, 20));
+        let url = store.put("c", "wav", b"0123456789".to_vec()).await.unwrap();
+        assert_eq!(url, "/public/audio/c.wav");
+        assert_eq!(store.usage(), (2, 20));
+        assert!(!dir.exists("a.mp3"));
+        assert!(dir.exists("b.mp3") && dir.exists("c.wav"));
+
+        // No temporary files are left behind
+        let names: Vec<_> = std::fs::read_dir(&dir.0)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        assert_eq!(names.len(), 2, "{:?}", names);
+    }
+}
@@ -0,0 +1,149 @@
+//! Storage of generated audio.
+//!
+//! This module defines the [`AudioStore`] trait used by the request handlers, and
+//! selects its implementation from configuration.
+//!
+//! Replies are stored under the SHA-256 of the voice and text they were
+//! synthesized from ([`key`]), so an identical reply reuses the stored audio
+//! instead of being synthesized again. Stored audio is identified by a URL path
+//! below [`URL_PREFIX`], which is what conversations keep; clients get it as is
+//! (served through [`crate::handlers::serve_audio`]) or as a presigned URL (see
+//! [`AudioStore::public_url`]).
+//!
+//! # Backends
+//!
+//! - `local` - Files in `AUDIO_DIR`, with TTL and size-based cleanup ([`local`])
+//! - `s3` - An S3-compatible bucket such as AWS S3 or MinIO, shared by all replicas ([`s3`])
+
+pub mod local;
+pub mod s3;
+
+use async_trait::async_trait;
+use axum::{extract::Request, response::Response};
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// URL path audio is served under.
+pub const URL_PREFIX: &str = "/public/audio/";
+
+/// A storage backend for generated audio.
+#[async_trait]
+pub trait AudioStore: Send + Sync {
+    /// Where the audio is stored (e.g. a directory or `s3://bucket/prefix`), used in logs.
+    fn location(&self) -> String;
+
+    /// Looks up stored audio by key.
+    ///
+    /// # Returns
+    ///
+    /// The URL path of the audio, or `None` if it is not stored.
+    async fn find(&self, key: &str) -> Result<Option<String>, String>;
+
+    /// Stores audio under a key.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Key from [`key`]
+    /// * `extension` - File extension matching the encoding (e.g. `"mp3"`)
+    /// * `bytes` - Encoded audio
+    ///
+    /// # Returns
+    ///
+    /// The URL path of the audio.
+    async fn put(&self, key: &str, extension: &str, bytes: Vec<u8>) -> Result<String, String>;
+
+    /// Reads the audio at a URL path returned by [`AudioStore::put`].
+    ///
+    /// # Returns
+    ///
+    /// `None` if the audio is not stored, e.g. because it expired.
+    async fn read(&self, url: &str) -> Result<Option<Vec<u8>>, String>;
+
+    /// Deletes the audio at a URL path returned by [`AudioStore::put`].
+    async fn remove(&self, url: &str) -> Result<(), String>;
+
+    /// Responds to `GET /public/audio/{filename}`, honoring `Range` headers.
+    ///
+    /// # Returns
+    ///
+    /// `None` if no audio of that name is stored.
+    async fn serve(&self, filename: &str, request: Request) -> Result<Option<Response>, String>;
+
+    /// The URL clients are given for a URL path returned by [`AudioStore::put`]:
+    /// the path itself, or a presigned URL when the backend is configured for them.
+    fn public_url(&self, url: &str) -> String {
+        url.to_string()
+    }
+
+    /// Deletes expired audio.
+    ///
+    /// # Returns
+    ///
+    /// The number of files deleted.
+    async fn cleanup(&self) -> usize {
+        0
+    }
+}
+
+/// Builds the audio store selected by `AUDIO_STORE`.
+///
+/// # Environment Variables
+///
+/// * `AUDIO_STORE` - `local` (default) or `s3`
+///
+/// See [`local::LocalStore::from_env`] and [`s3::S3Store::from_env`] for
+/// backend-specific settings.
+///
+/// # Panics
+///
+/// Panics if the backend name is unknown, its required settings are missing, or
+/// the local audio store cannot be set up.
+pub fn from_env(client: &Client) -> Arc<dyn AudioStore> {
+    match std::env::var("AUDIO_STORE")
+        .unwrap_or_default()
+        .to_lowercase()
+        .as_str()
+    {
+        "" | "local" => {
+            Arc::new(local::LocalStore::from_env().expect("Failed to set up local audio store"))
+        }
+        "s3" => Arc::new(s3::S3Store::from_env(client.clone())),
+        other => panic!("Unknown AUDIO_STORE {:?}; expected local or s3", other),
+    }
+}
+
+/// Key of the audio for a text spoken with a voice (see [`crate::speech::TextToSpeech::voice`]).
+pub fn key(voice: &str, text: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(voice.as_bytes());
+    hasher.update([0]);
+    hasher.update(text.as_bytes());
+    hex::encode(hasher.finalize())
+}
+
+/// Runs [`AudioStore::cleanup`] every `interval` in a background task.
+pub fn spawn_cleanup(store: Arc<dyn AudioStore>, interval: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            let deleted = store.cleanup().await;
+            if deleted > 0 {
+                tracing::info!("Deleted {} expired audio files", deleted);
+            }
+        }
+    });
+}
+
+/// File name in an audio URL path, if it is one of ours and names no other directory.
+fn filename_of(url: &str) -> Option<&str> {
+    let filename = url.strip_prefix(URL_PREFIX)?;
+    is_valid_filename(filename).then_some(filename)
+}
+
+/// Whether a file name from a URL stays inside the audio directory or prefix.
+fn is_valid_filename(filename: &str) -> bool {
+    !filename.is_empty() && !filename.starts_with('.') && !filename.contains(['/', '\\'])
+}
@@ -0,0 +1,363 @@
+//! Sign-In with Ethereum authentication.
+//!
+//! A client signs in by fetching a nonce from `GET /auth/nonce`, having the wallet
+//! sign an EIP-4361 message containing it with `personal_sign`, and posting the
+//! message and signature to `POST /auth/verify`. A valid signature is exchanged for
+//! a session JWT, which authenticates later requests as the wallet address through
+//! the [`AuthUser`] extractor.
+//!
+//! # Modules
+//!
+//! - [`siwe`] - EIP-4361 message parsing and signature recovery
+//! - [`jwt`] - `HS256` session tokens
+
+pub mod jwt;
+pub mod siwe;
+
+use crate::AppState;
+use crate::keys::KEY_PREFIX;
+use axum::{
+    Json,
+    extract::FromRequestParts,
+    http::{HeaderMap, StatusCode, header, request::Parts},
+};
+use jwt::Claims;
+use rand_core::{OsRng, RngCore};
+use siwe::SiweMessage;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// How long a nonce from `GET /auth/nonce` can be used to sign in.
+const NONCE_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Most nonces outstanding at once; the oldest is dropped to issue another.
+///
+/// `GET /auth/nonce` is unauthenticated, so this bounds the memory a client
+/// requesting nonces in a loop can use.
+const MAX_NONCES: usize = 10_000;
+
+/// How far ahead of the server's clock a message's `Issued At` may be.
+const MAX_CLOCK_SKEW_SECS: i64 = 5 * 60;
+
+/// A signed-in session, returned by [`Auth::verify`].
+pub struct Session {
+    /// The session JWT
+    pub token: String,
+    /// Wallet address that signed in, EIP-55 checksummed
+    pub address: String,
+    /// When the token expires (RFC 3339)
+    pub expires_at: String,
+}
+
+/// Issues nonces, verifies SIWE signatures and issues and checks session tokens.
+pub struct Auth {
+    /// HMAC key of the session tokens
+    secret: Vec<u8>,
+    /// Domain SIWE messages must be addressed to
+    domain: String,
+    /// Lifetime of session tokens
+    session_ttl: Duration,
+    /// Outstanding nonces and when they were issued
+    nonces: Mutex<HashMap<String, Instant>>,
+}
+
+impl Auth {
+    /// Builds the authenticator from environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// * `JWT_SECRET` - Key that signs session tokens (optional; a random key is
+    ///   generated when unset, so sessions do not survive a restart)
+    /// * `SIWE_DOMAIN` - Domain SIWE messages must be addressed to (default: `localhost:5173`)
+    /// * `SESSION_TTL_SECS` - Lifetime of session tokens (default: 86400)
+    pub fn from_env() -> Self {
+        let secret = match std::env::var("JWT_SECRET") {
+            Ok(secret) if !secret.is_empty() => secret.into_bytes(),
+            _ => {
+                tracing::warn!("JWT_SECRET not set; sessions will not survive a restart");
+                let mut secret = vec![0u8; 32];
+                OsRng.fill_bytes(&mut secret);
+                secret
+            }
+        };
+        let session_ttl = crate::env_number("SESSION_TTL_SECS", 24 * 60 * 60);
+        Self {
+            secret,
+            domain: std::env::var("SIWE_DOMAIN").unwrap_or_else(|_| "localhost:5173".to_string()),
+            session_ttl: Duration::from_secs(session_ttl),
+            nonces: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Domain SIWE messages must be addressed to.
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Issues a single-use nonce for a SIWE message.
+    ///
+    /// At most [`MAX_NONCES`] are outstanding; beyond that the oldest stops working.
+    pub fn issue_nonce(&self) -> String {
+        let nonce = Uuid::new_v4().simple().to_string();
+        let mut nonces = self.nonces.lock().unwrap();
+        nonces.retain(|_, issued| issued.elapsed() < NONCE_TTL);
+        if nonces.len() >= MAX_NONCES {
+            let oldest = nonces
+                .iter()
+                .min_by_key(|(_, issued)| **issued)
+                .map(|(nonce, _)| nonce.clone());
+            if let Some(oldest) = oldest {
+                nonces.remove(&oldest);
+            }
+        }
+        nonces.insert(nonce.clone(), Instant::now());
+        nonce
+    }
+
+    /// Verifies a signed SIWE message and starts a session for its address.
+    ///
+    /// The message must be addressed to `SIWE_DOMAIN`, carry an outstanding nonce
+    /// (which is used up), be within its validity period, and be signed by the
+    /// address it names.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the first check that failed.
+    pub fn verify(&self, message: &str, signature: &str) -> Result<Session, String> {
+        let siwe = SiweMessage::parse(message)?;
+        if siwe.domain != self.domain {
+            return Err(format!(
+                "Message is for {}, expected {}",
+                siwe.domain, self.domain
+            ));
+        }
+        if siwe.version != "1" {
+            return Err(format!("Unsupported SIWE version {}", siwe.version));
+        }
+        let now = chrono::Utc::now();
+        if siwe.issued_at > now + chrono::Duration::seconds(MAX_CLOCK_SKEW_SECS) {
+            return Err("Message is issued in the future".to_string());
+        }
+        if siwe.expiration_time.is_some_and(|t| t <= now) {
+            return Err("Message has expired".to_string());
+        }
+        if siwe.not_before.is_some_and(|t| t > now) {
+            return Err("Message is not valid yet".to_string());
+        }
+
+        let address = siwe::checksum_address(&siwe.address)?;
+        let signer = siwe::recover_personal_sign(message, signature)?;
+        if signer != address {
+            return Err(format!("Message was signed by {}, not {}", signer, address));
+        }
+
+        // Used up only by a valid signature, so a bad attempt does not burn it
+        let issued = self.nonces.lock().unwrap().remove(&siwe.nonce);
+        if issued.is_none_or(|issued| issued.elapsed() >= NONCE_TTL) {
+            return Err("Unknown or expired nonce".to_string());
+        }
+
+        let expires_at = now + self.session_ttl;
+        let claims = Claims {
+            sub: address.clone(),
+            chain_id: siwe.chain_id,
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+        Ok(Session {
+            token: jwt::encode(&claims, &self.secret),
+            address,
+            expires_at: expires_at.to_rfc3339(),
+        })
+    }
+
+    /// Checks a session token and returns the wallet address it was issued to.
+    pub fn authenticate(&self, token: &str) -> Result<String, String> {
+        jwt::decode(token, &self.secret).map(|claims| claims.sub)
+    }
+}
+
+/// The wallet a request is authenticated as.
+///
+/// Extracting it requires an `Authorization: Bearer <token>` header with either a
+/// session token from `POST /auth/verify` or an API key from `POST /keys`;
+/// requests without a valid one are rejected with `UNAUTHORIZED`.
+pub struct AuthUser {
+    /// Wallet address, EIP-55 checksummed
+    pub address: String,
+    /// ID of the API key the request was authenticated with, if it used one
+    pub key_id: Option<String>,
+}
+
+impl AuthUser {
+    /// Authenticates a request from its `Authorization` header.
+    ///
+    /// # Errors
+    ///
+    /// Returns a message for the `UNAUTHORIZED` response if the header is missing
+    /// or its token or key is invalid.
+    pub fn from_headers(state: &AppState, headers: &HeaderMap) -> Result<Self, String> {
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or("Sign in with Ethereum first: missing bearer token")?;
+
+        if token.starts_with(KEY_PREFIX) {
+            let key = state
+                .keys
+                .authenticate(token)
+                .map_err(|e| {
+                    tracing::error!("API key store failed: {}", e);
+                    "Failed to check API key".to_string()
+                })?
+                .ok_or("Unknown or revoked API key")?;
+            return Ok(Self {
+                address: key.owner,
+                key_id: Some(key.key_id),
+            });
+        }
+
+        let address = state.auth.authenticate(token)?;
+        Ok(Self {
+            address,
+            key_id: None,
+        })
+    }
+
+    /// Who rate limits and quotas are counted against: the API key, or the wallet
+    /// for browser sessions.
+    pub fn subject(&self) -> String {
+        match &self.key_id {
+            Some(key_id) => format!("key:{}", key_id),
+            None => format!("wallet:{}", self.address),
+        }
+    }
+}
+
+impl FromRequestParts<Arc<AppState>> for AuthUser {
+    type Rejection = (StatusCode, Json<String>);
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        Self::from_headers(state, &parts.headers).map_err(|e| (StatusCode::UNAUTHORIZED, Json(e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Signed by the first anvil/hardhat development key, whose address the
+    /// messages name; the signatures were computed independently of this crate.
+    const MESSAGE: &str = "localhost:5173 wants you to sign in with your Ethereum account:
+0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266
+
+Sign in to the voice agent.
+
+URI: http://localhost:5173
+Version: 1
+Chain ID: 1
+Nonce: kat4nonce01
+Issued At: 2024-01-01T00:00:00Z";
+    const SIGNATURE: &str = "0x41c5b35233599141afd39a81d98596a8c9107b61b695156fa93574dfa8e2bba04bfc4c222952f85a104df587693dc3e1a81516ce00a7e37dd284dd6d9952f4e81c";
+
+    /// `MESSAGE` with nonce `kat4nonce02` and an `Expiration Time` of 2024-01-02.
+    const EXPIRED_SIGNATURE: &str = "0x624348a0af9e7f6833e21d36ff40cca8bfb088c6a1052ba5c2b0941e547672553b9371645d699c19584ef9124187d938504b86ea8d2c508a56b1b01de3ee86731c";
+
+    /// `MESSAGE` for `evil.example` with nonce `kat4nonce03`.
+    const OTHER_DOMAIN_SIGNATURE: &str = "0x0eef291d14ea88ff0e7b665714f92afb30d3ded29df78dc98df00e9fe37a71f05e6ade243714084e8306d0794b425380ed5cbc154977fa098e0646729edef1b11c";
+
+    /// An authenticator for `localhost:5173` with the nonces of the test messages outstanding.
+    fn auth() -> Auth {
+        let nonces = ["kat4nonce01", "kat4nonce02", "kat4nonce03"]
+            .into_iter()
+            .map(|nonce| (nonce.to_string(), Instant::now()))
+            .collect();
+        Auth {
+            secret: b"test secret".to_vec(),
+            domain: "localhost:5173".to_string(),
+            session_ttl: Duration::from_secs(60),
+            nonces: Mutex::new(nonces),
+        }
+    }
+
+    #[test]
+    fn a_signed_message_starts_a_session() {
+        let auth = auth();
+        let session = auth.verify(MESSAGE, SIGNATURE).unwrap();
+        assert_eq!(
+            session.address,
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+        assert_eq!(auth.authenticate(&session.token).unwrap(), session.address);
+        assert!(auth.authenticate(&format!("{}x", session.token)).is_err());
+    }
+
+    #[test]
+    fn nonces_are_single_use() {
+        let auth = auth();
+        auth.verify(MESSAGE, SIGNATURE).unwrap();
+        assert_eq!(
+            auth.verify(MESSAGE, SIGNATURE).err().unwrap(),
+            "Unknown or expired nonce"
+        );
+    }
+
+    #[test]
+    fn rejects_tampered_signatures_without_using_the_nonce() {
+        let auth = auth();
+        let tampered = SIGNATURE.replacen("41c5", "41c6", 1);
+        assert!(auth.verify(MESSAGE, &tampered).is_err());
+        // The message itself changed after signing
+        let changed = MESSAGE.replace("Chain ID: 1", "Chain ID: 10");
+        assert!(
+            auth.verify(&changed, SIGNATURE)
+                .err()
+                .unwrap()
+                .contains("signed by")
+        );
+
+        assert!(auth.verify(MESSAGE, SIGNATURE).is_ok());
+    }
+
+    #[test]
+    fn rejects_expired_messages() {
+        let message = MESSAGE.replace("kat4nonce01", "kat4nonce02")
+            + "\nExpiration Time: 2024-01-02T00:00:00Z";
+        assert_eq!(
+            auth().verify(&message, EXPIRED_SIGNATURE).err().unwrap(),
+            "Message has expired"
+        );
+    }
+
+    #[test]
+    fn rejects_messages_for_other_domains() {
+        let message = MESSAGE
+            .replace("localhost:5173", "evil.example")
+            .replace("kat4nonce01", "kat4nonce03");
+        assert_eq!(
+            auth()
+                .verify(&message, OTHER_DOMAIN_SIGNATURE)
+                .err()
+                .unwrap(),
+            "Message is for evil.example, expected localhost:5173"
+        );
+    }
+
+    #[test]
+    fn outstanding_nonces_are_capped() {
+        let auth = auth();
+        for _ in 0..MAX_NONCES + 10 {
+            auth.issue_nonce();
+        }
+        let nonces = auth.nonces.lock().unwrap();
+        assert_eq!(nonces.len(), MAX_NONCES);
+        // The oldest were dropped first
+        assert!(!nonces.contains_key("kat4nonce01"));
+    }
+}
@@ -0,0 +1,261 @@
+//! EIP-4361 (Sign-In with Ethereum) messages and `personal_sign` signatures.
+//!
+//! Only what verification needs is implemented: parsing the message a wallet
+//! signed, and recovering the address that signed it.
+
+use chrono::{DateTime, Utc};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use sha3::{Digest, Keccak256};
+
+/// Preamble that ends the first line of every SIWE message.
+const PREAMBLE: &str = " wants you to sign in with your Ethereum account:";
+
+/// The fields of a SIWE message that sign-in checks.
+///
+/// The statement, URI and resources are only checked for well-formedness.
+#[derive(Debug)]
+pub struct SiweMessage {
+    /// Host (and port) of the site asking for the signature, e.g. `localhost:5173`
+    pub domain: String,
+    /// Address of the signing account, as written in the message
+    pub address: String,
+    pub version: String,
+    pub chain_id: u64,
+    pub nonce: String,
+    pub issued_at: DateTime<Utc>,
+    pub expiration_time: Option<DateTime<Utc>>,
+    pub not_before: Option<DateTime<Utc>>,
+}
+
+impl SiweMessage {
+    /// Parses a SIWE message in the EIP-4361 text format.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the problem if the message does not follow the
+    /// format or a required field is missing.
+    pub fn parse(message: &str) -> Result<Self, String> {
+        let mut lines = message.lines();
+
+        let header = lines.next().unwrap_or_default();
+        let domain = header
+            .strip_suffix(PREAMBLE)
+            .ok_or("Message does not start with a SIWE preamble")?;
+        // An optional scheme may precede the domain
+        let domain = domain
+            .split_once("://")
+            .map_or(domain, |(_, d)| d)
+            .to_string();
+        let address = lines.next().ok_or("Message has no address")?.to_string();
+
+        // A blank line, the optional statement and another blank line precede the fields
+        let mut has_statement = false;
+        let mut fields = Vec::new();
+        for line in lines.by_ref() {
+            if line.starts_with("URI: ") {
+                fields.push(line);
+                break;
+            }
+            if !line.is_empty() {
+                if has_statement {
+                    return Err("Message has more than one statement line".to_string());
+                }
+                has_statement = true;
+            }
+        }
+        for line in lines {
+            if line == "Resources:" {
+                break;
+            }
+            fields.push(line);
+        }
+
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find_map(|line| line.strip_prefix(name)?.strip_prefix(": "))
+        };
+        let required = |name: &str| field(name).ok_or(format!("Message has no {} field", name));
+        let timestamp = |name: &str, value: &str| {
+            DateTime::parse_from_rfc3339(value)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| format!("Invalid {} {:?}: {}", name, value, e))
+        };
+
+        required("URI")?;
+        Ok(Self {
+            domain,
+            address,
+            version: required("Version")?.to_string(),
+            chain_id: required("Chain ID")?
+                .parse()
+                .map_err(|_| "Invalid Chain ID".to_string())?,
+            nonce: required("Nonce")?.to_string(),
+            issued_at: timestamp("Issued At", required("Issued At")?)?,
+            expiration_time: field("Expiration Time")
+                .map(|v| timestamp("Expiration Time", v))
+                .transpose()?,
+            not_before: field("Not Before")
+                .map(|v| timestamp("Not Before", v))
+                .transpose()?,
+        })
+    }
+}
+
+/// Normalizes an address to its EIP-55 checksummed form.
+///
+/// All-lowercase and all-uppercase addresses are accepted as they are what many
+/// wallets return; mixed-case addresses must carry a valid checksum.
+///
+/// # Errors
+///
+/// Returns an error if the address is not 40 hex digits or its checksum is wrong.
+pub fn checksum_address(address: &str) -> Result<String, String> {
+    let digits = address
+        .strip_prefix("0x")
+        .ok_or_else(|| format!("Address {} has no 0x prefix", address))?;
+    let mut bytes = [0u8; 20];
+    hex::decode_to_slice(digits, &mut bytes)
+        .map_err(|_| format!("Address {} is not 40 hex digits", address))?;
+    let checksummed = checksummed(&bytes);
+
+    let has_lower = digits.chars().any(|c| c.is_ascii_lowercase());
+    let has_upper = digits.chars().any(|c| c.is_ascii_uppercase());
+    if has_lower && has_upper && checksummed != address {
+        return Err(format!(
+            "Address {} has an invalid EIP-55 checksum",
+            address
+        ));
+    }
+    Ok(checksummed)
+}
+
+/// Recovers the address that signed `message` with `personal_sign`.
+///
+/// # Arguments
+///
+/// * `message` - The signed text
+/// * `signature` - 65-byte `r || s || v` signature in hex, as returned by the wallet
+///
+/// # Returns
+///
+/// The signer's address in EIP-55 checksummed form.
+pub fn recover_personal_sign(message: &str, signature: &str) -> Result<String, String> {
+    let bytes = hex::decode(signature.strip_prefix("0x").unwrap_or(signature))
+        .map_err(|_| "Signature is not hex".to_string())?;
+    if bytes.len() != 65 {
+        return Err(format!("Signature is {} bytes, expected 65", bytes.len()));
+    }
+    let signature =
+        Signature::from_slice(&bytes[..64]).map_err(|e| format!("Invalid signature: {}", e))?;
+    // Wallets use v = 27/28; some hardware wallets return 0/1
+    let v = bytes[64];
+    let recovery_id = RecoveryId::from_byte(if v >= 27 { v - 27 } else { v })
+        .ok_or_else(|| format!("Invalid signature recovery byte {}", v))?;
+
+    // EIP-191 version 0x45: the prefixed message is what the wallet signs
+    let prefixed = format!("\x19Ethereum Signed Message:\n{}{}", message.len(), message);
+    let hash = Keccak256::digest(prefixed.as_bytes());
+    let key = VerifyingKey::recover_from_prehash(&hash, &signature, recovery_id)
+        .map_err(|_| "Signature does not match the message".to_string())?;
+
+    let point = key.to_encoded_point(false);
+    let hash = Keccak256::digest(&point.as_bytes()[1..]);
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&hash[12..]);
+    Ok(checksummed(&address))
+}
+
+/// The EIP-55 form of an address: a hex letter is uppercased when the matching
+/// nibble of the keccak-256 hash of the lowercase hex is 8 or more.
+fn checksummed(address: &[u8; 20]) -> String {
+    let lower = hex::encode(address);
+    let hash = Keccak256::digest(lower.as_bytes());
+    let digits: String = lower
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect();
+    format!("0x{}", digits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Address of the first anvil/hardhat development key
+    /// (`0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80`).
+    const ADDRESS: &str = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266";
+
+    /// `personal_sign("hello world")` by that key, as in viem's `signMessage` docs.
+    const HELLO_WORLD_SIGNATURE: &str = "0xa461f509887bd19e312c0c58467ce8ff8e300d3c1a90b608a760c5b80318eaf15fe57c96f9175d6cd4daad4663763baa7e78836e067d0163e9a2ccf2ff753f5b1b";
+
+    #[test]
+    fn recovers_the_signer_of_a_known_signature() {
+        assert_eq!(
+            recover_personal_sign("hello world", HELLO_WORLD_SIGNATURE).unwrap(),
+            ADDRESS
+        );
+
+        // v = 0/1 as returned by some hardware wallets
+        let v0 = format!("{}00", &HELLO_WORLD_SIGNATURE[..130]);
+        assert_eq!(recover_personal_sign("hello world", &v0).unwrap(), ADDRESS);
+    }
+
+    #[test]
+    fn tampered_signatures_do_not_recover_the_signer() {
+        // Another message, or a changed byte of the signature
+        let recovered = recover_personal_sign("hello world!", HELLO_WORLD_SIGNATURE);
+        assert_ne!(recovered.ok().as_deref(), Some(ADDRESS));
+        let tampered = HELLO_WORLD_SIGNATURE.replacen("a461", "a462", 1);
+        let recovered = recover_personal_sign("hello world", &tampered);
+        assert_ne!(recovered.ok().as_deref(), Some(ADDRESS));
+
+        assert!(recover_personal_sign("hello world", &HELLO_WORLD_SIGNATURE[..128]).is_err());
+        assert!(recover_personal_sign("hello world", "0xnothex").is_err());
+    }
+
+    #[test]
+    fn checksums_addresses() {
+        assert_eq!(checksum_address(&ADDRESS.to_lowercase()).unwrap(), ADDRESS);
+        assert_eq!(checksum_address(ADDRESS).unwrap(), ADDRESS);
+        assert!(checksum_address("0xF39Fd6e51aad88F6F4ce6aB8827279cffFb92266").is_err());
+        assert!(checksum_address("f39fd6e51aad88f6f4ce6ab8827279cfffb92266").is_err());
+    }
+
+    #[test]
+    fn parses_a_message() {
+        let message = "https://localhost:5173 wants you to sign in with your Ethereum account:\n\
+            0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266\n\
+            \n\
+            Sign in to the voice agent.\n\
+            \n\
+            URI: http://localhost:5173\n\
+            Version: 1\n\
+            Chain ID: 8453\n\
+            Nonce: kat4nonce01\n\
+            Issued At: 2024-01-01T00:00:00Z\n\
+            Expiration Time: 2024-01-02T00:00:00.000Z\n\
+            Resources:\n\
+            - https://example.com/Nonce: ignored";
+        let siwe = SiweMessage::parse(message).unwrap();
+        assert_eq!(siwe.domain, "localhost:5173");
+        assert_eq!(siwe.address, ADDRESS);
+        assert_eq!(siwe.chain_id, 8453);
+        assert_eq!(siwe.nonce, "kat4nonce01");
+        assert_eq!(siwe.issued_at.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+        assert!(siwe.expiration_time.is_some());
+        assert!(siwe.not_before.is_none());
+
+        assert!(SiweMessage::parse("hello world").is_err());
+        assert!(SiweMessage::parse(&message.replace("Nonce: kat4nonce01\n", "")).is_err());
+        assert!(SiweMessage::parse(&message.replace("2024-01-01T00:00:00Z", "yesterday")).is_err());
+    }
+}
@@ -0,0 +1,604 @@
+//! SQLite persistence for conversations.
+//!
+//! A conversation is identified by its MCP session ID. The MCP server only keeps
+//! the message history of a session in memory, for as long as it needs it for
+//! prompts, so every completed turn is stored here: the user's text (or the
+//! transcript of their audio), the agent's reply, where the reply's audio was
+//! stored and the processing metadata. The store backs the `/conversations`
+//! endpoints and `POST /conversations/{id}/mint`.
+//!
+//! Every conversation belongs to the wallet address that signed in to start it
+//! (see [`crate::auth`]); reads and deletes only see the caller's conversations.
+//!
+//! Each turn is minted at most once: its web3-minting job is recorded in `mints`,
+//! and asking to mint the turn again returns that job instead of a second token.
+
+use crate::models::{Conversation, ConversationSummary, Turn};
+use mcp_rpc::ProcessingMetadata;
+use rusqlite::{Connection, OptionalExtension, Row, params};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// How long a claimed mint may go without a job before the turn can be claimed
+/// again, e.g. after the server stopped while uploading the audio.
+const MINT_CLAIM_TIMEOUT: chrono::TimeDelta = chrono::TimeDelta::minutes(10);
+
+/// A completed turn, before it is stored.
+pub struct NewTurn {
+    /// Agent that replied
+    pub agent_id: String,
+    /// The user's text, or the transcript of their audio
+    pub user_text: String,
+    /// The agent's reply
+    pub reply_text: String,
+    /// Public URL path of the reply's audio, e.g. `/public/audio/<uuid>.mp3`
+    pub audio_url: String,
+    /// How the MCP server produced the reply
+    pub metadata: ProcessingMetadata,
+}
+
+/// Outcome of [`ConversationStore::claim_mint`].
+#[derive(Debug, PartialEq)]
+pub enum MintClaim {
+    /// The turn was not minted yet; the caller starts its mint job
+    Claimed,
+    /// Another request is starting the turn's mint job
+    Starting,
+    /// The turn's mint job
+    Started(String),
+}
+
+/// A mint job of a turn, as recorded by [`ConversationStore::set_mint_job`].
+pub struct TurnMint {
+    /// Index of the minted turn
+    pub turn: usize,
+    /// Where the turn's audio was uploaded for the mint
+    pub audio_url: String,
+}
+
+/// SQLite-backed store of conversations and their turns.
+///
+/// The connection is guarded by a `Mutex`; every operation is a few short
+/// statements, so requests do not wait on each other for long.
+pub struct ConversationStore {
+    conn: Mutex<Connection>,
+}
+
+impl ConversationStore {
+    /// Opens (or creates) the conversation database at the given path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be opened or the schema cannot be created.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS conversations (
+                id         TEXT PRIMARY KEY,
+                owner      TEXT NOT NULL,
+                agent_id   TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS turns (
+                conversation_id TEXT NOT NULL,
+                turn            INTEGER NOT NULL,
+                agent_id        TEXT NOT NULL,
+                user_text       TEXT NOT NULL,
+                reply_text      TEXT NOT NULL,
+                audio_url       TEXT NOT NULL,
+                metadata        TEXT NOT NULL,
+                created_at      TEXT NOT NULL,
+                PRIMARY KEY (conversation_id, turn)
+            );
+            CREATE TABLE IF NOT EXISTS mints (
+                conversation_id TEXT NOT NULL,
+                turn            INTEGER NOT NULL,
+                job_id          TEXT UNIQUE,
+                audio_url       TEXT,
+                claimed_at      TEXT NOT NULL,
+                PRIMARY KEY (conversation_id, turn)
+            );",
+        )
+        .map_err(|e| e.to_string())?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS conversations_owner ON conversations (owner, updated_at)",
+            [],
+        )
+        .map_err(|e| e.to_string())?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS turns_audio_url ON turns (audio_url)",
+            [],
+        )
+        .map_err(|e| e.to_string())?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Returns the wallet address that owns a conversation, or `None` if the
+    /// conversation does not exist.
+    pub fn owner(&self, conversation_id: &str) -> Result<Option<String>, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT owner FROM conversations WHERE id = ?1",
+            params![conversation_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    }
+
+    /// Appends a turn to a conversation, creating the conversation on its first turn.
+    ///
+    /// # Arguments
+    ///
+    /// * `conversation_id` - Session ID of the conversation
+    /// * `owner` - Wallet address the turn was made by
+    /// * `turn` - The turn to store
+    ///
+    /// # Returns
+    ///
+    /// The index of the turn in the conversation, starting at 0.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the conversation belongs to another wallet.
+    pub fn record(
+        &self,
+        conversation_id: &str,
+        owner: &str,
+        turn: &NewTurn,
+    ) -> Result<usize, String> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let metadata = serde_json::to_string(&turn.metadata).map_err(|e| e.to_string())?;
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let updated = tx
+            .execute(
+                "INSERT INTO conversations (id, owner, agent_id, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?4)
+                 ON CONFLICT(id) DO UPDATE SET agent_id = excluded.agent_id,
+                                               updated_at = excluded.updated_at
+                 WHERE conversations.owner = excluded.owner",
+                params![conversation_id, owner, turn.agent_id, now],
+            )
+            .map_err(|e| e.to_string())?;
+        if updated == 0 {
+            return Err(format!(
+                "Conversation {} belongs to another wallet",
+                conversation_id
+            ));
+        }
+        let index: usize = tx
+            .query_row(
+                "SELECT COUNT(*) FROM turns WHERE conversation_id = ?1",
+                params![conversation_id],
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())?;
+        tx.execute(
+            "INSERT INTO turns (conversation_id, turn, agent_id, user_text, reply_text, audio_url,
+                                metadata, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                conversation_id,
+                index,
+                turn.agent_id,
+                turn.user_text,
+                turn.reply_text,
+                turn.audio_url,
+                metadata,
+                now
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(index)
+    }
+
+    /// Lists a wallet's conversations, most recently active first.
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - Wallet address whose conversations are listed
+    /// * `limit` - Maximum number of conversations to return
+    /// * `offset` - Number of conversations to skip
+    pub fn list(
+        &self,
+        owner: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<ConversationSummary>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT c.id, c.agent_id, c.created_at, c.updated_at,
+                        (SELECT COUNT(*) FROM turns t WHERE t.conversation_id = c.id),
+                        (SELECT user_text FROM turns t WHERE t.conversation_id = c.id
+                         ORDER BY t.turn LIMIT 1)
+                 FROM conversations c
+                 WHERE c.owner = ?1
+                 ORDER BY c.updated_at DESC, c.id
+                 LIMIT ?2 OFFSET ?3",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![owner, limit, offset], |row| {
+                Ok(ConversationSummary {
+                    id: row.get(0)?,
+                    agent_id: row.get(1)?,
+                    created_at: row.get(2)?,
+                    updated_at: row.get(3)?,
+                    turn_count: row.get(4)?,
+                    preview: row.get::<_, Option<String>>(5)?.unwrap_or_default(),
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    /// Returns a conversation with all of its turns, or `None` if it does not exist
+    /// or belongs to another wallet.
+    pub fn get(&self, conversation_id: &str, owner: &str) -> Result<Option<Conversation>, String> {
+        let conn = self.conn.lock().unwrap();
+        let conversation = conn
+            .query_row(
+                "SELECT id, agent_id, created_at, updated_at FROM conversations
+                 WHERE id = ?1 AND owner = ?2",
+                params![conversation_id, owner],
+                |row| {
+                    Ok(Conversation {
+                        id: row.get(0)?,
+                        agent_id: row.get(1)?,
+                        created_at: row.get(2)?,
+                        updated_at: row.get(3)?,
+                        turns: Vec::new(),
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        let Some(mut conversation) = conversation else {
+            return Ok(None);
+        };
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT turn, agent_id, user_text, reply_text, audio_url, metadata, created_at
+                 FROM turns WHERE conversation_id = ?1 ORDER BY turn",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![conversation_id], read_turn)
+            .map_err(|e| e.to_string())?;
+        for row in rows {
+            conversation
+                .turns
+                .push(into_turn(row.map_err(|e| e.to_string())?)?);
+        }
+        Ok(Some(conversation))
+    }
+
+    /// Looks up a turn of a conversation.
+    ///
+    /// # Arguments
+    ///
+    /// * `conversation_id` - Session ID of the conversation
+    /// * `owner` - Wallet address that must own the conversation
+    /// * `index` - Index of the turn; the latest turn when `None`
+    ///
+    /// # Returns
+    ///
+    /// The turn, or `None` if the conversation or turn does not exist or the
+    /// conversation belongs to another wallet.
+    pub fn turn(
+        &self,
+        conversation_id: &str,
+        owner: &str,
+        index: Option<usize>,
+    ) -> Result<Option<Turn>, String> {
+        let conn = self.conn.lock().unwrap();
+        let owned = conn
+            .query_row(
+                "SELECT 1 FROM conversations WHERE id = ?1 AND owner = ?2",
+                params![conversation_id, owner],
+                |_| Ok(()),
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if owned.is_none() {
+            return Ok(None);
+        }
+        let row = match index {
+            Some(index) => conn
+                .query_row(
+                    "SELECT turn, agent_id, user_text, reply_text, audio_url, metadata, created_at
+                     FROM turns WHERE conversation_id = ?1 AND turn = ?2",
+                    params![conversation_id, index],
+                    read_turn,
+                )
+                .optional(),
+            None => conn
+                .query_row(
+                    "SELECT turn, agent_id, user_text, reply_text, audio_url, metadata, created_at
+                     FROM turns WHERE conversation_id = ?1 ORDER BY turn DESC LIMIT 1",
+                    params![conversation_id],
+                    read_turn,
+                )
+                .optional(),
+        }
+        .map_err(|e| e.to_string())?;
+        row.map(into_turn).transpose()
+    }
+
+    /// Claims a turn for minting, so it is minted only once.
+    ///
+    /// A claim without a job expires after [`MINT_CLAIM_TIMEOUT`]. The caller
+    /// records the job it starts with [`ConversationStore::set_mint_job`], or
+    /// gives the claim up with [`ConversationStore::release_mint`].
+    pub fn claim_mint(&self, conversation_id: &str, turn: usize) -> Result<MintClaim, String> {
+        let now = chrono::Utc::now();
+        let expired = (now - MINT_CLAIM_TIMEOUT).to_rfc3339();
+
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        tx.execute(
+            "DELETE FROM mints WHERE conversation_id = ?1 AND turn = ?2
+                                 AND job_id IS NULL AND claimed_at < ?3",
+            params![conversation_id, turn, expired],
+        )
+        .map_err(|e| e.to_string())?;
+        let claimed = tx
+            .execute(
+                "INSERT OR IGNORE INTO mints (conversation_id, turn, claimed_at)
+                 VALUES (?1, ?2, ?3)",
+                params![conversation_id, turn, now.to_rfc3339()],
+            )
+            .map_err(|e| e.to_string())?;
+        let claim = if claimed == 1 {
+            MintClaim::Claimed
+        } else {
+            let job_id: Option<String> = tx
+                .query_row(
+                    "SELECT job_id FROM mints WHERE conversation_id = ?1 AND turn = ?2",
+                    params![conversation_id, turn],
+                    |row| row.get(0),
+                )
+                .map_err(|e| e.to_string())?;
+            job_id.map_or(MintClaim::Starting, MintClaim::Started)
+        };
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(claim)
+    }
+
+    /// Claims a turn again for minting after its mint job `failed_job_id` failed.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the turn's mint job is no longer `failed_job_id`, i.e. another
+    /// request claimed it first.
+    pub fn reclaim_mint(
+        &self,
+        conversation_id: &str,
+        turn: usize,
+        failed_job_id: &str,
+    ) -> Result<bool, String> {
+        let conn = self.conn.lock().unwrap();
+        let reclaimed = conn
+            .execute(
+                "UPDATE mints SET job_id = NULL, audio_url = NULL, claimed_at = ?4
+                 WHERE conversation_id = ?1 AND turn = ?2 AND job_id = ?3",
+                params![
+                    conversation_id,
+                    turn,
+                    failed_job_id,
+                    chrono::Utc::now().to_rfc3339()
+                ],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(reclaimed == 1)
+    }
+
+    /// Records the mint job started for a claimed turn.
+    pub fn set_mint_job(
+        &self,
+        conversation_id: &str,
+        turn: usize,
+        job_id: &str,
+        audio_url: &str,
+    ) -> Result<(), String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE mints SET job_id = ?3, audio_url = ?4 WHERE conversation_id = ?1 AND turn = ?2",
+            params![conversation_id, turn, job_id, audio_url],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Gives up a claim that no mint job was started for.
+    pub fn release_mint(&self, conversation_id: &str, turn: usize) -> Result<(), String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM mints WHERE conversation_id = ?1 AND turn = ?2 AND job_id IS NULL",
+            params![conversation_id, turn],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Returns a mint job of a conversation's turn, or `None` if the job is not
+    /// one of the conversation's or the conversation belongs to another wallet.
+    pub fn mint(
+        &self,
+        conversation_id: &str,
+        owner: &str,
+        job_id: &str,
+    ) -> Result<Option<TurnMint>, String> {
+        let conn = self.conn.lock().unwrap();
+        conn.query_row(
+            "SELECT m.turn, m.audio_url FROM mints m
+             JOIN conversations c ON c.id = m.conversation_id
+             WHERE m.conversation_id = ?1 AND c.owner = ?2 AND m.job_id = ?3",
+            params![conversation_id, owner, job_id],
+            |row| {
+                Ok(TurnMint {
+                    turn: row.get(0)?,
+                    audio_url: row.get(1)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| e.to_string())
+    }
+
+    /// Deletes a conversation and its turns.
+    ///
+    /// # Returns
+    ///
+    /// The audio URLs of the deleted turns that no other turn shares (identical
+    /// replies share their audio), or `None` if the conversation did not exist or
+    /// belongs to another wallet.
+    pub fn delete(
+        &self,
+        conversation_id: &str,
+        owner: &str,
+    ) -> Result<Option<Vec<String>>, String> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction().map_err(|e| e.to_string())?;
+        let deleted = tx
+            .execute(
+                "DELETE FROM conversations WHERE id = ?1 AND owner = ?2",
+                params![conversation_id, owner],
+            )
+            .map_err(|e| e.to_string())?;
+        if deleted == 0 {
+            return Ok(None);
+        }
+        let audio_urls = {
+            let mut stmt = tx
+                .prepare(
+                    "SELECT DISTINCT audio_url FROM turns AS t WHERE conversation_id = ?1
+                     AND NOT EXISTS (SELECT 1 FROM turns WHERE audio_url = t.audio_url
+                                     AND conversation_id != ?1)",
+                )
+                .map_err(|e| e.to_string())?;
+            let rows = stmt
+                .query_map(params![conversation_id], |row| row.get(0))
+                .map_err(|e| e.to_string())?;
+            rows.collect::<Result<Vec<String>, _>>()
+                .map_err(|e| e.to_string())?
+        };
+        tx.execute(
+            "DELETE FROM turns WHERE conversation_id = ?1",
+            params![conversation_id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.execute(
+            "DELETE FROM mints WHERE conversation_id = ?1",
+            params![conversation_id],
+        )
+        .map_err(|e| e.to_string())?;
+        tx.commit().map_err(|e| e.to_string())?;
+        Ok(Some(audio_urls))
+    }
+}
+
+/// Columns of a `turns` row, before the metadata is decoded.
+type TurnRow = (usize, String, String, String, String, String, String);
+
+fn read_turn(row: &Row) -> rusqlite::Result<TurnRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+    ))
+}
+
+fn into_turn(row: TurnRow) -> Result<Turn, String> {
+    let (turn, agent_id, user_text, reply_text, audio_url, metadata, created_at) = row;
+    let metadata = serde_json::from_str(&metadata)
+        .map_err(|e| format!("Stored metadata of turn {} is corrupt: {}", turn, e))?;
+    Ok(Turn {
+        turn,
+        agent_id,
+        user_text,
+        reply_text,
+        audio_url,
+        metadata,
+        created_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store_with_turn() -> ConversationStore {
+        let store = ConversationStore::open(":memory:").unwrap();
+        let turn = NewTurn {
+            agent_id: "concierge".to_string(),
+            user_text: "Hi".to_string(),
+            reply_text: "Hello!".to_string(),
+            audio_url: "/public/audio/hello.mp3".to_string(),
+            metadata: ProcessingMetadata {
+                provider: "groq".to_string(),
+                model: "llama".to_string(),
+                tokens_used: None,
+                processing_time_ms: 1,
+                confidence: 1.0,
+            },
+        };
+        store.record("conv-1", "0xowner", &turn).unwrap();
+        store
+    }
+
+    #[test]
+    fn a_turn_is_minted_only_once() {
+        let store = store_with_turn();
+        assert_eq!(store.claim_mint("conv-1", 0).unwrap(), MintClaim::Claimed);
+        assert_eq!(store.claim_mint("conv-1", 0).unwrap(), MintClaim::Starting);
+
+        store
+            .set_mint_job("conv-1", 0, "job-1", "ipfs://audio")
+            .unwrap();
+        assert_eq!(
+            store.claim_mint("conv-1", 0).unwrap(),
+            MintClaim::Started("job-1".to_string())
+        );
+        let mint = store.mint("conv-1", "0xowner", "job-1").unwrap().unwrap();
+        assert_eq!((mint.turn, mint.audio_url.as_str()), (0, "ipfs://audio"));
+        assert!(store.mint("conv-1", "0xother", "job-1").unwrap().is_none());
+
+        // Only the request that sees the failed job claims the turn again
+        assert!(store.reclaim_mint("conv-1", 0, "job-1").unwrap());
+        assert!(!store.reclaim_mint("conv-1", 0, "job-1").unwrap());
+        assert_eq!(store.claim_mint("conv-1", 0).unwrap(), MintClaim::Starting);
+        assert!(store.mint("conv-1", "0xowner", "job-1").unwrap().is_none());
+    }
+
+    #[test]
+    fn released_and_expired_claims_can_be_claimed_again() {
+        let store = store_with_turn();
+        assert_eq!(store.claim_mint("conv-1", 0).unwrap(), MintClaim::Claimed);
+        store.release_mint("conv-1", 0).unwrap();
+        assert_eq!(store.claim_mint("conv-1", 0).unwrap(), MintClaim::Claimed);
+
+        let stale = (chrono::Utc::now() - MINT_CLAIM_TIMEOUT).to_rfc3339();
+        store
+            .conn
+            .lock()
+            .unwrap()
+            .execute("UPDATE mints SET claimed_at = ?1", params![stale])
+            .unwrap();
+        assert_eq!(store.claim_mint("conv-1", 0).unwrap(), MintClaim::Claimed);
+    }
+}
@@ -1,35 +1,66 @@
 //! Request handlers for all API endpoints.
 //!
 //! This module contains the handler functions for each API endpoint. All handlers
-//! integrate with external services including the MCP server and ElevenLabs APIs.
-//!
-//! # ElevenLabs Integration
-//!
-//! - **TTS Model**: `eleven_multilingual_v2` (free tier compatible)
-//! - **STT Model**: `scribe_v1` (only supported model)
-//! - **Voice**: Rachel (ID: `21m00Tcm4TlvDq8ikWAM`)
+//! integrate with external services: the MCP server, and the speech-to-text and
+//! text-to-speech providers configured in [`crate::speech`].
 //!
 //! # Handler Functions
 //!
 //! - [`get_agents_list`] - Retrieves available agents from MCP server
+//! - [`serve_audio`] - Serves a generated audio file from `AUDIO_DIR`
+//! - [`get_nonce`] - Issues a nonce for a Sign-In with Ethereum message
+//! - [`verify_signature`] - Verifies a signed SIWE message and issues a session token
 //! - [`handle_text_input`] - Processes text input through MCP and generates audio via TTS
+//! - [`handle_text_input_stream`] - Streams the agent reply as Server-Sent Events, then generates audio
 //! - [`handle_audio_input`] - Transcribes audio via STT, processes through MCP, and generates audio response
+//! - [`list_conversations`] - Lists stored conversations, most recently active first
+//! - [`get_conversation`] - Returns a stored conversation with all of its turns
+//! - [`delete_conversation`] - Deletes a conversation, its audio files and its MCP session
+//! - [`mint_conversation`] - Starts minting a conversation turn, with its audio, as an NFT through web3-minting
+//! - [`get_conversation_mint`] - Returns the progress of a conversation turn's mint job
+//! - [`create_api_key`] - Creates an API key for the signed-in wallet
+//! - [`list_api_keys`] - Lists the signed-in wallet's API keys
+//! - [`delete_api_key`] - Revokes an API key
+//! - [`get_usage`] - Returns the caller's usage of today's quotas
+//!
+//! Every handler except [`get_agents_list`], [`serve_audio`] and the `/auth`
+//! handlers takes an [`AuthUser`]: the request must carry a session token or an
+//! API key, and conversations are stored under, and only visible to, the wallet
+//! it belongs to.
 
 use crate::AppState;
+use crate::audio;
+use crate::auth::AuthUser;
+use crate::conversations::{MintClaim, NewTurn};
+use crate::minting::{MintAttribute, MintRequest};
 use crate::models::{
-    AgentInfo, AgentReplyResponse, InputTextRequest, JsonRpcRequest, JsonRpcResponse, 
-    ListAgentsResult, ProcessTextResult,
+    AgentInfo, AgentReplyResponse, ApiKeyInfo, Conversation, ConversationSummary,
+    CreateApiKeyRequest, CreateApiKeyResponse, InputTextRequest, ListConversationsQuery,
+    MintConversationRequest, MintConversationResponse, MintStatusResponse, NonceResponse,
+    StreamError, Turn, UsageResponse, VerifyRequest, VerifyResponse,
 };
+use crate::speech;
 use axum::{
     Json,
-    extract::{Multipart, State},
+    extract::{Multipart, Path, Query, Request, State},
     http::StatusCode,
+    response::{
+        Response,
+        sse::{Event, KeepAlive, Sse},
+    },
 };
-use std::path::PathBuf;
+use futures_util::{Stream, StreamExt};
+use mcp_rpc::{ClientError, Message, ProcessTextParams, StreamDelta};
+use std::convert::Infallible;
 use std::sync::Arc;
-use tokio::fs::File;
-use tokio::io::AsyncWriteExt;
-use uuid::Uuid;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Number of conversations `GET /conversations` returns without a `limit`.
+const DEFAULT_CONVERSATIONS_LIMIT: usize = 50;
+
+/// Largest `limit` accepted by `GET /conversations`.
+const MAX_CONVERSATIONS_LIMIT: usize = 200;
 
 /// Retrieves a list of all available AI agents from the MCP server.
 ///
@@ -38,7 +69,7 @@ use uuid::Uuid;
 ///
 /// # Arguments
 ///
-/// * `state` - Shared application state containing the HTTP client
+/// * `state` - Shared application state containing the MCP client
 ///
 /// # Returns
 ///
@@ -47,14 +78,8 @@ use uuid::Uuid;
 ///
 /// # Errors
 ///
-/// Returns `INTERNAL_SERVER_ERROR` if:
-/// - The MCP server is unreachable
-/// - The MCP server returns an error response
-/// - Response deserialization fails
-///
-/// # Environment Variables
-///
-/// Requires `MCP_SERVER_URL` to be set.
+/// Returns `INTERNAL_SERVER_ERROR` if the MCP server is unreachable or returns
+/// an error (see [`mcp_error`]).
 ///
 /// # Example Response
 ///
@@ -72,58 +97,168 @@ pub async fn get_agents_list(
 ) -> Result<Json<Vec<AgentInfo>>, (StatusCode, Json<String>)> {
     tracing::info!("Handler called: get_agents_list (REAL)");
 
-    let mcp_url = std::env::var("MCP_SERVER_URL").expect("MCP_SERVER_URL not set");
+    let agents = state
+        .mcp
+        .list_agents()
+        .await
+        .map_err(|e| mcp_error("list_agents", e))?;
 
-    let rpc_request = JsonRpcRequest {
-        jsonrpc: "2.0",
-        method: "list_agents",
-        params: serde_json::json!({}),
-        id: 1,
-    };
+    tracing::info!("Got {} agents from MCP", agents.len());
+    Ok(Json(agents.into_iter().map(AgentInfo::from).collect()))
+}
 
-    let mcp_response = state
-        .http_client
-        .post(mcp_url)
-        .json(&rpc_request)
-        .send()
-        .await;
-
-    match mcp_response {
-        Ok(response) => {
-            if response.status().is_success() {
-                let rpc_response: JsonRpcResponse<ListAgentsResult> = response.json().await.unwrap();
-
-                tracing::info!("Got {} agents from MCP", rpc_response.result.agents.len());
-                Ok(Json(rpc_response.result.agents))
-            } else {
-                tracing::error!("MCP /list_agents returned error: {:?}", response.status());
-                Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json("Error from MCP service".to_string()),
-                ))
-            }
-        }
-        Err(e) => {
-            tracing::error!("Failed to call MCP /list_agents: {:?}", e);
-            Err((
+/// Serves a stored audio file from the audio store.
+///
+/// Only files the store knows are served. With the local backend, serving one
+/// counts as using it for LRU eviction; with S3 the object is proxied, or the
+/// client is redirected to a presigned URL (see [`crate::audio`]). Range requests
+/// are supported, so audio players can seek.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the audio store
+/// * `filename` - Name of the file, as in the `audio_url` of a reply
+/// * `request` - The request, for its `Range` and conditional headers
+///
+/// # Returns
+///
+/// * `Ok(Response)` - The file, with a `Content-Type` from its extension
+/// * `Err((StatusCode, Json<String>))` - Error message with appropriate status code
+///
+/// # Errors
+///
+/// Returns `NOT_FOUND` if the file is not stored, e.g. because it expired.
+///
+/// # Example Request
+///
+/// `GET /public/audio/9f86d081884c7d659a2feaa0c55ad015a3bf4f1b2b0b822cd15d6c15b0f00a08.mp3`
+pub async fn serve_audio(
+    State(state): State<Arc<AppState>>,
+    Path(filename): Path<String>,
+    request: Request,
+) -> Result<Response, (StatusCode, Json<String>)> {
+    state
+        .audio
+        .serve(&filename, request)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to serve audio file {}: {}", filename, e);
+            (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json("Failed to call MCP service".to_string()),
-            ))
-        }
-    }
+                Json("Failed to read audio file".to_string()),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(format!("Audio file {} not found", filename)),
+            )
+        })
+}
+
+/// Issues a nonce for a Sign-In with Ethereum (EIP-4361) message.
+///
+/// The client puts the nonce in the SIWE message it asks the wallet to sign; it
+/// can be used once, within 10 minutes.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the authenticator
+///
+/// # Returns
+///
+/// * `Json<NonceResponse>` - The nonce and the domain the message must be addressed to
+///
+/// # Example Response
+///
+/// ```json
+/// {
+///   "nonce": "4f3c2b1a0e9d8c7b6a5f4e3d2c1b0a99",
+///   "domain": "localhost:5173"
+/// }
+/// ```
+pub async fn get_nonce(State(state): State<Arc<AppState>>) -> Json<NonceResponse> {
+    tracing::info!("Handler called: get_nonce");
+
+    Json(NonceResponse {
+        nonce: state.auth.issue_nonce(),
+        domain: state.auth.domain().to_string(),
+    })
+}
+
+/// Verifies a signed SIWE message and issues a session token.
+///
+/// This handler:
+/// 1. Parses the EIP-4361 message and checks its domain, version, validity
+///    period and nonce
+/// 2. Recovers the signer from the `personal_sign` signature and checks it is the
+///    address named in the message
+/// 3. Issues a session JWT for the address
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the authenticator
+/// * `payload` - JSON payload with the signed message and the signature
+///
+/// # Returns
+///
+/// * `Ok(Json<VerifyResponse>)` - The session token, address and expiry on success
+/// * `Err((StatusCode, Json<String>))` - Error message with appropriate status code
+///
+/// # Errors
+///
+/// Returns `UNAUTHORIZED` with the reason if any check fails.
+///
+/// # Environment Variables
+///
+/// - `SIWE_DOMAIN` - Domain messages must be addressed to (optional, defaults to "localhost:5173")
+/// - `JWT_SECRET` - Key that signs session tokens (optional, random per start when unset)
+/// - `SESSION_TTL_SECS` - Lifetime of session tokens (optional, defaults to 86400)
+///
+/// # Response Example
+///
+/// ```json
+/// {
+///   "token": "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9...",
+///   "address": "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+///   "expires_at": "2026-10-18T09:30:00+00:00"
+/// }
+/// ```
+pub async fn verify_signature(
+    State(state): State<Arc<AppState>>,
+    Json(payload): Json<VerifyRequest>,
+) -> Result<Json<VerifyResponse>, (StatusCode, Json<String>)> {
+    tracing::info!("Handler called: verify_signature");
+
+    let session = state
+        .auth
+        .verify(&payload.message, &payload.signature)
+        .map_err(|e| {
+            tracing::info!("Sign-in rejected: {}", e);
+            (StatusCode::UNAUTHORIZED, Json(e))
+        })?;
+    tracing::info!("Signed in {}", session.address);
+
+    Ok(Json(VerifyResponse {
+        token: session.token,
+        address: session.address,
+        expires_at: session.expires_at,
+    }))
 }
 
 /// Processes text input through the MCP agent and generates an audio response.
 ///
 /// This handler orchestrates a multi-step process:
-/// 1. Sends user text to the MCP server for agent processing
+/// 1. Sends user text to the MCP server for agent processing, starting a new
+///    MCP session when the request does not continue an existing one
 /// 2. Receives the agent's text response
 /// 3. Converts the response to audio using TTS API
 /// 4. Returns both text and audio URL to the client
 ///
 /// # Arguments
 ///
-/// * `state` - Shared application state containing the HTTP client
+/// * `state` - Shared application state containing the MCP client
+/// * `user` - The signed-in wallet; the conversation is stored under its address
 /// * `payload` - JSON payload containing agent_id and user_text
 ///
 /// # Returns
@@ -133,9 +268,17 @@ pub async fn get_agents_list(
 ///
 /// # Errors
 ///
+/// Returns `UNAUTHORIZED` without a valid session token or API key.
+///
+/// Returns `TOO_MANY_REQUESTS` over the rate limit or the daily quotas (see [`crate::limits`]).
+///
+/// Returns `BAD_REQUEST` if the MCP server rejects the request (e.g. unknown agent).
+///
+/// Returns `NOT_FOUND` if `session_id` names another wallet's conversation.
+///
 /// Returns `INTERNAL_SERVER_ERROR` if:
 /// - The MCP server is unreachable or returns an error
-/// - OpenAI TTS API fails or returns an error
+/// - The text-to-speech provider fails or returns an error
 /// - Audio file cannot be created or written
 /// - Any response deserialization fails
 ///
@@ -143,15 +286,17 @@ pub async fn get_agents_list(
 ///
 /// Requires:
 /// - `MCP_SERVER_URL` - URL of the MCP server
-/// - `ELEVENLABS_API_KEY` - ElevenLabs API key
-/// - `AUDIO_DIR` - Directory for storing audio files (optional, defaults to "public/audio")
+/// - `STT_PROVIDER` / `TTS_PROVIDER` - Speech providers (see [`crate::speech`])
+/// - `AUDIO_DIR` - Directory for storing audio files (optional, defaults to "public/audio";
+///   see [`crate::audio`] for its limits)
 ///
 /// # Request Example
 ///
 /// ```json
 /// {
 ///   "agent_id": "agent_001",
-///   "user_text": "Hello, how are you?"
+///   "user_text": "Hello, how are you?",
+///   "session_id": "5f0c6a8e-3f51-4c1e-9d3b-2a7f4f3b9c11"
 /// }
 /// ```
 ///
@@ -160,11 +305,13 @@ pub async fn get_agents_list(
 /// ```json
 /// {
 ///   "reply_text": "I'm doing great! How can I help you?",
-///   "audio_url": "https://example.com/audio/response.mp3"
+///   "audio_url": "https://example.com/audio/response.mp3",
+///   "session_id": "5f0c6a8e-3f51-4c1e-9d3b-2a7f4f3b9c11"
 /// }
 /// ```
 pub async fn handle_text_input(
     State(state): State<Arc<AppState>>,
+    user: AuthUser,
     Json(payload): Json<InputTextRequest>,
 ) -> Result<(StatusCode, Json<AgentReplyResponse>), (StatusCode, Json<String>)> {
     tracing::info!(
@@ -172,159 +319,168 @@ pub async fn handle_text_input(
         payload.agent_id
     );
 
-    let mcp_url = std::env::var("MCP_SERVER_URL").expect("MCP_SERVER_URL not set");
+    let session_id = resolve_session(&state, &user, payload.session_id, &payload.agent_id).await?;
 
-    let rpc_request = JsonRpcRequest {
-        jsonrpc: "2.0",
-        method: "process_text",
-        params: serde_json::json!({
-            "agent_id": payload.agent_id,
-            "user_text": payload.user_text,
-        }),
-        id: 1,
-    };
+    let params = ProcessTextParams::new(payload.agent_id.clone(), payload.user_text.clone())
+        .with_session(session_id.clone());
+    let result = state
+        .mcp
+        .process_text(&params)
+        .await
+        .map_err(|e| mcp_error("process_text", e))?;
+    let agent_reply_text = result.reply_text;
+    tracing::info!("Got agent reply from MCP: {}", agent_reply_text);
 
-    let mcp_response = state
-        .http_client
-        .post(mcp_url)
-        .json(&rpc_request)
-        .send()
-        .await;
-
-    let agent_reply_text: String = match mcp_response {
-        Ok(response) => {
-            if response.status().is_success() {
-                let response_text = response.text().await.unwrap();
-                tracing::info!("MCP response: {}", response_text);
-                
-                match serde_json::from_str::<JsonRpcResponse<ProcessTextResult>>(&response_text) {
-                    Ok(rpc_response) => {
-                        let reply = rpc_response.result.reply_text;
-                        tracing::info!("Got agent reply from MCP: {}", reply);
-                        reply
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to parse MCP response: {:?}", e);
-                        tracing::error!("Raw response was: {}", response_text);
-                        return Err((
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json("Error parsing MCP response".to_string()),
-                        ));
-                    }
-                }
-            } else {
-                tracing::error!("MCP /process_text returned error: {:?}", response.status());
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json("Error from MCP service".to_string()),
-                ));
-            }
-        }
-        Err(e) => {
-            tracing::error!("Failed to call MCP /process_text: {:?}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json("Failed to call MCP service".to_string()),
-            ));
-        }
+    let audio_url = synthesize_and_store(&state, &user, &agent_reply_text).await?;
+
+    let turn = record_turn(
+        &state,
+        &user,
+        &session_id,
+        NewTurn {
+            agent_id: payload.agent_id,
+            user_text: payload.user_text,
+            reply_text: agent_reply_text.clone(),
+            audio_url: audio_url.clone(),
+            metadata: result.metadata,
+        },
+    )?;
+
+    let final_reply = AgentReplyResponse {
+        reply_text: agent_reply_text,
+        audio_url: state.audio.public_url(&audio_url),
+        session_id,
+        turn,
     };
+    Ok((StatusCode::CREATED, Json(final_reply)))
+}
 
-    tracing::info!("Calling ElevenLabs TTS API for agent's reply");
+/// Streams the agent's reply to text input as Server-Sent Events.
+///
+/// This is the streaming counterpart of [`handle_text_input`]. The reply is
+/// forwarded token by token from the MCP server's `/process_text/stream`
+/// endpoint, so clients can render it while it is being generated. Once the
+/// reply is complete it is converted to audio as usual.
+///
+/// # Events
+///
+/// - `delta` - `{"text": "..."}` for each fragment of the reply
+/// - `done` - the final `AgentReplyResponse` (reply text, audio URL, session ID)
+/// - `error` - `{"message": "..."}` if any step fails; no further events follow
+///
+/// Requests without a valid session token or API key are rejected with
+/// `UNAUTHORIZED`, and requests over the rate limit or the daily quotas with
+/// `TOO_MANY_REQUESTS`, before the stream starts.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the MCP client
+/// * `user` - The signed-in wallet; the conversation is stored under its address
+/// * `payload` - JSON payload containing agent_id, user_text and optional session_id
+///
+/// # Environment Variables
+///
+/// Requires:
+/// - `MCP_SERVER_URL` - URL of the MCP server
+/// - `STT_PROVIDER` / `TTS_PROVIDER` - Speech providers (see [`crate::speech`])
+pub async fn handle_text_input_stream(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(payload): Json<InputTextRequest>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    tracing::info!(
+        "Handler called: handle_text_input_stream for agent: {}",
+        payload.agent_id
+    );
 
-    // ElevenLabs TTS API - using default voice "Rachel" (21m00Tcm4TlvDq8ikWAM)
-    let tts_url = "https://api.elevenlabs.io/v1/text-to-speech/21m00Tcm4TlvDq8ikWAM";
-    
-    let tts_payload = serde_json::json!({
-        "text": agent_reply_text,
-        "model_id": "eleven_multilingual_v2",
-        "output_format": "mp3_44100_128"
+    let (event_tx, event_rx) = mpsc::channel::<Event>(32);
+    tokio::spawn(async move {
+        let event = match stream_text_reply(&state, &user, payload, &event_tx).await {
+            Ok(reply) => json_event("done", &reply),
+            Err((_, Json(message))) => json_event("error", &StreamError { message }),
+        };
+        let _ = event_tx.send(event).await;
     });
 
-    let tts_response = state
-        .http_client
-        .post(tts_url)
-        .header("xi-api-key", &state.elevenlabs_api_key)
-        .header("Content-Type", "application/json")
-        .json(&tts_payload)
-        .send()
-        .await;
-
-    let audio_bytes = match tts_response {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.bytes().await {
-                    Ok(bytes) => bytes.to_vec(),
-                    Err(e) => {
-                        tracing::error!("Failed to read TTS audio bytes: {:?}", e);
-                        return Err((
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json("Failed to read TTS audio".to_string()),
-                        ));
-                    }
-                }
-            } else {
-                let status = response.status();
-                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-                tracing::error!("ElevenLabs TTS API error {}: {}", status, error_text);
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(format!("Error from TTS service: {}", error_text)),
-                ));
+    Sse::new(ReceiverStream::new(event_rx).map(Ok)).keep_alive(KeepAlive::default())
+}
+
+/// Proxies a streamed reply from the MCP server, then synthesizes its audio.
+///
+/// Every `delta` event from the MCP server is forwarded to `event_tx` as it arrives.
+async fn stream_text_reply(
+    state: &AppState,
+    user: &AuthUser,
+    payload: InputTextRequest,
+    event_tx: &mpsc::Sender<Event>,
+) -> Result<AgentReplyResponse, (StatusCode, Json<String>)> {
+    let session_id = resolve_session(state, user, payload.session_id, &payload.agent_id).await?;
+
+    let params = ProcessTextParams::new(payload.agent_id.clone(), payload.user_text.clone())
+        .with_session(session_id.clone());
+
+    // Forward each fragment to the client while the MCP stream is read
+    let (delta_tx, mut delta_rx) = mpsc::channel::<String>(32);
+    let forward = async {
+        while let Some(text) = delta_rx.recv().await {
+            if event_tx
+                .send(json_event("delta", &StreamDelta { text }))
+                .await
+                .is_err()
+            {
+                tracing::info!("Client disconnected from reply stream");
+                break;
             }
         }
-        Err(e) => {
-            tracing::error!("Failed to call ElevenLabs TTS API: {:?}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json("Failed to call TTS service".to_string()),
-            ));
-        }
     };
+    let (result, ()) = tokio::join!(state.mcp.process_text_stream(&params, delta_tx), forward);
+    let result = result.map_err(|e| mcp_error("process_text/stream", e))?;
+    tracing::info!("Got streamed agent reply from MCP: {}", result.reply_text);
 
-    let filename = format!("{}.mp3", Uuid::new_v4());
-    let filepath = PathBuf::from(&state.audio_dir).join(&filename);
-
-    match File::create(&filepath).await {
-        Ok(mut file) => {
-            if let Err(e) = file.write_all(&audio_bytes).await {
-                tracing::error!("Failed to write audio file: {:?}", e);
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json("Failed to save audio file".to_string()),
-                ));
-            }
-        }
-        Err(e) => {
-            tracing::error!("Failed to create audio file: {:?}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json("Failed to create audio file".to_string()),
-            ));
-        }
-    }
+    let audio_url = synthesize_and_store(state, user, &result.reply_text).await?;
 
-    let audio_url = format!("/public/audio/{}", filename);
-    tracing::info!("Audio saved to: {}", audio_url);
+    let turn = record_turn(
+        state,
+        user,
+        &session_id,
+        NewTurn {
+            agent_id: payload.agent_id,
+            user_text: payload.user_text,
+            reply_text: result.reply_text.clone(),
+            audio_url: audio_url.clone(),
+            metadata: result.metadata,
+        },
+    )?;
 
-    let final_reply = AgentReplyResponse {
-        reply_text: agent_reply_text,
-        audio_url,
-    };
-    Ok((StatusCode::CREATED, Json(final_reply)))
+    Ok(AgentReplyResponse {
+        reply_text: result.reply_text,
+        audio_url: state.audio.public_url(&audio_url),
+        session_id,
+        turn,
+    })
+}
+
+/// Builds a named SSE event with a JSON payload.
+fn json_event<T: serde::Serialize>(name: &str, data: &T) -> Event {
+    Event::default()
+        .event(name)
+        .data(serde_json::to_string(data).unwrap())
 }
 
 /// Processes audio input through the complete STT → MCP → TTS pipeline.
 ///
 /// This handler orchestrates the full audio processing pipeline:
 /// 1. Receives audio file from client via multipart form data
-/// 2. Transcribes audio to text using STT API
+/// 2. Charges its estimated duration to the caller's daily speech-to-text quota,
+///    and transcribes it to text using STT API
 /// 3. Sends transcribed text to MCP agent for processing
 /// 4. Converts agent's response to audio using TTS API
 /// 5. Returns both text and audio URL to the client
 ///
 /// # Arguments
 ///
-/// * `state` - Shared application state containing the HTTP client
+/// * `state` - Shared application state containing the MCP client
+/// * `user` - The signed-in wallet; the conversation is stored under its address
 /// * `multipart` - Multipart form data containing audio file and agent_id
 ///
 /// # Returns
@@ -334,39 +490,52 @@ pub async fn handle_text_input(
 ///
 /// # Errors
 ///
+/// Returns `UNAUTHORIZED` without a valid session token or API key.
+///
+/// Returns `TOO_MANY_REQUESTS` over the rate limit or the daily quotas (see
+/// [`crate::limits`]), including when the upload's estimated duration would
+/// exceed the remaining speech-to-text quota.
+///
 /// Returns `BAD_REQUEST` if:
 /// - Required form fields are missing (audio_file or agent_id)
+/// - The MCP server rejects the request (e.g. unknown agent)
+///
+/// Returns `NOT_FOUND` if `session_id` names another wallet's conversation.
 ///
 /// Returns `INTERNAL_SERVER_ERROR` if:
-/// - OpenAI Whisper API fails or returns an error
+/// - The speech-to-text provider fails or returns an error
 /// - The MCP server is unreachable or returns an error
-/// - OpenAI TTS API fails or returns an error
+/// - The text-to-speech provider fails or returns an error
 /// - Audio file cannot be created or written
 /// - Any response deserialization fails
 ///
 /// # Environment Variables
 ///
 /// Requires:
-/// - `ELEVENLABS_API_KEY` - ElevenLabs API key
+/// - `STT_PROVIDER` / `TTS_PROVIDER` - Speech providers (see [`crate::speech`])
 /// - `MCP_SERVER_URL` - URL of the MCP server
-/// - `AUDIO_DIR` - Directory for storing audio files (optional, defaults to "public/audio")
+/// - `AUDIO_DIR` - Directory for storing audio files (optional, defaults to "public/audio";
+///   see [`crate::audio`] for its limits)
 ///
 /// # Request Format
 ///
 /// Multipart form data with fields:
 /// - `audio_file`: Audio file (MP3, WAV, or other supported formats)
 /// - `agent_id`: String identifying the target agent
+/// - `session_id`: Optional session returned by a previous reply
 ///
 /// # Response Example
 ///
 /// ```json
 /// {
 ///   "reply_text": "I heard you say: Hello. Here's my response...",
-///   "audio_url": "https://example.com/audio/response.mp3"
+///   "audio_url": "https://example.com/audio/response.mp3",
+///   "session_id": "5f0c6a8e-3f51-4c1e-9d3b-2a7f4f3b9c11"
 /// }
 /// ```
 pub async fn handle_audio_input(
     State(state): State<Arc<AppState>>,
+    user: AuthUser,
     mut multipart: Multipart,
 ) -> Result<(StatusCode, Json<AgentReplyResponse>), (StatusCode, Json<String>)> {
     tracing::info!("Handler called: handle_audio_input (REAL)");
@@ -374,6 +543,7 @@ pub async fn handle_audio_input(
     let mut audio_data: Option<Vec<u8>> = None;
     let mut agent_id: Option<String> = None;
     let mut filename: Option<String> = None;
+    let mut session_id: Option<String> = None;
     
     while let Some(field) = multipart.next_field().await.unwrap() {
         let name = field.name().unwrap_or("unknown").to_string();
@@ -382,6 +552,8 @@ pub async fn handle_audio_input(
             audio_data = Some(field.bytes().await.unwrap().to_vec());
         } else if name == "agent_id" {
             agent_id = Some(field.text().await.unwrap());
+        } else if name == "session_id" {
+            session_id = Some(field.text().await.unwrap()).filter(|s| !s.is_empty());
         }
     }
     
@@ -396,204 +568,975 @@ pub async fn handle_audio_input(
     };
     tracing::info!("Got agent_id: {} and audio file", agent_id);
 
-    tracing::info!("Calling ElevenLabs Speech-to-Text API...");
-
     let original_filename = filename.unwrap_or_else(|| "audio.mp3".to_string());
 
-    // ElevenLabs STT API
-    let stt_url = "https://api.elevenlabs.io/v1/speech-to-text";
-    
-    let form = reqwest::multipart::Form::new()
-        .part("file", reqwest::multipart::Part::bytes(audio_data.clone())
-            .file_name(original_filename)
-            .mime_str("audio/mpeg").unwrap()
-        )
-        .text("model_id", "scribe_v1")
-        .text("language_code", "eng")
-        .text("tag_audio_events", "true");
-
-    let stt_response = state
-        .http_client
-        .post(stt_url)
-        .header("xi-api-key", &state.elevenlabs_api_key)
-        .multipart(form)
-        .send()
-        .await;
-
-    let user_text = match stt_response {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.json::<serde_json::Value>().await {
-                    Ok(json) => {
-                        let text = json["text"].as_str().unwrap_or("").to_string();
-                        tracing::info!("ElevenLabs transcribed text: {}", text);
-                        text
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to parse STT response: {:?}", e);
-                        return Err((
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json("Failed to parse STT response".to_string()),
-                        ));
-                    }
-                }
-            } else {
-                let status = response.status();
-                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-                tracing::error!("ElevenLabs STT API error {}: {}", status, error_text);
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(format!("Error from STT service: {}", error_text)),
-                ));
-            }
+    let seconds = speech::duration::estimate_secs(&audio_data);
+    let charged = state
+        .limits
+        .quotas
+        .try_charge_stt(&user.subject(), seconds)
+        .map_err(usage_error)?;
+    if !charged {
+        return Err((
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(format!(
+                "Daily speech-to-text quota of {} seconds would be exceeded by this {:.0}-second upload",
+                state.limits.quotas.stt_seconds, seconds
+            )),
+        ));
+    }
+
+    tracing::info!("Calling {} speech-to-text...", state.stt.name());
+
+    let user_text = match state.stt.transcribe(audio_data, &original_filename).await {
+        Ok(text) => {
+            tracing::info!("Transcribed text: {}", text);
+            text
         }
         Err(e) => {
-            tracing::error!("Failed to call ElevenLabs STT API: {:?}", e);
+            tracing::error!("Speech-to-text failed: {}", e);
             return Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json("Failed to call STT service".to_string()),
+                Json(format!("Error from STT service: {}", e)),
             ));
         }
     };
 
-    let mcp_url = std::env::var("MCP_SERVER_URL").expect("MCP_SERVER_URL not set");
+    let session_id = resolve_session(&state, &user, session_id, &agent_id).await?;
+
     tracing::info!("Calling MCP /process_text...");
 
-    let rpc_request = JsonRpcRequest {
-        jsonrpc: "2.0",
-        method: "process_text",
-        params: serde_json::json!({
-            "agent_id": agent_id,
-            "user_text": user_text,
-        }),
-        id: 1,
+    let params = ProcessTextParams::new(agent_id.clone(), user_text.clone())
+        .with_session(session_id.clone());
+    let result = state
+        .mcp
+        .process_text(&params)
+        .await
+        .map_err(|e| mcp_error("process_text", e))?;
+    let agent_reply_text = result.reply_text;
+    tracing::info!("Got agent reply from MCP: {}", agent_reply_text);
+
+    let audio_url = synthesize_and_store(&state, &user, &agent_reply_text).await?;
+
+    let turn = record_turn(
+        &state,
+        &user,
+        &session_id,
+        NewTurn {
+            agent_id,
+            user_text,
+            reply_text: agent_reply_text.clone(),
+            audio_url: audio_url.clone(),
+            metadata: result.metadata,
+        },
+    )?;
+
+    let final_reply = AgentReplyResponse {
+        reply_text: agent_reply_text,
+        audio_url: state.audio.public_url(&audio_url),
+        session_id,
+        turn,
     };
+    Ok((StatusCode::CREATED, Json(final_reply)))
+}
+
+/// Lists stored conversations, most recently active first.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the conversation store
+/// * `user` - The signed-in wallet; only its conversations are listed
+/// * `query` - Optional `limit` (default 50, at most 200) and `offset` for paging
+///
+/// # Returns
+///
+/// * `Ok(Json<Vec<ConversationSummary>>)` - One summary per conversation
+/// * `Err((StatusCode, Json<String>))` - Error message with appropriate status code
+///
+/// # Errors
+///
+/// Returns `UNAUTHORIZED` without a valid session token, and `INTERNAL_SERVER_ERROR`
+/// if the store fails.
+///
+/// # Example Request
+///
+/// `GET /conversations?limit=20&offset=40`
+pub async fn list_conversations(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Query(query): Query<ListConversationsQuery>,
+) -> Result<Json<Vec<ConversationSummary>>, (StatusCode, Json<String>)> {
+    tracing::info!("Handler called: list_conversations");
+
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_CONVERSATIONS_LIMIT)
+        .min(MAX_CONVERSATIONS_LIMIT);
+    let offset = query.offset.unwrap_or(0);
+    let conversations = state
+        .conversations
+        .list(&user.address, limit, offset)
+        .map_err(store_error)?;
+    Ok(Json(conversations))
+}
 
-    let mcp_response = state
-        .http_client
-        .post(mcp_url)
-        .json(&rpc_request)
-        .send()
-        .await;
-
-    let agent_reply_text: String = match mcp_response {
-        Ok(response) => {
-            if response.status().is_success() {
-                let response_text = response.text().await.unwrap_or_default();
-                tracing::info!("MCP response: {}", response_text);
-                
-                match serde_json::from_str::<JsonRpcResponse<ProcessTextResult>>(&response_text) {
-                    Ok(rpc_response) => {
-                        let reply = rpc_response.result.reply_text;
-                        tracing::info!("Got agent reply from MCP: {}", reply);
-                        reply
-                    }
-                    Err(e) => {
-                        tracing::error!("Failed to parse MCP response: {:?}", e);
-                        tracing::error!("Raw response was: {}", response_text);
-                        return Err((
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json("Error parsing MCP response".to_string()),
-                        ));
-                    }
-                }
-            } else {
-                tracing::error!("MCP /process_text returned error: {:?}", response.status());
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json("Error from MCP service".to_string()),
-                ));
+/// Returns a stored conversation with all of its turns.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the conversation store
+/// * `user` - The signed-in wallet, which must own the conversation
+/// * `conversation_id` - Session ID of the conversation
+///
+/// # Returns
+///
+/// * `Ok(Json<Conversation>)` - The conversation and its turns, in order
+/// * `Err((StatusCode, Json<String>))` - Error message with appropriate status code
+///
+/// # Errors
+///
+/// Returns `UNAUTHORIZED` without a valid session token.
+///
+/// Returns `NOT_FOUND` if the conversation is unknown or belongs to another
+/// wallet, and `INTERNAL_SERVER_ERROR`
+/// if the store fails.
+pub async fn get_conversation(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(conversation_id): Path<String>,
+) -> Result<Json<Conversation>, (StatusCode, Json<String>)> {
+    tracing::info!("Handler called: get_conversation for {}", conversation_id);
+
+    let mut conversation = state
+        .conversations
+        .get(&conversation_id, &user.address)
+        .map_err(store_error)?
+        .ok_or_else(|| conversation_not_found(&conversation_id))?;
+    for turn in &mut conversation.turns {
+        turn.audio_url = state.audio.public_url(&turn.audio_url);
+    }
+    Ok(Json(conversation))
+}
+
+/// Deletes a conversation.
+///
+/// Removes the conversation and its turns from the store, then deletes the
+/// turns' audio files from `AUDIO_DIR` (except those other conversations share)
+/// and the session on the MCP server. The latter two are best-effort: failures
+/// are logged, since the conversation is already gone.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the conversation store and MCP client
+/// * `user` - The signed-in wallet, which must own the conversation
+/// * `conversation_id` - Session ID of the conversation
+///
+/// # Returns
+///
+/// * `Ok(StatusCode::NO_CONTENT)` - The conversation was deleted
+/// * `Err((StatusCode, Json<String>))` - Error message with appropriate status code
+///
+/// # Errors
+///
+/// Returns `UNAUTHORIZED` without a valid session token.
+///
+/// Returns `NOT_FOUND` if the conversation is unknown or belongs to another
+/// wallet, and `INTERNAL_SERVER_ERROR`
+/// if the store fails.
+pub async fn delete_conversation(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(conversation_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<String>)> {
+    tracing::info!(
+        "Handler called: delete_conversation for {}",
+        conversation_id
+    );
+
+    let audio_urls = state
+        .conversations
+        .delete(&conversation_id, &user.address)
+        .map_err(store_error)?
+        .ok_or_else(|| conversation_not_found(&conversation_id))?;
+
+    for audio_url in audio_urls {
+        if let Err(e) = state.audio.remove(&audio_url).await {
+            tracing::warn!("Failed to delete audio file {}: {}", audio_url, e);
+        }
+    }
+
+    if let Err(e) = state.mcp.delete_session(&conversation_id).await {
+        tracing::warn!("Failed to delete MCP session {}: {}", conversation_id, e);
+    }
+
+    tracing::info!("Deleted conversation {}", conversation_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Starts minting a conversation turn as an NFT through web3-minting.
+///
+/// This handler:
+/// 1. Looks up the turn (the latest one unless `turn` is given)
+/// 2. Claims the turn, so it is minted only once; if a mint job was already
+///    started for it, that job is returned instead, unless it failed
+/// 3. Uploads the reply's audio to web3-minting's storage (`POST /assets`)
+/// 4. Queues a mint whose metadata holds the user text, the reply and the agent,
+///    with the audio as `animation_url` (`POST /mint`)
+/// 5. Returns the job right away; poll it with [`get_conversation_mint`]
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the conversation store and minting client
+/// * `user` - The signed-in wallet, which must own the conversation and receives the token by default
+/// * `conversation_id` - Session ID of the conversation
+/// * `payload` - JSON payload with the optional turn index, recipient and chain
+///
+/// # Returns
+///
+/// * `Ok((StatusCode::ACCEPTED, Json<MintConversationResponse>))` - The queued mint job
+/// * `Ok((StatusCode::OK, Json<MintConversationResponse>))` - The job already started for
+///   the turn; it mints to the recipient and chain of the request that started it
+/// * `Err((StatusCode, Json<String>))` - Error message with appropriate status code
+///
+/// # Errors
+///
+/// Returns `UNAUTHORIZED` without a valid session token.
+///
+/// Returns `NOT_FOUND` if the conversation or turn is unknown, or the conversation
+/// belongs to another wallet.
+///
+/// Returns `CONFLICT` while another request is starting the turn's mint.
+///
+/// Returns `GONE` if the turn's audio file has expired (see [`crate::audio`]).
+///
+/// Returns `INTERNAL_SERVER_ERROR` if the conversation store fails or the turn's
+/// audio file cannot be read.
+///
+/// Returns `BAD_GATEWAY` if web3-minting is unreachable or rejects the request.
+///
+/// # Environment Variables
+///
+/// - `WEB3_MINTING_URL` - URL of web3-minting (optional, defaults to "http://localhost:8081")
+/// - `MINT_API_TOKEN` - Service token web3-minting requires
+///
+/// # Request Example
+///
+/// ```json
+/// {
+///   "turn": 0,
+///   "recipient": "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+///   "chain": "sepolia"
+/// }
+/// ```
+pub async fn mint_conversation(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(conversation_id): Path<String>,
+    Json(payload): Json<MintConversationRequest>,
+) -> Result<(StatusCode, Json<MintConversationResponse>), (StatusCode, Json<String>)> {
+    tracing::info!("Handler called: mint_conversation for {}", conversation_id);
+
+    let turn = state
+        .conversations
+        .turn(&conversation_id, &user.address, payload.turn)
+        .map_err(store_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(format!("Conversation {} has no such turn", conversation_id)),
+            )
+        })?;
+
+    let response = |job_id: String| {
+        Json(MintConversationResponse {
+            job_id,
+            turn: turn.turn,
+        })
+    };
+    let already_minting = || {
+        (
+            StatusCode::CONFLICT,
+            Json(format!(
+                "Turn {} of conversation {} is already being minted",
+                turn.turn, conversation_id
+            )),
+        )
+    };
+    match state
+        .conversations
+        .claim_mint(&conversation_id, turn.turn)
+        .map_err(store_error)?
+    {
+        MintClaim::Claimed => {}
+        MintClaim::Starting => return Err(already_minting()),
+        MintClaim::Started(job_id) => {
+            let job = state
+                .minting
+                .get_job(&job_id)
+                .await
+                .map_err(minting_error)?;
+            if job.status != "failed" {
+                return Ok((StatusCode::OK, response(job_id)));
+            }
+            if !state
+                .conversations
+                .reclaim_mint(&conversation_id, turn.turn, &job_id)
+                .map_err(store_error)?
+            {
+                return Err(already_minting());
             }
+            tracing::info!(
+                "Mint job {} failed; minting turn {} again",
+                job_id,
+                turn.turn
+            );
         }
+    }
+
+    let recipient = payload.recipient.unwrap_or_else(|| user.address.clone());
+    let (job_id, audio_url) = match start_turn_mint(&state, &turn, recipient, payload.chain).await {
+        Ok(started) => started,
         Err(e) => {
-            tracing::error!("Failed to call MCP /process_text: {:?}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json("Failed to call MCP service".to_string()),
-            ));
+            if let Err(e) = state
+                .conversations
+                .release_mint(&conversation_id, turn.turn)
+            {
+                tracing::error!("Failed to release the mint of turn {}: {}", turn.turn, e);
+            }
+            return Err(e);
         }
     };
+    state
+        .conversations
+        .set_mint_job(&conversation_id, turn.turn, &job_id, &audio_url)
+        .map_err(store_error)?;
+    tracing::info!(
+        "Queued mint job {} for turn {} of {}",
+        job_id,
+        turn.turn,
+        conversation_id
+    );
 
-    tracing::info!("Calling ElevenLabs TTS API for agent's reply...");
+    Ok((StatusCode::ACCEPTED, response(job_id)))
+}
 
-    // ElevenLabs TTS API - using default voice "Rachel" (21m00Tcm4TlvDq8ikWAM)
-    let tts_url = "https://api.elevenlabs.io/v1/text-to-speech/21m00Tcm4TlvDq8ikWAM";
-    
-    let tts_payload = serde_json::json!({
-        "text": agent_reply_text,
-        "model_id": "eleven_multilingual_v2",
-        "output_format": "mp3_44100_128"
-    });
+/// Uploads a turn's audio and queues its mint job.
+///
+/// # Returns
+///
+/// The ID of the job and the URL of the uploaded audio.
+async fn start_turn_mint(
+    state: &AppState,
+    turn: &Turn,
+    recipient: String,
+    chain: Option<String>,
+) -> Result<(String, String), (StatusCode, Json<String>)> {
+    let audio = state
+        .audio
+        .read(&turn.audio_url)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to read audio file {}: {}", turn.audio_url, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json("Failed to read audio file".to_string()),
+            )
+        })?
+        .ok_or_else(|| {
+            (
+                StatusCode::GONE,
+                Json("The audio of this turn has expired and can no longer be minted".to_string()),
+            )
+        })?;
+    let filename = turn
+        .audio_url
+        .rsplit('/')
+        .next()
+        .unwrap_or_default()
+        .to_string();
 
-    let tts_response = state
-        .http_client
-        .post(tts_url)
-        .header("xi-api-key", &state.elevenlabs_api_key)
-        .header("Content-Type", "application/json")
-        .json(&tts_payload)
-        .send()
-        .await;
-
-    let audio_bytes = match tts_response {
-        Ok(response) => {
-            if response.status().is_success() {
-                match response.bytes().await {
-                    Ok(bytes) => bytes.to_vec(),
-                    Err(e) => {
-                        tracing::error!("Failed to read TTS audio bytes: {:?}", e);
-                        return Err((
-                            StatusCode::INTERNAL_SERVER_ERROR,
-                            Json("Failed to read TTS audio".to_string()),
-                        ));
-                    }
-                }
-            } else {
-                let status = response.status();
-                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-                tracing::error!("ElevenLabs TTS API error {}: {}", status, error_text);
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(format!("Error from TTS service: {}", error_text)),
-                ));
-            }
+    let audio_url = state
+        .minting
+        .upload_asset(audio, &filename, crate::speech::audio_mime_type(&filename))
+        .await
+        .map_err(minting_error)?;
+    tracing::info!("Uploaded turn audio to {}", audio_url);
+
+    let request = MintRequest {
+        name: format!("Conversation with {}", turn.agent_id),
+        description: format!("User: {}\n\nAgent: {}", turn.user_text, turn.reply_text),
+        animation_url: audio_url.clone(),
+        attributes: vec![
+            MintAttribute {
+                trait_type: "Agent".to_string(),
+                value: turn.agent_id.clone().into(),
+                display_type: None,
+            },
+            MintAttribute {
+                trait_type: "Turn".to_string(),
+                value: (turn.turn + 1).into(),
+                display_type: Some("number".to_string()),
+            },
+        ],
+        // Tokens go to the signed-in wallet unless another recipient is named
+        recipient: Some(recipient),
+        chain,
+    };
+    let job_id = state
+        .minting
+        .start_mint(&request)
+        .await
+        .map_err(minting_error)?;
+    Ok((job_id, audio_url))
+}
+
+/// Returns the progress of a conversation turn's mint job.
+///
+/// Poll this after [`mint_conversation`] until `status` is `confirmed` or `failed`.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the conversation store and minting client
+/// * `user` - The signed-in wallet, which must own the conversation
+/// * `conversation_id` - Session ID of the conversation
+/// * `job_id` - Mint job returned by [`mint_conversation`]
+///
+/// # Returns
+///
+/// * `Ok(Json<MintStatusResponse>)` - The job's status and, once sent, its transaction
+/// * `Err((StatusCode, Json<String>))` - Error message with appropriate status code
+///
+/// # Errors
+///
+/// Returns `UNAUTHORIZED` without a valid session token.
+///
+/// Returns `NOT_FOUND` if the job is not a mint of the conversation, or the
+/// conversation belongs to another wallet.
+///
+/// Returns `INTERNAL_SERVER_ERROR` if the conversation store fails, and
+/// `BAD_GATEWAY` if web3-minting is unreachable.
+pub async fn get_conversation_mint(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path((conversation_id, job_id)): Path<(String, String)>,
+) -> Result<Json<MintStatusResponse>, (StatusCode, Json<String>)> {
+    tracing::info!("Handler called: get_conversation_mint for {}", job_id);
+
+    let mint = state
+        .conversations
+        .mint(&conversation_id, &user.address, &job_id)
+        .map_err(store_error)?
+        .ok_or_else(|| {
+            (
+                StatusCode::NOT_FOUND,
+                Json(format!(
+                    "Conversation {} has no mint job {}",
+                    conversation_id, job_id
+                )),
+            )
+        })?;
+
+    let job = state
+        .minting
+        .get_job(&job_id)
+        .await
+        .map_err(minting_error)?;
+    let (tx_hash, token_id, chain_id, explorer_url) = match job.mint {
+        Some(tx) => (Some(tx.tx_hash), tx.token_id, tx.chain_id, tx.explorer_url),
+        None => (None, None, None, None),
+    };
+    Ok(Json(MintStatusResponse {
+        job_id,
+        turn: mint.turn,
+        status: job.status,
+        audio_url: mint.audio_url,
+        metadata_url: job.upload.map(|upload| upload.url),
+        tx_hash,
+        token_id,
+        chain_id,
+        explorer_url,
+        error: job.error,
+    }))
+}
+
+/// Creates an API key for the signed-in wallet.
+///
+/// The key authenticates requests as the wallet, as `Authorization: Bearer <key>`,
+/// with its own rate limit and daily quotas. Only its hash is stored, so the key
+/// is returned this once.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the API key store
+/// * `user` - The signed-in wallet that will own the key
+/// * `payload` - JSON payload with a name for the key
+///
+/// # Returns
+///
+/// * `Ok((StatusCode::CREATED, Json<CreateApiKeyResponse>))` - The key and its details
+/// * `Err((StatusCode, Json<String>))` - Error message with appropriate status code
+///
+/// # Errors
+///
+/// Returns `UNAUTHORIZED` without a valid session token, `FORBIDDEN` when called
+/// with an API key, `BAD_REQUEST` for an empty name, and `INTERNAL_SERVER_ERROR`
+/// if the store fails.
+///
+/// # Request Example
+///
+/// ```json
+/// {
+///   "name": "nightly report"
+/// }
+/// ```
+pub async fn create_api_key(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Json(payload): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreateApiKeyResponse>), (StatusCode, Json<String>)> {
+    tracing::info!("Handler called: create_api_key");
+
+    require_session(&user)?;
+    let name = payload.name.trim();
+    if name.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json("API key name must not be empty".to_string()),
+        ));
+    }
+
+    let (info, key) = state
+        .keys
+        .create(&user.address, name)
+        .map_err(key_store_error)?;
+    tracing::info!("Created API key {} for {}", info.id, user.address);
+    Ok((
+        StatusCode::CREATED,
+        Json(CreateApiKeyResponse { key, info }),
+    ))
+}
+
+/// Lists the signed-in wallet's API keys, newest first.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the API key store
+/// * `user` - The signed-in wallet
+///
+/// # Returns
+///
+/// * `Ok(Json<Vec<ApiKeyInfo>>)` - The keys' details; the keys themselves are not returned
+/// * `Err((StatusCode, Json<String>))` - Error message with appropriate status code
+///
+/// # Errors
+///
+/// Returns `UNAUTHORIZED` without a valid session token, `FORBIDDEN` when called
+/// with an API key, and `INTERNAL_SERVER_ERROR` if the store fails.
+pub async fn list_api_keys(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<Vec<ApiKeyInfo>>, (StatusCode, Json<String>)> {
+    tracing::info!("Handler called: list_api_keys");
+
+    require_session(&user)?;
+    let keys = state.keys.list(&user.address).map_err(key_store_error)?;
+    Ok(Json(keys))
+}
+
+/// Revokes one of the signed-in wallet's API keys.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the API key store
+/// * `user` - The signed-in wallet, which must own the key
+/// * `key_id` - ID of the key
+///
+/// # Returns
+///
+/// * `Ok(StatusCode::NO_CONTENT)` - The key was revoked
+/// * `Err((StatusCode, Json<String>))` - Error message with appropriate status code
+///
+/// # Errors
+///
+/// Returns `UNAUTHORIZED` without a valid session token, `FORBIDDEN` when called
+/// with an API key, `NOT_FOUND` if the key is unknown or belongs to another
+/// wallet, and `INTERNAL_SERVER_ERROR` if the store fails.
+pub async fn delete_api_key(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+    Path(key_id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<String>)> {
+    tracing::info!("Handler called: delete_api_key for {}", key_id);
+
+    require_session(&user)?;
+    if !state
+        .keys
+        .delete(&user.address, &key_id)
+        .map_err(key_store_error)?
+    {
+        return Err((
+            StatusCode::NOT_FOUND,
+            Json(format!("API key {} not found", key_id)),
+        ));
+    }
+    tracing::info!("Revoked API key {}", key_id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Returns the caller's usage of today's quotas.
+///
+/// Usage is counted per API key when the request uses one, and per wallet for
+/// session tokens.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the limits
+/// * `user` - The caller
+///
+/// # Returns
+///
+/// * `Ok(Json<UsageResponse>)` - Usage and limits for the current UTC day
+/// * `Err((StatusCode, Json<String>))` - Error message with appropriate status code
+///
+/// # Errors
+///
+/// Returns `UNAUTHORIZED` without a valid session token or API key, and
+/// `INTERNAL_SERVER_ERROR` if the usage store fails.
+///
+/// # Response Example
+///
+/// ```json
+/// {
+///   "day": "2026-10-17",
+///   "tts_chars": 1840,
+///   "tts_chars_limit": 20000,
+///   "stt_seconds": 42.5,
+///   "stt_seconds_limit": 1800,
+///   "resets_in_secs": 51300
+/// }
+/// ```
+pub async fn get_usage(
+    State(state): State<Arc<AppState>>,
+    user: AuthUser,
+) -> Result<Json<UsageResponse>, (StatusCode, Json<String>)> {
+    tracing::info!("Handler called: get_usage");
+
+    state.limits.usage(&user).map(Json).map_err(usage_error)
+}
+
+/// Starts a new conversation session on the MCP server.
+///
+/// The MCP server stores the message history of the session, so follow-up
+/// requests only need to send the returned session ID.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the MCP client
+/// * `agent_id` - Agent the session is started with
+///
+/// # Returns
+///
+/// * `Ok(String)` - ID of the new session
+/// * `Err((StatusCode, Json<String>))` - Error message if the MCP call fails
+async fn start_session(
+    state: &AppState,
+    agent_id: &str,
+) -> Result<String, (StatusCode, Json<String>)> {
+    let session = state
+        .mcp
+        .create_session(Some(agent_id))
+        .await
+        .map_err(|e| mcp_error("create_session", e))?;
+
+    tracing::info!("Started MCP session {}", session.id);
+    Ok(session.id)
+}
+
+/// Returns the session a turn continues, starting a new one when none is given.
+///
+/// A given session must not belong to another wallet's conversation. MCP
+/// sessions expire after an idle hour and are lost when the MCP server restarts;
+/// a stored conversation whose session is gone is recreated under the same ID
+/// from its stored turns, and any other unknown session is replaced by a new one.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the MCP client and conversation store
+/// * `user` - The signed-in wallet
+/// * `session_id` - Session sent by the client, if any
+/// * `agent_id` - Agent a new session is started with
+///
+/// # Returns
+///
+/// * `Ok(String)` - ID of the session
+/// * `Err((StatusCode, Json<String>))` - `NOT_FOUND` for another wallet's
+///   conversation, or the error of starting a session
+async fn resolve_session(
+    state: &AppState,
+    user: &AuthUser,
+    session_id: Option<String>,
+    agent_id: &str,
+) -> Result<String, (StatusCode, Json<String>)> {
+    let Some(session_id) = session_id else {
+        return start_session(state, agent_id).await;
+    };
+    let stored = match state
+        .conversations
+        .owner(&session_id)
+        .map_err(store_error)?
+    {
+        Some(owner) if owner != user.address => return Err(conversation_not_found(&session_id)),
+        owner => owner.is_some(),
+    };
+
+    match state.mcp.get_session(&session_id).await {
+        Ok(_) => Ok(session_id),
+        Err(ClientError::Rpc { code: -32602, .. }) if stored => {
+            restore_session(state, user, &session_id, agent_id).await
         }
-        Err(e) => {
-            tracing::error!("Failed to call ElevenLabs TTS API: {:?}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json("Failed to call TTS service".to_string()),
-            ));
+        Err(ClientError::Rpc { code: -32602, .. }) => {
+            tracing::info!("MCP session {} is gone; starting a new one", session_id);
+            start_session(state, agent_id).await
         }
-    };
+        Err(e) => Err(mcp_error("get_session", e)),
+    }
+}
 
-    let output_filename = format!("{}.mp3", Uuid::new_v4());
-    let filepath = PathBuf::from(&state.audio_dir).join(&output_filename);
-
-    match File::create(&filepath).await {
-        Ok(mut file) => {
-            if let Err(e) = file.write_all(&audio_bytes).await {
-                tracing::error!("Failed to write audio file: {:?}", e);
-                return Err((
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json("Failed to save audio file".to_string()),
-                ));
-            }
+/// Recreates the expired MCP session of a stored conversation under its ID,
+/// seeded with the conversation's stored turns.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the MCP client and conversation store
+/// * `user` - The signed-in wallet that owns the conversation
+/// * `session_id` - Session ID of the conversation
+/// * `agent_id` - Agent the session is recreated with
+///
+/// # Returns
+///
+/// * `Ok(String)` - ID of the session
+/// * `Err((StatusCode, Json<String>))` - Error message if the store or the MCP server fails
+async fn restore_session(
+    state: &AppState,
+    user: &AuthUser,
+    session_id: &str,
+    agent_id: &str,
+) -> Result<String, (StatusCode, Json<String>)> {
+    let turns = state
+        .conversations
+        .get(session_id, &user.address)
+        .map_err(store_error)?
+        .map(|conversation| conversation.turns)
+        .unwrap_or_default();
+    let messages = turns
+        .into_iter()
+        .flat_map(|turn| {
+            [
+                Message {
+                    role: "user".to_string(),
+                    content: turn.user_text,
+                },
+                Message {
+                    role: "assistant".to_string(),
+                    content: turn.reply_text,
+                },
+            ]
+        })
+        .collect::<Vec<_>>();
+
+    let count = messages.len();
+    match state
+        .mcp
+        .restore_session(session_id, Some(agent_id), messages)
+        .await
+    {
+        Ok(session) => {
+            tracing::info!(
+                "Restored MCP session {} with {} messages",
+                session.id,
+                count
+            );
+            Ok(session.id)
         }
-        Err(e) => {
-            tracing::error!("Failed to create audio file: {:?}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json("Failed to create audio file".to_string()),
-            ));
+        // Another request for the conversation restored it first
+        Err(ClientError::Rpc { code: -32602, .. }) => Ok(session_id.to_string()),
+        Err(e) => Err(mcp_error("create_session", e)),
+    }
+}
+
+/// Stores a completed turn in the conversation store.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the conversation store
+/// * `user` - The signed-in wallet that owns the conversation
+/// * `session_id` - Session ID of the conversation
+/// * `turn` - The turn to store
+///
+/// # Returns
+///
+/// * `Ok(usize)` - Index of the turn in the conversation
+/// * `Err((StatusCode, Json<String>))` - Error message if the store fails
+fn record_turn(
+    state: &AppState,
+    user: &AuthUser,
+    session_id: &str,
+    turn: NewTurn,
+) -> Result<usize, (StatusCode, Json<String>)> {
+    state
+        .conversations
+        .record(session_id, &user.address, &turn)
+        .map_err(store_error)
+}
+
+/// Rejects requests authenticated with an API key with `FORBIDDEN`, so a leaked
+/// key cannot be used to mint more keys or revoke the others.
+fn require_session(user: &AuthUser) -> Result<(), (StatusCode, Json<String>)> {
+    match user.key_id {
+        Some(_) => Err((
+            StatusCode::FORBIDDEN,
+            Json("API keys can only be managed with a wallet session".to_string()),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Maps an API key store failure to an `INTERNAL_SERVER_ERROR` response.
+fn key_store_error(error: String) -> (StatusCode, Json<String>) {
+    tracing::error!("API key store failed: {}", error);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json("Failed to access API key store".to_string()),
+    )
+}
+
+/// Maps a usage store failure to an `INTERNAL_SERVER_ERROR` response.
+fn usage_error(error: String) -> (StatusCode, Json<String>) {
+    tracing::error!("Usage store failed: {}", error);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json("Failed to check usage".to_string()),
+    )
+}
+
+/// Maps a conversation store failure to an `INTERNAL_SERVER_ERROR` response.
+fn store_error(error: String) -> (StatusCode, Json<String>) {
+    tracing::error!("Conversation store failed: {}", error);
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json("Failed to access conversation store".to_string()),
+    )
+}
+
+/// `BAD_GATEWAY` response for a failed call to web3-minting.
+fn minting_error(error: String) -> (StatusCode, Json<String>) {
+    tracing::error!("Minting failed: {}", error);
+    (
+        StatusCode::BAD_GATEWAY,
+        Json(format!("Error from minting service: {}", error)),
+    )
+}
+
+/// `NOT_FOUND` response for an unknown conversation.
+fn conversation_not_found(conversation_id: &str) -> (StatusCode, Json<String>) {
+    (
+        StatusCode::NOT_FOUND,
+        Json(format!("Conversation {} not found", conversation_id)),
+    )
+}
+
+/// Maps a failed MCP call to an HTTP error response.
+///
+/// Invalid params errors (`-32602`, e.g. an unknown agent) are the client's fault
+/// and become `BAD_REQUEST` with the MCP server's message; every other failure
+/// becomes `INTERNAL_SERVER_ERROR`.
+///
+/// # Arguments
+///
+/// * `method` - MCP method that was called, for logging
+/// * `error` - The error returned by the MCP client
+fn mcp_error(method: &str, error: ClientError) -> (StatusCode, Json<String>) {
+    tracing::error!("MCP {} failed: {}", method, error);
+    match error {
+        ClientError::Rpc {
+            code: -32602,
+            message,
+            ..
+        } => (StatusCode::BAD_REQUEST, Json(message)),
+        ClientError::Rpc { message, .. } => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(format!("Error from MCP service: {}", message)),
+        ),
+        _ => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json("Failed to call MCP service".to_string()),
+        ),
+    }
+}
+
+/// Converts an agent reply to speech and stores the audio file.
+///
+/// The audio is stored under a key derived from the TTS voice and the text (see
+/// [`crate::audio`]). An identical reply reuses the stored audio without calling
+/// the TTS provider, and is not charged to the caller's quota; otherwise the
+/// synthesized characters are charged to the caller's daily text-to-speech quota.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the TTS provider and audio store
+/// * `user` - The caller whose quota is charged
+/// * `text` - The text to synthesize
+///
+/// # Returns
+///
+/// * `Ok(String)` - Public URL path of the stored audio file
+/// * `Err((StatusCode, Json<String>))` - Error message if synthesis or saving fails
+async fn synthesize_and_store(
+    state: &AppState,
+    user: &AuthUser,
+    text: &str,
+) -> Result<String, (StatusCode, Json<String>)> {
+    let key = audio::key(&state.tts.voice(), text);
+    match state.audio.find(&key).await {
+        Ok(Some(audio_url)) => {
+            tracing::info!("Reusing stored audio {}", audio_url);
+            return Ok(audio_url);
         }
+        Ok(None) => {}
+        // Synthesizing again is only a waste, not a failure
+        Err(e) => tracing::warn!("Failed to look up stored audio: {}", e),
     }
 
-    let audio_url = format!("/public/audio/{}", output_filename);
-    tracing::info!("Audio saved to: {}", audio_url);
+    tracing::info!(
+        "Calling {} text-to-speech for agent's reply",
+        state.tts.name()
+    );
 
-    let final_reply = AgentReplyResponse {
-        reply_text: agent_reply_text,
-        audio_url,
-    };
-    Ok((StatusCode::CREATED, Json(final_reply)))
+    let audio = state.tts.synthesize(text).await.map_err(|e| {
+        tracing::error!("Text-to-speech failed: {}", e);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(format!("Error from TTS service: {}", e)),
+        )
+    })?;
+
+    // The audio is paid for by now, so a failure to count it must not fail the reply
+    let chars = text.chars().count() as u64;
+    if let Err(e) = state.limits.quotas.charge_tts(&user.subject(), chars) {
+        tracing::error!("Failed to record text-to-speech usage: {}", e);
+    }
+
+    let audio_url = state
+        .audio
+        .put(&key, audio.extension, audio.bytes)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to save audio file: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json("Failed to save audio file".to_string()),
+            )
+        })?;
+    tracing::info!("Audio saved to: {}", audio_url);
+    Ok(audio_url)
 }
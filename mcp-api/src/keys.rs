@@ -0,0 +1,208 @@
+//! API keys for programmatic access.
+//!
+//! A signed-in wallet can create API keys, which authenticate requests as that
+//! wallet without a browser sign-in. Only a SHA-256 hash of each key is stored;
+//! the key itself is shown once, when it is created. Rate limits and quotas are
+//! tracked per key (see [`crate::limits`]).
+
+use crate::models::ApiKeyInfo;
+use rand_core::{OsRng, RngCore};
+use rusqlite::{Connection, OptionalExtension, params};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// Prefix of every API key, so keys are recognizable in configs and logs.
+pub const KEY_PREFIX: &str = "mcp_";
+
+/// The wallet and key a request was authenticated with.
+pub struct KeyOwner {
+    /// ID of the key
+    pub key_id: String,
+    /// Wallet address the key belongs to
+    pub owner: String,
+}
+
+/// SQLite-backed store of hashed API keys.
+pub struct ApiKeyStore {
+    conn: Mutex<Connection>,
+}
+
+impl ApiKeyStore {
+    /// Opens (or creates) the API key table in the database at the given path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be opened or the schema cannot be created.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS api_keys (
+                id           TEXT PRIMARY KEY,
+                owner        TEXT NOT NULL,
+                name         TEXT NOT NULL,
+                key_hash     TEXT NOT NULL UNIQUE,
+                prefix       TEXT NOT NULL,
+                created_at   TEXT NOT NULL,
+                last_used_at TEXT
+            );
+            CREATE INDEX IF NOT EXISTS api_keys_owner ON api_keys (owner);",
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Creates a key for a wallet.
+    ///
+    /// # Returns
+    ///
+    /// The key's details and the key itself, which is not stored and cannot be
+    /// retrieved again.
+    pub fn create(&self, owner: &str, name: &str) -> Result<(ApiKeyInfo, String), String> {
+        let mut secret = [0u8; 32];
+        OsRng.fill_bytes(&mut secret);
+        let key = format!("{}{}", KEY_PREFIX, hex::encode(secret));
+
+        let info = ApiKeyInfo {
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            prefix: key[..KEY_PREFIX.len() + 8].to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            last_used_at: None,
+        };
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO api_keys (id, owner, name, key_hash, prefix, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                info.id,
+                owner,
+                info.name,
+                hash(&key),
+                info.prefix,
+                info.created_at
+            ],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok((info, key))
+    }
+
+    /// Lists a wallet's keys, newest first.
+    pub fn list(&self, owner: &str) -> Result<Vec<ApiKeyInfo>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare(
+                "SELECT id, name, prefix, created_at, last_used_at FROM api_keys
+                 WHERE owner = ?1 ORDER BY created_at DESC",
+            )
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map(params![owner], |row| {
+                Ok(ApiKeyInfo {
+                    id: row.get(0)?,
+                    name: row.get(1)?,
+                    prefix: row.get(2)?,
+                    created_at: row.get(3)?,
+                    last_used_at: row.get(4)?,
+                })
+            })
+            .map_err(|e| e.to_string())?;
+        rows.collect::<Result<_, _>>().map_err(|e| e.to_string())
+    }
+
+    /// Deletes one of a wallet's keys.
+    ///
+    /// # Returns
+    ///
+    /// Whether the key existed and belonged to the wallet.
+    pub fn delete(&self, owner: &str, key_id: &str) -> Result<bool, String> {
+        let conn = self.conn.lock().unwrap();
+        let deleted = conn
+            .execute(
+                "DELETE FROM api_keys WHERE id = ?1 AND owner = ?2",
+                params![key_id, owner],
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(deleted > 0)
+    }
+
+    /// Looks up the key a request presented and records that it was used.
+    ///
+    /// # Returns
+    ///
+    /// The key's ID and owner, or `None` if the key is unknown or was deleted.
+    pub fn authenticate(&self, key: &str) -> Result<Option<KeyOwner>, String> {
+        let conn = self.conn.lock().unwrap();
+        let found = conn
+            .query_row(
+                "SELECT id, owner FROM api_keys WHERE key_hash = ?1",
+                params![hash(key)],
+                |row| {
+                    Ok(KeyOwner {
+                        key_id: row.get(0)?,
+                        owner: row.get(1)?,
+                    })
+                },
+            )
+            .optional()
+            .map_err(|e| e.to_string())?;
+        if let Some(found) = &found {
+            conn.execute(
+                "UPDATE api_keys SET last_used_at = ?1 WHERE id = ?2",
+                params![chrono::Utc::now().to_rfc3339(), found.key_id],
+            )
+            .map_err(|e| e.to_string())?;
+        }
+        Ok(found)
+    }
+}
+
+/// SHA-256 of a key in hex. Keys are 256 random bits, so a fast hash is enough.
+fn hash(key: &str) -> String {
+    hex::encode(Sha256::digest(key.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keys_authenticate_as_their_owner_until_deleted() {
+        let store = ApiKeyStore::open(":memory:").unwrap();
+        let (info, key) = store.create("0xowner", "ci").unwrap();
+        assert!(key.starts_with(KEY_PREFIX));
+        assert!(key.starts_with(&info.prefix));
+
+        let owner = store.authenticate(&key).unwrap().unwrap();
+        assert_eq!(owner.key_id, info.id);
+        assert_eq!(owner.owner, "0xowner");
+        assert!(store.authenticate(&format!("{}0", key)).unwrap().is_none());
+
+        let listed = store.list("0xowner").unwrap();
+        assert_eq!(listed.len(), 1);
+        assert!(listed[0].last_used_at.is_some());
+        assert!(store.list("0xother").unwrap().is_empty());
+
+        // Only the owner can delete a key
+        assert!(!store.delete("0xother", &info.id).unwrap());
+        assert!(store.delete("0xowner", &info.id).unwrap());
+        assert!(store.authenticate(&key).unwrap().is_none());
+    }
+
+    #[test]
+    fn only_a_hash_of_the_key_is_stored() {
+        let store = ApiKeyStore::open(":memory:").unwrap();
+        let (_, key) = store.create("0xowner", "ci").unwrap();
+        let stored: String = store
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT key_hash FROM api_keys", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(stored, hash(&key));
+        assert!(!stored.contains(&key[KEY_PREFIX.len()..]));
+    }
+}
@@ -0,0 +1,462 @@
+//! Rate limits and daily quotas for the endpoints that spend provider credits.
+//!
+//! Requests to `/input/*` and `POST /conversations/{id}/mint` pass through
+//! [`enforce`], which applies two token buckets (one per client IP, one per API
+//! key or signed-in wallet) and rejects callers whose daily quota is used up.
+//! The handlers charge the quotas: text-to-speech characters once a reply is
+//! synthesized, and speech-to-text seconds before an upload is transcribed.
+//!
+//! Rejected requests get `429 Too Many Requests` with a `Retry-After` header.
+
+use crate::AppState;
+use crate::auth::AuthUser;
+use crate::env_number;
+use crate::models::UsageResponse;
+use axum::{
+    Json,
+    extract::{ConnectInfo, Request, State},
+    http::{HeaderValue, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use rusqlite::{Connection, params};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Number of idle buckets a limiter keeps before dropping the full ones.
+const MAX_IDLE_BUCKETS: usize = 10_000;
+
+/// A token bucket per key: `burst` requests at once, refilled at a steady rate.
+pub struct RateLimiter {
+    /// Tokens added per second
+    rate: f64,
+    /// Bucket capacity
+    burst: f64,
+    buckets: Mutex<HashMap<String, Bucket>>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl RateLimiter {
+    /// Creates a limiter allowing `per_minute` requests per minute on average and
+    /// bursts of up to `burst` requests.
+    pub fn new(per_minute: u32, burst: u32) -> Self {
+        Self {
+            rate: f64::from(per_minute) / 60.0,
+            burst: f64::from(burst.max(1)),
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Takes a token from the bucket of `key`.
+    ///
+    /// # Errors
+    ///
+    /// Returns how long until a token is available if the bucket is empty.
+    pub fn check(&self, key: &str) -> Result<(), Duration> {
+        self.check_at(key, Instant::now())
+    }
+
+    /// [`RateLimiter::check`] at a given time.
+    fn check_at(&self, key: &str, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap();
+        if buckets.len() >= MAX_IDLE_BUCKETS {
+            let (rate, burst) = (self.rate, self.burst);
+            buckets.retain(|_, b| {
+                b.tokens + now.duration_since(b.updated).as_secs_f64() * rate < burst
+            });
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: self.burst,
+            updated: now,
+        });
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.rate).min(self.burst);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else if self.rate > 0.0 {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.rate))
+        } else {
+            Err(until_midnight())
+        }
+    }
+}
+
+/// Daily usage of one caller, persisted so restarts do not reset it.
+///
+/// A limit of 0 disables that quota.
+pub struct Quotas {
+    /// Text-to-speech characters per day
+    pub tts_chars: u64,
+    /// Seconds of speech-to-text audio per day
+    pub stt_seconds: u64,
+    conn: Mutex<Connection>,
+}
+
+impl Quotas {
+    /// Opens (or creates) the usage table in the database at the given path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be opened or the schema cannot be created.
+    pub fn open(path: impl AsRef<Path>, tts_chars: u64, stt_seconds: u64) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS usage (
+                subject     TEXT NOT NULL,
+                day         TEXT NOT NULL,
+                tts_chars   INTEGER NOT NULL DEFAULT 0,
+                stt_seconds REAL NOT NULL DEFAULT 0,
+                PRIMARY KEY (subject, day)
+            );",
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(Self {
+            tts_chars,
+            stt_seconds,
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Today's usage of a caller: text-to-speech characters and speech-to-text seconds.
+    pub fn usage(&self, subject: &str) -> Result<(u64, f64), String> {
+        let conn = self.conn.lock().unwrap();
+        Self::usage_locked(&conn, subject)
+    }
+
+    /// Whether a caller has used up any of today's quotas.
+    pub fn exhausted(&self, subject: &str) -> Result<bool, String> {
+        let (tts_chars, stt_seconds) = self.usage(subject)?;
+        Ok((self.tts_chars > 0 && tts_chars >= self.tts_chars)
+            || (self.stt_seconds > 0 && stt_seconds >= self.stt_seconds as f64))
+    }
+
+    /// Adds synthesized characters to a caller's usage.
+    ///
+    /// The reply has already been generated when it is synthesized, so this never
+    /// rejects; once the quota is used up, [`enforce`] rejects further requests.
+    pub fn charge_tts(&self, subject: &str, chars: u64) -> Result<(), String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO usage (subject, day, tts_chars) VALUES (?1, ?2, ?3)
+             ON CONFLICT(subject, day) DO UPDATE SET tts_chars = tts_chars + excluded.tts_chars",
+            params![subject, today(), chars],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    /// Adds transcribed seconds to a caller's usage, unless that would exceed the quota.
+    ///
+    /// # Returns
+    ///
+    /// Whether the seconds were charged.
+    pub fn try_charge_stt(&self, subject: &str, seconds: f64) -> Result<bool, String> {
+        let conn = self.conn.lock().unwrap();
+        let (_, used) = Self::usage_locked(&conn, subject)?;
+        if self.stt_seconds > 0 && used + seconds > self.stt_seconds as f64 {
+            return Ok(false);
+        }
+        conn.execute(
+            "INSERT INTO usage (subject, day, stt_seconds) VALUES (?1, ?2, ?3)
+             ON CONFLICT(subject, day) DO UPDATE SET stt_seconds = stt_seconds + excluded.stt_seconds",
+            params![subject, today(), seconds],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+
+    fn usage_locked(conn: &Connection, subject: &str) -> Result<(u64, f64), String> {
+        conn.query_row(
+            "SELECT COALESCE(SUM(tts_chars), 0), COALESCE(SUM(stt_seconds), 0) FROM usage
+             WHERE subject = ?1 AND day = ?2",
+            params![subject, today()],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| e.to_string())
+    }
+}
+
+/// Rate limiters and quotas, configured from the environment.
+pub struct Limits {
+    /// Per client IP
+    pub ip: RateLimiter,
+    /// Per API key, or per wallet for browser sessions
+    pub caller: RateLimiter,
+    pub quotas: Quotas,
+}
+
+impl Limits {
+    /// Builds the limits, storing usage in the database at `db_path`.
+    ///
+    /// # Environment Variables
+    ///
+    /// * `RATE_LIMIT_PER_MINUTE` / `RATE_LIMIT_BURST` - Per API key or wallet (default: 20 / 5)
+    /// * `IP_RATE_LIMIT_PER_MINUTE` / `IP_RATE_LIMIT_BURST` - Per client IP (default: 60 / 20)
+    /// * `DAILY_TTS_CHARS` - Text-to-speech characters per caller per day (default: 20000; 0 = unlimited)
+    /// * `DAILY_STT_SECONDS` - Speech-to-text seconds per caller per day (default: 1800; 0 = unlimited)
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the usage table cannot be opened.
+    pub fn from_env(db_path: &str) -> Result<Self, String> {
+        Ok(Self {
+            ip: RateLimiter::new(
+                env_number("IP_RATE_LIMIT_PER_MINUTE", 60) as u32,
+                env_number("IP_RATE_LIMIT_BURST", 20) as u32,
+            ),
+            caller: RateLimiter::new(
+                env_number("RATE_LIMIT_PER_MINUTE", 20) as u32,
+                env_number("RATE_LIMIT_BURST", 5) as u32,
+            ),
+            quotas: Quotas::open(
+                db_path,
+                env_number("DAILY_TTS_CHARS", 20_000),
+                env_number("DAILY_STT_SECONDS", 1_800),
+            )?,
+        })
+    }
+
+    /// Today's usage and limits of a caller, for `GET /usage`.
+    pub fn usage(&self, user: &AuthUser) -> Result<UsageResponse, String> {
+        let (tts_chars, stt_seconds) = self.quotas.usage(&user.subject())?;
+        Ok(UsageResponse {
+            day: today(),
+            tts_chars,
+            tts_chars_limit: self.quotas.tts_chars,
+            stt_seconds,
+            stt_seconds_limit: self.quotas.stt_seconds,
+            resets_in_secs: until_midnight().as_secs(),
+        })
+    }
+}
+
+/// Middleware applying only the per-IP rate limit, for the unauthenticated
+/// sign-in endpoints.
+pub async fn enforce_ip(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(response) = check_ip(&state, &addr) {
+        return response;
+    }
+    next.run(request).await
+}
+
+/// Middleware applying the rate limits and quotas to a request.
+///
+/// Unauthenticated requests are only limited per IP; the handler rejects them.
+/// A `429` returned by the handler itself (a quota it charges) gets a
+/// `Retry-After` until the quotas reset.
+pub async fn enforce(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(response) = check_ip(&state, &addr) {
+        return response;
+    }
+
+    if let Ok(user) = AuthUser::from_headers(&state, request.headers()) {
+        let subject = user.subject();
+        if let Err(retry_after) = state.limits.caller.check(&subject) {
+            tracing::info!("Rate limited {}", subject);
+            return too_many_requests(retry_after, "Too many requests for this API key or wallet");
+        }
+        match state.limits.quotas.exhausted(&subject) {
+            Ok(false) => {}
+            Ok(true) => {
+                tracing::info!("Daily quota exhausted for {}", subject);
+                return too_many_requests(until_midnight(), "Daily quota exhausted");
+            }
+            Err(e) => {
+                tracing::error!("Usage store failed: {}", e);
+                return (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json("Failed to check usage".to_string()),
+                )
+                    .into_response();
+            }
+        }
+    }
+
+    let mut response = next.run(request).await;
+    if response.status() == StatusCode::TOO_MANY_REQUESTS
+        && !response.headers().contains_key(header::RETRY_AFTER)
+    {
+        response
+            .headers_mut()
+            .insert(header::RETRY_AFTER, retry_after_value(until_midnight()));
+    }
+    response
+}
+
+/// Counts a request against its client IP's rate limit, returning the `429`
+/// response when the IP is over it.
+fn check_ip(state: &AppState, addr: &SocketAddr) -> Option<Response> {
+    let ip = addr.ip().to_string();
+    let retry_after = state.limits.ip.check(&ip).err()?;
+    tracing::info!("Rate limited IP {}", ip);
+    Some(too_many_requests(
+        retry_after,
+        "Too many requests from this IP address",
+    ))
+}
+
+/// `429 Too Many Requests` with a `Retry-After` header.
+fn too_many_requests(retry_after: Duration, message: &str) -> Response {
+    (
+        StatusCode::TOO_MANY_REQUESTS,
+        [(header::RETRY_AFTER, retry_after_value(retry_after))],
+        Json(message.to_string()),
+    )
+        .into_response()
+}
+
+/// `Retry-After` in whole seconds, rounded up.
+fn retry_after_value(retry_after: Duration) -> HeaderValue {
+    HeaderValue::from(retry_after.as_secs_f64().ceil().max(1.0) as u64)
+}
+
+/// Time until the quotas reset at midnight UTC.
+fn until_midnight() -> Duration {
+    let now = chrono::Utc::now();
+    let midnight = (now.date_naive() + chrono::Days::new(1))
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time")
+        .and_utc();
+    (midnight - now).to_std().unwrap_or_default()
+}
+
+/// The current quota day (UTC), e.g. `2026-10-17`.
+fn today() -> String {
+    chrono::Utc::now().format("%Y-%m-%d").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rate_limiter_allows_a_burst_then_refills() {
+        let limiter = RateLimiter::new(60, 3);
+        let start = Instant::now();
+        for _ in 0..3 {
+            assert!(limiter.check_at("a", start).is_ok());
+        }
+        // One token per second at 60 per minute
+        assert_eq!(limiter.check_at("a", start), Err(Duration::from_secs(1)));
+        // Other keys have their own bucket
+        assert!(limiter.check_at("b", start).is_ok());
+
+        let later = start + Duration::from_millis(500);
+        let retry_after = limiter.check_at("a", later).unwrap_err();
+        assert!((retry_after.as_secs_f64() - 0.5).abs() < 1e-6);
+        assert!(
+            limiter
+                .check_at("a", start + Duration::from_secs(1))
+                .is_ok()
+        );
+        assert!(
+            limiter
+                .check_at("a", start + Duration::from_secs(1))
+                .is_err()
+        );
+
+        // The bucket never holds more than the burst
+        let much_later = start + Duration::from_secs(3600);
+        for _ in 0..3 {
+            assert!(limiter.check_at("a", much_later).is_ok());
+        }
+        assert!(limiter.check_at("a", much_later).is_err());
+    }
+
+    #[test]
+    fn rate_limiter_without_a_rate_only_allows_the_burst() {
+        let limiter = RateLimiter::new(0, 2);
+        let start = Instant::now();
+        assert!(limiter.check_at("a", start).is_ok());
+        assert!(limiter.check_at("a", start).is_ok());
+        let retry_after = limiter
+            .check_at("a", start + Duration::from_secs(3600))
+            .unwrap_err();
+        assert!(retry_after <= Duration::from_secs(24 * 60 * 60));
+
+        // A burst of 0 still lets one request through
+        let limiter = RateLimiter::new(0, 0);
+        assert!(limiter.check_at("a", start).is_ok());
+        assert!(limiter.check_at("a", start).is_err());
+    }
+
+    #[test]
+    fn rate_limiter_drops_full_buckets_when_there_are_too_many() {
+        let limiter = RateLimiter::new(60, 1);
+        let start = Instant::now();
+        for i in 0..MAX_IDLE_BUCKETS {
+            limiter.check_at(&i.to_string(), start).unwrap();
+        }
+        // After a second every bucket is full again and can be forgotten
+        limiter
+            .check_at("new", start + Duration::from_secs(1))
+            .unwrap();
+        assert_eq!(limiter.buckets.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn stt_charges_stop_at_the_quota() {
+        let quotas = Quotas::open(":memory:", 100, 60).unwrap();
+        assert_eq!(quotas.try_charge_stt("key:a", 45.5), Ok(true));
+        assert_eq!(quotas.try_charge_stt("key:a", 20.0), Ok(false));
+        assert_eq!(quotas.usage("key:a"), Ok((0, 45.5)));
+        assert_eq!(quotas.exhausted("key:a"), Ok(false));
+
+        assert_eq!(quotas.try_charge_stt("key:a", 14.5), Ok(true));
+        assert_eq!(quotas.exhausted("key:a"), Ok(true));
+        assert_eq!(quotas.try_charge_stt("key:a", 0.1), Ok(false));
+        // Other callers are not affected
+        assert_eq!(quotas.exhausted("wallet:0xb"), Ok(false));
+    }
+
+    #[test]
+    fn tts_charges_exhaust_the_quota() {
+        let quotas = Quotas::open(":memory:", 100, 60).unwrap();
+        quotas.charge_tts("key:a", 60).unwrap();
+        assert_eq!(quotas.exhausted("key:a"), Ok(false));
+        // Charged even past the quota, since the reply was already synthesized
+        quotas.charge_tts("key:a", 60).unwrap();
+        assert_eq!(quotas.usage("key:a"), Ok((120, 0.0)));
+        assert_eq!(quotas.exhausted("key:a"), Ok(true));
+    }
+
+    #[test]
+    fn zero_disables_a_quota() {
+        let quotas = Quotas::open(":memory:", 0, 0).unwrap();
+        assert_eq!(quotas.try_charge_stt("key:a", 1e6), Ok(true));
+        quotas.charge_tts("key:a", 1_000_000).unwrap();
+        assert_eq!(quotas.exhausted("key:a"), Ok(false));
+    }
+
+    #[test]
+    fn retry_after_is_rounded_up_to_whole_seconds() {
+        assert_eq!(retry_after_value(Duration::from_millis(1500)), "2");
+        assert_eq!(retry_after_value(Duration::from_secs(30)), "30");
+        assert_eq!(retry_after_value(Duration::ZERO), "1");
+
+        let response = too_many_requests(Duration::from_millis(200), "slow down");
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+    }
+}
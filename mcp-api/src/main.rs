@@ -15,22 +15,58 @@
 //!
 //! - `GET /health` - Health check endpoint
 //! - `GET /agents` - List all available agents from MCP
+//! - `GET /auth/nonce` - Get a nonce for a Sign-In with Ethereum message
+//! - `POST /auth/verify` - Verify a signed SIWE message and get a session token
 //! - `POST /input/text` - Process text input and return agent response with audio
+//! - `POST /input/text/stream` - Stream the agent response as Server-Sent Events, then audio
 //! - `POST /input/audio` - Process audio input, transcribe, and return agent response
+//! - `GET /conversations` - List stored conversations, most recently active first
+//! - `GET /conversations/{id}` - Get a stored conversation with all of its turns
+//! - `DELETE /conversations/{id}` - Delete a conversation, its audio files and its MCP session
+//! - `POST /conversations/{id}/mint` - Start minting a conversation turn as an NFT through web3-minting
+//! - `GET /conversations/{id}/mint/{job_id}` - Get the progress of a conversation turn's mint
+//! - `POST /keys` - Create an API key for the signed-in wallet
+//! - `GET /keys` - List the signed-in wallet's API keys
+//! - `DELETE /keys/{id}` - Revoke an API key
+//! - `GET /usage` - Get the caller's usage of today's quotas
+//! - `GET /public/audio/{filename}` - Serve a generated audio file from the audio store
+//!
+//! The `/input`, `/conversations`, `/keys` and `/usage` endpoints require a session
+//! token from `POST /auth/verify` (see [`auth`]) or an API key (see [`keys`]). The
+//! `/input` endpoints and `POST /conversations/{id}/mint` are rate limited per
+//! client IP and per key or wallet, and speech is subject to daily quotas (see
+//! [`limits`]).
 
+use audio::AudioStore;
+use auth::Auth;
 use axum::{
     Router,
+    http::{HeaderValue, Method, header},
     response::IntoResponse,
-    routing::{get, post},
+    middleware,
+    routing::{delete, get, post},
 };
+use conversations::ConversationStore;
+use keys::ApiKeyStore;
+use limits::Limits;
+use mcp_rpc::McpClient;
+use minting::MintingClient;
 use reqwest::Client;
+use speech::{SpeechToText, TextToSpeech};
 use std::net::SocketAddr;
 use std::sync::Arc;
-use tower_http::cors::{Any, CorsLayer};
-use tower_http::services::ServeDir;
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 
+mod audio;
+mod auth;
+mod conversations;
 mod handlers;
+mod keys;
+mod limits;
+mod minting;
 mod models;
+mod speech;
 
 /// Application state shared across all request handlers.
 ///
@@ -38,13 +74,25 @@ mod models;
 /// providing thread-safe access to shared resources.
 #[derive(Clone)]
 struct AppState {
-    /// Shared HTTP client for making requests to external services (MCP, ElevenLabs).
-    /// Using a single client enables connection pooling and better performance.
-    http_client: Client,
-    /// ElevenLabs API key for STT and TTS operations.
-    elevenlabs_api_key: String,
-    /// Directory path where generated audio files are stored.
-    audio_dir: String,
+    /// Typed JSON-RPC client for the MCP server at `MCP_SERVER_URL`.
+    /// It shares the HTTP client of the speech providers for connection pooling.
+    mcp: McpClient,
+    /// Speech-to-text provider selected by `STT_PROVIDER`.
+    stt: Arc<dyn SpeechToText>,
+    /// Text-to-speech provider selected by `TTS_PROVIDER`.
+    tts: Arc<dyn TextToSpeech>,
+    /// Generated audio, in `AUDIO_DIR` or an S3-compatible bucket.
+    audio: Arc<dyn AudioStore>,
+    /// SQLite store of conversations and their turns at `CONVERSATIONS_DB_PATH`.
+    conversations: Arc<ConversationStore>,
+    /// Client for the web3-minting service at `WEB3_MINTING_URL`.
+    minting: MintingClient,
+    /// Sign-In with Ethereum nonces and session tokens.
+    auth: Arc<Auth>,
+    /// Hashed API keys, stored alongside the conversations.
+    keys: Arc<ApiKeyStore>,
+    /// Rate limiters and daily quotas.
+    limits: Arc<Limits>,
 }
 
 /// Main entry point for the MCP API server.
@@ -68,44 +116,151 @@ async fn main() {
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
-    let elevenlabs_api_key = std::env::var("ELEVENLABS_API_KEY")
-        .expect("ELEVENLABS_API_KEY must be set in .env file");
-    
-    let audio_dir = std::env::var("AUDIO_DIR").unwrap_or_else(|_| "public/audio".to_string());
-    std::fs::create_dir_all(&audio_dir).expect("Failed to create audio directory");
-
     let shared_client = Client::new();
-    
+
+    let audio = audio::from_env(&shared_client);
+    tracing::info!("Audio files are stored in {}", audio.location());
+    let cleanup_interval = env_number("AUDIO_CLEANUP_INTERVAL_SECS", 60 * 60);
+    audio::spawn_cleanup(audio.clone(), Duration::from_secs(cleanup_interval));
+
+    let mcp_url = std::env::var("MCP_SERVER_URL").expect("MCP_SERVER_URL not set");
+    let mcp = McpClient::with_client(shared_client.clone(), mcp_url);
+
+    let stt = speech::stt_from_env(&shared_client);
+    let tts = speech::tts_from_env(&shared_client);
+    tracing::info!("Speech-to-text provider: {}", stt.name());
+    tracing::info!("Text-to-speech provider: {}", tts.name());
+    tracing::info!("Using MCP server at {}", mcp.url());
+
+    let db_path =
+        std::env::var("CONVERSATIONS_DB_PATH").unwrap_or_else(|_| "conversations.db".to_string());
+    let conversations =
+        ConversationStore::open(&db_path).expect("Failed to open conversation database");
+    tracing::info!("Storing conversations in {}", db_path);
+
+    let minting = MintingClient::from_env(shared_client.clone());
+    tracing::info!("Using web3-minting at {}", minting.url());
+
+    let auth = Auth::from_env();
+    tracing::info!("Accepting Sign-In with Ethereum for {}", auth.domain());
+
+    let keys = ApiKeyStore::open(&db_path).expect("Failed to open API key database");
+    let limits = Limits::from_env(&db_path).expect("Failed to open usage database");
+    tracing::info!(
+        "Daily quotas: {} TTS characters, {} STT seconds",
+        limits.quotas.tts_chars,
+        limits.quotas.stt_seconds
+    );
+
     let app_state = Arc::new(AppState {
-        http_client: shared_client,
-        elevenlabs_api_key,
-        audio_dir: audio_dir.clone(),
+        mcp,
+        stt,
+        tts,
+        audio,
+        conversations: Arc::new(conversations),
+        minting,
+        auth: Arc::new(auth),
+        keys: Arc::new(keys),
+        limits: Arc::new(limits),
     });
 
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    let cors = cors_from_env();
+
+    // Endpoints that spend provider credits or gas
+    let limited = Router::new()
+        .route("/input/text", post(handlers::handle_text_input))
+        .route("/input/text/stream", post(handlers::handle_text_input_stream))
+        .route("/input/audio", post(handlers::handle_audio_input))
+        .route("/conversations/{id}/mint", post(handlers::mint_conversation))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), limits::enforce));
+
+    // Sign-in is unauthenticated, so it is only limited per IP
+    let sign_in = Router::new()
+        .route("/auth/nonce", get(handlers::get_nonce))
+        .route("/auth/verify", post(handlers::verify_signature))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), limits::enforce_ip));
 
     let app = Router::new()
         .route("/health", get(health_check))
         .route("/agents", get(handlers::get_agents_list))
-        .route("/input/text", post(handlers::handle_text_input))
-        .route("/input/audio", post(handlers::handle_audio_input))
-        .nest_service("/public", ServeDir::new("public"))
+        .route("/conversations", get(handlers::list_conversations))
+        .route(
+            "/conversations/{id}",
+            get(handlers::get_conversation).delete(handlers::delete_conversation),
+        )
+        .route("/conversations/{id}/mint/{job_id}", get(handlers::get_conversation_mint))
+        .route("/keys", get(handlers::list_api_keys).post(handlers::create_api_key))
+        .route("/keys/{id}", delete(handlers::delete_api_key))
+        .route("/usage", get(handlers::get_usage))
+        .merge(sign_in)
+        .merge(limited)
+        .route("/public/audio/{filename}", get(handlers::serve_audio))
         .layer(cors)
         .with_state(app_state);
 
     let addr = SocketAddr::from(([127, 0, 0, 1], 8000));
     tracing::info!("Server listening on http://{}", addr);
-    tracing::info!("Audio files will be stored in: {}", audio_dir);
 
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app.into_make_service())
+    // Client addresses are needed for the per-IP rate limit
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .await
         .unwrap();
 }
 
+/// Builds the CORS layer from `CORS_ORIGINS`.
+///
+/// Only the listed origins may call the API from a browser; `*` allows any
+/// origin. Session tokens are sent as `Authorization` headers, not cookies, so
+/// credentials are not allowed. `Retry-After` is exposed so browsers can read
+/// when a rate-limited request may be retried.
+///
+/// # Environment Variables
+///
+/// - `CORS_ORIGINS` - Comma-separated origins (optional, defaults to "http://localhost:5173")
+///
+/// # Panics
+///
+/// Panics if an origin is not a valid header value.
+fn cors_from_env() -> CorsLayer {
+    let origins =
+        std::env::var("CORS_ORIGINS").unwrap_or_else(|_| "http://localhost:5173".to_string());
+    let allow_origin = if origins.trim() == "*" {
+        AllowOrigin::any()
+    } else {
+        let origins: Vec<HeaderValue> = origins
+            .split(',')
+            .map(str::trim)
+            .filter(|origin| !origin.is_empty())
+            .map(|origin| origin.parse().expect("Invalid origin in CORS_ORIGINS"))
+            .collect();
+        AllowOrigin::list(origins)
+    };
+    tracing::info!("Allowing CORS from: {}", origins);
+
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::DELETE])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+        .expose_headers([header::RETRY_AFTER])
+}
+
+/// Reads a numeric environment variable, falling back to a default.
+///
+/// Shared by the modules configured from numeric variables, such as durations in
+/// seconds, sizes and rate limits.
+///
+/// # Arguments
+///
+/// * `key` - Environment variable name
+/// * `default` - Value used when the variable is unset or not a number
+fn env_number(key: &str, default: u64) -> u64 {
+    std::env::var(key)
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(default)
+}
+
 /// Health check endpoint handler.
 ///
 /// Returns a simple "OK" response to indicate the server is running.
@@ -0,0 +1,194 @@
+//! Client for the web3-minting service.
+//!
+//! Minting a conversation turn takes three calls to web3-minting: the reply's audio
+//! is uploaded with `POST /assets`, a mint job referencing it is queued with
+//! `POST /mint`, and the job is read with `GET /mint/{id}` whenever the client
+//! polls it through mcp-api.
+
+use reqwest::multipart::{Form, Part};
+use reqwest::{Client, Method, RequestBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Talks to web3-minting over HTTP.
+#[derive(Clone)]
+pub struct MintingClient {
+    client: Client,
+    base_url: String,
+    /// Service token web3-minting requires on its mint and asset routes
+    token: Option<String>,
+}
+
+/// A token trait in the mint request's metadata.
+#[derive(Serialize)]
+pub struct MintAttribute {
+    pub trait_type: String,
+    pub value: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_type: Option<String>,
+}
+
+/// Request body of web3-minting's `POST /mint`.
+#[derive(Serialize)]
+pub struct MintRequest {
+    pub name: String,
+    pub description: String,
+    /// URL of the uploaded audio
+    pub animation_url: String,
+    pub attributes: Vec<MintAttribute>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recipient: Option<String>,
+    /// Chain configured in web3-minting; its default chain when `None`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chain: Option<String>,
+}
+
+/// A mint job as returned by web3-minting's `GET /mint/{id}`.
+#[derive(Deserialize)]
+pub struct MintJob {
+    /// `pending`, `uploading`, `submitted`, `confirmed` or `failed`
+    pub status: String,
+    pub upload: Option<Upload>,
+    pub mint: Option<MintResult>,
+    pub error: Option<String>,
+}
+
+/// Where the token metadata was stored.
+#[derive(Deserialize)]
+pub struct Upload {
+    pub url: String,
+}
+
+/// The mint transaction of a job.
+#[derive(Deserialize)]
+pub struct MintResult {
+    pub tx_hash: String,
+    pub token_id: Option<String>,
+    #[serde(default)]
+    pub chain_id: Option<u64>,
+    #[serde(default)]
+    pub explorer_url: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UploadedAsset {
+    url: String,
+}
+
+#[derive(Deserialize)]
+struct MintAccepted {
+    job_id: String,
+}
+
+impl MintingClient {
+    /// Configures the client from environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// * `WEB3_MINTING_URL` - Base URL of web3-minting (default `http://localhost:8081`)
+    /// * `MINT_API_TOKEN` - Service token configured in web3-minting
+    pub fn from_env(client: Client) -> Self {
+        let base_url = std::env::var("WEB3_MINTING_URL")
+            .unwrap_or_else(|_| "http://localhost:8081".to_string());
+        let token = std::env::var("MINT_API_TOKEN")
+            .ok()
+            .filter(|token| !token.is_empty());
+        if token.is_none() {
+            tracing::warn!("MINT_API_TOKEN is not set; web3-minting will reject mints");
+        }
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+        }
+    }
+
+    /// Base URL of web3-minting.
+    pub fn url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Starts a request to web3-minting, authenticated with the service token.
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let request = self
+            .client
+            .request(method, format!("{}{}", self.base_url, path));
+        match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Uploads a file to web3-minting's storage.
+    ///
+    /// # Returns
+    ///
+    /// The URL of the stored file, or a description of the failure.
+    pub async fn upload_asset(
+        &self,
+        bytes: Vec<u8>,
+        filename: &str,
+        content_type: &str,
+    ) -> Result<String, String> {
+        let file = Part::bytes(bytes)
+            .file_name(filename.to_string())
+            .mime_str(content_type)
+            .map_err(|e| format!("Invalid asset MIME type: {}", e))?;
+        let response = self
+            .request(Method::POST, "/assets")
+            .multipart(Form::new().part("file", file))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach web3-minting: {}", e))?;
+        let asset: UploadedAsset = json_response(response).await?;
+        Ok(asset.url)
+    }
+
+    /// Queues a mint job.
+    ///
+    /// # Returns
+    ///
+    /// The ID of the job, or a description of the failure.
+    pub async fn start_mint(&self, request: &MintRequest) -> Result<String, String> {
+        let response = self
+            .request(Method::POST, "/mint")
+            .json(request)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach web3-minting: {}", e))?;
+        let accepted: MintAccepted = json_response(response).await?;
+        Ok(accepted.job_id)
+    }
+
+    /// Fetches a mint job.
+    ///
+    /// # Returns
+    ///
+    /// The job with its current status, or a description of the failure.
+    pub async fn get_job(&self, job_id: &str) -> Result<MintJob, String> {
+        let response = self
+            .request(Method::GET, &format!("/mint/{}", job_id))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to reach web3-minting: {}", e))?;
+        json_response(response).await
+    }
+}
+
+/// Parses a JSON response, turning error statuses into their `error` message.
+async fn json_response<T: serde::de::DeserializeOwned>(
+    response: reqwest::Response,
+) -> Result<T, String> {
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v["error"].as_str().map(str::to_string))
+            .unwrap_or(body);
+        return Err(format!("web3-minting returned {}: {}", status, message));
+    }
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid response from web3-minting: {}", e))
+}
@@ -1,14 +1,16 @@
 //! Data models and types for the MCP API server.
 //!
-//! This module defines all the request and response types used in the API,
-//! including JSON-RPC protocol structures for communication with the MCP server.
+//! This module defines the request and response types of the REST API. The
+//! JSON-RPC types for communication with the MCP server come from the `mcp-rpc`
+//! crate.
 
 use serde::{Deserialize, Serialize};
 
 /// Information about an AI agent available in the system.
 ///
 /// This struct represents an agent that can process user requests and generate responses.
-/// Agents are retrieved from the MCP server via the `GET /agents` endpoint.
+/// Agents are retrieved from the MCP server and returned by the `GET /agents` endpoint;
+/// only the public fields are exposed, not the agent's prompt or model settings.
 ///
 /// # Fields
 ///
@@ -22,48 +24,14 @@ pub struct AgentInfo {
     pub description: String,
 }
 
-/// Response from the MCP server's list_agents method.
-///
-/// This struct wraps the array of agents returned by the MCP server.
-///
-/// # Fields
-///
-/// * `agents` - List of available agents
-#[derive(Serialize, Deserialize)]
-pub struct ListAgentsResult {
-    pub agents: Vec<AgentInfo>,
-}
-
-/// Response from the MCP server's process_text method.
-///
-/// This struct represents the result of processing user text through an agent.
-///
-/// # Fields
-///
-/// * `agent_id` - ID of the agent that processed the text
-/// * `reply_text` - The agent's text response
-/// * `metadata` - Additional metadata about the processing
-#[derive(Serialize, Deserialize)]
-pub struct ProcessTextResult {
-    pub agent_id: String,
-    pub reply_text: String,
-    pub metadata: ProcessingMetadata,
-}
-
-/// Metadata about the agent's text processing.
-///
-/// # Fields
-///
-/// * `model` - The AI model used
-/// * `tokens_used` - Number of tokens consumed (if available)
-/// * `processing_time_ms` - Time taken to process in milliseconds
-/// * `confidence` - Confidence score of the response
-#[derive(Serialize, Deserialize)]
-pub struct ProcessingMetadata {
-    pub model: String,
-    pub tokens_used: Option<u32>,
-    pub processing_time_ms: u64,
-    pub confidence: f64,
+impl From<mcp_rpc::Agent> for AgentInfo {
+    fn from(agent: mcp_rpc::Agent) -> Self {
+        Self {
+            id: agent.id,
+            name: agent.name,
+            description: agent.description,
+        }
+    }
 }
 
 /// Request payload for text input from the user.
@@ -75,19 +43,24 @@ pub struct ProcessingMetadata {
 ///
 /// * `agent_id` - ID of the agent that should process the text
 /// * `user_text` - The actual text input from the user
+/// * `session_id` - Session returned by a previous reply; a new session is
+///   started when omitted
 ///
 /// # Example
 ///
 /// ```json
 /// {
 ///   "agent_id": "agent_001",
-///   "user_text": "Hello, how are you?"
+///   "user_text": "Hello, how are you?",
+///   "session_id": "5f0c6a8e-3f51-4c1e-9d3b-2a7f4f3b9c11"
 /// }
 /// ```
 #[derive(Deserialize)]
 pub struct InputTextRequest {
     pub agent_id: String,
     pub user_text: String,
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
 /// Response containing the agent's reply in both text and audio formats.
@@ -99,95 +72,367 @@ pub struct InputTextRequest {
 ///
 /// * `reply_text` - The agent's text response
 /// * `audio_url` - URL to the audio file containing the spoken response
+/// * `session_id` - Session to send with the next message to continue the conversation
+/// * `turn` - Index of this exchange in the conversation, for `POST /conversations/{id}/mint`
 ///
 /// # Example
 ///
 /// ```json
 /// {
 ///   "reply_text": "I'm doing great! How can I help you?",
-///   "audio_url": "https://example.com/audio/response.mp3"
+///   "audio_url": "https://example.com/audio/response.mp3",
+///   "session_id": "5f0c6a8e-3f51-4c1e-9d3b-2a7f4f3b9c11",
+///   "turn": 0
 /// }
 /// ```
 #[derive(Serialize)]
 pub struct AgentReplyResponse {
     pub reply_text: String,
     pub audio_url: String,
+    pub session_id: String,
+    pub turn: usize,
+}
+
+/// Error payload of an `error` event on `POST /input/text/stream`.
+///
+/// # Fields
+///
+/// * `message` - Human-readable description of the failure
+#[derive(Serialize, Deserialize)]
+pub struct StreamError {
+    pub message: String,
+}
+
+/// Request payload for minting a conversation turn as an NFT.
+///
+/// # Fields
+///
+/// * `turn` - Index of the turn to mint (from `AgentReplyResponse::turn`);
+///   the latest turn when omitted
+/// * `recipient` - Wallet address or `.eth` name that receives the token;
+///   web3-minting's own wallet when omitted
+/// * `chain` - Name of a chain configured in web3-minting; its default chain when omitted
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "turn": 2,
+///   "recipient": "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266",
+///   "chain": "base-sepolia"
+/// }
+/// ```
+#[derive(Deserialize)]
+pub struct MintConversationRequest {
+    #[serde(default)]
+    pub turn: Option<usize>,
+    #[serde(default)]
+    pub recipient: Option<String>,
+    #[serde(default)]
+    pub chain: Option<String>,
 }
 
-/// Generic JSON-RPC 2.0 request structure.
+/// A conversation turn's mint job, as returned by `POST /conversations/{id}/mint`.
 ///
-/// This struct is used to construct requests to the MCP server following the
-/// JSON-RPC 2.0 specification. The params field is generic to allow different
-/// parameter types for different RPC methods.
+/// # Fields
 ///
-/// # Type Parameters
+/// * `job_id` - Mint job to poll with `GET /conversations/{id}/mint/{job_id}`
+/// * `turn` - Index of the minted turn
 ///
-/// * `T` - The type of the params field, typically a JSON object
+/// # Example
+///
+/// ```json
+/// {
+///   "job_id": "0b6f5a3c-6f0e-4c0a-9d55-64b1f6e0a3d2",
+///   "turn": 2
+/// }
+/// ```
+#[derive(Serialize)]
+pub struct MintConversationResponse {
+    pub job_id: String,
+    pub turn: usize,
+}
+
+/// Progress of a conversation turn's mint job.
 ///
 /// # Fields
 ///
-/// * `jsonrpc` - Protocol version, always "2.0"
-/// * `method` - Name of the RPC method to call
-/// * `params` - Parameters for the method call
-/// * `id` - Request identifier for matching responses
+/// * `job_id` - Mint job on web3-minting
+/// * `turn` - Index of the minted turn
+/// * `status` - `pending`, `uploading`, `submitted`, `confirmed` or `failed`
+/// * `audio_url` - Where the reply's audio was stored
+/// * `metadata_url` - Where the token metadata was stored, once uploaded
+/// * `tx_hash` - Hash of the mint transaction, once sent
+/// * `token_id` - ID of the minted token, if it could be determined
+/// * `chain_id` - Chain the token is minted on (absent for mock mints)
+/// * `explorer_url` - The transaction on the chain's block explorer, if configured
+/// * `error` - Why the job failed, or why a sent transaction is not confirmed yet
 ///
 /// # Example
 ///
 /// ```json
 /// {
-///   "jsonrpc": "2.0",
-///   "method": "call_agent",
-///   "params": {
-///     "agent_id": "agent_001",
-///     "text": "Hello"
+///   "job_id": "0b6f5a3c-6f0e-4c0a-9d55-64b1f6e0a3d2",
+///   "turn": 2,
+///   "status": "confirmed",
+///   "audio_url": "https://ipfs.io/ipfs/bafkrei...",
+///   "metadata_url": "https://ipfs.io/ipfs/bafkrei...",
+///   "tx_hash": "0x8f3c...",
+///   "token_id": "7",
+///   "chain_id": 84532,
+///   "explorer_url": "https://sepolia.basescan.org/tx/0x8f3c...",
+///   "error": null
+/// }
+/// ```
+#[derive(Serialize)]
+pub struct MintStatusResponse {
+    pub job_id: String,
+    pub turn: usize,
+    pub status: String,
+    pub audio_url: String,
+    pub metadata_url: Option<String>,
+    pub tx_hash: Option<String>,
+    pub token_id: Option<String>,
+    pub chain_id: Option<u64>,
+    pub explorer_url: Option<String>,
+    pub error: Option<String>,
+}
+
+/// One exchange of a stored conversation: the user's message and the agent's reply.
+///
+/// # Fields
+///
+/// * `turn` - Index of the turn in the conversation, starting at 0
+/// * `agent_id` - Agent that replied
+/// * `user_text` - The user's text, or the transcript of their audio
+/// * `reply_text` - The agent's reply
+/// * `audio_url` - Public URL path of the reply's audio
+/// * `metadata` - How the MCP server produced the reply (provider, model, tokens, timing)
+/// * `created_at` - When the turn was stored (RFC 3339)
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "turn": 0,
+///   "agent_id": "crypto_valet",
+///   "user_text": "What is a gas fee?",
+///   "reply_text": "A gas fee is what you pay the network to process a transaction...",
+///   "audio_url": "/public/audio/123e4567-e89b-12d3-a456-426614174000.mp3",
+///   "metadata": {
+///     "provider": "openai",
+///     "model": "gpt-4o-mini",
+///     "tokens_used": 142,
+///     "processing_time_ms": 1840,
+///     "confidence": 0.95
 ///   },
-///   "id": 1
+///   "created_at": "2026-10-17T09:30:12.345678+00:00"
 /// }
 /// ```
 #[derive(Serialize)]
-pub struct JsonRpcRequest<T> {
-    pub jsonrpc: &'static str,
-    pub method: &'static str,
-    pub params: T,
-    pub id: u32,
+pub struct Turn {
+    pub turn: usize,
+    pub agent_id: String,
+    pub user_text: String,
+    pub reply_text: String,
+    pub audio_url: String,
+    pub metadata: mcp_rpc::ProcessingMetadata,
+    pub created_at: String,
 }
 
-/// Generic JSON-RPC 2.0 response structure.
+/// A stored conversation with all of its turns, returned by `GET /conversations/{id}`.
 ///
-/// This struct represents responses received from the MCP server following the
-/// JSON-RPC 2.0 specification. The result field is generic to accommodate
-/// different response types for different RPC methods.
+/// # Fields
 ///
-/// # Type Parameters
+/// * `id` - Session ID of the conversation
+/// * `agent_id` - Agent of the latest turn
+/// * `created_at` - When the first turn was stored (RFC 3339)
+/// * `updated_at` - When the latest turn was stored (RFC 3339)
+/// * `turns` - The turns, in order
+#[derive(Serialize)]
+pub struct Conversation {
+    pub id: String,
+    pub agent_id: String,
+    pub created_at: String,
+    pub updated_at: String,
+    pub turns: Vec<Turn>,
+}
+
+/// A stored conversation as listed by `GET /conversations`.
 ///
-/// * `T` - The type of the result field
+/// # Fields
+///
+/// * `id` - Session ID of the conversation
+/// * `agent_id` - Agent of the latest turn
+/// * `turn_count` - Number of turns in the conversation
+/// * `preview` - The user's first message
+/// * `created_at` - When the first turn was stored (RFC 3339)
+/// * `updated_at` - When the latest turn was stored (RFC 3339)
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "id": "b7d2c1a0-5f3e-4e8b-9c6d-2a1f0e9d8c7b",
+///   "agent_id": "crypto_valet",
+///   "turn_count": 3,
+///   "preview": "What is a gas fee?",
+///   "created_at": "2026-10-17T09:30:12.345678+00:00",
+///   "updated_at": "2026-10-17T09:34:50.123456+00:00"
+/// }
+/// ```
+#[derive(Serialize)]
+pub struct ConversationSummary {
+    pub id: String,
+    pub agent_id: String,
+    pub turn_count: usize,
+    pub preview: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+/// Query parameters of `GET /conversations`.
 ///
 /// # Fields
 ///
-/// * `jsonrpc` - Protocol version, always "2.0"
-/// * `result` - The result data from the RPC call
-/// * `id` - Request identifier matching the original request
+/// * `limit` - Maximum number of conversations to return (default 50, at most 200)
+/// * `offset` - Number of conversations to skip, for paging (default 0)
+#[derive(Deserialize)]
+pub struct ListConversationsQuery {
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub offset: Option<usize>,
+}
+
+/// Response of `GET /auth/nonce`.
 ///
-/// # Note
+/// # Fields
 ///
-/// This implementation does not include error handling fields. A production
-/// implementation should include a `JsonRpcError` variant.
+/// * `nonce` - Single-use nonce to put in the SIWE message; valid for 10 minutes
+/// * `domain` - Domain the SIWE message must be addressed to
 ///
 /// # Example
 ///
 /// ```json
 /// {
-///   "jsonrpc": "2.0",
-///   "result": {
-///     "reply": "Agent response text"
-///   },
-///   "id": 1
+///   "nonce": "4f3c2b1a0e9d8c7b6a5f4e3d2c1b0a99",
+///   "domain": "localhost:5173"
+/// }
+/// ```
+#[derive(Serialize)]
+pub struct NonceResponse {
+    pub nonce: String,
+    pub domain: String,
+}
+
+/// Request payload of `POST /auth/verify`.
+///
+/// # Fields
+///
+/// * `message` - The EIP-4361 message the wallet signed, exactly as signed
+/// * `signature` - The `personal_sign` signature of the message, in hex
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "message": "localhost:5173 wants you to sign in with your Ethereum account:\n0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266\n\nSign in to the Web3 concierge.\n\nURI: http://localhost:5173\nVersion: 1\nChain ID: 1\nNonce: 4f3c2b1a0e9d8c7b6a5f4e3d2c1b0a99\nIssued At: 2026-10-17T09:30:00.000Z",
+///   "signature": "0x5d1e...1b"
 /// }
 /// ```
 #[derive(Deserialize)]
-#[allow(dead_code)]
-pub struct JsonRpcResponse<T> {
-    pub jsonrpc: String,
-    pub result: T,
-    pub id: u32,
+pub struct VerifyRequest {
+    pub message: String,
+    pub signature: String,
+}
+
+/// Response of `POST /auth/verify`.
+///
+/// # Fields
+///
+/// * `token` - Session JWT, sent as `Authorization: Bearer <token>`
+/// * `address` - Wallet address that signed in, EIP-55 checksummed
+/// * `expires_at` - When the token expires (RFC 3339)
+#[derive(Serialize)]
+pub struct VerifyResponse {
+    pub token: String,
+    pub address: String,
+    pub expires_at: String,
+}
+
+/// An API key, as listed by `GET /keys`. The key itself is never returned again
+/// after it is created.
+///
+/// # Fields
+///
+/// * `id` - Key ID, used to delete the key
+/// * `name` - Label given when the key was created
+/// * `prefix` - First characters of the key, to tell keys apart
+/// * `created_at` - When the key was created (RFC 3339)
+/// * `last_used_at` - When the key last authenticated a request (RFC 3339), if ever
+#[derive(Serialize)]
+pub struct ApiKeyInfo {
+    pub id: String,
+    pub name: String,
+    pub prefix: String,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+}
+
+/// Request payload of `POST /keys`.
+///
+/// # Fields
+///
+/// * `name` - Label for the key, e.g. the script or service that uses it
+#[derive(Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+}
+
+/// Response of `POST /keys`.
+///
+/// # Fields
+///
+/// * `key` - The API key, sent as `Authorization: Bearer <key>`; it is only shown this once
+/// * The fields of [`ApiKeyInfo`]
+///
+/// # Example
+///
+/// ```json
+/// {
+///   "key": "mcp_3f9a1c07e4b25d68...",
+///   "id": "2b7e1516-28ae-4d2a-a6ab-f7158809cf4f",
+///   "name": "nightly report",
+///   "prefix": "mcp_3f9a1c07",
+///   "created_at": "2026-10-17T09:30:00+00:00",
+///   "last_used_at": null
+/// }
+/// ```
+#[derive(Serialize)]
+pub struct CreateApiKeyResponse {
+    pub key: String,
+    #[serde(flatten)]
+    pub info: ApiKeyInfo,
+}
+
+/// Response of `GET /usage`: today's usage of the caller's daily quotas.
+///
+/// Usage is counted per API key, or per wallet for browser sessions. A limit of 0
+/// means the quota is disabled.
+///
+/// # Fields
+///
+/// * `day` - The quota day (UTC)
+/// * `tts_chars` / `tts_chars_limit` - Text-to-speech characters synthesized
+/// * `stt_seconds` / `stt_seconds_limit` - Seconds of audio transcribed
+/// * `resets_in_secs` - Seconds until the quotas reset at midnight UTC
+#[derive(Serialize)]
+pub struct UsageResponse {
+    pub day: String,
+    pub tts_chars: u64,
+    pub tts_chars_limit: u64,
+    pub stt_seconds: f64,
+    pub stt_seconds_limit: u64,
+    pub resets_in_secs: u64,
 }
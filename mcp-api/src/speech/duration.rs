@@ -0,0 +1,446 @@
+//! Duration of uploaded audio, for the speech-to-text quota.
+//!
+//! Durations are worked out the way a decoder would, from the audio itself rather
+//! than from header fields a client could set to anything: WAV from the number of
+//! sample frames, MP3 from its frames, and WebM from the Opus packets it contains
+//! (what browsers' `MediaRecorder` produces). Bytes that cannot be accounted for
+//! this way, and formats that are not parsed at all, are charged as if they were
+//! 64 kbit/s audio.
+
+/// Bitrate assumed for audio whose duration cannot be worked out.
+const ASSUMED_BITRATE_BPS: f64 = 64_000.0;
+
+/// Estimates the duration of uploaded audio in seconds.
+pub(crate) fn estimate_secs(audio: &[u8]) -> f64 {
+    wav_secs(audio)
+        .or_else(|| mp3_secs(audio))
+        .or_else(|| webm_secs(audio))
+        .unwrap_or_else(|| assumed_secs(audio.len()))
+}
+
+/// Duration of bytes of audio at [`ASSUMED_BITRATE_BPS`].
+fn assumed_secs(bytes: usize) -> f64 {
+    bytes as f64 * 8.0 / ASSUMED_BITRATE_BPS
+}
+
+/// Duration of a RIFF/WAVE file: the sample frames in the `data` chunk over the
+/// sample rate.
+///
+/// The frame size must match the channels and sample size, and only
+/// uncompressed encodings are read; other files are left to the fallback.
+fn wav_secs(audio: &[u8]) -> Option<f64> {
+    if audio.len() < 12 || &audio[..4] != b"RIFF" || &audio[8..12] != b"WAVE" {
+        return None;
+    }
+    let u16_at = |offset: usize| {
+        audio
+            .get(offset..offset + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]))
+    };
+    let u32_at = |offset: usize| {
+        audio
+            .get(offset..offset + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+
+    // (sample rate, bytes per sample frame)
+    let mut format = None;
+    let mut offset = 12;
+    while offset + 8 <= audio.len() {
+        let id = &audio[offset..offset + 4];
+        let size = u32_at(offset + 4)? as usize;
+        let body = offset + 8;
+        match id {
+            b"fmt " => {
+                // PCM, IEEE float, A-law, mu-law or WAVE_FORMAT_EXTENSIBLE
+                if !matches!(u16_at(body)?, 1 | 3 | 6 | 7 | 0xfffe) {
+                    return None;
+                }
+                let channels = u32::from(u16_at(body + 2)?);
+                let sample_rate = u32_at(body + 4)?;
+                let block_align = u32::from(u16_at(body + 12)?);
+                let bits_per_sample = u32::from(u16_at(body + 14)?);
+                let valid = (1..=32).contains(&channels)
+                    && (1..=32).contains(&bits_per_sample)
+                    && (1..=768_000).contains(&sample_rate)
+                    && block_align == channels * bits_per_sample.div_ceil(8);
+                if !valid {
+                    return None;
+                }
+                format = Some((sample_rate, block_align));
+            }
+            b"data" => {
+                let (sample_rate, block_align) = format?;
+                // Streamed WAVs may leave the size unset; count what was uploaded
+                let size = size.min(audio.len() - body);
+                let frames = size / block_align as usize;
+                return Some(frames as f64 / f64::from(sample_rate));
+            }
+            _ => {}
+        }
+        // Chunks are padded to an even size
+        offset = body.checked_add(size)?.checked_add(size % 2)?;
+    }
+    None
+}
+
+/// Duration of an MP3 file (MPEG audio layer III, after any ID3v2 tag), summed
+/// over its frames.
+///
+/// Bytes after the last frame that could be read are charged at
+/// [`ASSUMED_BITRATE_BPS`]. Returns `None` if the file does not start with a frame.
+fn mp3_secs(audio: &[u8]) -> Option<f64> {
+    let mut offset = 0;
+    if audio.len() >= 10 && &audio[..3] == b"ID3" {
+        // The tag size is a 28-bit "syncsafe" integer, excluding the 10-byte header
+        let size = audio[6..10]
+            .iter()
+            .fold(0usize, |n, &b| (n << 7) | usize::from(b & 0x7f));
+        offset = 10 + size;
+    }
+
+    let mut secs = 0.0;
+    let mut frames = 0;
+    while let Some((length, frame_secs)) = audio.get(offset..offset + 4).and_then(mp3_frame) {
+        secs += frame_secs;
+        frames += 1;
+        offset += length;
+    }
+    if frames == 0 {
+        return None;
+    }
+    Some(secs + assumed_secs(audio.len().saturating_sub(offset)))
+}
+
+/// Length in bytes and duration in seconds of the layer III frame with a header.
+fn mp3_frame(header: &[u8]) -> Option<(usize, f64)> {
+    if header[0] != 0xff || header[1] & 0xe0 != 0xe0 {
+        return None;
+    }
+    let version = (header[1] >> 3) & 0b11;
+    let layer = (header[1] >> 1) & 0b11;
+    if layer != 0b01 {
+        return None;
+    }
+    const MPEG1_KBPS: [u32; 15] = [
+        0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320,
+    ];
+    const MPEG2_KBPS: [u32; 15] = [0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160];
+    const MPEG1_RATES: [u32; 3] = [44_100, 48_000, 32_000];
+    // MPEG-2 halves the sample rates of MPEG-1 and MPEG-2.5 quarters them, and
+    // both have half as many samples per frame
+    let (kbps_table, rate_divisor, samples) = match version {
+        0b11 => (&MPEG1_KBPS, 1, 1152),
+        0b10 => (&MPEG2_KBPS, 2, 576),
+        0b00 => (&MPEG2_KBPS, 4, 576),
+        _ => return None,
+    };
+    let kbps = *kbps_table
+        .get(usize::from(header[2] >> 4))
+        .filter(|&&kbps| kbps > 0)?;
+    let sample_rate = MPEG1_RATES.get(usize::from((header[2] >> 2) & 0b11))? / rate_divisor;
+    let padding = u32::from((header[2] >> 1) & 1);
+    let length = samples / 8 * kbps * 1000 / sample_rate + padding;
+    Some((length as usize, f64::from(samples) / f64::from(sample_rate)))
+}
+
+/// Matroska element IDs read by [`webm_secs`].
+mod ebml_id {
+    pub const EBML: u32 = 0x1a45_dfa3;
+    pub const SEGMENT: u32 = 0x1853_8067;
+    pub const TRACKS: u32 = 0x1654_ae6b;
+    pub const TRACK_ENTRY: u32 = 0xae;
+    pub const TRACK_NUMBER: u32 = 0xd7;
+    pub const CODEC_ID: u32 = 0x86;
+    pub const CLUSTER: u32 = 0x1f43_b675;
+    pub const BLOCK_GROUP: u32 = 0xa0;
+    pub const BLOCK: u32 = 0xa1;
+    pub const SIMPLE_BLOCK: u32 = 0xa3;
+}
+
+/// Duration of a WebM file with an Opus track, summed over its Opus packets.
+///
+/// Other blocks, such as laced blocks or those of other tracks, are charged at
+/// [`ASSUMED_BITRATE_BPS`]. Returns `None` for other files, including WebM files
+/// without an Opus track.
+fn webm_secs(audio: &[u8]) -> Option<f64> {
+    let ((id, _), _) = ebml_element(audio, 0)?;
+    if id != ebml_id::EBML {
+        return None;
+    }
+
+    // Track number and codec of each track entry
+    let mut tracks: Vec<(Option<u64>, Option<&[u8]>)> = Vec::new();
+    let mut secs = 0.0;
+    let mut blocks = 0;
+    let mut offset = 0;
+    while let Some(((id, size), body)) = ebml_element(audio, offset) {
+        match id {
+            // Master elements are entered, so their children are read in turn;
+            // recorders write segments and clusters of unknown size
+            ebml_id::SEGMENT | ebml_id::TRACKS | ebml_id::CLUSTER | ebml_id::BLOCK_GROUP => {
+                offset = body;
+                continue;
+            }
+            ebml_id::TRACK_ENTRY => {
+                tracks.push((None, None));
+                offset = body;
+                continue;
+            }
+            _ => {}
+        }
+        // Other elements must have a size
+        let end = body.checked_add(size?)?;
+        let Some(data) = audio.get(body..end.min(audio.len())) else {
+            break;
+        };
+        match id {
+            ebml_id::TRACK_NUMBER => {
+                tracks.last_mut()?.0 = Some(data.iter().fold(0, |n, &b| (n << 8) | u64::from(b)));
+            }
+            ebml_id::CODEC_ID => tracks.last_mut()?.1 = Some(data),
+            ebml_id::SIMPLE_BLOCK | ebml_id::BLOCK => {
+                let opus_track = tracks
+                    .iter()
+                    .find(|(_, codec)| *codec == Some(b"A_OPUS".as_slice()))
+                    .and_then(|(number, _)| *number)?;
+                match opus_block_secs(data, opus_track) {
+                    Some(block_secs) => {
+                        secs += block_secs;
+                        blocks += 1;
+                    }
+                    None => secs += assumed_secs(data.len()),
+                }
+            }
+            _ => {}
+        }
+        offset = end;
+    }
+    (blocks > 0).then_some(secs)
+}
+
+/// Duration of a Matroska block holding one Opus packet of a track, or `None` if
+/// it belongs to another track or uses lacing.
+fn opus_block_secs(block: &[u8], opus_track: u64) -> Option<f64> {
+    let ((track, _), header_len) = ebml_vint(block, 0, false)?;
+    // Track number, 16-bit timecode, flags
+    let flags = *block.get(header_len + 2)?;
+    if track != opus_track || flags & 0x06 != 0 {
+        return None;
+    }
+    opus_packet_secs(block.get(header_len + 3..)?)
+}
+
+/// Duration of an Opus packet, from the frame size and count in its TOC byte
+/// (RFC 6716, section 3.1).
+fn opus_packet_secs(packet: &[u8]) -> Option<f64> {
+    let toc = *packet.first()?;
+    let config = usize::from(toc >> 3);
+    let frame_ms = match config {
+        0..=11 => [10.0, 20.0, 40.0, 60.0][config % 4],
+        12..=15 => [10.0, 20.0][config % 2],
+        _ => [2.5, 5.0, 10.0, 20.0][config % 4],
+    };
+    let frames = match toc & 0b11 {
+        0 => 1,
+        1 | 2 => 2,
+        _ => packet.get(1)? & 0x3f,
+    };
+    let ms = frame_ms * f64::from(frames);
+    // Packets hold at most 120 ms of audio
+    (ms <= 120.0).then_some(ms / 1000.0)
+}
+
+/// Reads the ID and size of the EBML element at `offset`.
+///
+/// # Returns
+///
+/// `((id, size), body offset)`, where the size is `None` if it is unknown.
+fn ebml_element(data: &[u8], offset: usize) -> Option<((u32, Option<usize>), usize)> {
+    let ((id, _), size_offset) = ebml_vint(data, offset, true)?;
+    let ((size, unknown), body) = ebml_vint(data, size_offset, false)?;
+    let size = (!unknown).then(|| usize::try_from(size).ok()).flatten();
+    Some(((id as u32, size), body))
+}
+
+/// Reads an EBML variable-length integer at `offset`; IDs keep their length
+/// marker bits, sizes do not.
+///
+/// # Returns
+///
+/// `((value, all value bits set), offset after it)`
+fn ebml_vint(data: &[u8], offset: usize, keep_marker: bool) -> Option<((u64, bool), usize)> {
+    let first = *data.get(offset)?;
+    let length = first.leading_zeros() as usize + 1;
+    if length > 8 || (keep_marker && length > 4) {
+        return None;
+    }
+    let bytes = data.get(offset..offset + length)?;
+    let mask = (1u64 << (7 * length)) - 1;
+    let raw = bytes.iter().fold(0u64, |n, &b| (n << 8) | u64::from(b));
+    let value = if keep_marker { raw } else { raw & mask };
+    Some(((value, raw & mask == mask), offset + length))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A WAV file with `frames` silent sample frames.
+    fn wav(channels: u16, sample_rate: u32, bits: u16, frames: usize) -> Vec<u8> {
+        let block_align = channels * bits.div_ceil(8);
+        let data_len = frames * usize::from(block_align);
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&(36 + data_len as u32).to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        wav.extend_from_slice(&1u16.to_le_bytes());
+        wav.extend_from_slice(&channels.to_le_bytes());
+        wav.extend_from_slice(&sample_rate.to_le_bytes());
+        wav.extend_from_slice(&(sample_rate * u32::from(block_align)).to_le_bytes());
+        wav.extend_from_slice(&block_align.to_le_bytes());
+        wav.extend_from_slice(&bits.to_le_bytes());
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&(data_len as u32).to_le_bytes());
+        wav.resize(wav.len() + data_len, 0);
+        wav
+    }
+
+    #[test]
+    fn wav_duration_comes_from_sample_frames() {
+        let audio = wav(1, 16_000, 16, 16_000 * 3);
+        assert_eq!(estimate_secs(&audio), 3.0);
+        let audio = wav(2, 44_100, 24, 44_100 / 2);
+        assert_eq!(estimate_secs(&audio), 0.5);
+    }
+
+    #[test]
+    fn wav_byte_rate_is_ignored() {
+        let mut audio = wav(1, 16_000, 16, 16_000 * 10);
+        audio[28..32].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(estimate_secs(&audio), 10.0);
+    }
+
+    #[test]
+    fn malformed_wav_falls_back_to_assumed_bitrate() {
+        // A frame size that does not match the channels and sample size
+        let mut audio = wav(1, 16_000, 16, 16_000);
+        audio[32..34].copy_from_slice(&1000u16.to_le_bytes());
+        assert_eq!(estimate_secs(&audio), assumed_secs(audio.len()));
+
+        // A sample rate of zero
+        let mut audio = wav(1, 16_000, 16, 16_000);
+        audio[24..28].copy_from_slice(&0u32.to_le_bytes());
+        assert_eq!(estimate_secs(&audio), assumed_secs(audio.len()));
+
+        // Truncated inside the format chunk
+        let audio = &wav(1, 16_000, 16, 16_000)[..30];
+        assert_eq!(estimate_secs(audio), assumed_secs(audio.len()));
+    }
+
+    /// `frames` MPEG-1 layer III frames at 44.1 kHz and `kbps`, without padding.
+    fn mp3(kbps_index: u8, frames: usize) -> Vec<u8> {
+        let (length, _) = mp3_frame(&[0xff, 0xfb, kbps_index << 4]).unwrap();
+        let mut frame = vec![0; length];
+        frame[..4].copy_from_slice(&[0xff, 0xfb, kbps_index << 4, 0x00]);
+        frame.repeat(frames)
+    }
+
+    #[test]
+    fn mp3_duration_is_summed_over_frames() {
+        // 128 kbit/s: 417-byte frames of 1152 samples
+        let audio = mp3(9, 100);
+        assert_eq!(audio.len(), 41_700);
+        let expected = 100.0 * 1152.0 / 44_100.0;
+        assert!((estimate_secs(&audio) - expected).abs() < 1e-9);
+
+        let mut tagged = b"ID3\x04\x00\x00\x00\x00\x00\x02ab".to_vec();
+        tagged.extend_from_slice(&audio);
+        assert!((estimate_secs(&tagged) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn mp3_bitrate_of_the_first_frame_does_not_decide_the_duration() {
+        // A 320 kbit/s first frame followed by data that is not a frame
+        let mut audio = mp3(14, 1);
+        audio.resize(80_000, 0);
+        let frame_secs = 1152.0 / 44_100.0;
+        let expected = frame_secs + assumed_secs(80_000 - 1044);
+        assert!((estimate_secs(&audio) - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn malformed_mp3_falls_back_to_assumed_bitrate() {
+        // Free-format and invalid bitrates, a reserved sample rate, layer II
+        for header in [
+            [0xff, 0xfb, 0x00, 0x00],
+            [0xff, 0xfb, 0xf0, 0x00],
+            [0xff, 0xfb, 0x9c, 0x00],
+            [0xff, 0xfd, 0x90, 0x00],
+        ] {
+            let mut audio = header.to_vec();
+            audio.resize(8_000, 0);
+            assert_eq!(estimate_secs(&audio), 1.0);
+        }
+    }
+
+    /// An EBML element with a one-byte size, or an unknown size.
+    fn element(id: &[u8], body: &[u8], unknown_size: bool) -> Vec<u8> {
+        let mut element = id.to_vec();
+        if unknown_size {
+            element.push(0xff);
+        } else {
+            element.push(0x80 | body.len() as u8);
+        }
+        element.extend_from_slice(body);
+        element
+    }
+
+    /// A WebM file as `MediaRecorder` writes it, with an Opus track and simple
+    /// blocks holding the packets.
+    fn webm(codec: &[u8], packets: &[&[u8]]) -> Vec<u8> {
+        let mut track = element(&[0xd7], &[1], false);
+        track.extend(element(&[0x86], codec, false));
+        let tracks = element(
+            &[0x16, 0x54, 0xae, 0x6b],
+            &element(&[0xae], &track, false),
+            false,
+        );
+        let mut cluster = element(&[0xe7], &[0], false);
+        for packet in packets {
+            let mut block = vec![0x81, 0x00, 0x00, 0x80];
+            block.extend_from_slice(packet);
+            cluster.extend(element(&[0xa3], &block, false));
+        }
+        let mut segment = tracks;
+        segment.extend(element(&[0x1f, 0x43, 0xb6, 0x75], &cluster, true));
+        let mut webm = element(
+            &[0x1a, 0x45, 0xdf, 0xa3],
+            &element(&[0x42, 0x82], b"webm", false),
+            false,
+        );
+        webm.extend(element(&[0x18, 0x53, 0x80, 0x67], &segment, true));
+        webm
+    }
+
+    #[test]
+    fn webm_duration_is_summed_over_opus_packets() {
+        // CELT 20 ms frames: one frame, two frames, and a code 3 packet of three
+        let one = [0xf8, 1, 2, 3];
+        let two = [0xf9, 1, 2, 3];
+        let three = [0xfb, 0x03, 1, 2, 3];
+        let audio = webm(b"A_OPUS", &[&one, &two, &three]);
+        assert!((estimate_secs(&audio) - 0.12).abs() < 1e-9);
+
+        // SILK 60 ms frames
+        let audio = webm(b"A_OPUS", &[&[0x18; 50][..]; 50]);
+        assert!((estimate_secs(&audio) - 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn webm_without_opus_falls_back_to_assumed_bitrate() {
+        let audio = webm(b"A_VORBIS", &[&[0xf8; 100][..]; 10]);
+        assert_eq!(estimate_secs(&audio), assumed_secs(audio.len()));
+    }
+}
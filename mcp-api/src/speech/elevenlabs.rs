@@ -0,0 +1,123 @@
+//! ElevenLabs speech provider.
+
+use super::{SpeechToText, SynthesizedAudio, TextToSpeech, audio_mime_type, check_status, env_or};
+use async_trait::async_trait;
+use reqwest::Client;
+use reqwest::multipart::{Form, Part};
+
+/// Base URL of the ElevenLabs API.
+const ELEVENLABS_BASE_URL: &str = "https://api.elevenlabs.io/v1";
+
+/// ElevenLabs STT (Scribe) and TTS provider.
+pub struct ElevenLabs {
+    client: Client,
+    api_key: String,
+    voice_id: String,
+    tts_model: String,
+    stt_model: String,
+    language_code: String,
+}
+
+impl ElevenLabs {
+    /// Configures the provider from environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// * `ELEVENLABS_API_KEY` - Required
+    /// * `ELEVENLABS_VOICE_ID` - TTS voice (default: Rachel, `21m00Tcm4TlvDq8ikWAM`)
+    /// * `ELEVENLABS_TTS_MODEL` - TTS model (default: `eleven_multilingual_v2`)
+    /// * `ELEVENLABS_STT_MODEL` - STT model (default: `scribe_v1`)
+    /// * `ELEVENLABS_LANGUAGE` - STT language code (default: `eng`)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `ELEVENLABS_API_KEY` is not set.
+    pub fn from_env(client: Client) -> Self {
+        Self {
+            client,
+            api_key: std::env::var("ELEVENLABS_API_KEY")
+                .expect("ELEVENLABS_API_KEY must be set in .env file"),
+            voice_id: env_or("ELEVENLABS_VOICE_ID", "21m00Tcm4TlvDq8ikWAM"),
+            tts_model: env_or("ELEVENLABS_TTS_MODEL", "eleven_multilingual_v2"),
+            stt_model: env_or("ELEVENLABS_STT_MODEL", "scribe_v1"),
+            language_code: env_or("ELEVENLABS_LANGUAGE", "eng"),
+        }
+    }
+}
+
+#[async_trait]
+impl SpeechToText for ElevenLabs {
+    fn name(&self) -> &str {
+        "elevenlabs"
+    }
+
+    async fn transcribe(&self, audio: Vec<u8>, filename: &str) -> Result<String, String> {
+        let file = Part::bytes(audio)
+            .file_name(filename.to_string())
+            .mime_str(audio_mime_type(filename))
+            .map_err(|e| format!("Invalid audio MIME type: {}", e))?;
+        let form = Form::new()
+            .part("file", file)
+            .text("model_id", self.stt_model.clone())
+            .text("language_code", self.language_code.clone())
+            .text("tag_audio_events", "true");
+
+        let response = self
+            .client
+            .post(format!("{}/speech-to-text", ELEVENLABS_BASE_URL))
+            .header("xi-api-key", &self.api_key)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to call ElevenLabs STT API: {}", e))?;
+        let response = check_status("ElevenLabs STT", response).await?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse STT response: {}", e))?;
+        Ok(json["text"].as_str().unwrap_or("").to_string())
+    }
+}
+
+#[async_trait]
+impl TextToSpeech for ElevenLabs {
+    fn name(&self) -> &str {
+        "elevenlabs"
+    }
+
+    fn voice(&self) -> String {
+        format!("elevenlabs/{}/{}", self.tts_model, self.voice_id)
+    }
+
+    async fn synthesize(&self, text: &str) -> Result<SynthesizedAudio, String> {
+        let payload = serde_json::json!({
+            "text": text,
+            "model_id": self.tts_model,
+            "output_format": "mp3_44100_128"
+        });
+
+        let response = self
+            .client
+            .post(format!(
+                "{}/text-to-speech/{}",
+                ELEVENLABS_BASE_URL, self.voice_id
+            ))
+            .header("xi-api-key", &self.api_key)
+            .header("Content-Type", "application/json")
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to call ElevenLabs TTS API: {}", e))?;
+        let response = check_status("ElevenLabs TTS", response).await?;
+
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read TTS audio: {}", e))?;
+        Ok(SynthesizedAudio {
+            bytes: bytes.to_vec(),
+            extension: "mp3",
+        })
+    }
+}
@@ -0,0 +1,128 @@
+//! Self-hosted speech providers.
+//!
+//! - [`WhisperCpp`] talks to the HTTP server bundled with whisper.cpp
+//!   (`whisper-server`), which exposes `POST /inference`.
+//! - [`Piper`] talks to Piper's HTTP server (`python -m piper.http_server`),
+//!   which returns WAV audio.
+//!
+//! Both run entirely offline, which makes them useful for development and for
+//! deployments that must not send audio to third parties.
+
+use super::{SpeechToText, SynthesizedAudio, TextToSpeech, audio_mime_type, check_status};
+use async_trait::async_trait;
+use reqwest::Client;
+use reqwest::multipart::{Form, Part};
+
+/// Speech-to-text through a whisper.cpp server.
+pub struct WhisperCpp {
+    client: Client,
+    base_url: String,
+}
+
+impl WhisperCpp {
+    /// Configures the provider from environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// * `WHISPER_URL` - Base URL of the whisper.cpp server (e.g. `http://localhost:8080`)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `WHISPER_URL` is not set.
+    pub fn from_env(client: Client) -> Self {
+        let base_url = std::env::var("WHISPER_URL").expect("WHISPER_URL must be set in .env file");
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl SpeechToText for WhisperCpp {
+    fn name(&self) -> &str {
+        "whisper.cpp"
+    }
+
+    async fn transcribe(&self, audio: Vec<u8>, filename: &str) -> Result<String, String> {
+        let file = Part::bytes(audio)
+            .file_name(filename.to_string())
+            .mime_str(audio_mime_type(filename))
+            .map_err(|e| format!("Invalid audio MIME type: {}", e))?;
+        let form = Form::new()
+            .part("file", file)
+            .text("response_format", "json");
+
+        let response = self
+            .client
+            .post(format!("{}/inference", self.base_url))
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to call whisper.cpp server: {}", e))?;
+        let response = check_status("whisper.cpp", response).await?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse STT response: {}", e))?;
+        Ok(json["text"].as_str().unwrap_or("").trim().to_string())
+    }
+}
+
+/// Text-to-speech through a Piper HTTP server.
+pub struct Piper {
+    client: Client,
+    base_url: String,
+}
+
+impl Piper {
+    /// Configures the provider from environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// * `PIPER_URL` - Base URL of the Piper HTTP server (e.g. `http://localhost:5000`)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `PIPER_URL` is not set.
+    pub fn from_env(client: Client) -> Self {
+        let base_url = std::env::var("PIPER_URL").expect("PIPER_URL must be set in .env file");
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl TextToSpeech for Piper {
+    fn name(&self) -> &str {
+        "piper"
+    }
+
+    // The Piper server's voice is configured on the server, so its URL stands in for it
+    fn voice(&self) -> String {
+        format!("piper/{}", self.base_url)
+    }
+
+    async fn synthesize(&self, text: &str) -> Result<SynthesizedAudio, String> {
+        let response = self
+            .client
+            .post(format!("{}/", self.base_url))
+            .json(&serde_json::json!({ "text": text }))
+            .send()
+            .await
+            .map_err(|e| format!("Failed to call Piper server: {}", e))?;
+        let response = check_status("Piper", response).await?;
+
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read TTS audio: {}", e))?;
+        Ok(SynthesizedAudio {
+            bytes: bytes.to_vec(),
+            extension: "wav",
+        })
+    }
+}
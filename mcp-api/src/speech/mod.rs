@@ -0,0 +1,161 @@
+//! Pluggable speech providers.
+//!
+//! This module defines the [`SpeechToText`] and [`TextToSpeech`] traits used by the
+//! request handlers, and selects their implementations from configuration.
+//!
+//! # Providers
+//!
+//! - `elevenlabs` - ElevenLabs Scribe STT and multilingual TTS ([`elevenlabs`])
+//! - `openai` - OpenAI Whisper STT and TTS, or any compatible server ([`openai`])
+//! - `local` - Self-hosted whisper.cpp and Piper HTTP servers ([`local`])
+
+pub mod duration;
+pub mod elevenlabs;
+pub mod local;
+pub mod openai;
+
+use async_trait::async_trait;
+use reqwest::Client;
+use std::sync::Arc;
+
+/// Audio produced by a [`TextToSpeech`] provider.
+pub struct SynthesizedAudio {
+    /// Encoded audio data
+    pub bytes: Vec<u8>,
+    /// File extension matching the encoding (e.g. `"mp3"` or `"wav"`)
+    pub extension: &'static str,
+}
+
+/// A speech-to-text backend.
+#[async_trait]
+pub trait SpeechToText: Send + Sync {
+    /// Name of the provider (e.g. `"elevenlabs"`), used in logs.
+    fn name(&self) -> &str;
+
+    /// Transcribes an uploaded audio file to text.
+    ///
+    /// # Arguments
+    ///
+    /// * `audio` - Raw bytes of the audio file
+    /// * `filename` - Original file name, used to infer the audio format
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the failure if the request fails, the provider
+    /// returns an error status, or the response cannot be parsed.
+    async fn transcribe(&self, audio: Vec<u8>, filename: &str) -> Result<String, String>;
+}
+
+/// A text-to-speech backend.
+#[async_trait]
+pub trait TextToSpeech: Send + Sync {
+    /// Name of the provider (e.g. `"elevenlabs"`), used in logs.
+    fn name(&self) -> &str;
+
+    /// Identifies the provider, model and voice the audio is generated with, so
+    /// stored audio is only reused for the same settings.
+    fn voice(&self) -> String;
+
+    /// Converts text to spoken audio.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the failure if the request fails or the
+    /// provider returns an error status.
+    async fn synthesize(&self, text: &str) -> Result<SynthesizedAudio, String>;
+}
+
+/// Builds the speech-to-text provider selected by `STT_PROVIDER`.
+///
+/// # Environment Variables
+///
+/// * `STT_PROVIDER` - `elevenlabs` (default), `openai` or `local`
+///
+/// See [`elevenlabs::ElevenLabs::from_env`], [`openai::OpenAiSpeech::from_env`] and
+/// [`local::WhisperCpp::from_env`] for provider-specific settings.
+///
+/// # Panics
+///
+/// Panics if the provider name is unknown or its required settings are missing.
+pub fn stt_from_env(client: &Client) -> Arc<dyn SpeechToText> {
+    match provider_setting("STT_PROVIDER").as_str() {
+        "elevenlabs" => Arc::new(elevenlabs::ElevenLabs::from_env(client.clone())),
+        "openai" => Arc::new(openai::OpenAiSpeech::from_env(client.clone())),
+        "local" => Arc::new(local::WhisperCpp::from_env(client.clone())),
+        other => panic!(
+            "Unknown STT_PROVIDER '{}': use elevenlabs, openai or local",
+            other
+        ),
+    }
+}
+
+/// Builds the text-to-speech provider selected by `TTS_PROVIDER`.
+///
+/// # Environment Variables
+///
+/// * `TTS_PROVIDER` - `elevenlabs` (default), `openai` or `local`
+///
+/// See [`elevenlabs::ElevenLabs::from_env`], [`openai::OpenAiSpeech::from_env`] and
+/// [`local::Piper::from_env`] for provider-specific settings.
+///
+/// # Panics
+///
+/// Panics if the provider name is unknown or its required settings are missing.
+pub fn tts_from_env(client: &Client) -> Arc<dyn TextToSpeech> {
+    match provider_setting("TTS_PROVIDER").as_str() {
+        "elevenlabs" => Arc::new(elevenlabs::ElevenLabs::from_env(client.clone())),
+        "openai" => Arc::new(openai::OpenAiSpeech::from_env(client.clone())),
+        "local" => Arc::new(local::Piper::from_env(client.clone())),
+        other => panic!(
+            "Unknown TTS_PROVIDER '{}': use elevenlabs, openai or local",
+            other
+        ),
+    }
+}
+
+/// Reads a provider selection variable, defaulting to ElevenLabs.
+fn provider_setting(key: &str) -> String {
+    std::env::var(key)
+        .map(|v| v.trim().to_lowercase())
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| "elevenlabs".to_string())
+}
+
+/// Reads an optional environment variable, falling back to a default.
+fn env_or(key: &str, default: &str) -> String {
+    std::env::var(key)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .unwrap_or_else(|| default.to_string())
+}
+
+/// Guesses the MIME type of an uploaded audio file from its extension.
+pub(crate) fn audio_mime_type(filename: &str) -> &'static str {
+    let extension = filename.rsplit('.').next().unwrap_or("").to_lowercase();
+    match extension.as_str() {
+        "wav" => "audio/wav",
+        "ogg" | "oga" => "audio/ogg",
+        "webm" => "audio/webm",
+        "m4a" | "mp4" => "audio/mp4",
+        "flac" => "audio/flac",
+        _ => "audio/mpeg",
+    }
+}
+
+/// Turns a non-success provider response into an error carrying its body.
+async fn check_status(
+    provider: &str,
+    response: reqwest::Response,
+) -> Result<reqwest::Response, String> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let error_text = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Unknown error".to_string());
+    tracing::error!("{} API error {}: {}", provider, status, error_text);
+    Err(error_text)
+}
@@ -0,0 +1,120 @@
+//! OpenAI speech provider (Whisper transcription and TTS).
+//!
+//! Also works with any server implementing OpenAI's `/audio/transcriptions`
+//! and `/audio/speech` endpoints.
+
+use super::{SpeechToText, SynthesizedAudio, TextToSpeech, audio_mime_type, check_status, env_or};
+use async_trait::async_trait;
+use reqwest::Client;
+use reqwest::multipart::{Form, Part};
+
+/// OpenAI Whisper STT and TTS provider.
+pub struct OpenAiSpeech {
+    client: Client,
+    base_url: String,
+    api_key: String,
+    stt_model: String,
+    tts_model: String,
+    voice: String,
+}
+
+impl OpenAiSpeech {
+    /// Configures the provider from environment variables.
+    ///
+    /// # Environment Variables
+    ///
+    /// * `OPENAI_API_KEY` - Required
+    /// * `OPENAI_BASE_URL` - API base URL (default: `https://api.openai.com/v1`)
+    /// * `OPENAI_STT_MODEL` - Transcription model (default: `whisper-1`)
+    /// * `OPENAI_TTS_MODEL` - Speech model (default: `tts-1`)
+    /// * `OPENAI_TTS_VOICE` - Voice (default: `alloy`)
+    ///
+    /// # Panics
+    ///
+    /// Panics if `OPENAI_API_KEY` is not set.
+    pub fn from_env(client: Client) -> Self {
+        Self {
+            client,
+            base_url: env_or("OPENAI_BASE_URL", "https://api.openai.com/v1")
+                .trim_end_matches('/')
+                .to_string(),
+            api_key: std::env::var("OPENAI_API_KEY")
+                .expect("OPENAI_API_KEY must be set in .env file"),
+            stt_model: env_or("OPENAI_STT_MODEL", "whisper-1"),
+            tts_model: env_or("OPENAI_TTS_MODEL", "tts-1"),
+            voice: env_or("OPENAI_TTS_VOICE", "alloy"),
+        }
+    }
+}
+
+#[async_trait]
+impl SpeechToText for OpenAiSpeech {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    async fn transcribe(&self, audio: Vec<u8>, filename: &str) -> Result<String, String> {
+        let file = Part::bytes(audio)
+            .file_name(filename.to_string())
+            .mime_str(audio_mime_type(filename))
+            .map_err(|e| format!("Invalid audio MIME type: {}", e))?;
+        let form = Form::new()
+            .part("file", file)
+            .text("model", self.stt_model.clone());
+
+        let response = self
+            .client
+            .post(format!("{}/audio/transcriptions", self.base_url))
+            .bearer_auth(&self.api_key)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to call OpenAI transcription API: {}", e))?;
+        let response = check_status("OpenAI STT", response).await?;
+
+        let json: serde_json::Value = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse STT response: {}", e))?;
+        Ok(json["text"].as_str().unwrap_or("").to_string())
+    }
+}
+
+#[async_trait]
+impl TextToSpeech for OpenAiSpeech {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn voice(&self) -> String {
+        format!("openai/{}/{}/{}", self.base_url, self.tts_model, self.voice)
+    }
+
+    async fn synthesize(&self, text: &str) -> Result<SynthesizedAudio, String> {
+        let payload = serde_json::json!({
+            "model": self.tts_model,
+            "input": text,
+            "voice": self.voice,
+            "response_format": "mp3"
+        });
+
+        let response = self
+            .client
+            .post(format!("{}/audio/speech", self.base_url))
+            .bearer_auth(&self.api_key)
+            .json(&payload)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to call OpenAI speech API: {}", e))?;
+        let response = check_status("OpenAI TTS", response).await?;
+
+        let bytes = response
+            .bytes()
+            .await
+            .map_err(|e| format!("Failed to read TTS audio: {}", e))?;
+        Ok(SynthesizedAudio {
+            bytes: bytes.to_vec(),
+            extension: "mp3",
+        })
+    }
+}
@@ -0,0 +1,365 @@
+//! Typed async client for the MCP server.
+
+use crate::types::*;
+use futures_util::StreamExt;
+use serde::{de::DeserializeOwned, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Errors returned by [`McpClient`].
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// The request could not be sent or the response could not be read
+    #[error("request to MCP server failed: {0}")]
+    Transport(#[from] reqwest::Error),
+    /// The server answered with a non-success HTTP status
+    #[error("MCP server returned HTTP {status}: {body}")]
+    Http {
+        /// HTTP status code
+        status: u16,
+        /// Response body, for diagnostics
+        body: String,
+    },
+    /// The response was not a valid JSON-RPC response for the method
+    #[error("invalid response from MCP server: {0}")]
+    Decode(String),
+    /// The server answered with a JSON-RPC error
+    #[error("MCP server error {code}: {message}")]
+    Rpc {
+        /// JSON-RPC error code (e.g. `-32602` for invalid params)
+        code: i32,
+        /// Error message
+        message: String,
+        /// Additional error data, if any
+        data: Option<serde_json::Value>,
+    },
+    /// The receiver of streamed deltas was dropped before the reply finished
+    #[error("stream receiver dropped")]
+    StreamClosed,
+}
+
+impl From<JsonRpcError> for ClientError {
+    fn from(error: JsonRpcError) -> Self {
+        ClientError::Rpc {
+            code: error.code,
+            message: error.message,
+            data: error.data,
+        }
+    }
+}
+
+/// Async client for the MCP server's JSON-RPC API.
+///
+/// Cheap to clone: clones share the underlying `reqwest::Client` connection pool
+/// and the counter that request IDs are taken from.
+#[derive(Clone)]
+pub struct McpClient {
+    http: reqwest::Client,
+    url: String,
+    next_id: Arc<AtomicU64>,
+}
+
+impl McpClient {
+    /// Creates a client for the server at `url` (e.g. `http://localhost:3000`).
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_client(reqwest::Client::new(), url)
+    }
+
+    /// Creates a client that reuses an existing HTTP client.
+    pub fn with_client(http: reqwest::Client, url: impl Into<String>) -> Self {
+        Self {
+            http,
+            url: url.into().trim_end_matches('/').to_string(),
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    /// Returns the server URL.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Calls a JSON-RPC method and decodes its result.
+    ///
+    /// # Errors
+    ///
+    /// See [`ClientError`]; JSON-RPC error replies become [`ClientError::Rpc`].
+    pub async fn call<P, R>(&self, method: &str, params: P) -> Result<R, ClientError>
+    where
+        P: Serialize,
+        R: DeserializeOwned,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let request = JsonRpcRequest::new(method, params, id);
+
+        let response = self.http.post(&self.url).json(&request).send().await?;
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(ClientError::Http {
+                status: status.as_u16(),
+                body,
+            });
+        }
+
+        let response: JsonRpcResponse<R> = serde_json::from_str(&body)
+            .map_err(|e| ClientError::Decode(format!("{}: {}", e, body)))?;
+        if let Some(error) = response.error {
+            return Err(error.into());
+        }
+        response
+            .result
+            .ok_or_else(|| ClientError::Decode(format!("response has no result: {}", body)))
+    }
+
+    /// Lists all available agents (`list_agents`).
+    pub async fn list_agents(&self) -> Result<Vec<Agent>, ClientError> {
+        let result: ListAgentsResult = self.call("list_agents", serde_json::json!({})).await?;
+        Ok(result.agents)
+    }
+
+    /// Processes user text through an agent (`process_text`).
+    pub async fn process_text(
+        &self,
+        params: &ProcessTextParams,
+    ) -> Result<ProcessTextResult, ClientError> {
+        self.call("process_text", params).await
+    }
+
+    /// Starts a server-side conversation session (`create_session`).
+    pub async fn create_session(&self, agent_id: Option<&str>) -> Result<Session, ClientError> {
+        let params = CreateSessionParams {
+            agent_id: agent_id.map(|id| id.to_string()),
+            ..Default::default()
+        };
+        self.call("create_session", params).await
+    }
+
+    /// Recreates an expired session under its ID with the given history
+    /// (`create_session`), e.g. after the server restarted.
+    pub async fn restore_session(
+        &self,
+        session_id: &str,
+        agent_id: Option<&str>,
+        messages: Vec<Message>,
+    ) -> Result<Session, ClientError> {
+        let params = CreateSessionParams {
+            agent_id: agent_id.map(|id| id.to_string()),
+            session_id: Some(session_id.to_string()),
+            messages,
+        };
+        self.call("create_session", params).await
+    }
+
+    /// Returns a session and its history (`get_session`).
+    pub async fn get_session(&self, session_id: &str) -> Result<Session, ClientError> {
+        let params = SessionIdParams {
+            session_id: session_id.to_string(),
+        };
+        self.call("get_session", params).await
+    }
+
+    /// Deletes a session (`delete_session`), returning whether it existed.
+    pub async fn delete_session(&self, session_id: &str) -> Result<bool, ClientError> {
+        let params = SessionIdParams {
+            session_id: session_id.to_string(),
+        };
+        let result: DeleteSessionResult = self.call("delete_session", params).await?;
+        Ok(result.deleted)
+    }
+
+    /// Processes user text through an agent, streaming the reply.
+    ///
+    /// Each fragment of the reply is sent to `tx` as it arrives from
+    /// `POST /process_text/stream`; the complete result is returned at the end.
+    ///
+    /// # Errors
+    ///
+    /// Besides the errors of [`McpClient::call`], fails with
+    /// [`ClientError::StreamClosed`] if `tx`'s receiver is dropped.
+    pub async fn process_text_stream(
+        &self,
+        params: &ProcessTextParams,
+        tx: mpsc::Sender<String>,
+    ) -> Result<ProcessTextResult, ClientError> {
+        let response = self
+            .http
+            .post(format!("{}/process_text/stream", self.url))
+            .json(params)
+            .send()
+            .await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ClientError::Http {
+                status: status.as_u16(),
+                body: response.text().await.unwrap_or_default(),
+            });
+        }
+
+        let mut body = response.bytes_stream();
+        let mut parser = SseParser::default();
+
+        while let Some(chunk) = body.next().await {
+            for (event, data) in parser.push(&chunk?) {
+                match event.as_str() {
+                    "delta" => {
+                        let delta: StreamDelta = decode(&data)?;
+                        tx.send(delta.text)
+                            .await
+                            .map_err(|_| ClientError::StreamClosed)?;
+                    }
+                    "done" => return decode(&data),
+                    "error" => return Err(decode::<JsonRpcError>(&data)?.into()),
+                    _ => {}
+                }
+            }
+        }
+
+        Err(ClientError::Decode(
+            "stream ended without a result".to_string(),
+        ))
+    }
+}
+
+fn decode<T: DeserializeOwned>(data: &str) -> Result<T, ClientError> {
+    serde_json::from_str(data).map_err(|e| ClientError::Decode(format!("{}: {}", e, data)))
+}
+
+/// Incremental parser for `text/event-stream` bodies.
+///
+/// Bytes are buffered until a line is complete, so multi-byte characters split
+/// across chunks are decoded intact.
+#[derive(Default)]
+struct SseParser {
+    buffer: Vec<u8>,
+    event: String,
+    data: String,
+}
+
+impl SseParser {
+    /// Feeds a chunk of the body and returns the `(event, data)` pairs it completes.
+    fn push(&mut self, chunk: &[u8]) -> Vec<(String, String)> {
+        self.buffer.extend_from_slice(chunk);
+        let mut events = Vec::new();
+
+        while let Some(newline) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            let line = line.trim_end_matches(['\r', '\n']);
+
+            if let Some(value) = line.strip_prefix("event:") {
+                self.event = value.trim().to_string();
+            } else if let Some(value) = line.strip_prefix("data:") {
+                if !self.data.is_empty() {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value.strip_prefix(' ').unwrap_or(value));
+            } else if line.is_empty() {
+                // A blank line terminates the event
+                if !self.data.is_empty() {
+                    events.push((
+                        std::mem::take(&mut self.event),
+                        std::mem::take(&mut self.data),
+                    ));
+                }
+                self.event.clear();
+            }
+        }
+
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+
+    /// Serves `handler` on a random local port and returns its URL.
+    async fn serve(app: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn error_replies_become_rpc_errors() {
+        let app = Router::new().route(
+            "/",
+            post(|| async {
+                Json(serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "error": { "code": -32602, "message": "Agent not found: agent_999" },
+                    "id": 1
+                }))
+            }),
+        );
+        let client = McpClient::new(serve(app).await);
+
+        let err = client
+            .process_text(&ProcessTextParams::new("agent_999", "hi"))
+            .await
+            .unwrap_err();
+        match err {
+            ClientError::Rpc { code, message, .. } => {
+                assert_eq!(code, -32602);
+                assert_eq!(message, "Agent not found: agent_999");
+            }
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn streamed_replies_forward_deltas() {
+        let app = Router::new().route(
+            "/process_text/stream",
+            post(|| async {
+                concat!(
+                    "event: delta\ndata: {\"text\":\"Hel\"}\n\n",
+                    "event: delta\ndata: {\"text\":\"lo\"}\n\n",
+                    "event: done\ndata: {\"agent_id\":\"agent_001\",\"reply_text\":\"Hello\",",
+                    "\"metadata\":{\"provider\":\"groq\",\"model\":\"m\",\"tokens_used\":null,",
+                    "\"processing_time_ms\":1,\"confidence\":0.9}}\n\n"
+                )
+            }),
+        );
+        let client = McpClient::new(serve(app).await);
+
+        let (tx, mut rx) = mpsc::channel(8);
+        let result = client
+            .process_text_stream(&ProcessTextParams::new("agent_001", "hi"), tx)
+            .await
+            .unwrap();
+
+        assert_eq!(result.reply_text, "Hello");
+        assert_eq!(rx.recv().await.as_deref(), Some("Hel"));
+        assert_eq!(rx.recv().await.as_deref(), Some("lo"));
+    }
+
+    #[test]
+    fn sse_parser_handles_split_chunks() {
+        let mut parser = SseParser::default();
+        assert!(parser.push(b"event: del").is_empty());
+        assert!(parser.push(b"ta\r\ndata: {}\r\n").is_empty());
+        assert_eq!(
+            parser.push(b"\r\n"),
+            vec![("delta".to_string(), "{}".to_string())]
+        );
+    }
+
+    #[test]
+    fn sse_parser_joins_characters_split_across_chunks() {
+        let mut parser = SseParser::default();
+        // "é" is 0xc3 0xa9 and "👋" is 0xf0 0x9f 0x91 0x8b
+        assert!(parser
+            .push(b"event: delta\ndata: {\"text\":\"caf\xc3")
+            .is_empty());
+        assert!(parser.push(b"\xa9 \xf0\x9f").is_empty());
+        assert_eq!(
+            parser.push(b"\x91\x8b\"}\n\n"),
+            vec![("delta".to_string(), "{\"text\":\"café 👋\"}".to_string())]
+        );
+    }
+}
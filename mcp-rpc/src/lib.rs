@@ -0,0 +1,30 @@
+//! Shared JSON-RPC protocol for the MCP server.
+//!
+//! This crate holds the wire types exchanged with `mcp-server` and a typed async
+//! client for them, so the server and its clients (such as `mcp-api`) cannot drift
+//! apart.
+//!
+//! - [`types`] - JSON-RPC 2.0 envelopes and the method params/results
+//! - [`client`] - [`McpClient`], with one method per JSON-RPC method
+//!
+//! # Example
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), mcp_rpc::ClientError> {
+//! use mcp_rpc::{McpClient, ProcessTextParams};
+//!
+//! let client = McpClient::new("http://localhost:3000");
+//! let agents = client.list_agents().await?;
+//! let reply = client
+//!     .process_text(&ProcessTextParams::new(&agents[0].id, "Hello!"))
+//!     .await?;
+//! println!("{}", reply.reply_text);
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod client;
+pub mod types;
+
+pub use client::{ClientError, McpClient};
+pub use types::*;
@@ -0,0 +1,230 @@
+//! Wire types of the MCP server's JSON-RPC API.
+//!
+//! These types are serialized by `mcp-server` and deserialized by its clients, so
+//! any change here is a protocol change for both sides.
+
+use serde::{Deserialize, Serialize};
+
+/// JSON-RPC 2.0 request structure.
+///
+/// Represents an incoming JSON-RPC request following the 2.0 specification.
+///
+/// # Type Parameters
+///
+/// * `T` - The type of the params field
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonRpcRequest<T> {
+    /// Protocol version, must be "2.0"
+    pub jsonrpc: String,
+    /// Name of the method to call
+    pub method: String,
+    /// Optional parameters for the method
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<T>,
+    /// Request identifier for matching responses; absent for notifications
+    #[serde(default)]
+    pub id: serde_json::Value,
+}
+
+impl<T> JsonRpcRequest<T> {
+    /// Creates a JSON-RPC 2.0 request.
+    pub fn new(method: impl Into<String>, params: T, id: impl Into<serde_json::Value>) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method: method.into(),
+            params: Some(params),
+            id: id.into(),
+        }
+    }
+}
+
+/// JSON-RPC 2.0 response structure.
+///
+/// Represents an outgoing JSON-RPC response following the 2.0 specification.
+///
+/// # Type Parameters
+///
+/// * `T` - The type of the result field
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonRpcResponse<T> {
+    /// Protocol version, always "2.0"
+    pub jsonrpc: String,
+    /// Optional result data on success
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<T>,
+    /// Optional error object on failure
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<JsonRpcError>,
+    /// Request identifier matching the original request
+    pub id: serde_json::Value,
+}
+
+/// JSON-RPC 2.0 error object.
+///
+/// Represents an error in JSON-RPC response.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JsonRpcError {
+    /// Error code
+    pub code: i32,
+    /// Error message
+    pub message: String,
+    /// Optional additional error data
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+/// Information about an AI agent.
+///
+/// Represents a specialized AI agent with unique capabilities and system instructions.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Agent {
+    /// Unique identifier for the agent
+    pub id: String,
+    /// Human-readable name
+    pub name: String,
+    /// Brief description of the agent's purpose
+    pub description: String,
+    /// List of capabilities (e.g., "text", "web3", "coding")
+    pub capabilities: Vec<String>,
+    /// Name of the LLM provider serving this agent ("groq", "gemini", "openai" or "ollama")
+    pub provider: String,
+    /// Model identifier understood by the provider (e.g., "llama-3.3-70b-versatile" for Groq)
+    pub model: String,
+    /// System prompt that defines the agent's behavior
+    pub system_prompt: String,
+    /// Names of the built-in tools the agent may call (e.g. "mint_nft", "http_fetch")
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<String>,
+}
+
+/// Result of the list_agents JSON-RPC method.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListAgentsResult {
+    /// List of all available agents
+    pub agents: Vec<Agent>,
+}
+
+/// Parameters for the process_text JSON-RPC method.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProcessTextParams {
+    /// ID of the agent to process the text
+    pub agent_id: String,
+    /// User's text input
+    pub user_text: String,
+    /// Optional conversation history for context
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conversation_history: Option<Vec<Message>>,
+    /// Optional server-side session to read history from and record the turn in.
+    /// When set, `conversation_history` is ignored.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+}
+
+impl ProcessTextParams {
+    /// Creates params for a single message without history or session.
+    pub fn new(agent_id: impl Into<String>, user_text: impl Into<String>) -> Self {
+        Self {
+            agent_id: agent_id.into(),
+            user_text: user_text.into(),
+            conversation_history: None,
+            session_id: None,
+        }
+    }
+
+    /// Continues the given server-side session.
+    pub fn with_session(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+}
+
+/// A message in the conversation history.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Message {
+    /// Role of the message sender ("user" or "assistant")
+    pub role: String,
+    /// Content of the message
+    pub content: String,
+}
+
+/// Result of the process_text JSON-RPC method.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProcessTextResult {
+    /// ID of the agent that processed the text
+    pub agent_id: String,
+    /// Agent's text response
+    pub reply_text: String,
+    /// Session the turn was recorded in, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// Metadata about the processing
+    pub metadata: ProcessingMetadata,
+}
+
+/// Metadata about text processing.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProcessingMetadata {
+    /// LLM provider that generated the reply
+    pub provider: String,
+    /// AI model used
+    pub model: String,
+    /// Number of tokens consumed (if available)
+    pub tokens_used: Option<u32>,
+    /// Processing time in milliseconds
+    pub processing_time_ms: u64,
+    /// Confidence score (currently hardcoded)
+    pub confidence: f64,
+}
+
+/// Payload of a `delta` event on the `/process_text/stream` endpoint.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StreamDelta {
+    /// Next piece of the agent's reply
+    pub text: String,
+}
+
+/// A server-side conversation session.
+///
+/// Sessions store the message history of a conversation so clients do not
+/// have to resend the transcript with every `process_text` call.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Session {
+    /// Unique identifier for the session
+    pub id: String,
+    /// ID of the agent that handled the most recent turn (if any)
+    pub agent_id: Option<String>,
+    /// Conversation history, oldest message first
+    pub messages: Vec<Message>,
+    /// When the session was created
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    /// When the session last received a turn
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Parameters for the create_session JSON-RPC method.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CreateSessionParams {
+    /// Optional agent the session is started with
+    #[serde(default)]
+    pub agent_id: Option<String>,
+    /// ID to recreate an expired session under; a new ID is generated when omitted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    /// History the session starts with, oldest message first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<Message>,
+}
+
+/// Parameters for the get_session and delete_session JSON-RPC methods.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionIdParams {
+    /// ID of the session
+    pub session_id: String,
+}
+
+/// Result of the delete_session JSON-RPC method.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeleteSessionResult {
+    /// Whether a session was found and deleted
+    pub deleted: bool,
+}
@@ -0,0 +1,112 @@
+//! SQLite persistence for agents managed at runtime.
+//!
+//! Agents created, updated or deleted through the `create_agent`, `update_agent` and
+//! `delete_agent` JSON-RPC methods are stored here as overrides on top of the agent
+//! definition files. A stored definition replaces the file agent with the same ID,
+//! and a deleted agent is kept as a tombstone so that it stays deleted when the
+//! files are reloaded.
+
+use crate::models::Agent;
+use rusqlite::{params, Connection};
+use std::path::Path;
+use std::sync::Mutex;
+
+/// A runtime change to an agent.
+#[derive(Debug, Clone)]
+pub enum AgentOverride {
+    /// The agent was created or updated with this definition
+    Defined(Agent),
+    /// The agent was deleted
+    Deleted(String),
+}
+
+/// SQLite-backed store of agent overrides.
+///
+/// The connection is guarded by a `Mutex`; every operation is a single short
+/// statement, so contention is not a concern for admin traffic.
+pub struct AgentStore {
+    conn: Mutex<Connection>,
+}
+
+impl AgentStore {
+    /// Opens (or creates) the agent database at the given path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database cannot be opened or the schema cannot be created.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, String> {
+        let conn = Connection::open(path).map_err(|e| e.to_string())?;
+        Self::with_connection(conn)
+    }
+
+    /// Opens a store backed by a private in-memory database.
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self, String> {
+        let conn = Connection::open_in_memory().map_err(|e| e.to_string())?;
+        Self::with_connection(conn)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self, String> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS agent_overrides (
+                id         TEXT PRIMARY KEY,
+                definition TEXT,
+                updated_at TEXT NOT NULL
+            );",
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Returns every stored override, ordered by agent ID.
+    pub fn overrides(&self) -> Result<Vec<AgentOverride>, String> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn
+            .prepare("SELECT id, definition FROM agent_overrides ORDER BY id")
+            .map_err(|e| e.to_string())?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+            })
+            .map_err(|e| e.to_string())?;
+
+        let mut overrides = Vec::new();
+        for row in rows {
+            let (id, definition) = row.map_err(|e| e.to_string())?;
+            match definition {
+                Some(json) => {
+                    let agent = serde_json::from_str(&json)
+                        .map_err(|e| format!("Stored agent '{}' is corrupt: {}", id, e))?;
+                    overrides.push(AgentOverride::Defined(agent));
+                }
+                None => overrides.push(AgentOverride::Deleted(id)),
+            }
+        }
+        Ok(overrides)
+    }
+
+    /// Stores the definition of an agent, replacing any previous override.
+    pub fn put(&self, agent: &Agent) -> Result<(), String> {
+        let definition = serde_json::to_string(agent).map_err(|e| e.to_string())?;
+        self.upsert(&agent.id, Some(definition))
+    }
+
+    /// Records that an agent was deleted.
+    pub fn mark_deleted(&self, agent_id: &str) -> Result<(), String> {
+        self.upsert(agent_id, None)
+    }
+
+    fn upsert(&self, agent_id: &str, definition: Option<String>) -> Result<(), String> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO agent_overrides (id, definition, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(id) DO UPDATE SET definition = excluded.definition,
+                                           updated_at = excluded.updated_at",
+            params![agent_id, definition, chrono::Utc::now().to_rfc3339()],
+        )
+        .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+}
@@ -1,105 +1,513 @@
 //! AI agent definitions and management.
 //!
-//! This module provides the configuration and metadata for all available AI agents.
-//! Each agent has a unique ID, capabilities, and system prompt that defines its behavior.
+//! Agents are declared in configuration files (one agent per `.toml`, `.yaml` or
+//! `.yml` file) inside the agents directory, `agents/` by default. The
+//! [`AgentRegistry`] loads and validates them at startup, reloads them when files
+//! change, and can be reloaded on demand through the `reload_agents` JSON-RPC method.
+//!
+//! Agents can also be created, updated and deleted at runtime. Those changes are
+//! persisted in an [`AgentStore`] and layered on top of the files, so they survive
+//! both restarts and reloads.
+//!
+//! # Example
+//!
+//! ```toml
+//! id = "agent_002"
+//! name = "Web3 Expert"
+//! description = "Specialized in blockchain, Web3, and cryptocurrency technologies"
+//! capabilities = ["web3", "crypto", "blockchain", "nft"]
+//! provider = "groq"
+//! model = "llama-3.3-70b-versatile"
+//! system_prompt = "You are a Web3 and blockchain expert..."
+//! tools = ["mint_nft", "http_fetch"]
+//! ```
 
-use crate::models::Agent;
+use crate::agent_store::{AgentOverride, AgentStore};
+use crate::models::{Agent, UpdateAgentParams};
+use crate::tools::BUILTIN_TOOLS;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
-/// Returns the list of all available AI agents.
-///
-/// Each agent has a unique ID, name, description, capabilities, and system prompt.
-/// The system prompt defines the agent's behavior and expertise area.
+/// Capabilities an agent may declare.
 ///
-/// # Available Agents
+/// Unknown capabilities are rejected so that typos in configuration files are
+/// caught at load time instead of silently producing unmatched agents.
+pub const KNOWN_CAPABILITIES: &[&str] = &[
+    "text",
+    "conversation",
+    "reasoning",
+    "web3",
+    "crypto",
+    "blockchain",
+    "nft",
+    "voice",
+    "audio",
+    "coding",
+    "debugging",
+    "technical",
+];
+
+/// Errors returned when agents are changed at runtime.
+#[derive(Debug)]
+pub enum AgentError {
+    /// No agent with this ID exists
+    NotFound(String),
+    /// An agent with this ID already exists
+    AlreadyExists(String),
+    /// The agent definition failed validation
+    Invalid(Vec<String>),
+    /// The change could not be persisted
+    Storage(String),
+}
+
+impl fmt::Display for AgentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AgentError::NotFound(id) => write!(f, "Agent not found: {}", id),
+            AgentError::AlreadyExists(id) => write!(f, "Agent already exists: {}", id),
+            AgentError::Invalid(problems) => write!(f, "Invalid agent: {}", problems.join("; ")),
+            AgentError::Storage(e) => write!(f, "Agent storage error: {}", e),
+        }
+    }
+}
+
+/// Registry of the agents loaded from the agents directory.
 ///
-/// - `agent_001` - General Assistant (general-purpose)
-/// - `agent_002` - Web3 Expert (blockchain, crypto, DeFi)
-/// - `agent_003` - Voice Specialist (conversational, voice-optimized)
-/// - `agent_004` - Code Assistant (programming, debugging)
+/// The effective set of agents is the file definitions with the runtime overrides
+/// from the [`AgentStore`] applied on top. It is replaced atomically on every change.
+/// If a reload fails validation, the previously loaded agents stay in effect.
+pub struct AgentRegistry {
+    /// Directory the agent files are loaded from
+    dir: PathBuf,
+    /// Persistent runtime overrides
+    store: AgentStore,
+    /// Agents as defined in the files, sorted by ID
+    file_agents: RwLock<Vec<Agent>>,
+    /// Effective agents (files plus overrides), sorted by ID
+    agents: RwLock<Vec<Agent>>,
+}
+
+impl AgentRegistry {
+    /// Loads all agents from a directory and applies the stored overrides.
+    ///
+    /// # Errors
+    ///
+    /// Returns every problem found (unreadable or unparsable files, duplicate IDs,
+    /// unknown capabilities or tools, empty system prompts, unreadable overrides) if the
+    /// agents cannot be loaded.
+    pub fn load(dir: impl Into<PathBuf>, store: AgentStore) -> Result<Self, Vec<String>> {
+        let dir = dir.into();
+        let file_agents = load_agents_from_dir(&dir)?;
+        let agents = apply_overrides(&file_agents, &store).map_err(|e| vec![e])?;
+        Ok(Self {
+            dir,
+            store,
+            file_agents: RwLock::new(file_agents),
+            agents: RwLock::new(agents),
+        })
+    }
+
+    /// Returns the directory the agents are loaded from.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns all loaded agents, sorted by ID.
+    pub fn list(&self) -> Vec<Agent> {
+        self.agents.read().unwrap().clone()
+    }
+
+    /// Finds an agent by ID.
+    ///
+    /// # Returns
+    ///
+    /// `Some(Agent)` if found, `None` otherwise
+    pub fn find(&self, agent_id: &str) -> Option<Agent> {
+        self.agents
+            .read()
+            .unwrap()
+            .iter()
+            .find(|a| a.id == agent_id)
+            .cloned()
+    }
+
+    /// Re-reads the agents directory and replaces the loaded agents.
+    ///
+    /// Runtime overrides are applied on top of the freshly loaded files.
+    ///
+    /// # Returns
+    ///
+    /// The newly loaded agents on success. On failure the previous agents are kept
+    /// and every validation problem is returned.
+    pub fn reload(&self) -> Result<Vec<Agent>, Vec<String>> {
+        let file_agents = load_agents_from_dir(&self.dir)?;
+        let mut agents = self.agents.write().unwrap();
+        let merged = apply_overrides(&file_agents, &self.store).map_err(|e| vec![e])?;
+        *self.file_agents.write().unwrap() = file_agents;
+        *agents = merged.clone();
+        Ok(merged)
+    }
+
+    /// Creates a new agent and persists it.
+    ///
+    /// # Errors
+    ///
+    /// Fails if an agent with the same ID exists, the definition is invalid, or it
+    /// cannot be stored.
+    pub fn create(&self, agent: Agent) -> Result<Agent, AgentError> {
+        let mut agents = self.agents.write().unwrap();
+        if agents.iter().any(|a| a.id == agent.id) {
+            return Err(AgentError::AlreadyExists(agent.id));
+        }
+        self.persist(&mut agents, agent)
+    }
+
+    /// Updates the given fields of an existing agent and persists the result.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the agent does not exist, the updated definition is invalid, or it
+    /// cannot be stored.
+    pub fn update(&self, params: UpdateAgentParams) -> Result<Agent, AgentError> {
+        let mut agents = self.agents.write().unwrap();
+        let mut agent = agents
+            .iter()
+            .find(|a| a.id == params.agent_id)
+            .cloned()
+            .ok_or_else(|| AgentError::NotFound(params.agent_id.clone()))?;
+
+        if let Some(name) = params.name {
+            agent.name = name;
+        }
+        if let Some(description) = params.description {
+            agent.description = description;
+        }
+        if let Some(capabilities) = params.capabilities {
+            agent.capabilities = capabilities;
+        }
+        if let Some(provider) = params.provider {
+            agent.provider = provider;
+        }
+        if let Some(model) = params.model {
+            agent.model = model;
+        }
+        if let Some(system_prompt) = params.system_prompt {
+            agent.system_prompt = system_prompt;
+        }
+        if let Some(tools) = params.tools {
+            agent.tools = tools;
+        }
+        self.persist(&mut agents, agent)
+    }
+
+    /// Deletes an agent.
+    ///
+    /// The deletion is persisted, so an agent defined in a file stays deleted after
+    /// reloads and restarts until it is created again.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the agent does not exist or the deletion cannot be stored.
+    pub fn delete(&self, agent_id: &str) -> Result<(), AgentError> {
+        let mut agents = self.agents.write().unwrap();
+        let Some(index) = agents.iter().position(|a| a.id == agent_id) else {
+            return Err(AgentError::NotFound(agent_id.to_string()));
+        };
+        self.store
+            .mark_deleted(agent_id)
+            .map_err(AgentError::Storage)?;
+        agents.remove(index);
+        Ok(())
+    }
+
+    /// Validates and stores an agent, then replaces it in the effective set.
+    fn persist(&self, agents: &mut Vec<Agent>, agent: Agent) -> Result<Agent, AgentError> {
+        let problems = validate_agent(&agent);
+        if !problems.is_empty() {
+            return Err(AgentError::Invalid(problems));
+        }
+        self.store.put(&agent).map_err(AgentError::Storage)?;
+
+        agents.retain(|a| a.id != agent.id);
+        agents.push(agent.clone());
+        agents.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(agent)
+    }
+}
+
+/// Applies the stored runtime overrides to the agents loaded from files.
+fn apply_overrides(file_agents: &[Agent], store: &AgentStore) -> Result<Vec<Agent>, String> {
+    let mut agents: BTreeMap<String, Agent> = file_agents
+        .iter()
+        .map(|a| (a.id.clone(), a.clone()))
+        .collect();
+
+    for entry in store.overrides()? {
+        match entry {
+            AgentOverride::Defined(agent) => {
+                agents.insert(agent.id.clone(), agent);
+            }
+            AgentOverride::Deleted(id) => {
+                agents.remove(&id);
+            }
+        }
+    }
+
+    Ok(agents.into_values().collect())
+}
+
+/// Watches the agents directory and reloads the registry whenever a file changes.
 ///
-/// # Returns
+/// Bursts of file-system events (editors often write several times per save) are
+/// coalesced into a single reload. Failed reloads are logged and the previous
+/// agents stay in effect.
 ///
-/// A vector of `Agent` structs representing all available agents.
+/// The returned watcher must be kept alive for as long as reloading is wanted.
 ///
-/// # Example
+/// # Errors
 ///
-/// ```rust
-/// let agents = get_agents();
-/// for agent in agents {
-///     println!("{}: {}", agent.id, agent.name);
-/// }
-/// ```
-pub fn get_agents() -> Vec<Agent> {
-    vec![
-        Agent {
-            id: "agent_001".to_string(),
-            name: "General Assistant".to_string(),
-            description: "A helpful general-purpose AI assistant powered by Groq".to_string(),
-            capabilities: vec![
-                "text".to_string(),
-                "conversation".to_string(),
-                "reasoning".to_string(),
-            ],
-            model: "mixtral-8x7b-32768".to_string(),
-            system_prompt: "You are a helpful, friendly, and knowledgeable AI assistant. Provide clear, accurate, and concise responses.".to_string(),
-        },
-        Agent {
-            id: "agent_002".to_string(),
-            name: "Web3 Expert".to_string(),
-            description: "Specialized in blockchain, Web3, and cryptocurrency technologies".to_string(),
-            capabilities: vec![
-                "web3".to_string(),
-                "crypto".to_string(),
-                "blockchain".to_string(),
-                "nft".to_string(),
-            ],
-            model: "mixtral-8x7b-32768".to_string(),
-            system_prompt: "You are a Web3 and blockchain expert. Help users understand cryptocurrency, NFTs, smart contracts, DeFi, and related technologies. Provide accurate technical information and practical guidance.".to_string(),
-        },
-        Agent {
-            id: "agent_003".to_string(),
-            name: "Voice Specialist".to_string(),
-            description: "Optimized for natural voice conversations and audio interactions".to_string(),
-            capabilities: vec![
-                "voice".to_string(),
-                "audio".to_string(),
-                "conversation".to_string(),
-            ],
-            model: "mixtral-8x7b-32768".to_string(),
-            system_prompt: "You are an AI assistant optimized for voice interactions. Respond in a natural, conversational tone suitable for speech. Keep responses concise and easy to understand when spoken aloud.".to_string(),
-        },
-        Agent {
-            id: "agent_004".to_string(),
-            name: "Code Assistant".to_string(),
-            description: "Expert in programming, software development, and technical problem-solving".to_string(),
-            capabilities: vec![
-                "coding".to_string(),
-                "debugging".to_string(),
-                "technical".to_string(),
-            ],
-            model: "mixtral-8x7b-32768".to_string(),
-            system_prompt: "You are an expert programming assistant. Help users with code, debugging, architecture, and technical decisions. Provide clear explanations and working code examples.".to_string(),
-        },
-    ]
+/// Returns an error if the directory cannot be watched.
+pub fn watch(registry: Arc<AgentRegistry>) -> notify::Result<RecommendedWatcher> {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        if let Ok(event) = res {
+            if !event.kind.is_access() {
+                let _ = tx.send(());
+            }
+        }
+    })?;
+    watcher.watch(registry.dir(), RecursiveMode::NonRecursive)?;
+
+    tokio::spawn(async move {
+        while rx.recv().await.is_some() {
+            tokio::time::sleep(Duration::from_millis(250)).await;
+            while rx.try_recv().is_ok() {}
+
+            match registry.reload() {
+                Ok(agents) => tracing::info!("🔄 Reloaded {} agents from disk", agents.len()),
+                Err(errors) => {
+                    tracing::error!("Agent reload failed, keeping previous agents:");
+                    for error in errors {
+                        tracing::error!("   - {}", error);
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(watcher)
 }
 
-/// Finds an agent by ID.
-///
-/// # Arguments
+/// Reads, parses and validates every agent file in a directory.
 ///
-/// * `agent_id` - The unique identifier of the agent to find
+/// Files without a `.toml`, `.yaml` or `.yml` extension are ignored.
+fn load_agents_from_dir(dir: &Path) -> Result<Vec<Agent>, Vec<String>> {
+    let entries = std::fs::read_dir(dir).map_err(|e| {
+        vec![format!(
+            "Cannot read agents directory {}: {}",
+            dir.display(),
+            e
+        )]
+    })?;
+
+    let mut agents = Vec::new();
+    let mut errors = Vec::new();
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        if !matches!(extension, "toml" | "yaml" | "yml") {
+            continue;
+        }
+        match parse_agent_file(&path) {
+            Ok(agent) => agents.push((path, agent)),
+            Err(e) => errors.push(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    agents.sort_by(|a, b| a.1.id.cmp(&b.1.id));
+
+    let mut seen = HashSet::new();
+    for (path, agent) in &agents {
+        if !seen.insert(agent.id.as_str()) {
+            errors.push(format!(
+                "{}: duplicate agent id '{}'",
+                path.display(),
+                agent.id
+            ));
+        }
+        for problem in validate_agent(agent) {
+            errors.push(format!("{}: {}", path.display(), problem));
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(agents.into_iter().map(|(_, agent)| agent).collect())
+}
+
+/// Parses a single agent file based on its extension.
+fn parse_agent_file(path: &Path) -> Result<Agent, String> {
+    let contents = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("toml") => toml::from_str(&contents).map_err(|e| e.to_string()),
+        _ => serde_yaml::from_str(&contents).map_err(|e| e.to_string()),
+    }
+}
+
+/// Checks a single agent definition.
 ///
 /// # Returns
 ///
-/// `Some(Agent)` if found, `None` otherwise
-///
-/// # Example
-///
-/// ```rust
-/// if let Some(agent) = find_agent_by_id("agent_002") {
-///     println!("Found agent: {}", agent.name);
-/// }
-/// ```
-pub fn find_agent_by_id(agent_id: &str) -> Option<Agent> {
-    get_agents().into_iter().find(|a| a.id == agent_id)
+/// A list of problems; empty if the agent is valid.
+pub fn validate_agent(agent: &Agent) -> Vec<String> {
+    let mut problems = Vec::new();
+
+    if agent.id.trim().is_empty() {
+        problems.push("id must not be empty".to_string());
+    }
+    if agent.system_prompt.trim().is_empty() {
+        problems.push(format!("agent '{}' has an empty system_prompt", agent.id));
+    }
+    for tool in &agent.tools {
+        if !BUILTIN_TOOLS.contains(&tool.as_str()) {
+            problems.push(format!(
+                "agent '{}' has unknown tool '{}' (known: {})",
+                agent.id,
+                tool,
+                BUILTIN_TOOLS.join(", ")
+            ));
+        }
+    }
+    for capability in &agent.capabilities {
+        if !KNOWN_CAPABILITIES.contains(&capability.as_str()) {
+            problems.push(format!(
+                "agent '{}' has unknown capability '{}' (known: {})",
+                agent.id,
+                capability,
+                KNOWN_CAPABILITIES.join(", ")
+            ));
+        }
+    }
+
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_agents_dir(name: &str, files: &[(&str, &str)]) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("mcp-agents-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file, contents) in files {
+            std::fs::write(dir.join(file), contents).unwrap();
+        }
+        dir
+    }
+
+    const TOML_AGENT: &str = r#"
+id = "agent_a"
+name = "A"
+description = "First"
+capabilities = ["text"]
+provider = "groq"
+model = "llama-3.3-70b-versatile"
+system_prompt = "You are A."
+"#;
+
+    #[test]
+    fn loads_toml_and_yaml_agents_sorted_by_id() {
+        let yaml = "id: agent_b\nname: B\ndescription: Second\ncapabilities: [coding]\n\
+                    provider: ollama\nmodel: llama3.2\nsystem_prompt: You are B.\n";
+        let dir = temp_agents_dir(
+            "ok",
+            &[
+                ("b.yaml", yaml),
+                ("a.toml", TOML_AGENT),
+                ("README.md", "ignored"),
+            ],
+        );
+
+        let registry = AgentRegistry::load(&dir, AgentStore::open_in_memory().unwrap())
+            .expect("agents should load");
+        let ids: Vec<String> = registry.list().into_iter().map(|a| a.id).collect();
+        assert_eq!(ids, vec!["agent_a", "agent_b"]);
+        assert_eq!(registry.find("agent_b").unwrap().provider, "ollama");
+    }
+
+    #[test]
+    fn rejects_duplicates_unknown_capabilities_and_empty_prompts() {
+        let bad = TOML_AGENT
+            .replace("[\"text\"]", "[\"telepathy\"]\ntools = [\"teleport\"]")
+            .replace("You are A.", "  ");
+        let dir = temp_agents_dir("bad", &[("a.toml", TOML_AGENT), ("a2.toml", &bad)]);
+
+        let errors = AgentRegistry::load(&dir, AgentStore::open_in_memory().unwrap())
+            .err()
+            .expect("load should fail");
+        assert!(errors
+            .iter()
+            .any(|e| e.contains("duplicate agent id 'agent_a'")));
+        assert!(errors
+            .iter()
+            .any(|e| e.contains("unknown capability 'telepathy'")));
+        assert!(errors.iter().any(|e| e.contains("unknown tool 'teleport'")));
+        assert!(errors.iter().any(|e| e.contains("empty system_prompt")));
+    }
+
+    #[test]
+    fn runtime_changes_survive_reload() {
+        let dir = temp_agents_dir("crud", &[("a.toml", TOML_AGENT)]);
+        let registry = AgentRegistry::load(&dir, AgentStore::open_in_memory().unwrap()).unwrap();
+
+        let mut created = registry.find("agent_a").unwrap();
+        created.id = "agent_c".to_string();
+        registry.create(created.clone()).unwrap();
+        assert!(matches!(
+            registry.create(created),
+            Err(AgentError::AlreadyExists(_))
+        ));
+
+        let update = UpdateAgentParams {
+            agent_id: "agent_c".to_string(),
+            system_prompt: Some("You are C.".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(registry.update(update).unwrap().system_prompt, "You are C.");
+
+        let invalid = UpdateAgentParams {
+            agent_id: "agent_c".to_string(),
+            system_prompt: Some(" ".to_string()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            registry.update(invalid),
+            Err(AgentError::Invalid(_))
+        ));
+
+        registry.delete("agent_a").unwrap();
+        assert!(matches!(
+            registry.delete("agent_a"),
+            Err(AgentError::NotFound(_))
+        ));
+
+        let ids: Vec<String> = registry
+            .reload()
+            .unwrap()
+            .into_iter()
+            .map(|a| a.id)
+            .collect();
+        assert_eq!(ids, vec!["agent_c"]);
+        assert_eq!(
+            registry.find("agent_c").unwrap().system_prompt,
+            "You are C."
+        );
+    }
 }
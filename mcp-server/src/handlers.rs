@@ -3,34 +3,159 @@
 //! This module contains the HTTP request handlers that process incoming JSON-RPC
 //! requests and route them to the appropriate functionality.
 
-use crate::agents::{find_agent_by_id, get_agents};
-use crate::gemini::process_with_gemini;
+use crate::agents::AgentError;
+use crate::llm::{build_messages, complete_with_tools};
 use crate::models::*;
 use crate::AppState;
-use axum::{extract::State, response::Json};
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Json, Response,
+    },
+};
+use futures_util::{stream, Stream, StreamExt};
+use std::convert::Infallible;
 use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+/// Largest batch accepted, on this endpoint and on `/mcp`.
+pub const MAX_BATCH_SIZE: usize = 50;
+
+/// How many requests of a batch are processed at once.
+const BATCH_CONCURRENCY: usize = 4;
 
 /// Main JSON-RPC 2.0 request handler.
 ///
-/// Routes incoming JSON-RPC requests to the appropriate handler based on the method name.
-/// Validates the JSON-RPC version and returns appropriate error responses for invalid requests.
+/// Accepts a single request object or a batch (an array of requests). The requests
+/// of a batch are processed concurrently, at most [`BATCH_CONCURRENCY`] at a time,
+/// and their responses returned as an array in the order of the requests.
+/// Notifications (requests without an `id`) are executed but never answered; if
+/// nothing needs answering the response is `204 No Content`.
 ///
 /// # Supported Methods
 ///
 /// - `list_agents` - Lists all available agents
+/// - `reload_agents` - Reloads the agent definitions from disk
+/// - `get_agent` - Returns a single agent
+/// - `create_agent` / `update_agent` / `delete_agent` - Manage agents (admin only)
 /// - `process_text` - Processes user text through an agent
+/// - `create_session` - Starts a new server-side conversation session
+/// - `get_session` - Returns a session and its message history
+/// - `delete_session` - Deletes a session
 ///
 /// # Arguments
 ///
 /// * `state` - Shared application state
-/// * `request` - JSON-RPC request with dynamic params
+/// * `headers` - HTTP headers; admin methods require `Authorization: Bearer <ADMIN_TOKEN>`
+/// * `body` - Raw request body, parsed here so malformed JSON gets a JSON-RPC error
 ///
 /// # Returns
 ///
-/// A JSON-RPC response with either result or error
+/// A JSON-RPC response, an array of responses for a batch, or `204 No Content`
+///
+/// # Errors
+///
+/// Returns JSON-RPC errors for:
+/// - Malformed JSON (`-32700`)
+/// - An empty batch, a batch of more than [`MAX_BATCH_SIZE`] requests, or batch
+///   entries that are not valid request objects (`-32600`)
 pub async fn handle_jsonrpc(
     State(state): State<Arc<AppState>>,
-    Json(request): Json<JsonRpcRequest<serde_json::Value>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    let payload: serde_json::Value = match serde_json::from_slice(&body) {
+        Ok(payload) => payload,
+        Err(e) => {
+            return rpc_error(
+                serde_json::Value::Null,
+                -32700,
+                format!("Parse error: {}", e),
+            )
+            .into_response();
+        }
+    };
+
+    match payload {
+        serde_json::Value::Array(items) if items.is_empty() => rpc_error(
+            serde_json::Value::Null,
+            -32600,
+            "Invalid Request: empty batch".to_string(),
+        )
+        .into_response(),
+        serde_json::Value::Array(items) if items.len() > MAX_BATCH_SIZE => rpc_error(
+            serde_json::Value::Null,
+            -32600,
+            format!(
+                "Invalid Request: batch of {} requests exceeds the limit of {}",
+                items.len(),
+                MAX_BATCH_SIZE
+            ),
+        )
+        .into_response(),
+        serde_json::Value::Array(items) => {
+            tracing::info!("Received JSON-RPC batch of {} requests", items.len());
+            let responses: Vec<_> = stream::iter(items)
+                .map(|item| handle_batch_item(state.clone(), headers.clone(), item))
+                .buffered(BATCH_CONCURRENCY)
+                .filter_map(|response| async move { response })
+                .collect()
+                .await;
+            if responses.is_empty() {
+                StatusCode::NO_CONTENT.into_response()
+            } else {
+                Json(responses).into_response()
+            }
+        }
+        item => match handle_batch_item(state, headers, item).await {
+            Some(response) => Json(response).into_response(),
+            None => StatusCode::NO_CONTENT.into_response(),
+        },
+    }
+}
+
+/// Validates and dispatches a single request object.
+///
+/// # Returns
+///
+/// The response, or `None` if the request was a notification.
+async fn handle_batch_item(
+    state: Arc<AppState>,
+    headers: HeaderMap,
+    item: serde_json::Value,
+) -> Option<JsonRpcResponse<serde_json::Value>> {
+    let is_notification = item.is_object() && item.get("id").is_none();
+    let request: JsonRpcRequest<serde_json::Value> = match serde_json::from_value(item) {
+        Ok(request) => request,
+        Err(e) => {
+            let Json(response) = rpc_error(
+                serde_json::Value::Null,
+                -32600,
+                format!("Invalid Request: {}", e),
+            );
+            return Some(response);
+        }
+    };
+
+    let Json(response) = dispatch(state, headers, request).await;
+    if is_notification {
+        if let Some(ref error) = response.error {
+            tracing::warn!("JSON-RPC notification failed: {}", error.message);
+        }
+        return None;
+    }
+    Some(response)
+}
+
+/// Routes a request to the handler for its method.
+async fn dispatch(
+    state: Arc<AppState>,
+    headers: HeaderMap,
+    request: JsonRpcRequest<serde_json::Value>,
 ) -> Json<JsonRpcResponse<serde_json::Value>> {
     tracing::info!("Received JSON-RPC request: method={}", request.method);
 
@@ -50,8 +175,16 @@ pub async fn handle_jsonrpc(
 
     // Route to the appropriate handler
     match request.method.as_str() {
-        "list_agents" => handle_list_agents(request).await,
+        "list_agents" => handle_list_agents(State(state), request).await,
+        "reload_agents" => handle_reload_agents(State(state), request).await,
+        "get_agent" => handle_get_agent(State(state), request).await,
+        "create_agent" => handle_create_agent(State(state), headers, request).await,
+        "update_agent" => handle_update_agent(State(state), headers, request).await,
+        "delete_agent" => handle_delete_agent(State(state), headers, request).await,
         "process_text" => handle_process_text(State(state), request).await,
+        "create_session" => handle_create_session(State(state), request).await,
+        "get_session" => handle_get_session(State(state), request).await,
+        "delete_session" => handle_delete_session(State(state), request).await,
         _ => Json(JsonRpcResponse {
             jsonrpc: "2.0".to_string(),
             result: None,
@@ -71,15 +204,17 @@ pub async fn handle_jsonrpc(
 ///
 /// # Arguments
 ///
+/// * `state` - Shared application state containing the agent registry
 /// * `request` - The JSON-RPC request
 ///
 /// # Returns
 ///
 /// A JSON-RPC response containing the list of agents
 pub async fn handle_list_agents(
+    State(state): State<Arc<AppState>>,
     request: JsonRpcRequest<serde_json::Value>,
 ) -> Json<JsonRpcResponse<serde_json::Value>> {
-    let agents = get_agents();
+    let agents = state.agents.list();
     let result = ListAgentsResult { agents };
 
     Json(JsonRpcResponse {
@@ -90,20 +225,194 @@ pub async fn handle_list_agents(
     })
 }
 
+/// Handles the `reload_agents` JSON-RPC method.
+///
+/// Re-reads the agent definition files from the agents directory. If any file is
+/// invalid, the currently loaded agents are kept and the validation errors are
+/// returned in the error's `data.errors` field.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the agent registry
+/// * `request` - The JSON-RPC request
+///
+/// # Returns
+///
+/// A JSON-RPC response containing the reloaded agents, or an error
+pub async fn handle_reload_agents(
+    State(state): State<Arc<AppState>>,
+    request: JsonRpcRequest<serde_json::Value>,
+) -> Json<JsonRpcResponse<serde_json::Value>> {
+    match state.agents.reload() {
+        Ok(agents) => {
+            tracing::info!("🔄 Reloaded {} agents via JSON-RPC", agents.len());
+            rpc_result(request.id, ReloadAgentsResult { agents })
+        }
+        Err(errors) => Json(JsonRpcResponse {
+            jsonrpc: "2.0".to_string(),
+            result: None,
+            error: Some(JsonRpcError {
+                code: -32000,
+                message: "Agent definitions are invalid; previous agents kept".to_string(),
+                data: Some(serde_json::json!({ "errors": errors })),
+            }),
+            id: request.id,
+        }),
+    }
+}
+
+/// Handles the `get_agent` JSON-RPC method.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the agent registry
+/// * `request` - JSON-RPC request containing `agent_id`
+///
+/// # Returns
+///
+/// A JSON-RPC response containing the agent, or an error if it does not exist
+pub async fn handle_get_agent(
+    State(state): State<Arc<AppState>>,
+    request: JsonRpcRequest<serde_json::Value>,
+) -> Json<JsonRpcResponse<serde_json::Value>> {
+    let params: AgentIdParams = match parse_params(&request) {
+        Ok(params) => params,
+        Err(message) => return rpc_error(request.id, -32602, message),
+    };
+
+    match state.agents.find(&params.agent_id) {
+        Some(agent) => rpc_result(request.id, agent),
+        None => rpc_error(
+            request.id,
+            -32602,
+            format!("Agent not found: {}", params.agent_id),
+        ),
+    }
+}
+
+/// Handles the `create_agent` JSON-RPC method.
+///
+/// Validates the agent definition and persists it in the agent database.
+/// Requires the admin token.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the agent registry
+/// * `headers` - HTTP headers carrying the admin token
+/// * `request` - JSON-RPC request containing a full agent definition
+///
+/// # Returns
+///
+/// A JSON-RPC response containing the created agent, or an error
+pub async fn handle_create_agent(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    request: JsonRpcRequest<serde_json::Value>,
+) -> Json<JsonRpcResponse<serde_json::Value>> {
+    if let Err(message) = require_admin(&state, &headers) {
+        return rpc_error(request.id, -32001, message);
+    }
+    let agent: Agent = match parse_params(&request) {
+        Ok(agent) => agent,
+        Err(message) => return rpc_error(request.id, -32602, message),
+    };
+
+    match state.agents.create(agent) {
+        Ok(agent) => {
+            tracing::info!("Created agent {}", agent.id);
+            rpc_result(request.id, agent)
+        }
+        Err(e) => agent_error(request.id, e),
+    }
+}
+
+/// Handles the `update_agent` JSON-RPC method.
+///
+/// Changes only the fields present in the params and persists the result.
+/// Requires the admin token.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the agent registry
+/// * `headers` - HTTP headers carrying the admin token
+/// * `request` - JSON-RPC request containing `agent_id` and the fields to change
+///
+/// # Returns
+///
+/// A JSON-RPC response containing the updated agent, or an error
+pub async fn handle_update_agent(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    request: JsonRpcRequest<serde_json::Value>,
+) -> Json<JsonRpcResponse<serde_json::Value>> {
+    if let Err(message) = require_admin(&state, &headers) {
+        return rpc_error(request.id, -32001, message);
+    }
+    let params: UpdateAgentParams = match parse_params(&request) {
+        Ok(params) => params,
+        Err(message) => return rpc_error(request.id, -32602, message),
+    };
+
+    match state.agents.update(params) {
+        Ok(agent) => {
+            tracing::info!("Updated agent {}", agent.id);
+            rpc_result(request.id, agent)
+        }
+        Err(e) => agent_error(request.id, e),
+    }
+}
+
+/// Handles the `delete_agent` JSON-RPC method.
+///
+/// The deletion is persisted, so agents defined in files stay deleted across
+/// reloads and restarts. Requires the admin token.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the agent registry
+/// * `headers` - HTTP headers carrying the admin token
+/// * `request` - JSON-RPC request containing `agent_id`
+///
+/// # Returns
+///
+/// A JSON-RPC response confirming the deletion, or an error
+pub async fn handle_delete_agent(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    request: JsonRpcRequest<serde_json::Value>,
+) -> Json<JsonRpcResponse<serde_json::Value>> {
+    if let Err(message) = require_admin(&state, &headers) {
+        return rpc_error(request.id, -32001, message);
+    }
+    let params: AgentIdParams = match parse_params(&request) {
+        Ok(params) => params,
+        Err(message) => return rpc_error(request.id, -32602, message),
+    };
+
+    match state.agents.delete(&params.agent_id) {
+        Ok(()) => {
+            tracing::info!("Deleted agent {}", params.agent_id);
+            rpc_result(request.id, DeleteAgentResult { deleted: true })
+        }
+        Err(e) => agent_error(request.id, e),
+    }
+}
+
 /// Handles the `process_text` JSON-RPC method.
 ///
-/// Processes user text through a specified agent using Google's Gemini API.
+/// Processes user text through a specified agent using the agent's LLM provider.
 /// This includes:
-/// 1. Validating the agent ID
-/// 2. Building the Gemini API request with system instructions and conversation history
-/// 3. Calling the Gemini API
-/// 4. Parsing the response and extracting the agent's reply
+/// 1. Validating the agent ID (and the session ID, if given)
+/// 2. Building the message list from the conversation history
+/// 3. Calling the provider configured for the agent (Groq, Gemini, OpenAI or Ollama)
+/// 4. Extracting the agent's reply
 /// 5. Returning metadata about tokens used and processing time
 ///
 /// # Arguments
 ///
-/// * `state` - Shared application state containing the HTTP client and API key
-/// * `request` - JSON-RPC request containing agent_id, user_text, and optional conversation history
+/// * `state` - Shared application state containing the LLM providers
+/// * `request` - JSON-RPC request containing agent_id, user_text, and optional
+///   conversation history or session_id
 ///
 /// # Returns
 ///
@@ -114,8 +423,9 @@ pub async fn handle_list_agents(
 /// Returns JSON-RPC errors for:
 /// - Invalid parameters
 /// - Unknown agent ID
-/// - Gemini API failures
-/// - Response parsing errors
+/// - Unknown or expired session ID
+/// - Unconfigured LLM provider
+/// - LLM provider API failures
 pub async fn handle_process_text(
     State(state): State<Arc<AppState>>,
     request: JsonRpcRequest<serde_json::Value>,
@@ -152,7 +462,7 @@ pub async fn handle_process_text(
     };
 
     // Find the requested agent
-    let agent = match find_agent_by_id(&params.agent_id) {
+    let agent = match state.agents.find(&params.agent_id) {
         Some(a) => a,
         None => {
             return Json(JsonRpcResponse {
@@ -168,21 +478,36 @@ pub async fn handle_process_text(
         }
     };
 
+    // Use the stored session history when a session is given
+    let conversation_history = match resolve_history(&state, &params).await {
+        Ok(history) => history,
+        Err(message) => return rpc_error(request.id, -32602, message),
+    };
+
+    // Look up the agent's LLM provider
+    let Some(provider) = state.providers.get(&agent.provider) else {
+        return rpc_error(
+            request.id,
+            -32603,
+            format!(
+                "Internal error: LLM provider not configured: {}",
+                agent.provider
+            ),
+        );
+    };
+
     // Start timing
     let start_time = std::time::Instant::now();
 
-    // Process the text with Gemini or Groq
-    let (reply_text, tokens_used) = match process_with_gemini(
-        &state.http_client,
-        &state.gemini_api_key,
-        &agent,
-        params.user_text,
-        params.conversation_history,
-        state.use_groq,
-    )
-    .await
-    {
-        Ok(result) => result,
+    // Process the text with the agent's provider
+    let messages = build_messages(conversation_history, params.user_text.clone());
+    let outcome = if agent.tools.is_empty() {
+        provider.complete(&agent, &messages).await
+    } else {
+        complete_with_tools(provider.as_ref(), &state.tools, &agent, &messages).await
+    };
+    let (reply_text, tokens_used) = match outcome {
+        Ok(completion) => (completion.reply_text, completion.tokens_used),
         Err(err_msg) => {
             tracing::error!("AI processing error: {}", err_msg);
             return Json(JsonRpcResponse {
@@ -190,7 +515,7 @@ pub async fn handle_process_text(
                 result: None,
                 error: Some(JsonRpcError {
                     code: -32603,
-                    message: "Internal error: Gemini API processing failed".to_string(),
+                    message: format!("Internal error: {} API processing failed", agent.provider),
                     data: Some(serde_json::json!({ "details": err_msg })),
                 }),
                 id: request.id,
@@ -200,11 +525,27 @@ pub async fn handle_process_text(
 
     let processing_time = start_time.elapsed().as_millis() as u64;
 
+    // Record the turn in the session
+    if let Some(ref session_id) = params.session_id {
+        let recorded = state
+            .sessions
+            .append_turn(session_id, &agent.id, params.user_text, reply_text.clone())
+            .await;
+        if !recorded {
+            tracing::warn!(
+                "Session {} was deleted before the turn was recorded",
+                session_id
+            );
+        }
+    }
+
     // Build the result
     let result = ProcessTextResult {
         agent_id: params.agent_id,
         reply_text,
+        session_id: params.session_id,
         metadata: ProcessingMetadata {
+            provider: agent.provider.clone(),
             model: agent.model.clone(),
             tokens_used,
             processing_time_ms: processing_time,
@@ -219,3 +560,482 @@ pub async fn handle_process_text(
         id: request.id,
     })
 }
+
+/// Streams the reply to a `process_text` request as Server-Sent Events.
+///
+/// This is the streaming counterpart of [`handle_process_text`], served at
+/// `POST /process_text/stream`. It accepts the same parameters as the
+/// `process_text` method (as a plain JSON body) and emits:
+///
+/// - `delta` - `{"text": "..."}` for every fragment of the reply as it is generated
+/// - `done` - the complete `ProcessTextResult` once the reply is finished
+/// - `error` - a `JsonRpcError` object if processing fails; no further events follow
+///
+/// When a `session_id` is given, the turn is recorded in the session after
+/// the stream completes, exactly like `process_text`.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the LLM providers
+/// * `params` - Agent ID, user text, and optional history or session ID
+pub async fn handle_process_text_stream(
+    State(state): State<Arc<AppState>>,
+    Json(params): Json<ProcessTextParams>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    tracing::info!("Received streaming request for agent: {}", params.agent_id);
+
+    let (event_tx, event_rx) = mpsc::channel::<Event>(32);
+    tokio::spawn(async move {
+        let event = match stream_process_text(&state, params, &event_tx).await {
+            Ok(result) => json_event("done", &result),
+            Err(error) => json_event("error", &error),
+        };
+        let _ = event_tx.send(event).await;
+    });
+
+    Sse::new(ReceiverStream::new(event_rx).map(Ok)).keep_alive(KeepAlive::default())
+}
+
+/// Runs a streaming `process_text` request, forwarding reply fragments as `delta` events.
+async fn stream_process_text(
+    state: &AppState,
+    params: ProcessTextParams,
+    event_tx: &mpsc::Sender<Event>,
+) -> Result<ProcessTextResult, JsonRpcError> {
+    let invalid_params = |message: String| JsonRpcError {
+        code: -32602,
+        message,
+        data: None,
+    };
+
+    let agent = state
+        .agents
+        .find(&params.agent_id)
+        .ok_or_else(|| invalid_params(format!("Agent not found: {}", params.agent_id)))?;
+    let conversation_history = resolve_history(state, &params)
+        .await
+        .map_err(invalid_params)?;
+    let provider = state
+        .providers
+        .get(&agent.provider)
+        .ok_or_else(|| JsonRpcError {
+            code: -32603,
+            message: format!(
+                "Internal error: LLM provider not configured: {}",
+                agent.provider
+            ),
+            data: None,
+        })?;
+
+    let start_time = std::time::Instant::now();
+
+    // Forward fragments from the AI stream to the client as they arrive
+    let (delta_tx, mut delta_rx) = mpsc::channel::<String>(32);
+    let forward = async {
+        while let Some(text) = delta_rx.recv().await {
+            if event_tx
+                .send(json_event("delta", &StreamDelta { text }))
+                .await
+                .is_err()
+            {
+                break;
+            }
+        }
+    };
+    let messages = build_messages(conversation_history, params.user_text.clone());
+    let stream = async {
+        if agent.tools.is_empty() {
+            return provider.complete_stream(&agent, &messages, delta_tx).await;
+        }
+        // Tool calls must be resolved before the answer exists, so it arrives as one delta
+        let completion =
+            complete_with_tools(provider.as_ref(), &state.tools, &agent, &messages).await?;
+        delta_tx
+            .send(completion.reply_text.clone())
+            .await
+            .map_err(|_| "Stream receiver dropped".to_string())?;
+        Ok(completion)
+    };
+    let (outcome, _) = tokio::join!(stream, forward);
+
+    let completion = outcome.map_err(|err_msg| {
+        tracing::error!("AI streaming error: {}", err_msg);
+        JsonRpcError {
+            code: -32603,
+            message: "Internal error: AI streaming failed".to_string(),
+            data: Some(serde_json::json!({ "details": err_msg })),
+        }
+    })?;
+
+    let processing_time = start_time.elapsed().as_millis() as u64;
+
+    if let Some(ref session_id) = params.session_id {
+        state
+            .sessions
+            .append_turn(
+                session_id,
+                &agent.id,
+                params.user_text,
+                completion.reply_text.clone(),
+            )
+            .await;
+    }
+
+    Ok(ProcessTextResult {
+        agent_id: params.agent_id,
+        reply_text: completion.reply_text,
+        session_id: params.session_id,
+        metadata: ProcessingMetadata {
+            provider: agent.provider.clone(),
+            model: agent.model.clone(),
+            tokens_used: completion.tokens_used,
+            processing_time_ms: processing_time,
+            confidence: 0.95,
+        },
+    })
+}
+
+/// Builds a named SSE event with a JSON payload.
+fn json_event<T: serde::Serialize>(name: &str, data: &T) -> Event {
+    Event::default()
+        .event(name)
+        .data(serde_json::to_string(data).unwrap())
+}
+
+/// Returns the conversation history to send to the AI for a `process_text` request.
+///
+/// When the request names a session, its stored messages are used and any
+/// explicit `conversation_history` is ignored.
+async fn resolve_history(
+    state: &AppState,
+    params: &ProcessTextParams,
+) -> Result<Option<Vec<Message>>, String> {
+    match params.session_id {
+        Some(ref session_id) => match state.sessions.get(session_id).await {
+            Some(session) => Ok(Some(session.messages)),
+            None => Err(format!("Session not found: {}", session_id)),
+        },
+        None => Ok(params.conversation_history.clone()),
+    }
+}
+
+/// Handles the `create_session` JSON-RPC method.
+///
+/// Creates an empty conversation session. The returned session ID can be passed
+/// to `process_text` so the server keeps track of the conversation history.
+///
+/// With a `session_id`, an expired session is recreated under that ID with the
+/// given `messages`, so clients that store conversations can continue them
+/// after the server restarts.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the session store
+/// * `request` - JSON-RPC request with an optional `agent_id`, `session_id` and `messages`
+///
+/// # Returns
+///
+/// A JSON-RPC response containing the new session, or an error if the agent
+/// does not exist or a session with the ID still exists
+pub async fn handle_create_session(
+    State(state): State<Arc<AppState>>,
+    request: JsonRpcRequest<serde_json::Value>,
+) -> Json<JsonRpcResponse<serde_json::Value>> {
+    let params: CreateSessionParams = match request.params {
+        Some(ref p) if !p.is_null() => match serde_json::from_value(p.clone()) {
+            Ok(params) => params,
+            Err(e) => return rpc_error(request.id, -32602, format!("Invalid params: {}", e)),
+        },
+        _ => CreateSessionParams::default(),
+    };
+
+    if let Some(ref agent_id) = params.agent_id {
+        if state.agents.find(agent_id).is_none() {
+            return rpc_error(request.id, -32602, format!("Agent not found: {}", agent_id));
+        }
+    }
+
+    let session = match params.session_id {
+        Some(session_id) if session_id.is_empty() => {
+            return rpc_error(
+                request.id,
+                -32602,
+                "session_id must not be empty".to_string(),
+            )
+        }
+        Some(session_id) => {
+            match state
+                .sessions
+                .restore(session_id.clone(), params.agent_id, params.messages)
+                .await
+            {
+                Some(session) => session,
+                None => {
+                    return rpc_error(
+                        request.id,
+                        -32602,
+                        format!("Session already exists: {}", session_id),
+                    )
+                }
+            }
+        }
+        None => state.sessions.create(params.agent_id).await,
+    };
+    tracing::info!("Created session {}", session.id);
+    rpc_result(request.id, session)
+}
+
+/// Handles the `get_session` JSON-RPC method.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the session store
+/// * `request` - JSON-RPC request containing `session_id`
+///
+/// # Returns
+///
+/// A JSON-RPC response containing the session and its messages, or an error
+/// if the session does not exist or has expired
+pub async fn handle_get_session(
+    State(state): State<Arc<AppState>>,
+    request: JsonRpcRequest<serde_json::Value>,
+) -> Json<JsonRpcResponse<serde_json::Value>> {
+    let params: SessionIdParams = match parse_params(&request) {
+        Ok(params) => params,
+        Err(message) => return rpc_error(request.id, -32602, message),
+    };
+
+    match state.sessions.get(&params.session_id).await {
+        Some(session) => rpc_result(request.id, session),
+        None => rpc_error(
+            request.id,
+            -32602,
+            format!("Session not found: {}", params.session_id),
+        ),
+    }
+}
+
+/// Handles the `delete_session` JSON-RPC method.
+///
+/// # Arguments
+///
+/// * `state` - Shared application state containing the session store
+/// * `request` - JSON-RPC request containing `session_id`
+///
+/// # Returns
+///
+/// A JSON-RPC response reporting whether the session existed
+pub async fn handle_delete_session(
+    State(state): State<Arc<AppState>>,
+    request: JsonRpcRequest<serde_json::Value>,
+) -> Json<JsonRpcResponse<serde_json::Value>> {
+    let params: SessionIdParams = match parse_params(&request) {
+        Ok(params) => params,
+        Err(message) => return rpc_error(request.id, -32602, message),
+    };
+
+    let deleted = state.sessions.delete(&params.session_id).await;
+    tracing::info!("Delete session {}: deleted={}", params.session_id, deleted);
+    rpc_result(request.id, DeleteSessionResult { deleted })
+}
+
+/// Checks the `Authorization: Bearer` header against the configured admin token.
+///
+/// Admin methods are disabled entirely when no `ADMIN_TOKEN` is configured.
+fn require_admin(state: &AppState, headers: &HeaderMap) -> Result<(), String> {
+    let Some(ref expected) = state.admin_token else {
+        return Err("Unauthorized: admin methods are disabled (ADMIN_TOKEN not set)".to_string());
+    };
+    let provided = headers
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+
+    match provided {
+        Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(()),
+        _ => Err("Unauthorized: invalid or missing admin token".to_string()),
+    }
+}
+
+/// Compares two byte strings without exiting early on the first difference.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Maps an agent registry error to a JSON-RPC error response.
+fn agent_error(
+    id: serde_json::Value,
+    error: AgentError,
+) -> Json<JsonRpcResponse<serde_json::Value>> {
+    let (code, data) = match &error {
+        AgentError::NotFound(_) | AgentError::AlreadyExists(_) => (-32602, None),
+        AgentError::Invalid(problems) => (-32602, Some(serde_json::json!({ "errors": problems }))),
+        AgentError::Storage(_) => (-32603, None),
+    };
+    Json(JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        result: None,
+        error: Some(JsonRpcError {
+            code,
+            message: error.to_string(),
+            data,
+        }),
+        id,
+    })
+}
+
+/// Deserializes the params of a request into a typed struct.
+///
+/// Returns a ready-to-send error message when params are missing or malformed.
+fn parse_params<T: serde::de::DeserializeOwned>(
+    request: &JsonRpcRequest<serde_json::Value>,
+) -> Result<T, String> {
+    match request.params {
+        Some(ref p) => {
+            serde_json::from_value(p.clone()).map_err(|e| format!("Invalid params: {}", e))
+        }
+        None => Err("Invalid params: params are required".to_string()),
+    }
+}
+
+/// Builds a successful JSON-RPC response.
+fn rpc_result<T: serde::Serialize>(
+    id: serde_json::Value,
+    result: T,
+) -> Json<JsonRpcResponse<serde_json::Value>> {
+    Json(JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        result: Some(serde_json::to_value(result).unwrap()),
+        error: None,
+        id,
+    })
+}
+
+/// Builds a JSON-RPC error response.
+fn rpc_error(
+    id: serde_json::Value,
+    code: i32,
+    message: String,
+) -> Json<JsonRpcResponse<serde_json::Value>> {
+    Json(JsonRpcResponse {
+        jsonrpc: "2.0".to_string(),
+        result: None,
+        error: Some(JsonRpcError {
+            code,
+            message,
+            data: None,
+        }),
+        id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn post(body: &str) -> (StatusCode, serde_json::Value) {
+        let response = handle_jsonrpc(
+            State(AppState::for_tests()),
+            HeaderMap::new(),
+            Bytes::from(body.to_string()),
+        )
+        .await;
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let json = serde_json::from_slice(&bytes).unwrap_or(serde_json::Value::Null);
+        (status, json)
+    }
+
+    #[tokio::test]
+    async fn malformed_json_is_a_parse_error() {
+        let (_, response) = post("{\"jsonrpc\": \"2.0\", \"method\"").await;
+        assert_eq!(response["error"]["code"], -32700);
+        assert_eq!(response["id"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn batches_answer_requests_but_not_notifications() {
+        let (_, response) = post(
+            r#"[
+                {"jsonrpc": "2.0", "method": "list_agents", "id": 1},
+                {"jsonrpc": "2.0", "method": "list_agents"},
+                {"jsonrpc": "2.0", "method": "no_such_method", "id": "b"},
+                42
+            ]"#,
+        )
+        .await;
+
+        let responses = response.as_array().unwrap();
+        assert_eq!(responses.len(), 3);
+        assert_eq!(responses[0]["id"], 1);
+        assert!(responses[0]["result"]["agents"].is_array());
+        assert_eq!(responses[1]["error"]["code"], -32601);
+        assert_eq!(responses[2]["error"]["code"], -32600);
+    }
+
+    #[tokio::test]
+    async fn notifications_only_get_no_content() {
+        let (status, _) = post(r#"{"jsonrpc": "2.0", "method": "list_agents"}"#).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+
+        let (status, _) = post(r#"[{"jsonrpc": "2.0", "method": "list_agents"}]"#).await;
+        assert_eq!(status, StatusCode::NO_CONTENT);
+    }
+
+    #[tokio::test]
+    async fn empty_batch_is_invalid() {
+        let (_, response) = post("[]").await;
+        assert_eq!(response["error"]["code"], -32600);
+    }
+
+    #[tokio::test]
+    async fn oversized_batch_is_invalid() {
+        let request = serde_json::json!({ "jsonrpc": "2.0", "method": "list_agents", "id": 1 });
+        let batch = |size| serde_json::Value::Array(vec![request.clone(); size]).to_string();
+
+        let (_, response) = post(&batch(MAX_BATCH_SIZE)).await;
+        assert_eq!(response.as_array().unwrap().len(), MAX_BATCH_SIZE);
+
+        let (_, response) = post(&batch(MAX_BATCH_SIZE + 1)).await;
+        assert_eq!(response["error"]["code"], -32600);
+        assert_eq!(response["id"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn expired_sessions_are_restored_under_their_id() {
+        let state = AppState::for_tests();
+        let create = |params: serde_json::Value| {
+            handle_create_session(
+                State(state.clone()),
+                JsonRpcRequest {
+                    jsonrpc: "2.0".to_string(),
+                    method: "create_session".to_string(),
+                    params: Some(params),
+                    id: serde_json::json!(1),
+                },
+            )
+        };
+        let params = serde_json::json!({
+            "session_id": "restored",
+            "messages": [
+                { "role": "user", "content": "Hi" },
+                { "role": "assistant", "content": "Hello!" }
+            ]
+        });
+
+        let Json(response) = create(params.clone()).await;
+        let session = response.result.unwrap();
+        assert_eq!(session["id"], "restored");
+        assert_eq!(session["messages"][1]["content"], "Hello!");
+        assert_eq!(
+            state.sessions.get("restored").await.unwrap().messages.len(),
+            2
+        );
+
+        // A live session is never overwritten
+        let Json(response) = create(params).await;
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+}
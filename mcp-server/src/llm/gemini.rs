@@ -0,0 +1,271 @@
+//! Google Gemini provider.
+//!
+//! Talks to the `generateContent` and `streamGenerateContent` endpoints of the
+//! Gemini `v1beta` API, which is required for `system_instruction` support.
+
+use super::{
+    check_status, for_each_sse_data, read_response_text, ChatItem, Completion, LlmProvider,
+    ModelTurn, ToolCall, ToolDefinition,
+};
+use crate::models::*;
+use async_trait::async_trait;
+use reqwest::Client;
+use tokio::sync::mpsc;
+
+/// Base URL of the Gemini API.
+const GEMINI_BASE_URL: &str = "https://generativelanguage.googleapis.com/v1beta";
+
+/// Provider for Google Gemini models (e.g. `gemini-2.0-flash`).
+pub struct GeminiProvider {
+    client: Client,
+    api_key: String,
+}
+
+impl GeminiProvider {
+    /// Creates a Gemini provider authenticating with the given API key.
+    pub fn new(client: Client, api_key: String) -> Self {
+        Self { client, api_key }
+    }
+
+    /// Builds the Gemini request with the agent's system instruction.
+    ///
+    /// Gemini calls the assistant role `model`; messages with other roles are skipped.
+    fn build_request(agent: &Agent, messages: &[Message]) -> GeminiRequest {
+        let contents = messages
+            .iter()
+            .filter_map(|msg| {
+                let role = match msg.role.as_str() {
+                    "user" => "user",
+                    "assistant" => "model",
+                    _ => return None,
+                };
+                Some(GeminiContent {
+                    role: role.to_string(),
+                    parts: vec![GeminiPart::text(msg.content.clone())],
+                })
+            })
+            .collect();
+
+        GeminiRequest {
+            contents,
+            system_instruction: Some(GeminiSystemInstruction {
+                parts: vec![GeminiPart::text(agent.system_prompt.clone())],
+            }),
+            tools: None,
+        }
+    }
+
+    /// Builds a request that offers `tools` as Gemini `functionDeclarations`.
+    ///
+    /// Tool calls are sent as `functionCall` parts from the model, and their results
+    /// as `functionResponse` parts from the user.
+    fn build_tool_request(
+        agent: &Agent,
+        conversation: &[ChatItem],
+        tools: &[ToolDefinition],
+    ) -> GeminiRequest {
+        let mut contents = Vec::new();
+        for item in conversation {
+            match item {
+                ChatItem::Message(msg) => {
+                    let role = match msg.role.as_str() {
+                        "user" => "user",
+                        "assistant" => "model",
+                        _ => continue,
+                    };
+                    contents.push(GeminiContent {
+                        role: role.to_string(),
+                        parts: vec![GeminiPart::text(msg.content.clone())],
+                    });
+                }
+                ChatItem::ToolCalls(calls) => contents.push(GeminiContent {
+                    role: "model".to_string(),
+                    parts: calls
+                        .iter()
+                        .map(|call| GeminiPart {
+                            function_call: Some(GeminiFunctionCall {
+                                name: call.name.clone(),
+                                args: call.arguments.clone(),
+                            }),
+                            ..Default::default()
+                        })
+                        .collect(),
+                }),
+                ChatItem::ToolResults(results) => contents.push(GeminiContent {
+                    role: "user".to_string(),
+                    parts: results
+                        .iter()
+                        .map(|result| GeminiPart {
+                            function_response: Some(GeminiFunctionResponse {
+                                name: result.name.clone(),
+                                response: result.content.clone(),
+                            }),
+                            ..Default::default()
+                        })
+                        .collect(),
+                }),
+            }
+        }
+
+        GeminiRequest {
+            contents,
+            system_instruction: Some(GeminiSystemInstruction {
+                parts: vec![GeminiPart::text(agent.system_prompt.clone())],
+            }),
+            tools: Some(vec![GeminiTool {
+                function_declarations: tools
+                    .iter()
+                    .map(|tool| GeminiFunctionDeclaration {
+                        name: tool.name.clone(),
+                        description: tool.description.clone(),
+                        parameters: tool.parameters.clone(),
+                    })
+                    .collect(),
+            }]),
+        }
+    }
+
+    async fn send(&self, url: &str, request: &GeminiRequest) -> Result<reqwest::Response, String> {
+        self.client
+            .post(url)
+            .header("x-goog-api-key", &self.api_key)
+            .header("Content-Type", "application/json")
+            .json(request)
+            .send()
+            .await
+            .map_err(|e| format!("Gemini API request failed: {}", e))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for GeminiProvider {
+    fn name(&self) -> &str {
+        "gemini"
+    }
+
+    async fn complete(&self, agent: &Agent, messages: &[Message]) -> Result<Completion, String> {
+        let url = format!("{}/models/{}:generateContent", GEMINI_BASE_URL, agent.model);
+        let response = self
+            .send(&url, &Self::build_request(agent, messages))
+            .await?;
+        let response_text = read_response_text("Gemini", response).await?;
+
+        tracing::info!("Gemini API response received successfully");
+
+        let gemini_response: GeminiResponse =
+            serde_json::from_str(&response_text).map_err(|e| {
+                format!(
+                    "Failed to parse Gemini response: {}. Raw: {}",
+                    e, response_text
+                )
+            })?;
+
+        let reply_text = gemini_response
+            .candidates
+            .first()
+            .map(|c| c.text())
+            .filter(|text| !text.is_empty())
+            .unwrap_or_else(|| "Sorry, I couldn't generate a response.".to_string());
+
+        let tokens_used = gemini_response
+            .usage_metadata
+            .and_then(|u| u.total_token_count);
+
+        Ok(Completion {
+            reply_text,
+            tokens_used,
+        })
+    }
+
+    async fn complete_stream(
+        &self,
+        agent: &Agent,
+        messages: &[Message],
+        tx: mpsc::Sender<String>,
+    ) -> Result<Completion, String> {
+        let url = format!(
+            "{}/models/{}:streamGenerateContent?alt=sse",
+            GEMINI_BASE_URL, agent.model
+        );
+        let response = self
+            .send(&url, &Self::build_request(agent, messages))
+            .await?;
+        let response = check_status("Gemini", response).await?;
+
+        let mut reply_text = String::new();
+        let mut tokens_used = None;
+
+        for_each_sse_data(response, &tx, &mut reply_text, |data| {
+            let chunk: GeminiResponse = serde_json::from_str(data).map_err(|e| {
+                format!("Failed to parse Gemini stream chunk: {}. Raw: {}", e, data)
+            })?;
+            if let Some(usage) = chunk.usage_metadata {
+                tokens_used = usage.total_token_count.or(tokens_used);
+            }
+            Ok(chunk.candidates.first().map(|c| c.text()))
+        })
+        .await?;
+
+        tracing::info!("Gemini API stream completed successfully");
+        Ok(Completion {
+            reply_text,
+            tokens_used,
+        })
+    }
+
+    async fn complete_turn(
+        &self,
+        agent: &Agent,
+        conversation: &[ChatItem],
+        tools: &[ToolDefinition],
+    ) -> Result<ModelTurn, String> {
+        let url = format!("{}/models/{}:generateContent", GEMINI_BASE_URL, agent.model);
+        let response = self
+            .send(&url, &Self::build_tool_request(agent, conversation, tools))
+            .await?;
+        let response_text = read_response_text("Gemini", response).await?;
+
+        let gemini_response: GeminiResponse =
+            serde_json::from_str(&response_text).map_err(|e| {
+                format!(
+                    "Failed to parse Gemini response: {}. Raw: {}",
+                    e, response_text
+                )
+            })?;
+        let tokens_used = gemini_response
+            .usage_metadata
+            .as_ref()
+            .and_then(|u| u.total_token_count);
+        let Some(candidate) = gemini_response.candidates.first() else {
+            return Err("Gemini returned no candidates".to_string());
+        };
+
+        // Gemini does not assign call IDs, so number the calls within the turn
+        let calls: Vec<ToolCall> = candidate
+            .content
+            .parts
+            .iter()
+            .filter_map(|p| p.function_call.as_ref())
+            .enumerate()
+            .map(|(i, call)| ToolCall {
+                id: format!("call_{}", i),
+                name: call.name.clone(),
+                arguments: call.args.clone(),
+            })
+            .collect();
+
+        if !calls.is_empty() {
+            return Ok(ModelTurn::ToolCalls { calls, tokens_used });
+        }
+
+        let reply_text = candidate.text();
+        Ok(ModelTurn::Reply(Completion {
+            reply_text: if reply_text.is_empty() {
+                "Sorry, I couldn't generate a response.".to_string()
+            } else {
+                reply_text
+            },
+            tokens_used,
+        }))
+    }
+}
@@ -0,0 +1,572 @@
+//! Pluggable LLM providers.
+//!
+//! This module defines the [`LlmProvider`] trait that every AI backend implements,
+//! and the [`ProviderRegistry`] that maps provider names (as referenced by
+//! `Agent.provider`) to configured provider instances. Agents on different
+//! backends can therefore be served by the same server at the same time.
+//!
+//! # Providers
+//!
+//! - `groq` - Groq's OpenAI-compatible API ([`openai::OpenAiCompatibleProvider`])
+//! - `gemini` - Google Gemini ([`gemini::GeminiProvider`])
+//! - `openai` - OpenAI or any OpenAI-compatible server ([`openai::OpenAiCompatibleProvider`])
+//! - `ollama` - A local Ollama daemon ([`ollama::OllamaProvider`])
+//!
+//! # Tool calling
+//!
+//! Agents that declare `tools` are served by [`complete_with_tools`], which lets the
+//! model call tools from the [`ToolRegistry`] and feeds the results back until the
+//! model produces a final answer. Groq/OpenAI (`tools`) and Gemini
+//! (`functionDeclarations`) support tool calling.
+
+pub mod gemini;
+pub mod ollama;
+pub mod openai;
+
+use crate::models::{Agent, Message};
+use crate::tools::ToolRegistry;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use reqwest::Client;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+/// Maximum number of model turns in a tool-calling loop before giving up.
+///
+/// Guards against models that keep calling tools without ever answering.
+pub const MAX_TOOL_ROUNDS: usize = 6;
+
+/// Default base URL of Groq's OpenAI-compatible API.
+const GROQ_BASE_URL: &str = "https://api.groq.com/openai/v1";
+
+/// Default base URL of the OpenAI API.
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+
+/// A completed reply from an LLM provider.
+#[derive(Debug, Clone)]
+pub struct Completion {
+    /// The generated reply text
+    pub reply_text: String,
+    /// Total tokens consumed, if the provider reports it
+    pub tokens_used: Option<u32>,
+}
+
+/// A tool the model may call, described by a JSON Schema of its arguments.
+#[derive(Debug, Clone)]
+pub struct ToolDefinition {
+    /// Name the model uses to call the tool
+    pub name: String,
+    /// What the tool does, shown to the model
+    pub description: String,
+    /// JSON Schema (object) describing the arguments
+    pub parameters: serde_json::Value,
+}
+
+/// A tool call requested by the model.
+#[derive(Debug, Clone)]
+pub struct ToolCall {
+    /// Provider-assigned call ID, echoed back with the result
+    pub id: String,
+    /// Name of the tool to call
+    pub name: String,
+    /// Arguments as decoded JSON
+    pub arguments: serde_json::Value,
+}
+
+/// The outcome of a tool call, sent back to the model.
+#[derive(Debug, Clone)]
+pub struct ToolResult {
+    /// ID of the call this result answers
+    pub call_id: String,
+    /// Name of the tool that was called
+    pub name: String,
+    /// `{"result": ...}` on success or `{"error": "..."}` on failure
+    pub content: serde_json::Value,
+}
+
+/// An entry in a tool-calling conversation.
+#[derive(Debug, Clone)]
+pub enum ChatItem {
+    /// A plain user or assistant message
+    Message(Message),
+    /// Tool calls made by the model in one turn
+    ToolCalls(Vec<ToolCall>),
+    /// Results of the tool calls of the preceding turn
+    ToolResults(Vec<ToolResult>),
+}
+
+/// One model turn in a tool-calling conversation.
+#[derive(Debug, Clone)]
+pub enum ModelTurn {
+    /// The model answered
+    Reply(Completion),
+    /// The model wants tools to be called before it answers
+    ToolCalls {
+        /// The requested calls
+        calls: Vec<ToolCall>,
+        /// Tokens consumed by this turn, if reported
+        tokens_used: Option<u32>,
+    },
+}
+
+/// A chat-completion backend.
+///
+/// Implementations receive the agent (for its model and system prompt) and the
+/// conversation so far, ending with the current user message.
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Name under which the provider is registered (e.g. `"groq"`).
+    fn name(&self) -> &str;
+
+    /// Generates a complete reply.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the failure if the request fails, the provider
+    /// returns an error status, or the response cannot be parsed.
+    async fn complete(&self, agent: &Agent, messages: &[Message]) -> Result<Completion, String>;
+
+    /// Generates a reply, forwarding each text fragment to `tx` as it arrives.
+    ///
+    /// Returns the complete reply once the stream has ended.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`LlmProvider::complete`], and additionally fails if the stream is
+    /// interrupted or the receiving side of `tx` is dropped.
+    async fn complete_stream(
+        &self,
+        agent: &Agent,
+        messages: &[Message],
+        tx: mpsc::Sender<String>,
+    ) -> Result<Completion, String>;
+
+    /// Runs a single model turn with tools available.
+    ///
+    /// Providers without tool calling support keep the default, which fails.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`LlmProvider::complete`].
+    async fn complete_turn(
+        &self,
+        _agent: &Agent,
+        _conversation: &[ChatItem],
+        _tools: &[ToolDefinition],
+    ) -> Result<ModelTurn, String> {
+        Err(format!(
+            "Provider '{}' does not support tool calling",
+            self.name()
+        ))
+    }
+}
+
+/// The set of configured LLM providers, keyed by name.
+#[derive(Default)]
+pub struct ProviderRegistry {
+    providers: HashMap<String, Arc<dyn LlmProvider>>,
+}
+
+impl ProviderRegistry {
+    /// Builds the registry from environment variables.
+    ///
+    /// A provider is registered when its configuration is present:
+    ///
+    /// * `GROQ_API_KEY` - registers `groq`
+    /// * `GEMINI_API_KEY` - registers `gemini`
+    /// * `OPENAI_API_KEY` and/or `OPENAI_BASE_URL` - registers `openai`
+    ///   (the base URL defaults to `https://api.openai.com/v1`)
+    /// * `OLLAMA_URL` - registers `ollama` (e.g. `http://localhost:11434`)
+    pub fn from_env(client: &Client) -> Self {
+        let env = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        let mut registry = Self::default();
+
+        if let Some(api_key) = env("GROQ_API_KEY") {
+            registry.register(openai::OpenAiCompatibleProvider::new(
+                "groq",
+                client.clone(),
+                GROQ_BASE_URL,
+                Some(api_key),
+            ));
+        }
+        if let Some(api_key) = env("GEMINI_API_KEY") {
+            registry.register(gemini::GeminiProvider::new(client.clone(), api_key));
+        }
+        let openai_key = env("OPENAI_API_KEY");
+        let openai_url = env("OPENAI_BASE_URL");
+        if openai_key.is_some() || openai_url.is_some() {
+            registry.register(openai::OpenAiCompatibleProvider::new(
+                "openai",
+                client.clone(),
+                openai_url.as_deref().unwrap_or(OPENAI_BASE_URL),
+                openai_key,
+            ));
+        }
+        if let Some(base_url) = env("OLLAMA_URL") {
+            registry.register(ollama::OllamaProvider::new(client.clone(), &base_url));
+        }
+
+        registry
+    }
+
+    /// Adds a provider, replacing any provider registered under the same name.
+    pub fn register(&mut self, provider: impl LlmProvider + 'static) {
+        self.providers
+            .insert(provider.name().to_string(), Arc::new(provider));
+    }
+
+    /// Returns the provider with the given name, if configured.
+    pub fn get(&self, name: &str) -> Option<Arc<dyn LlmProvider>> {
+        self.providers.get(name).cloned()
+    }
+
+    /// Returns the names of all configured providers, sorted.
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self.providers.keys().cloned().collect();
+        names.sort();
+        names
+    }
+
+    /// Returns `true` if no provider is configured.
+    pub fn is_empty(&self) -> bool {
+        self.providers.is_empty()
+    }
+}
+
+/// Builds the message list sent to a provider: the history followed by the new user message.
+pub fn build_messages(
+    conversation_history: Option<Vec<Message>>,
+    user_text: String,
+) -> Vec<Message> {
+    let mut messages = conversation_history.unwrap_or_default();
+    messages.push(Message {
+        role: "user".to_string(),
+        content: user_text,
+    });
+    messages
+}
+
+/// Generates a reply for an agent that declares tools.
+///
+/// Runs the model, executes any tool calls it makes through `tools`, and feeds the
+/// results back until the model answers or [`MAX_TOOL_ROUNDS`] is reached. Failing
+/// tool calls are reported to the model as errors rather than aborting the request.
+///
+/// # Errors
+///
+/// Returns an error if a provider call fails or the model never produces an answer.
+pub async fn complete_with_tools(
+    provider: &dyn LlmProvider,
+    tools: &ToolRegistry,
+    agent: &Agent,
+    messages: &[Message],
+) -> Result<Completion, String> {
+    let definitions = tools.definitions_for(agent);
+    let mut conversation: Vec<ChatItem> = messages.iter().cloned().map(ChatItem::Message).collect();
+    let mut tokens_used: Option<u32> = None;
+
+    for _ in 0..MAX_TOOL_ROUNDS {
+        let turn = provider
+            .complete_turn(agent, &conversation, &definitions)
+            .await?;
+        let (calls, turn_tokens) = match turn {
+            ModelTurn::Reply(completion) => {
+                return Ok(Completion {
+                    reply_text: completion.reply_text,
+                    tokens_used: add_tokens(tokens_used, completion.tokens_used),
+                });
+            }
+            ModelTurn::ToolCalls { calls, tokens_used } => (calls, tokens_used),
+        };
+        tokens_used = add_tokens(tokens_used, turn_tokens);
+
+        let mut results = Vec::with_capacity(calls.len());
+        for call in &calls {
+            tracing::info!("Agent {} calls tool {}", agent.id, call.name);
+            let content = match tools.call(agent, &call.name, call.arguments.clone()).await {
+                Ok(result) => serde_json::json!({ "result": result }),
+                Err(e) => {
+                    tracing::warn!("Tool {} failed: {}", call.name, e);
+                    serde_json::json!({ "error": e })
+                }
+            };
+            results.push(ToolResult {
+                call_id: call.id.clone(),
+                name: call.name.clone(),
+                content,
+            });
+        }
+        conversation.push(ChatItem::ToolCalls(calls));
+        conversation.push(ChatItem::ToolResults(results));
+    }
+
+    Err(format!(
+        "Agent {} did not produce an answer after {} tool rounds",
+        agent.id, MAX_TOOL_ROUNDS
+    ))
+}
+
+fn add_tokens(total: Option<u32>, turn: Option<u32>) -> Option<u32> {
+    match (total, turn) {
+        (None, None) => None,
+        (a, b) => Some(a.unwrap_or(0) + b.unwrap_or(0)),
+    }
+}
+
+/// Reads a Server-Sent Events response body and handles each `data:` payload.
+///
+/// `on_data` turns a payload into an optional text fragment. Non-empty fragments
+/// are appended to `reply_text` and forwarded to `tx`.
+pub(crate) async fn for_each_sse_data<F>(
+    response: reqwest::Response,
+    tx: &mpsc::Sender<String>,
+    reply_text: &mut String,
+    mut on_data: F,
+) -> Result<(), String>
+where
+    F: FnMut(&str) -> Result<Option<String>, String>,
+{
+    for_each_line(response, tx, reply_text, |line| {
+        match line.strip_prefix("data:") {
+            Some(data) => on_data(data.trim_start()),
+            None => Ok(None),
+        }
+    })
+    .await
+}
+
+/// Reads a streamed response body line by line.
+///
+/// `on_line` turns a line into an optional text fragment. Non-empty fragments are
+/// appended to `reply_text` and forwarded to `tx`. Lines are decoded once complete,
+/// so characters split across chunks are not mangled.
+pub(crate) async fn for_each_line<F>(
+    response: reqwest::Response,
+    tx: &mpsc::Sender<String>,
+    reply_text: &mut String,
+    mut on_line: F,
+) -> Result<(), String>
+where
+    F: FnMut(&str) -> Result<Option<String>, String>,
+{
+    let mut body = response.bytes_stream();
+    let mut buffer = Vec::new();
+
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| format!("AI stream interrupted: {}", e))?;
+        buffer.extend_from_slice(&chunk);
+
+        while let Some(newline) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=newline).collect();
+            let Some(fragment) = on_line(String::from_utf8_lossy(&line).trim_end())? else {
+                continue;
+            };
+            if fragment.is_empty() {
+                continue;
+            }
+            reply_text.push_str(&fragment);
+            tx.send(fragment)
+                .await
+                .map_err(|_| "Stream receiver dropped".to_string())?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Reads a provider response, turning non-success statuses into errors.
+///
+/// # Arguments
+///
+/// * `provider` - Human-readable provider name used in log and error messages
+/// * `response` - The HTTP response to check
+pub(crate) async fn read_response_text(
+    provider: &str,
+    response: reqwest::Response,
+) -> Result<String, String> {
+    let response = check_status(provider, response).await?;
+    response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read {} response: {}", provider, e))
+}
+
+/// Returns the response unchanged if it has a success status, or the provider's
+/// error body as an `Err` otherwise.
+pub(crate) async fn check_status(
+    provider: &str,
+    response: reqwest::Response,
+) -> Result<reqwest::Response, String> {
+    let response_status = response.status();
+    if response_status.is_success() {
+        return Ok(response);
+    }
+
+    let response_text = response.text().await.unwrap_or_default();
+    tracing::error!(
+        "{} API error response ({}): {}",
+        provider,
+        response_status,
+        response_text
+    );
+    Err(format!(
+        "{} API error ({}): {}",
+        provider, response_status, response_text
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tools::Tool;
+    use std::sync::Mutex;
+
+    /// Provider that plays back a fixed sequence of turns and records what it was sent.
+    struct ScriptedProvider {
+        turns: Mutex<Vec<ModelTurn>>,
+        seen: Mutex<Vec<usize>>,
+    }
+
+    #[async_trait]
+    impl LlmProvider for ScriptedProvider {
+        fn name(&self) -> &str {
+            "scripted"
+        }
+
+        async fn complete(&self, _: &Agent, _: &[Message]) -> Result<Completion, String> {
+            unreachable!()
+        }
+
+        async fn complete_stream(
+            &self,
+            _: &Agent,
+            _: &[Message],
+            _: mpsc::Sender<String>,
+        ) -> Result<Completion, String> {
+            unreachable!()
+        }
+
+        async fn complete_turn(
+            &self,
+            _: &Agent,
+            conversation: &[ChatItem],
+            tools: &[ToolDefinition],
+        ) -> Result<ModelTurn, String> {
+            assert_eq!(tools.len(), 1);
+            self.seen.lock().unwrap().push(conversation.len());
+            Ok(self.turns.lock().unwrap().remove(0))
+        }
+    }
+
+    struct EchoTool;
+
+    #[async_trait]
+    impl Tool for EchoTool {
+        fn definition(&self) -> ToolDefinition {
+            ToolDefinition {
+                name: "http_fetch".to_string(),
+                description: "echo".to_string(),
+                parameters: serde_json::json!({ "type": "object" }),
+            }
+        }
+
+        async fn call(&self, arguments: serde_json::Value) -> Result<serde_json::Value, String> {
+            Ok(arguments)
+        }
+    }
+
+    fn agent() -> Agent {
+        Agent {
+            id: "agent_t".to_string(),
+            name: "T".to_string(),
+            description: String::new(),
+            capabilities: vec![],
+            provider: "scripted".to_string(),
+            model: "m".to_string(),
+            system_prompt: "You are T.".to_string(),
+            tools: vec!["http_fetch".to_string()],
+        }
+    }
+
+    #[tokio::test]
+    async fn tool_calls_are_executed_until_the_model_answers() {
+        let provider = ScriptedProvider {
+            turns: Mutex::new(vec![
+                ModelTurn::ToolCalls {
+                    calls: vec![ToolCall {
+                        id: "call_0".to_string(),
+                        name: "http_fetch".to_string(),
+                        arguments: serde_json::json!({ "url": "https://example.com" }),
+                    }],
+                    tokens_used: Some(10),
+                },
+                ModelTurn::Reply(Completion {
+                    reply_text: "done".to_string(),
+                    tokens_used: Some(5),
+                }),
+            ]),
+            seen: Mutex::new(vec![]),
+        };
+        let mut tools = ToolRegistry::default();
+        tools.register(EchoTool);
+
+        let messages = build_messages(None, "fetch it".to_string());
+        let completion = complete_with_tools(&provider, &tools, &agent(), &messages)
+            .await
+            .unwrap();
+
+        assert_eq!(completion.reply_text, "done");
+        assert_eq!(completion.tokens_used, Some(15));
+        // Second turn sees the user message, the tool call and its result
+        assert_eq!(*provider.seen.lock().unwrap(), vec![1, 3]);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_tool_rounds() {
+        let call = ModelTurn::ToolCalls {
+            calls: vec![ToolCall {
+                id: "call_0".to_string(),
+                name: "mint_nft".to_string(),
+                arguments: serde_json::json!({}),
+            }],
+            tokens_used: None,
+        };
+        let provider = ScriptedProvider {
+            turns: Mutex::new(vec![call; MAX_TOOL_ROUNDS]),
+            seen: Mutex::new(vec![]),
+        };
+        let mut tools = ToolRegistry::default();
+        tools.register(EchoTool);
+
+        let messages = build_messages(None, "loop".to_string());
+        let err = complete_with_tools(&provider, &tools, &agent(), &messages)
+            .await
+            .unwrap_err();
+        assert!(err.contains("did not produce an answer"));
+    }
+
+    #[tokio::test]
+    async fn lines_are_decoded_after_chunks_are_joined() {
+        // "é" and "👋" are split between chunks
+        let chunks: Vec<Result<Vec<u8>, std::io::Error>> = vec![
+            Ok(b"caf\xc3".to_vec()),
+            Ok(b"\xa9\nhi \xf0\x9f".to_vec()),
+            Ok(b"\x91\x8b\n".to_vec()),
+        ];
+        let body = reqwest::Body::wrap_stream(futures_util::stream::iter(chunks));
+        let response = reqwest::Response::from(axum::http::Response::new(body));
+
+        let (tx, mut rx) = mpsc::channel(8);
+        let mut reply_text = String::new();
+        for_each_line(response, &tx, &mut reply_text, |line| {
+            Ok(Some(line.to_string()))
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(reply_text, "caféhi 👋");
+        assert_eq!(rx.recv().await.unwrap(), "café");
+        assert_eq!(rx.recv().await.unwrap(), "hi 👋");
+    }
+}
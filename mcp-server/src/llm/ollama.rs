@@ -0,0 +1,137 @@
+//! Ollama provider.
+//!
+//! Uses Ollama's native `/api/chat` endpoint, which streams newline-delimited
+//! JSON objects rather than Server-Sent Events.
+
+use super::{check_status, for_each_line, read_response_text, Completion, LlmProvider};
+use crate::models::{Agent, Message};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+/// Provider for models served by a local Ollama daemon (e.g. `llama3.2`).
+pub struct OllamaProvider {
+    client: Client,
+    base_url: String,
+}
+
+impl OllamaProvider {
+    /// Creates a provider for the Ollama daemon at `base_url` (e.g. `http://localhost:11434`).
+    pub fn new(client: Client, base_url: &str) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn build_request(agent: &Agent, messages: &[Message], stream: bool) -> serde_json::Value {
+        let mut chat = vec![json!({
+            "role": "system",
+            "content": agent.system_prompt.clone()
+        })];
+        for msg in messages {
+            chat.push(json!({
+                "role": msg.role,
+                "content": msg.content
+            }));
+        }
+
+        json!({
+            "model": agent.model,
+            "messages": chat,
+            "stream": stream,
+            "options": { "temperature": 0.7 }
+        })
+    }
+
+    async fn send(&self, request: &serde_json::Value) -> Result<reqwest::Response, String> {
+        self.client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(request)
+            .send()
+            .await
+            .map_err(|e| format!("Ollama API request failed: {}", e))
+    }
+
+    /// Sums the prompt and completion token counts reported on the final message.
+    fn tokens_used(response: &serde_json::Value) -> Option<u32> {
+        let prompt = response["prompt_eval_count"].as_u64();
+        let completion = response["eval_count"].as_u64();
+        match (prompt, completion) {
+            (None, None) => None,
+            (p, c) => Some((p.unwrap_or(0) + c.unwrap_or(0)) as u32),
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    async fn complete(&self, agent: &Agent, messages: &[Message]) -> Result<Completion, String> {
+        let response = self
+            .send(&Self::build_request(agent, messages, false))
+            .await?;
+        let response_text = read_response_text("Ollama", response).await?;
+
+        tracing::info!("Ollama API response received successfully");
+
+        let response: serde_json::Value = serde_json::from_str(&response_text).map_err(|e| {
+            format!(
+                "Failed to parse Ollama response: {}. Raw: {}",
+                e, response_text
+            )
+        })?;
+
+        let reply_text = response["message"]["content"]
+            .as_str()
+            .unwrap_or("Sorry, I couldn't generate a response.")
+            .to_string();
+
+        Ok(Completion {
+            reply_text,
+            tokens_used: Self::tokens_used(&response),
+        })
+    }
+
+    async fn complete_stream(
+        &self,
+        agent: &Agent,
+        messages: &[Message],
+        tx: mpsc::Sender<String>,
+    ) -> Result<Completion, String> {
+        let response = self
+            .send(&Self::build_request(agent, messages, true))
+            .await?;
+        let response = check_status("Ollama", response).await?;
+
+        let mut reply_text = String::new();
+        let mut tokens_used = None;
+
+        for_each_line(response, &tx, &mut reply_text, |line| {
+            if line.is_empty() {
+                return Ok(None);
+            }
+            let chunk: serde_json::Value = serde_json::from_str(line).map_err(|e| {
+                format!("Failed to parse Ollama stream chunk: {}. Raw: {}", e, line)
+            })?;
+            if let Some(error) = chunk["error"].as_str() {
+                return Err(format!("Ollama stream error: {}", error));
+            }
+            if chunk["done"].as_bool() == Some(true) {
+                tokens_used = Self::tokens_used(&chunk);
+            }
+            Ok(chunk["message"]["content"].as_str().map(|s| s.to_string()))
+        })
+        .await?;
+
+        tracing::info!("Ollama API stream completed successfully");
+        Ok(Completion {
+            reply_text,
+            tokens_used,
+        })
+    }
+}
@@ -0,0 +1,267 @@
+//! OpenAI-compatible chat-completions provider.
+//!
+//! Used for Groq and OpenAI, and for any other server that implements the
+//! `/chat/completions` API (vLLM, LM Studio, LocalAI, ...).
+
+use super::{
+    check_status, for_each_sse_data, read_response_text, ChatItem, Completion, LlmProvider,
+    ModelTurn, ToolCall, ToolDefinition,
+};
+use crate::models::{Agent, Message};
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use tokio::sync::mpsc;
+
+/// Provider for any OpenAI-compatible chat-completions API.
+pub struct OpenAiCompatibleProvider {
+    name: String,
+    client: Client,
+    base_url: String,
+    api_key: Option<String>,
+}
+
+impl OpenAiCompatibleProvider {
+    /// Creates a provider.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - Name the provider is registered under (e.g. `"groq"`)
+    /// * `client` - Shared HTTP client
+    /// * `base_url` - API base URL, without the `/chat/completions` suffix
+    /// * `api_key` - Bearer token; omitted for servers without authentication
+    pub fn new(name: &str, client: Client, base_url: &str, api_key: Option<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            api_key,
+        }
+    }
+
+    fn build_request(agent: &Agent, messages: &[Message], stream: bool) -> serde_json::Value {
+        let mut chat = vec![json!({
+            "role": "system",
+            "content": agent.system_prompt.clone()
+        })];
+        for msg in messages {
+            chat.push(json!({
+                "role": msg.role,
+                "content": msg.content
+            }));
+        }
+
+        let mut request = json!({
+            "model": agent.model,
+            "messages": chat,
+            "temperature": 0.7,
+            "max_tokens": 1024
+        });
+        if stream {
+            request["stream"] = json!(true);
+        }
+        request
+    }
+
+    /// Builds a request that offers `tools` in the OpenAI `tools` format.
+    fn build_tool_request(
+        agent: &Agent,
+        conversation: &[ChatItem],
+        tools: &[ToolDefinition],
+    ) -> serde_json::Value {
+        let mut chat = vec![json!({
+            "role": "system",
+            "content": agent.system_prompt.clone()
+        })];
+        for item in conversation {
+            match item {
+                ChatItem::Message(msg) => chat.push(json!({
+                    "role": msg.role,
+                    "content": msg.content
+                })),
+                ChatItem::ToolCalls(calls) => chat.push(json!({
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": calls.iter().map(|call| json!({
+                        "id": call.id,
+                        "type": "function",
+                        "function": {
+                            "name": call.name,
+                            "arguments": call.arguments.to_string()
+                        }
+                    })).collect::<Vec<_>>()
+                })),
+                ChatItem::ToolResults(results) => {
+                    for result in results {
+                        chat.push(json!({
+                            "role": "tool",
+                            "tool_call_id": result.call_id,
+                            "content": result.content.to_string()
+                        }));
+                    }
+                }
+            }
+        }
+
+        json!({
+            "model": agent.model,
+            "messages": chat,
+            "tools": tools.iter().map(|tool| json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.parameters
+                }
+            })).collect::<Vec<_>>(),
+            "tool_choice": "auto",
+            "temperature": 0.7,
+            "max_tokens": 1024
+        })
+    }
+
+    async fn send(&self, request: &serde_json::Value) -> Result<reqwest::Response, String> {
+        let mut builder = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .header("Content-Type", "application/json")
+            .json(request);
+        if let Some(ref api_key) = self.api_key {
+            builder = builder.header("Authorization", format!("Bearer {}", api_key));
+        }
+        builder
+            .send()
+            .await
+            .map_err(|e| format!("{} API request failed: {}", self.name, e))
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn complete(&self, agent: &Agent, messages: &[Message]) -> Result<Completion, String> {
+        let response = self
+            .send(&Self::build_request(agent, messages, false))
+            .await?;
+        let response_text = read_response_text(&self.name, response).await?;
+
+        tracing::info!("{} API response received successfully", self.name);
+
+        let response: serde_json::Value = serde_json::from_str(&response_text).map_err(|e| {
+            format!(
+                "Failed to parse {} response: {}. Raw: {}",
+                self.name, e, response_text
+            )
+        })?;
+
+        let reply_text = response["choices"][0]["message"]["content"]
+            .as_str()
+            .unwrap_or("Sorry, I couldn't generate a response.")
+            .to_string();
+        let tokens_used = response["usage"]["total_tokens"].as_u64().map(|t| t as u32);
+
+        Ok(Completion {
+            reply_text,
+            tokens_used,
+        })
+    }
+
+    async fn complete_stream(
+        &self,
+        agent: &Agent,
+        messages: &[Message],
+        tx: mpsc::Sender<String>,
+    ) -> Result<Completion, String> {
+        let response = self
+            .send(&Self::build_request(agent, messages, true))
+            .await?;
+        let response = check_status(&self.name, response).await?;
+
+        let mut reply_text = String::new();
+        let mut tokens_used = None;
+
+        for_each_sse_data(response, &tx, &mut reply_text, |data| {
+            if data == "[DONE]" {
+                return Ok(None);
+            }
+            let chunk: serde_json::Value = serde_json::from_str(data).map_err(|e| {
+                format!(
+                    "Failed to parse {} stream chunk: {}. Raw: {}",
+                    self.name, e, data
+                )
+            })?;
+            // Groq reports usage on the final chunk under `x_groq`
+            let usage = chunk.get("usage").or_else(|| chunk["x_groq"].get("usage"));
+            if let Some(total) = usage.and_then(|u| u["total_tokens"].as_u64()) {
+                tokens_used = Some(total as u32);
+            }
+            Ok(chunk["choices"][0]["delta"]["content"]
+                .as_str()
+                .map(|s| s.to_string()))
+        })
+        .await?;
+
+        tracing::info!("{} API stream completed successfully", self.name);
+        Ok(Completion {
+            reply_text,
+            tokens_used,
+        })
+    }
+
+    async fn complete_turn(
+        &self,
+        agent: &Agent,
+        conversation: &[ChatItem],
+        tools: &[ToolDefinition],
+    ) -> Result<ModelTurn, String> {
+        let response = self
+            .send(&Self::build_tool_request(agent, conversation, tools))
+            .await?;
+        let response_text = read_response_text(&self.name, response).await?;
+
+        let response: serde_json::Value = serde_json::from_str(&response_text).map_err(|e| {
+            format!(
+                "Failed to parse {} response: {}. Raw: {}",
+                self.name, e, response_text
+            )
+        })?;
+        let message = &response["choices"][0]["message"];
+        let tokens_used = response["usage"]["total_tokens"].as_u64().map(|t| t as u32);
+
+        let calls: Vec<ToolCall> = message["tool_calls"]
+            .as_array()
+            .map(|calls| {
+                calls
+                    .iter()
+                    .map(|call| {
+                        let raw = call["function"]["arguments"].as_str().unwrap_or("{}");
+                        ToolCall {
+                            id: call["id"].as_str().unwrap_or_default().to_string(),
+                            name: call["function"]["name"]
+                                .as_str()
+                                .unwrap_or_default()
+                                .to_string(),
+                            arguments: serde_json::from_str(raw)
+                                .unwrap_or_else(|_| serde_json::Value::String(raw.to_string())),
+                        }
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        if !calls.is_empty() {
+            return Ok(ModelTurn::ToolCalls { calls, tokens_used });
+        }
+
+        Ok(ModelTurn::Reply(Completion {
+            reply_text: message["content"]
+                .as_str()
+                .unwrap_or("Sorry, I couldn't generate a response.")
+                .to_string(),
+            tokens_used,
+        }))
+    }
+}
@@ -1,37 +1,59 @@
 //! MCP Server (Model Context Protocol)
 //!
-//! A JSON-RPC 2.0 server that provides AI agent functionality using Groq API.
-//! This server manages multiple specialized AI agents and processes user requests with
-//! context-aware responses.
+//! A JSON-RPC 2.0 server that provides AI agent functionality using pluggable LLM
+//! providers (Groq, Gemini, OpenAI-compatible APIs and Ollama). This server manages
+//! multiple specialized AI agents and processes user requests with context-aware responses.
 //!
 //! # Architecture
 //!
 //! The server is organized into several modules:
 //! - `models` - Data structures for JSON-RPC, agents, and AI API
-//! - `agents` - Agent definitions and management
-//! - `gemini` - AI API client and communication (supports both Groq and Gemini)
+//! - `agents` - Agent registry loaded from TOML/YAML files, with hot reload
+//! - `agent_store` - SQLite persistence for agents managed at runtime
+//! - `llm` - The `LlmProvider` trait and its Groq, Gemini, OpenAI-compatible and Ollama backends
 //! - `handlers` - HTTP request handlers for JSON-RPC methods
+//! - `mcp` - Model Context Protocol over stdio and streamable HTTP
+//! - `sessions` - Server-side conversation session storage
+//! - `tools` - Built-in tools agents can call (`mint_nft`, `http_fetch`)
 //!
 //! # Supported Methods
 //!
 //! - `list_agents` - Returns all available AI agents
+//! - `reload_agents` - Reloads the agent definitions from disk
+//! - `get_agent` / `create_agent` / `update_agent` / `delete_agent` - Manage agents at runtime
 //! - `process_text` - Processes user text through a specified agent
+//! - `create_session` / `get_session` / `delete_session` - Manage conversation sessions
+//!
+//! Replies can also be streamed as Server-Sent Events from `POST /process_text/stream`.
+//!
+//! The agents are also available to standard MCP clients as tools, prompts and
+//! resources, at `POST /mcp` or over stdio with `mcp-server --stdio`.
 //!
 //! # Quick Start
 //!
-//! 1. Set `GROQ_API_KEY` in your `.env` file
+//! 1. Set `GROQ_API_KEY` (or another provider's configuration) in your `.env` file
 //! 2. Run `cargo run --release`
 //! 3. Server starts on `http://0.0.0.0:3000`
 //! 4. Send JSON-RPC 2.0 requests to the root path
 
+mod agent_store;
 mod agents;
-mod gemini;
 mod handlers;
+mod llm;
+mod mcp;
 mod models;
+mod sessions;
+mod tools;
 
+use agent_store::AgentStore;
+use agents::AgentRegistry;
 use axum::{routing::post, Router};
+use llm::ProviderRegistry;
 use reqwest::Client;
+use sessions::SessionStore;
 use std::sync::Arc;
+use std::time::Duration;
+use tools::ToolRegistry;
 use tower_http::cors::CorsLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
@@ -41,12 +63,32 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 /// providing thread-safe access to shared resources.
 #[derive(Clone)]
 pub struct AppState {
-    /// Shared HTTP client for making requests to AI API.
-    pub http_client: Client,
-    /// AI API key for authentication (Groq or Gemini).
-    pub gemini_api_key: String,
-    /// Flag to indicate if using Groq instead of Gemini
-    pub use_groq: bool,
+    /// Agents loaded from the agents directory.
+    pub agents: Arc<AgentRegistry>,
+    /// Configured LLM providers, looked up by each agent's `provider` field.
+    pub providers: Arc<ProviderRegistry>,
+    /// Built-in tools available to agents that declare them.
+    pub tools: Arc<ToolRegistry>,
+    /// Server-side conversation sessions.
+    pub sessions: Arc<SessionStore>,
+    /// Token required for the agent management methods; `None` disables them.
+    pub admin_token: Option<String>,
+}
+
+#[cfg(test)]
+impl AppState {
+    /// State with the bundled agents, an in-memory agent store and no providers or tools.
+    pub fn for_tests() -> Arc<Self> {
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/agents");
+        let store = AgentStore::open_in_memory().unwrap();
+        Arc::new(Self {
+            agents: Arc::new(AgentRegistry::load(dir, store).unwrap()),
+            providers: Arc::new(ProviderRegistry::default()),
+            tools: Arc::new(ToolRegistry::default()),
+            sessions: Arc::new(SessionStore::new(Duration::from_secs(60))),
+            admin_token: None,
+        })
+    }
 }
 
 /// Main entry point for the MCP server.
@@ -55,67 +97,132 @@ pub struct AppState {
 /// - Environment variable loading from .env file
 /// - Structured logging with tracing
 /// - CORS middleware for cross-origin requests
-/// - Shared application state with AI API key
+/// - Shared application state with the configured LLM providers
 /// - HTTP route handlers for JSON-RPC methods
 ///
 /// # Environment Variables
 ///
-/// * `GROQ_API_KEY` - Groq API key for agent responses (recommended)
-/// * `GEMINI_API_KEY` - Alternative: Google Gemini API key
+/// * `GROQ_API_KEY` - Enables the `groq` provider (recommended)
+/// * `GEMINI_API_KEY` - Enables the `gemini` provider
+/// * `OPENAI_API_KEY` / `OPENAI_BASE_URL` - Enables the `openai` provider
+/// * `OLLAMA_URL` - Enables the `ollama` provider (e.g. `http://localhost:11434`)
+/// * `AGENTS_DIR` - Optional. Directory containing agent definition files (default: agents)
+/// * `AGENTS_DB_PATH` - Optional. SQLite database for agents managed at runtime (default: agents.db)
+/// * `ADMIN_TOKEN` - Optional. Bearer token enabling create/update/delete_agent
+/// * `MINTING_SERVICE_URL` - Optional. web3-minting base URL for the `mint_nft` tool (default: http://localhost:8081)
+/// * `MINT_TIMEOUT_SECS` - Optional. How long `mint_nft` waits for a mint to be confirmed (default: 120)
+/// * `HTTP_FETCH_ALLOWED_HOSTS` - Optional. Comma-separated hosts the `http_fetch` tool may access
+/// * `HTTP_FETCH_ALLOW_PRIVATE` - Optional. `true` lets `http_fetch` reach loopback and private addresses
+/// * `SESSION_TTL_SECS` - Optional. Idle time before a session expires (default: 3600)
 /// * `RUST_LOG` - Optional. Logging level (default: info)
 ///
+/// # Arguments
+///
+/// * `--stdio` - Serve the Model Context Protocol on stdin/stdout instead of HTTP
+///
 /// # Panics
 ///
 /// Panics if:
-/// - Neither GROQ_API_KEY nor GEMINI_API_KEY is set
+/// - No LLM provider is configured
+/// - The agent database cannot be opened
+/// - The agent definitions cannot be loaded or fail validation
 /// - Server fails to bind to port 3000
 #[tokio::main]
 async fn main() {
     // Load environment variables from .env file
     dotenv::dotenv().ok();
 
+    // With --stdio, stdout carries MCP messages, so logs must go to stderr
+    let stdio = std::env::args().any(|arg| arg == "--stdio");
+
     // Initialize structured logging
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "mcp_server=debug,tower_http=debug,axum=trace".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(
+            tracing_subscriber::fmt::layer()
+                .with_writer(std::io::stderr)
+                .with_ansi(!stdio),
+        )
         .init();
 
-    // Load API keys from environment
-    let gemini_api_key = std::env::var("GEMINI_API_KEY").ok();
-    let groq_api_key = std::env::var("GROQ_API_KEY").ok();
-    
-    let (api_key, use_groq) = match (groq_api_key, gemini_api_key) {
-        (Some(groq_key), _) => {
-            tracing::info!("🔧 Using Groq API");
-            (groq_key, true)
-        },
-        (None, Some(gemini_key)) => {
-            tracing::info!("🔧 Using Gemini API (fallback)");
-            (gemini_key, false)
-        },
-        (None, None) => {
-            panic!("Either GROQ_API_KEY or GEMINI_API_KEY must be set in .env file");
+    // Create shared HTTP client
+    let http_client = Client::new();
+
+    // Configure LLM providers from environment
+    let providers = ProviderRegistry::from_env(&http_client);
+    if providers.is_empty() {
+        panic!(
+            "No LLM provider configured: set GROQ_API_KEY, GEMINI_API_KEY, \
+             OPENAI_API_KEY/OPENAI_BASE_URL or OLLAMA_URL in .env file"
+        );
+    }
+
+    // Load agent definitions and reload them whenever a file changes
+    let agents_dir = std::env::var("AGENTS_DIR").unwrap_or_else(|_| "agents".to_string());
+    let agents_db_path =
+        std::env::var("AGENTS_DB_PATH").unwrap_or_else(|_| "agents.db".to_string());
+    let agent_store = AgentStore::open(&agents_db_path)
+        .unwrap_or_else(|e| panic!("Failed to open agent database {}: {}", agents_db_path, e));
+    let agents = match AgentRegistry::load(&agents_dir, agent_store) {
+        Ok(registry) => Arc::new(registry),
+        Err(errors) => {
+            for error in &errors {
+                tracing::error!("   - {}", error);
+            }
+            panic!("Failed to load agents from {}", agents_dir);
+        }
+    };
+    let _agents_watcher = match agents::watch(agents.clone()) {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            tracing::warn!("⚠️  Agent hot reload disabled: {}", e);
+            None
         }
     };
 
-    // Create shared HTTP client
-    let http_client = Client::new();
+    // Sessions expire after an hour of inactivity unless configured otherwise
+    let session_ttl = std::env::var("SESSION_TTL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(3600);
+
+    let admin_token = std::env::var("ADMIN_TOKEN").ok().filter(|t| !t.is_empty());
+    if admin_token.is_none() {
+        tracing::warn!("⚠️  ADMIN_TOKEN not set: agent management methods are disabled");
+    }
 
     // Create shared application state
     let state = Arc::new(AppState {
-        http_client,
-        gemini_api_key: api_key,
-        use_groq,
+        agents,
+        providers: Arc::new(providers),
+        tools: Arc::new(ToolRegistry::from_env(&http_client)),
+        sessions: Arc::new(SessionStore::new(Duration::from_secs(session_ttl))),
+        admin_token,
     });
 
+    // Serve MCP over stdio instead of HTTP when launched by an MCP client
+    if stdio {
+        tracing::info!(
+            "🔌 Serving MCP over stdio with {} agents",
+            state.agents.list().len()
+        );
+        mcp::serve_stdio(state).await;
+        return;
+    }
+
     // Build the router with CORS support
     let app = Router::new()
         .route("/", post(handlers::handle_jsonrpc))
+        .route("/mcp", post(mcp::handle_http).get(mcp::handle_http_get))
+        .route(
+            "/process_text/stream",
+            post(handlers::handle_process_text_stream),
+        )
         .layer(CorsLayer::permissive())
-        .with_state(state);
+        .with_state(state.clone());
 
     // Bind to TCP listener
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000")
@@ -124,15 +231,29 @@ async fn main() {
 
     // Log startup information
     tracing::info!("🚀 MCP Server starting on http://0.0.0.0:3000");
-    tracing::info!("📋 Available agents: {}", agents::get_agents().len());
-    if use_groq {
-        tracing::info!("🤖 Using Groq API for agent responses");
-    } else {
-        tracing::info!("🤖 Using Google Gemini for agent responses");
+    tracing::info!(
+        "📋 Available agents: {} (from {})",
+        state.agents.list().len(),
+        agents_dir
+    );
+    tracing::info!("🤖 LLM providers: {}", state.providers.names().join(", "));
+    for agent in state.agents.list() {
+        if state.providers.get(&agent.provider).is_none() {
+            tracing::warn!(
+                "⚠️  Agent {} uses provider '{}', which is not configured",
+                agent.id,
+                agent.provider
+            );
+        }
     }
     tracing::info!("📡 Supported JSON-RPC methods:");
     tracing::info!("   - list_agents");
+    tracing::info!("   - reload_agents");
+    tracing::info!("   - get_agent / create_agent / update_agent / delete_agent");
     tracing::info!("   - process_text");
+    tracing::info!("   - create_session / get_session / delete_session");
+    tracing::info!("🌊 Streaming replies at POST /process_text/stream");
+    tracing::info!("🔌 Model Context Protocol at POST /mcp (or run with --stdio)");
 
     // Start the server
     axum::serve(listener, app)
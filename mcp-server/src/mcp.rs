@@ -0,0 +1,585 @@
+//! Model Context Protocol (MCP) support.
+//!
+//! Exposes the agents to standard MCP clients over two transports:
+//!
+//! - **stdio** - newline-delimited JSON-RPC on stdin/stdout (`mcp-server --stdio`)
+//! - **Streamable HTTP** - JSON-RPC `POST`ed to `/mcp`
+//!
+//! # Mapping
+//!
+//! - Each agent is a **tool** named `ask_<agent_id>` that sends a message to the agent
+//!   and returns its reply with a server-side session to continue the conversation in
+//! - Each agent is a **prompt** named after its ID that returns its system prompt
+//! - Each agent is a **resource** at `agent://<agent_id>` holding its definition as JSON
+//!
+//! # Supported Methods
+//!
+//! `initialize`, `ping`, `tools/list`, `tools/call`, `prompts/list`, `prompts/get`,
+//! `resources/list`, `resources/read`, and the `notifications/*` notifications.
+
+use crate::handlers::{handle_process_text, MAX_BATCH_SIZE};
+use crate::models::{Agent, JsonRpcRequest, ProcessTextResult};
+use crate::AppState;
+use axum::{
+    body::Bytes,
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+/// Protocol revisions this server implements, newest first.
+pub const SUPPORTED_PROTOCOL_VERSIONS: &[&str] = &["2025-06-18", "2025-03-26", "2024-11-05"];
+
+/// Prefix of the tool names agents are exposed under.
+const TOOL_PREFIX: &str = "ask_";
+
+/// URI scheme of agent resources.
+const RESOURCE_SCHEME: &str = "agent://";
+
+/// Handles a single MCP JSON-RPC message.
+///
+/// # Returns
+///
+/// The response to send back, or `None` if the message was a notification (or a
+/// response to a server request) that must not be answered.
+pub async fn handle_message(state: Arc<AppState>, message: Value) -> Option<Value> {
+    let Some(method) = message["method"].as_str() else {
+        // Responses from the client carry no method; the server never sends requests
+        return match message.get("id") {
+            Some(_) if message.get("result").is_some() || message.get("error").is_some() => None,
+            id => Some(error_response(
+                id.cloned().unwrap_or(Value::Null),
+                -32600,
+                "Invalid Request: missing method",
+            )),
+        };
+    };
+    let Some(id) = message.get("id").cloned() else {
+        tracing::debug!("MCP notification: {}", method);
+        return None;
+    };
+    if message["jsonrpc"] != "2.0" {
+        return Some(error_response(
+            id,
+            -32600,
+            "Invalid Request: jsonrpc must be '2.0'",
+        ));
+    }
+
+    tracing::info!("Received MCP request: method={}", method);
+    let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+    let outcome = match method {
+        "initialize" => Ok(initialize(&params)),
+        "ping" => Ok(json!({})),
+        "tools/list" => Ok(list_tools(&state)),
+        "tools/call" => call_tool(state, &params).await,
+        "prompts/list" => Ok(list_prompts(&state)),
+        "prompts/get" => get_prompt(&state, &params),
+        "resources/list" => Ok(list_resources(&state)),
+        "resources/read" => read_resource(&state, &params),
+        _ => Err((-32601, format!("Method not found: {}", method))),
+    };
+
+    Some(match outcome {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err((code, message)) => error_response(id, code, &message),
+    })
+}
+
+/// Streamable HTTP transport: handles `POST /mcp`.
+///
+/// Accepts a single JSON-RPC message or a batch of up to [`MAX_BATCH_SIZE`].
+/// Requests are answered with an `application/json` body; input consisting only of
+/// notifications and responses is acknowledged with `202 Accepted`.
+pub async fn handle_http(State(state): State<Arc<AppState>>, body: Bytes) -> Response {
+    let message: Value = match serde_json::from_slice(&body) {
+        Ok(message) => message,
+        Err(e) => {
+            let error = error_response(Value::Null, -32700, &format!("Parse error: {}", e));
+            return (StatusCode::BAD_REQUEST, Json(error)).into_response();
+        }
+    };
+
+    match handle_payload(state, message).await {
+        Some(response) => Json(response).into_response(),
+        None => StatusCode::ACCEPTED.into_response(),
+    }
+}
+
+/// Streamable HTTP transport: `GET /mcp`.
+///
+/// This server never initiates messages, so it does not offer a server-to-client
+/// event stream.
+pub async fn handle_http_get() -> StatusCode {
+    StatusCode::METHOD_NOT_ALLOWED
+}
+
+/// stdio transport: reads one JSON-RPC message per line from stdin and writes one
+/// response per line to stdout until stdin is closed.
+///
+/// Logging must go to stderr while this runs, since stdout carries the protocol.
+pub async fn serve_stdio(state: Arc<AppState>) {
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+
+    loop {
+        let line = match lines.next_line().await {
+            Ok(Some(line)) => line,
+            Ok(None) => break,
+            Err(e) => {
+                tracing::error!("Failed to read from stdin: {}", e);
+                break;
+            }
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(message) => handle_payload(state.clone(), message).await,
+            Err(e) => Some(error_response(
+                Value::Null,
+                -32700,
+                &format!("Parse error: {}", e),
+            )),
+        };
+        let Some(response) = response else {
+            continue;
+        };
+
+        let mut out = response.to_string();
+        out.push('\n');
+        if let Err(e) = stdout.write_all(out.as_bytes()).await {
+            tracing::error!("Failed to write to stdout: {}", e);
+            break;
+        }
+        let _ = stdout.flush().await;
+    }
+
+    tracing::info!("stdin closed, shutting down MCP stdio transport");
+}
+
+/// Handles a single message or a batch, returning `None` if nothing needs answering.
+async fn handle_payload(state: Arc<AppState>, payload: Value) -> Option<Value> {
+    match payload {
+        Value::Array(messages) if messages.is_empty() => Some(error_response(
+            Value::Null,
+            -32600,
+            "Invalid Request: empty batch",
+        )),
+        Value::Array(messages) if messages.len() > MAX_BATCH_SIZE => Some(error_response(
+            Value::Null,
+            -32600,
+            &format!(
+                "Invalid Request: batch of {} messages exceeds the limit of {}",
+                messages.len(),
+                MAX_BATCH_SIZE
+            ),
+        )),
+        Value::Array(messages) => {
+            let mut responses = Vec::new();
+            for message in messages {
+                if let Some(response) = handle_message(state.clone(), message).await {
+                    responses.push(response);
+                }
+            }
+            (!responses.is_empty()).then_some(Value::Array(responses))
+        }
+        message => handle_message(state, message).await,
+    }
+}
+
+/// Handles `initialize`, agreeing on the protocol version.
+///
+/// The client's version is accepted if supported; otherwise the newest version
+/// this server implements is offered.
+fn initialize(params: &Value) -> Value {
+    let requested = params["protocolVersion"].as_str().unwrap_or_default();
+    let version = SUPPORTED_PROTOCOL_VERSIONS
+        .iter()
+        .find(|v| **v == requested)
+        .unwrap_or(&SUPPORTED_PROTOCOL_VERSIONS[0]);
+
+    json!({
+        "protocolVersion": version,
+        "capabilities": {
+            "tools": { "listChanged": false },
+            "prompts": { "listChanged": false },
+            "resources": { "listChanged": false }
+        },
+        "serverInfo": {
+            "name": env!("CARGO_PKG_NAME"),
+            "version": env!("CARGO_PKG_VERSION")
+        },
+        "instructions": "Each tool named ask_<agent_id> sends a message to a specialized \
+                         AI agent and returns its reply. Omit session_id to start a new \
+                         conversation; pass the session_id of a reply back to continue it."
+    })
+}
+
+/// Handles `tools/list`: one tool per agent.
+fn list_tools(state: &AppState) -> Value {
+    let tools: Vec<Value> = state
+        .agents
+        .list()
+        .iter()
+        .map(|agent| {
+            json!({
+                "name": format!("{}{}", TOOL_PREFIX, agent.id),
+                "title": agent.name,
+                "description": format!("Ask the {} agent: {}", agent.name, agent.description),
+                "inputSchema": {
+                    "type": "object",
+                    "properties": {
+                        "message": {
+                            "type": "string",
+                            "description": "The message to send to the agent"
+                        },
+                        "session_id": {
+                            "type": "string",
+                            "description": "Session to continue, from a previous reply; \
+                                            omit to start a new conversation"
+                        }
+                    },
+                    "required": ["message"]
+                }
+            })
+        })
+        .collect();
+    json!({ "tools": tools })
+}
+
+/// Handles `tools/call` by running the message through the agent.
+///
+/// Without a `session_id`, a session is started for the call, so every reply
+/// carries a session the conversation can be continued in. Agent failures are
+/// reported as tool results with `isError` set, as the protocol requires, so the
+/// client's model can see them.
+async fn call_tool(state: Arc<AppState>, params: &Value) -> Result<Value, (i32, String)> {
+    let name = params["name"]
+        .as_str()
+        .ok_or((-32602, "Invalid params: name is required".to_string()))?;
+    let agent_id = name
+        .strip_prefix(TOOL_PREFIX)
+        .filter(|id| state.agents.find(id).is_some())
+        .ok_or_else(|| (-32602, format!("Unknown tool: {}", name)))?;
+    let arguments = &params["arguments"];
+    let message = arguments["message"].as_str().ok_or((
+        -32602,
+        "Invalid params: arguments.message is required".to_string(),
+    ))?;
+
+    let (session_id, new_session) = match arguments["session_id"].as_str() {
+        Some(id) if !id.is_empty() => (id.to_string(), false),
+        _ => {
+            let session = state.sessions.create(Some(agent_id.to_string())).await;
+            (session.id, true)
+        }
+    };
+
+    let request = JsonRpcRequest {
+        jsonrpc: "2.0".to_string(),
+        method: "process_text".to_string(),
+        params: Some(json!({
+            "agent_id": agent_id,
+            "user_text": message,
+            "session_id": session_id,
+        })),
+        id: Value::Null,
+    };
+    let Json(response) = handle_process_text(State(state.clone()), request).await;
+
+    if let Some(error) = response.error {
+        if new_session {
+            state.sessions.delete(&session_id).await;
+        }
+        let details = error
+            .data
+            .as_ref()
+            .and_then(|d| d["details"].as_str())
+            .map(|d| format!(" ({})", d))
+            .unwrap_or_default();
+        return Ok(json!({
+            "content": [{ "type": "text", "text": format!("{}{}", error.message, details) }],
+            "isError": true
+        }));
+    }
+
+    let result: ProcessTextResult = response
+        .result
+        .and_then(|r| serde_json::from_value(r).ok())
+        .ok_or((-32603, "Internal error: malformed agent reply".to_string()))?;
+    Ok(json!({
+        "content": [
+            { "type": "text", "text": result.reply_text },
+            { "type": "text", "text": format!("session_id: {}", session_id) }
+        ],
+        "structuredContent": {
+            "reply_text": result.reply_text,
+            "session_id": result.session_id,
+            "metadata": result.metadata
+        },
+        "isError": false
+    }))
+}
+
+/// Handles `prompts/list`: one prompt per agent.
+fn list_prompts(state: &AppState) -> Value {
+    let prompts: Vec<Value> = state
+        .agents
+        .list()
+        .iter()
+        .map(|agent| {
+            json!({
+                "name": agent.id,
+                "title": agent.name,
+                "description": agent.description,
+                "arguments": [{
+                    "name": "message",
+                    "description": "Optional first user message",
+                    "required": false
+                }]
+            })
+        })
+        .collect();
+    json!({ "prompts": prompts })
+}
+
+/// Handles `prompts/get`: the agent's system prompt, followed by the optional message.
+fn get_prompt(state: &AppState, params: &Value) -> Result<Value, (i32, String)> {
+    let agent = find_agent(state, params["name"].as_str())?;
+
+    let mut messages = vec![json!({
+        "role": "user",
+        "content": { "type": "text", "text": agent.system_prompt }
+    })];
+    if let Some(message) = params["arguments"]["message"].as_str() {
+        messages.push(json!({
+            "role": "user",
+            "content": { "type": "text", "text": message }
+        }));
+    }
+
+    Ok(json!({
+        "description": agent.description,
+        "messages": messages
+    }))
+}
+
+/// Handles `resources/list`: one resource per agent.
+fn list_resources(state: &AppState) -> Value {
+    let resources: Vec<Value> = state
+        .agents
+        .list()
+        .iter()
+        .map(|agent| {
+            json!({
+                "uri": format!("{}{}", RESOURCE_SCHEME, agent.id),
+                "name": agent.id,
+                "title": agent.name,
+                "description": agent.description,
+                "mimeType": "application/json"
+            })
+        })
+        .collect();
+    json!({ "resources": resources })
+}
+
+/// Handles `resources/read`: the agent definition as JSON.
+fn read_resource(state: &AppState, params: &Value) -> Result<Value, (i32, String)> {
+    let uri = params["uri"]
+        .as_str()
+        .ok_or((-32602, "Invalid params: uri is required".to_string()))?;
+    let agent = find_agent(state, uri.strip_prefix(RESOURCE_SCHEME))
+        .map_err(|_| (-32002, format!("Resource not found: {}", uri)))?;
+
+    Ok(json!({
+        "contents": [{
+            "uri": uri,
+            "mimeType": "application/json",
+            "text": serde_json::to_string_pretty(&agent).unwrap_or_default()
+        }]
+    }))
+}
+
+fn find_agent(state: &AppState, agent_id: Option<&str>) -> Result<Agent, (i32, String)> {
+    let agent_id = agent_id.ok_or((-32602, "Invalid params: name is required".to_string()))?;
+    state
+        .agents
+        .find(agent_id)
+        .ok_or_else(|| (-32602, format!("Agent not found: {}", agent_id)))
+}
+
+fn error_response(id: Value, code: i32, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state() -> Arc<AppState> {
+        AppState::for_tests()
+    }
+
+    #[tokio::test]
+    async fn initialize_negotiates_the_protocol_version() {
+        let request = |version: &str| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "initialize",
+                "params": { "protocolVersion": version, "capabilities": {} }
+            })
+        };
+
+        let response = handle_message(state(), request("2025-03-26"))
+            .await
+            .unwrap();
+        assert_eq!(response["result"]["protocolVersion"], "2025-03-26");
+        assert_eq!(response["result"]["serverInfo"]["name"], "mcp-server");
+
+        let response = handle_message(state(), request("1999-01-01"))
+            .await
+            .unwrap();
+        assert_eq!(
+            response["result"]["protocolVersion"],
+            SUPPORTED_PROTOCOL_VERSIONS[0]
+        );
+    }
+
+    #[tokio::test]
+    async fn notifications_are_not_answered() {
+        let notification = json!({ "jsonrpc": "2.0", "method": "notifications/initialized" });
+        assert!(handle_message(state(), notification).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn oversized_batches_are_rejected() {
+        let ping = json!({ "jsonrpc": "2.0", "id": 1, "method": "ping" });
+        let batch = Value::Array(vec![ping; MAX_BATCH_SIZE + 1]);
+        let response = handle_payload(state(), batch).await.unwrap();
+        assert_eq!(response["error"]["code"], -32600);
+    }
+
+    #[tokio::test]
+    async fn agents_are_listed_as_tools_prompts_and_resources() {
+        let list = |method: &str| json!({ "jsonrpc": "2.0", "id": 1, "method": method });
+
+        let tools = handle_message(state(), list("tools/list")).await.unwrap();
+        assert_eq!(tools["result"]["tools"][0]["name"], "ask_agent_001");
+
+        let prompts = handle_message(state(), list("prompts/list")).await.unwrap();
+        assert_eq!(prompts["result"]["prompts"][1]["name"], "agent_002");
+
+        let resources = handle_message(state(), list("resources/list"))
+            .await
+            .unwrap();
+        assert_eq!(
+            resources["result"]["resources"][0]["uri"],
+            "agent://agent_001"
+        );
+    }
+
+    #[tokio::test]
+    async fn tool_errors_are_reported_as_results() {
+        let call = json!({
+            "jsonrpc": "2.0",
+            "id": 7,
+            "method": "tools/call",
+            "params": { "name": "ask_agent_001", "arguments": { "message": "hi" } }
+        });
+
+        // No provider is configured, so the agent call fails
+        let response = handle_message(state(), call).await.unwrap();
+        assert_eq!(response["id"], 7);
+        assert_eq!(response["result"]["isError"], true);
+    }
+
+    /// Provider that replies with the number of messages it was sent.
+    struct CountingProvider;
+
+    #[async_trait::async_trait]
+    impl crate::llm::LlmProvider for CountingProvider {
+        fn name(&self) -> &str {
+            "groq"
+        }
+
+        async fn complete(
+            &self,
+            _: &Agent,
+            messages: &[crate::models::Message],
+        ) -> Result<crate::llm::Completion, String> {
+            Ok(crate::llm::Completion {
+                reply_text: format!("{} messages", messages.len()),
+                tokens_used: None,
+            })
+        }
+
+        async fn complete_stream(
+            &self,
+            _: &Agent,
+            _: &[crate::models::Message],
+            _: tokio::sync::mpsc::Sender<String>,
+        ) -> Result<crate::llm::Completion, String> {
+            unreachable!()
+        }
+    }
+
+    #[tokio::test]
+    async fn tool_calls_start_a_session_to_continue_in() {
+        let base = state();
+        let mut providers = crate::llm::ProviderRegistry::default();
+        providers.register(CountingProvider);
+        let state = Arc::new(AppState {
+            agents: base.agents.clone(),
+            providers: Arc::new(providers),
+            tools: base.tools.clone(),
+            sessions: base.sessions.clone(),
+            admin_token: None,
+        });
+        let call = |arguments: Value| {
+            json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "method": "tools/call",
+                "params": { "name": "ask_agent_001", "arguments": arguments }
+            })
+        };
+
+        let first = handle_message(state.clone(), call(json!({ "message": "Hi" })))
+            .await
+            .unwrap();
+        let result = &first["result"];
+        assert_eq!(result["isError"], false, "{}", result);
+        let session_id = result["structuredContent"]["session_id"].as_str().unwrap();
+        assert_eq!(
+            result["content"][1]["text"],
+            format!("session_id: {}", session_id)
+        );
+
+        let second = handle_message(
+            state.clone(),
+            call(json!({ "message": "Again", "session_id": session_id })),
+        )
+        .await
+        .unwrap();
+        let result = &second["result"];
+        assert_eq!(result["structuredContent"]["session_id"], session_id);
+        // The first turn's messages were sent along with the second message
+        assert_eq!(result["structuredContent"]["reply_text"], "3 messages");
+        assert_eq!(
+            state.sessions.get(session_id).await.unwrap().messages.len(),
+            4
+        );
+    }
+}
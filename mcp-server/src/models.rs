@@ -1,135 +1,70 @@
 //! Data models for the MCP server.
 //!
 //! This module contains all the data structures used throughout the server,
-//! including JSON-RPC protocol types, agent definitions, AI API types (Groq/Gemini),
+//! including JSON-RPC protocol types, agent definitions, Gemini API types,
 //! and processing results.
+//!
+//! Types that are part of the public JSON-RPC protocol live in the shared
+//! `mcp-rpc` crate and are re-exported here; this module only defines the
+//! server-internal ones.
 
 use serde::{Deserialize, Serialize};
 
-/// JSON-RPC 2.0 request structure.
-///
-/// Represents an incoming JSON-RPC request following the 2.0 specification.
-///
-/// # Type Parameters
-///
-/// * `T` - The type of the params field
-#[derive(Debug, Serialize, Deserialize)]
-pub struct JsonRpcRequest<T> {
-    /// Protocol version, must be "2.0"
-    pub jsonrpc: String,
-    /// Name of the method to call
-    pub method: String,
-    /// Optional parameters for the method
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub params: Option<T>,
-    /// Request identifier for matching responses
-    pub id: serde_json::Value,
-}
-
-/// JSON-RPC 2.0 response structure.
-///
-/// Represents an outgoing JSON-RPC response following the 2.0 specification.
-///
-/// # Type Parameters
-///
-/// * `T` - The type of the result field
-#[derive(Debug, Serialize, Deserialize)]
-pub struct JsonRpcResponse<T> {
-    /// Protocol version, always "2.0"
-    pub jsonrpc: String,
-    /// Optional result data on success
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub result: Option<T>,
-    /// Optional error object on failure
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub error: Option<JsonRpcError>,
-    /// Request identifier matching the original request
-    pub id: serde_json::Value,
-}
-
-/// JSON-RPC 2.0 error object.
-///
-/// Represents an error in JSON-RPC response.
-#[derive(Debug, Serialize, Deserialize)]
-pub struct JsonRpcError {
-    /// Error code
-    pub code: i32,
-    /// Error message
-    pub message: String,
-    /// Optional additional error data
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub data: Option<serde_json::Value>,
-}
+pub use mcp_rpc::types::{
+    Agent, CreateSessionParams, DeleteSessionResult, JsonRpcError, JsonRpcRequest, JsonRpcResponse,
+    ListAgentsResult, Message, ProcessTextParams, ProcessTextResult, ProcessingMetadata, Session,
+    SessionIdParams, StreamDelta,
+};
 
-/// Information about an AI agent.
-///
-/// Represents a specialized AI agent with unique capabilities and system instructions.
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Agent {
-    /// Unique identifier for the agent
-    pub id: String,
-    /// Human-readable name
-    pub name: String,
-    /// Brief description of the agent's purpose
-    pub description: String,
-    /// List of capabilities (e.g., "text", "web3", "coding")
-    pub capabilities: Vec<String>,
-    /// AI model used by this agent (e.g., "mixtral-8x7b-32768" for Groq)
-    pub model: String,
-    /// System prompt that defines the agent's behavior
-    pub system_prompt: String,
-}
-
-/// Result of the list_agents JSON-RPC method.
+/// Result of the reload_agents JSON-RPC method.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct ListAgentsResult {
-    /// List of all available agents
+pub struct ReloadAgentsResult {
+    /// Agents loaded after the reload
     pub agents: Vec<Agent>,
 }
 
-/// Parameters for the process_text JSON-RPC method.
+/// Parameters for the get_agent and delete_agent JSON-RPC methods.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct ProcessTextParams {
-    /// ID of the agent to process the text
+pub struct AgentIdParams {
+    /// ID of the agent
     pub agent_id: String,
-    /// User's text input
-    pub user_text: String,
-    /// Optional conversation history for context
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub conversation_history: Option<Vec<Message>>,
 }
 
-/// A message in the conversation history.
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Message {
-    /// Role of the message sender ("user" or "assistant")
-    pub role: String,
-    /// Content of the message
-    pub content: String,
-}
-
-/// Result of the process_text JSON-RPC method.
-#[derive(Debug, Serialize, Deserialize)]
-pub struct ProcessTextResult {
-    /// ID of the agent that processed the text
+/// Parameters for the update_agent JSON-RPC method.
+///
+/// Only the fields that are present are changed; the agent ID cannot be changed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateAgentParams {
+    /// ID of the agent to update
     pub agent_id: String,
-    /// Agent's text response
-    pub reply_text: String,
-    /// Metadata about the processing
-    pub metadata: ProcessingMetadata,
-}
-
-/// Metadata about text processing.
+    /// New human-readable name
+    #[serde(default)]
+    pub name: Option<String>,
+    /// New description
+    #[serde(default)]
+    pub description: Option<String>,
+    /// New list of capabilities
+    #[serde(default)]
+    pub capabilities: Option<Vec<String>>,
+    /// New LLM provider name
+    #[serde(default)]
+    pub provider: Option<String>,
+    /// New model identifier
+    #[serde(default)]
+    pub model: Option<String>,
+    /// New system prompt
+    #[serde(default)]
+    pub system_prompt: Option<String>,
+    /// New list of tools
+    #[serde(default)]
+    pub tools: Option<Vec<String>>,
+}
+
+/// Result of the delete_agent JSON-RPC method.
 #[derive(Debug, Serialize, Deserialize)]
-pub struct ProcessingMetadata {
-    /// AI model used
-    pub model: String,
-    /// Number of tokens consumed (if available)
-    pub tokens_used: Option<u32>,
-    /// Processing time in milliseconds
-    pub processing_time_ms: u64,
-    /// Confidence score (currently hardcoded)
-    pub confidence: f64,
+pub struct DeleteAgentResult {
+    /// Whether the agent was deleted
+    pub deleted: bool,
 }
 
 /// Request structure for Google Gemini API.
@@ -142,6 +77,28 @@ pub struct GeminiRequest {
     /// Optional system instruction to define agent behavior
     #[serde(skip_serializing_if = "Option::is_none")]
     pub system_instruction: Option<GeminiSystemInstruction>,
+    /// Tools the model may call
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<GeminiTool>>,
+}
+
+/// A group of function declarations offered to Gemini.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeminiTool {
+    /// Functions the model may call
+    #[serde(rename = "functionDeclarations")]
+    pub function_declarations: Vec<GeminiFunctionDeclaration>,
+}
+
+/// Declaration of a function the model may call.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeminiFunctionDeclaration {
+    /// Function name
+    pub name: String,
+    /// What the function does
+    pub description: String,
+    /// OpenAPI-style schema of the arguments
+    pub parameters: serde_json::Value,
 }
 
 /// A single message/content in the Gemini conversation.
@@ -153,11 +110,55 @@ pub struct GeminiContent {
     pub parts: Vec<GeminiPart>,
 }
 
-/// A part of a Gemini message (currently only text).
-#[derive(Debug, Serialize, Deserialize)]
+/// A part of a Gemini message: text, a function call, or a function response.
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct GeminiPart {
     /// Text content of the message part
-    pub text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Function call requested by the model
+    #[serde(
+        default,
+        rename = "functionCall",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub function_call: Option<GeminiFunctionCall>,
+    /// Result of a function call, sent back to the model
+    #[serde(
+        default,
+        rename = "functionResponse",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub function_response: Option<GeminiFunctionResponse>,
+}
+
+impl GeminiPart {
+    /// Creates a text part.
+    pub fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// A function call made by Gemini.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeminiFunctionCall {
+    /// Name of the function
+    pub name: String,
+    /// Arguments chosen by the model
+    #[serde(default)]
+    pub args: serde_json::Value,
+}
+
+/// The result of a function call, returned to Gemini.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GeminiFunctionResponse {
+    /// Name of the function that was called
+    pub name: String,
+    /// Result object
+    pub response: serde_json::Value,
 }
 
 /// System instruction for Gemini to define agent behavior.
@@ -171,9 +172,10 @@ pub struct GeminiSystemInstruction {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GeminiResponse {
     /// List of candidate responses (usually one)
+    #[serde(default)]
     pub candidates: Vec<GeminiCandidate>,
     /// Optional metadata about token usage
-    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(skip_serializing_if = "Option::is_none", alias = "usageMetadata")]
     pub usage_metadata: Option<GeminiUsageMetadata>,
 }
 
@@ -184,6 +186,17 @@ pub struct GeminiCandidate {
     pub content: GeminiContent,
 }
 
+impl GeminiCandidate {
+    /// Concatenates the text parts of the candidate.
+    pub fn text(&self) -> String {
+        self.content
+            .parts
+            .iter()
+            .filter_map(|p| p.text.as_deref())
+            .collect()
+    }
+}
+
 /// Metadata about token usage in the Gemini API call.
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -0,0 +1,193 @@
+//! Server-side conversation sessions.
+//!
+//! This module keeps the message history of each conversation in memory so that
+//! clients only need to send a `session_id` with `process_text` instead of the
+//! full transcript. Sessions expire after a configurable idle period.
+
+use crate::models::{Message, Session};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Maximum number of messages kept per session.
+///
+/// Older turns are dropped first once this limit is exceeded, which keeps the
+/// prompt sent to the AI provider bounded for long conversations.
+pub const MAX_SESSION_MESSAGES: usize = 50;
+
+/// In-memory store of conversation sessions.
+///
+/// The store is shared through `AppState` and guarded by an async `RwLock`,
+/// so lookups from concurrent requests do not block each other.
+pub struct SessionStore {
+    /// Sessions keyed by their ID
+    sessions: RwLock<HashMap<String, Session>>,
+    /// Idle time after which a session is discarded
+    ttl: Duration,
+}
+
+impl SessionStore {
+    /// Creates an empty session store.
+    ///
+    /// # Arguments
+    ///
+    /// * `ttl` - How long a session may stay idle before it is removed
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            sessions: RwLock::new(HashMap::new()),
+            ttl,
+        }
+    }
+
+    /// Creates a new, empty session and returns a copy of it.
+    ///
+    /// Expired sessions are purged as a side effect, so the store does not grow
+    /// without bound when clients never delete their sessions.
+    ///
+    /// # Arguments
+    ///
+    /// * `agent_id` - Optional agent the session is started with
+    pub async fn create(&self, agent_id: Option<String>) -> Session {
+        let now = chrono::Utc::now();
+        let session = Session {
+            id: uuid::Uuid::new_v4().to_string(),
+            agent_id,
+            messages: Vec::new(),
+            created_at: now,
+            updated_at: now,
+        };
+
+        let mut sessions = self.sessions.write().await;
+        self.purge_expired(&mut sessions);
+        sessions.insert(session.id.clone(), session.clone());
+        session
+    }
+
+    /// Recreates an expired session under its ID with the given history, and
+    /// returns a copy of it. Only the last [`MAX_SESSION_MESSAGES`] messages are kept.
+    ///
+    /// # Returns
+    ///
+    /// `None` if a session with the ID still exists
+    pub async fn restore(
+        &self,
+        session_id: String,
+        agent_id: Option<String>,
+        mut messages: Vec<Message>,
+    ) -> Option<Session> {
+        let mut sessions = self.sessions.write().await;
+        self.purge_expired(&mut sessions);
+        if sessions.contains_key(&session_id) {
+            return None;
+        }
+
+        let excess = messages.len().saturating_sub(MAX_SESSION_MESSAGES);
+        messages.drain(..excess);
+        let now = chrono::Utc::now();
+        let session = Session {
+            id: session_id,
+            agent_id,
+            messages,
+            created_at: now,
+            updated_at: now,
+        };
+        sessions.insert(session.id.clone(), session.clone());
+        Some(session)
+    }
+
+    /// Returns a copy of the session with the given ID, if it exists and has not expired.
+    pub async fn get(&self, session_id: &str) -> Option<Session> {
+        let sessions = self.sessions.read().await;
+        sessions
+            .get(session_id)
+            .filter(|s| !self.is_expired(s))
+            .cloned()
+    }
+
+    /// Deletes a session.
+    ///
+    /// # Returns
+    ///
+    /// `true` if a session was removed, `false` if it did not exist
+    pub async fn delete(&self, session_id: &str) -> bool {
+        self.sessions.write().await.remove(session_id).is_some()
+    }
+
+    /// Records a completed turn (user message and agent reply) in a session.
+    ///
+    /// # Returns
+    ///
+    /// `false` if the session no longer exists (e.g. it was deleted or expired
+    /// mid-request); an expired session is removed rather than kept alive
+    pub async fn append_turn(
+        &self,
+        session_id: &str,
+        agent_id: &str,
+        user_text: String,
+        reply_text: String,
+    ) -> bool {
+        let mut sessions = self.sessions.write().await;
+        let Some(session) = sessions.get_mut(session_id) else {
+            return false;
+        };
+        if self.is_expired(session) {
+            sessions.remove(session_id);
+            return false;
+        }
+
+        session.messages.push(Message {
+            role: "user".to_string(),
+            content: user_text,
+        });
+        session.messages.push(Message {
+            role: "assistant".to_string(),
+            content: reply_text,
+        });
+        if session.messages.len() > MAX_SESSION_MESSAGES {
+            let excess = session.messages.len() - MAX_SESSION_MESSAGES;
+            session.messages.drain(..excess);
+        }
+        session.agent_id = Some(agent_id.to_string());
+        session.updated_at = chrono::Utc::now();
+        true
+    }
+
+    fn is_expired(&self, session: &Session) -> bool {
+        let idle = chrono::Utc::now() - session.updated_at;
+        idle.to_std().map(|d| d > self.ttl).unwrap_or(false)
+    }
+
+    fn purge_expired(&self, sessions: &mut HashMap<String, Session>) {
+        sessions.retain(|_, s| !self.is_expired(s));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn turns_are_not_appended_to_expired_sessions() {
+        let store = SessionStore::new(Duration::from_millis(20));
+        let session = store.create(None).await;
+        assert!(
+            store
+                .append_turn(&session.id, "agent_001", "Hi".into(), "Hello".into())
+                .await
+        );
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(
+            !store
+                .append_turn(
+                    &session.id,
+                    "agent_001",
+                    "Still there?".into(),
+                    "Yes".into()
+                )
+                .await
+        );
+        assert!(store.get(&session.id).await.is_none());
+        assert!(store.sessions.read().await.is_empty());
+    }
+}
@@ -0,0 +1,373 @@
+//! `http_fetch` tool for simple HTTP(S) GET requests.
+//!
+//! The model chooses the URLs, so by default only public addresses may be
+//! fetched: loopback, private, link-local and other special-purpose addresses
+//! are refused, both as literal hosts and as the addresses a host name resolves
+//! to. Redirects are followed only to URLs that pass the same checks.
+
+use super::{required_str, Tool};
+use crate::llm::ToolDefinition;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use reqwest::{redirect, Client, Url};
+use serde_json::json;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Maximum number of characters of a response body returned to the model.
+const MAX_BODY_CHARS: usize = 16_000;
+
+/// Maximum number of bytes of a response body read, enough for [`MAX_BODY_CHARS`]
+/// characters of any UTF-8 text. The rest of the body is never downloaded.
+const MAX_BODY_BYTES: usize = MAX_BODY_CHARS * 4;
+
+/// Timeout for a single fetch.
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Maximum number of redirects followed for a single fetch.
+const MAX_REDIRECTS: usize = 5;
+
+/// Fetches a URL with `GET` and returns its status and (truncated) body.
+pub struct HttpFetchTool {
+    client: Client,
+    policy: UrlPolicy,
+}
+
+/// Which URLs may be fetched.
+#[derive(Clone)]
+struct UrlPolicy {
+    /// Hosts that may be fetched; `None` allows any host
+    allowed_hosts: Option<Vec<String>>,
+    /// Whether loopback, private and other non-public addresses may be fetched
+    allow_private: bool,
+}
+
+impl HttpFetchTool {
+    /// Creates the tool, optionally restricted to a list of lowercase host names.
+    ///
+    /// Unless `allow_private` is set, hosts on loopback, private, link-local or
+    /// other non-public addresses are refused, even when they are allowlisted.
+    pub fn new(allowed_hosts: Option<Vec<String>>, allow_private: bool) -> Self {
+        let policy = UrlPolicy {
+            allowed_hosts,
+            allow_private,
+        };
+        let redirect_policy = policy.clone();
+        let mut builder = Client::builder().redirect(redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            // Literal IP addresses are not resolved, so they are checked here
+            match redirect_policy.check(attempt.url()) {
+                Ok(()) => attempt.follow(),
+                Err(e) => attempt.error(e),
+            }
+        }));
+        if !allow_private {
+            builder = builder.dns_resolver(Arc::new(PublicResolver));
+        }
+        Self {
+            client: builder.build().expect("Failed to build http_fetch client"),
+            policy,
+        }
+    }
+
+    fn check_url(&self, url: &str) -> Result<Url, String> {
+        let url = Url::parse(url).map_err(|e| format!("Invalid URL: {}", e))?;
+        self.policy.check(&url)?;
+        Ok(url)
+    }
+}
+
+impl UrlPolicy {
+    /// Checks the scheme, the allowlist and, for IP address hosts, the address.
+    ///
+    /// Host names are checked again when they are resolved (see [`PublicResolver`]).
+    fn check(&self, url: &Url) -> Result<(), String> {
+        if !matches!(url.scheme(), "http" | "https") {
+            return Err("Only http and https URLs can be fetched".to_string());
+        }
+        let host = url.host_str().unwrap_or_default().to_lowercase();
+        if let Some(ref allowed) = self.allowed_hosts {
+            if !allowed.contains(&host) {
+                return Err(format!("Host not allowed: {}", host));
+            }
+        }
+        if !self.allow_private {
+            let literal = host.trim_start_matches('[').trim_end_matches(']');
+            let private = match literal.parse::<IpAddr>() {
+                Ok(ip) => !is_public(ip),
+                Err(_) => host.is_empty() || host == "localhost" || host.ends_with(".localhost"),
+            };
+            if private {
+                return Err(format!(
+                    "Host not allowed: {} is not a public address",
+                    host
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Resolves host names like the system resolver, but fails for names that
+/// resolve to non-public addresses.
+///
+/// Checking the addresses the connection is actually made to also covers
+/// redirects and DNS rebinding.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_string();
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if let Some(addr) = addrs.iter().find(|addr| !is_public(addr.ip())) {
+                return Err(
+                    format!("{} resolves to non-public address {}", host, addr.ip()).into(),
+                );
+            }
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+/// Whether an address is publicly routable: not loopback, private, link-local,
+/// shared, multicast, documentation or otherwise reserved.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public_v4(v4);
+            }
+            let segments = ip.segments();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                // Unique local fc00::/7
+                || (segments[0] & 0xfe00) == 0xfc00
+                // Link-local fe80::/10
+                || (segments[0] & 0xffc0) == 0xfe80
+                // Documentation 2001:db8::/32
+                || (segments[0] == 0x2001 && segments[1] == 0x0db8)
+                // NAT64 64:ff9b::/96 embeds an IPv4 address
+                || (segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0]
+                    && !is_public_v4(Ipv4Addr::from(
+                        ((segments[6] as u32) << 16) | segments[7] as u32,
+                    ))))
+        }
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, ..] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // "This network" 0.0.0.0/8
+        || a == 0
+        // Shared address space 100.64.0.0/10
+        || (a == 100 && (b & 0xc0) == 64)
+        // Benchmarking 198.18.0.0/15
+        || (a == 198 && (b & 0xfe) == 18)
+        // Reserved 240.0.0.0/4
+        || a >= 240)
+}
+
+#[async_trait]
+impl Tool for HttpFetchTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "http_fetch".to_string(),
+            description: "Fetch a web page or JSON API with an HTTP GET request and return \
+                          the status code and response body."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "url": { "type": "string", "description": "The http:// or https:// URL to fetch" }
+                },
+                "required": ["url"]
+            }),
+        }
+    }
+
+    async fn call(&self, arguments: serde_json::Value) -> Result<serde_json::Value, String> {
+        let url = self.check_url(required_str(&arguments, "url")?)?;
+
+        let response = self
+            .client
+            .get(url)
+            .timeout(FETCH_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| format!("Fetch failed: {}", error_chain(&e)))?;
+
+        let status = response.status().as_u16();
+        let content_type = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string());
+        let (body, truncated_bytes) = read_body(response).await?;
+
+        let truncated = truncated_bytes || body.chars().count() > MAX_BODY_CHARS;
+        let body: String = body.chars().take(MAX_BODY_CHARS).collect();
+
+        Ok(json!({
+            "status": status,
+            "content_type": content_type,
+            "body": body,
+            "truncated": truncated,
+        }))
+    }
+}
+
+/// Reads at most [`MAX_BODY_BYTES`] of a response body and decodes it as UTF-8,
+/// replacing invalid sequences.
+///
+/// # Returns
+///
+/// The text, and whether the body was longer than the cap
+async fn read_body(response: reqwest::Response) -> Result<(String, bool), String> {
+    let mut bytes = Vec::new();
+    let mut truncated = false;
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|e| format!("Failed to read response body: {}", e))?;
+        let room = MAX_BODY_BYTES - bytes.len();
+        if chunk.len() > room {
+            bytes.extend_from_slice(&chunk[..room]);
+            truncated = true;
+            break;
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    // Drop a character cut in half by the cap
+    if let Err(e) = std::str::from_utf8(&bytes) {
+        if truncated && e.error_len().is_none() {
+            bytes.truncate(e.valid_up_to());
+        }
+    }
+    Ok((String::from_utf8_lossy(&bytes).into_owned(), truncated))
+}
+
+/// An error with its sources, so the model sees why a redirect or lookup was refused.
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(e) = source {
+        message.push_str(": ");
+        message.push_str(&e.to_string());
+        source = e.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, response::Redirect, routing::get, Router};
+
+    fn tool(allowed_hosts: Option<&[&str]>) -> HttpFetchTool {
+        HttpFetchTool::new(
+            allowed_hosts.map(|hosts| hosts.iter().map(|h| h.to_string()).collect()),
+            false,
+        )
+    }
+
+    #[test]
+    fn refuses_non_public_addresses() {
+        let tool = tool(None);
+        for url in [
+            "http://127.0.0.1:8081/mint",
+            "http://localhost:8081/mint",
+            "http://api.localhost/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.0.0.1/",
+            "http://172.16.5.4/",
+            "http://192.168.1.1/",
+            "http://100.64.0.1/",
+            "http://0.0.0.0/",
+            "http://[::1]/",
+            "http://[fd00::1]/",
+            "http://[fe80::1]/",
+            "http://[::ffff:127.0.0.1]/",
+            "http://[64:ff9b::a9fe:a9fe]/",
+        ] {
+            assert!(tool.check_url(url).is_err(), "{} was allowed", url);
+        }
+        assert!(tool.check_url("https://93.184.215.14/").is_ok());
+        assert!(tool.check_url("https://[2606:4700::1111]/").is_ok());
+        assert!(tool.check_url("https://example.com/").is_ok());
+        assert!(tool.check_url("file:///etc/passwd").is_err());
+    }
+
+    #[test]
+    fn allowlist_does_not_admit_private_addresses() {
+        let tool = tool(Some(&["example.com", "127.0.0.1"]));
+        assert!(tool.check_url("https://example.com/").is_ok());
+        assert!(tool.check_url("https://example.org/").is_err());
+        assert!(tool.check_url("http://127.0.0.1/").is_err());
+    }
+
+    #[tokio::test]
+    async fn refuses_host_names_resolving_to_private_addresses() {
+        let result = PublicResolver.resolve("localhost".parse().unwrap()).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn endless_bodies_are_read_up_to_the_cap() {
+        // Never ends, so reading all of it would never return
+        let app = Router::new().route(
+            "/",
+            get(|| async {
+                let chunk = axum::body::Bytes::from("é".repeat(1000));
+                Body::from_stream(futures_util::stream::repeat(chunk).map(Ok::<_, std::io::Error>))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let result = HttpFetchTool::new(None, true)
+            .call(json!({ "url": format!("http://{}/", addr) }))
+            .await
+            .unwrap();
+        assert_eq!(result["status"], 200);
+        assert_eq!(result["truncated"], true);
+        let body = result["body"].as_str().unwrap();
+        assert_eq!(body.chars().count(), MAX_BODY_CHARS);
+        assert!(body.chars().all(|c| c == 'é'));
+    }
+
+    #[tokio::test]
+    async fn redirects_to_private_addresses_are_refused() {
+        // The server itself is on loopback; the tool's client is called directly,
+        // skipping the check of the first URL, so only the redirect is checked
+        let app = Router::new().route(
+            "/",
+            get(|| async { Redirect::temporary("http://169.254.169.254/latest/meta-data/") }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let error = tool(None)
+            .client
+            .get(format!("http://{}/", addr))
+            .send()
+            .await
+            .unwrap_err();
+        assert!(error_chain(&error).contains("not a public address"));
+    }
+}
@@ -0,0 +1,216 @@
+//! `mint_nft` tool backed by the web3-minting service.
+
+use super::{required_str, Tool};
+use crate::llm::ToolDefinition;
+use async_trait::async_trait;
+use reqwest::Client;
+use serde_json::json;
+use std::time::Duration;
+
+/// How often a mint job is polled.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Mints an NFT through the web3-minting service: the mint is queued with
+/// `POST /mint`, and the job is polled with `GET /mint/{id}` until it is confirmed
+/// or failed, or the timeout passes.
+pub struct MintNftTool {
+    client: Client,
+    base_url: String,
+    /// Service token web3-minting requires on `/mint`
+    token: Option<String>,
+    /// How long to wait for a mint job to finish
+    timeout: Duration,
+    poll_interval: Duration,
+}
+
+impl MintNftTool {
+    /// Creates the tool for the minting service at `base_url` (e.g. `http://localhost:8081`),
+    /// authenticating with its service `token` and waiting up to `timeout` for each mint.
+    pub fn new(client: Client, base_url: &str, token: Option<String>, timeout: Duration) -> Self {
+        Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            token,
+            timeout,
+            poll_interval: POLL_INTERVAL,
+        }
+    }
+
+    /// Sends a request to the minting service and parses its JSON response.
+    async fn send(&self, request: reqwest::RequestBuilder) -> Result<serde_json::Value, String> {
+        let request = match &self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        };
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Minting service request failed: {}", e))?;
+
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| format!("Failed to read minting service response: {}", e))?;
+        if !status.is_success() {
+            return Err(format!("Minting service error ({}): {}", status, text));
+        }
+        serde_json::from_str(&text)
+            .map_err(|e| format!("Failed to parse minting service response: {}", e))
+    }
+}
+
+#[async_trait]
+impl Tool for MintNftTool {
+    fn definition(&self) -> ToolDefinition {
+        ToolDefinition {
+            name: "mint_nft".to_string(),
+            description: "Mint an NFT. Uploads the metadata to storage, mints a token \
+                          pointing at it and waits for the transaction to be confirmed. \
+                          Returns the job with its metadata URL, transaction hash and token \
+                          ID. If the mint is still in progress after the wait, returns the \
+                          job ID and status instead."
+                .to_string(),
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string", "description": "Title of the NFT" },
+                    "description": { "type": "string", "description": "Description of the NFT" },
+                    "asset_url": { "type": "string", "description": "URL of the image or audio asset" },
+                    "recipient": { "type": "string", "description": "Wallet address receiving the token" }
+                },
+                "required": ["name"]
+            }),
+        }
+    }
+
+    async fn call(&self, arguments: serde_json::Value) -> Result<serde_json::Value, String> {
+        let body = json!({
+            "name": required_str(&arguments, "name")?,
+            "description": arguments["description"].as_str(),
+            "asset_url": arguments["asset_url"].as_str(),
+            "recipient": arguments["recipient"].as_str(),
+        });
+
+        let accepted = self
+            .send(
+                self.client
+                    .post(format!("{}/mint", self.base_url))
+                    .json(&body),
+            )
+            .await?;
+        let job_id = accepted["job_id"]
+            .as_str()
+            .ok_or("Minting service response has no job_id")?;
+
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        loop {
+            let job = self
+                .send(
+                    self.client
+                        .get(format!("{}/mint/{}", self.base_url, job_id)),
+                )
+                .await?;
+            match job["status"].as_str() {
+                Some("confirmed") => return Ok(job),
+                Some("failed") => {
+                    return Err(format!(
+                        "Mint job {} failed: {}",
+                        job_id,
+                        job["error"].as_str().unwrap_or("unknown error")
+                    ))
+                }
+                _ if tokio::time::Instant::now() + self.poll_interval > deadline => return Ok(job),
+                _ => tokio::time::sleep(self.poll_interval).await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        extract::{Path, Request},
+        http::{header, StatusCode},
+        middleware::{self, Next},
+        routing::{get, post},
+        Json, Router,
+    };
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// Starts a minting service whose jobs are pending for two polls and then
+    /// have `final_status`, and returns a tool using it. Like web3-minting, the
+    /// service rejects requests without its token.
+    async fn tool(final_status: &'static str, timeout: Duration) -> MintNftTool {
+        let polls = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route(
+                "/mint",
+                post(|| async { Json(json!({ "job_id": "job-1", "status": "pending" })) }),
+            )
+            .route(
+                "/mint/{id}",
+                get(move |Path(id): Path<String>| async move {
+                    let status = match polls.fetch_add(1, Ordering::SeqCst) {
+                        0 | 1 => "submitted",
+                        _ => final_status,
+                    };
+                    Json(json!({
+                        "id": id,
+                        "status": status,
+                        "mint": { "tx_hash": "0xabc", "token_id": "7" },
+                        "error": (status == "failed").then_some("reverted"),
+                    }))
+                }),
+            )
+            .layer(middleware::from_fn(
+                |request: Request, next: Next| async move {
+                    match request.headers().get(header::AUTHORIZATION) {
+                        Some(token) if token == "Bearer s3cret" => Ok(next.run(request).await),
+                        _ => Err(StatusCode::UNAUTHORIZED),
+                    }
+                },
+            ));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut tool = MintNftTool::new(
+            Client::new(),
+            &format!("http://{}", addr),
+            Some("s3cret".to_string()),
+            timeout,
+        );
+        tool.poll_interval = Duration::from_millis(10);
+        tool
+    }
+
+    #[tokio::test]
+    async fn waits_for_the_job_to_be_confirmed() {
+        let tool = tool("confirmed", Duration::from_secs(5)).await;
+        let job = tool.call(json!({ "name": "Test" })).await.unwrap();
+        assert_eq!(job["status"], "confirmed");
+        assert_eq!(job["mint"]["tx_hash"], "0xabc");
+    }
+
+    #[tokio::test]
+    async fn reports_a_failed_job() {
+        let tool = tool("failed", Duration::from_secs(5)).await;
+        let error = tool.call(json!({ "name": "Test" })).await.unwrap_err();
+        assert!(
+            error.contains("job-1") && error.contains("reverted"),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn returns_the_job_in_progress_after_the_timeout() {
+        let tool = tool("confirmed", Duration::ZERO).await;
+        let job = tool.call(json!({ "name": "Test" })).await.unwrap();
+        assert_eq!(job["id"], "job-1");
+        assert_eq!(job["status"], "submitted");
+    }
+}
@@ -0,0 +1,131 @@
+//! Built-in tools that agents can call.
+//!
+//! An agent opts into tools by listing their names in its `tools` field. The
+//! tool-calling loop in [`crate::llm::complete_with_tools`] offers those tools to
+//! the model and executes the calls it makes through the [`ToolRegistry`].
+//!
+//! # Tools
+//!
+//! - `mint_nft` - Mints an NFT through the web3-minting service ([`mint_nft::MintNftTool`])
+//! - `http_fetch` - Fetches a URL over HTTP(S) ([`http_fetch::HttpFetchTool`])
+
+pub mod http_fetch;
+pub mod mint_nft;
+
+use crate::llm::ToolDefinition;
+use crate::models::Agent;
+use async_trait::async_trait;
+use reqwest::Client;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Names of the built-in tools agents may declare.
+pub const BUILTIN_TOOLS: &[&str] = &["mint_nft", "http_fetch"];
+
+/// A function the model can call.
+#[async_trait]
+pub trait Tool: Send + Sync {
+    /// Describes the tool to the model.
+    fn definition(&self) -> ToolDefinition;
+
+    /// Executes the tool with the arguments chosen by the model.
+    ///
+    /// # Errors
+    ///
+    /// Returns a description of the failure; it is passed back to the model.
+    async fn call(&self, arguments: serde_json::Value) -> Result<serde_json::Value, String>;
+}
+
+/// The set of available tools, keyed by name.
+#[derive(Default)]
+pub struct ToolRegistry {
+    tools: HashMap<String, Arc<dyn Tool>>,
+}
+
+impl ToolRegistry {
+    /// Builds the registry of built-in tools.
+    ///
+    /// * `MINTING_SERVICE_URL` - Base URL of web3-minting (default: `http://localhost:8081`)
+    /// * `MINT_API_TOKEN` - Service token configured in web3-minting
+    /// * `MINT_TIMEOUT_SECS` - How long `mint_nft` waits for a mint to be confirmed (default: 120)
+    /// * `HTTP_FETCH_ALLOWED_HOSTS` - Optional comma-separated host allowlist for `http_fetch`
+    /// * `HTTP_FETCH_ALLOW_PRIVATE` - `true` to let `http_fetch` reach loopback and private addresses
+    pub fn from_env(client: &Client) -> Self {
+        let env = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+        let mut registry = Self::default();
+
+        registry.register(mint_nft::MintNftTool::new(
+            client.clone(),
+            env("MINTING_SERVICE_URL")
+                .as_deref()
+                .unwrap_or("http://localhost:8081"),
+            env("MINT_API_TOKEN"),
+            Duration::from_secs(
+                env("MINT_TIMEOUT_SECS")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(120),
+            ),
+        ));
+        registry.register(http_fetch::HttpFetchTool::new(
+            env("HTTP_FETCH_ALLOWED_HOSTS").map(|hosts| {
+                hosts
+                    .split(',')
+                    .map(|h| h.trim().to_lowercase())
+                    .filter(|h| !h.is_empty())
+                    .collect()
+            }),
+            env("HTTP_FETCH_ALLOW_PRIVATE").is_some_and(|v| v == "true" || v == "1"),
+        ));
+
+        registry
+    }
+
+    /// Adds a tool, replacing any tool registered under the same name.
+    pub fn register(&mut self, tool: impl Tool + 'static) {
+        self.tools.insert(tool.definition().name, Arc::new(tool));
+    }
+
+    /// Returns the definitions of the tools declared by an agent.
+    pub fn definitions_for(&self, agent: &Agent) -> Vec<ToolDefinition> {
+        agent
+            .tools
+            .iter()
+            .filter_map(|name| self.tools.get(name))
+            .map(|tool| tool.definition())
+            .collect()
+    }
+
+    /// Calls a tool on behalf of an agent.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the agent did not declare the tool, the tool does not exist, or the
+    /// call itself fails.
+    pub async fn call(
+        &self,
+        agent: &Agent,
+        name: &str,
+        arguments: serde_json::Value,
+    ) -> Result<serde_json::Value, String> {
+        if !agent.tools.iter().any(|t| t == name) {
+            return Err(format!("Tool '{}' is not available to this agent", name));
+        }
+        let tool = self
+            .tools
+            .get(name)
+            .ok_or_else(|| format!("Unknown tool: {}", name))?;
+        tool.call(arguments).await
+    }
+}
+
+/// Returns a required string argument.
+pub(crate) fn required_str<'a>(
+    arguments: &'a serde_json::Value,
+    key: &str,
+) -> Result<&'a str, String> {
+    arguments[key]
+        .as_str()
+        .filter(|v| !v.trim().is_empty())
+        .ok_or_else(|| format!("Missing required argument: {}", key))
+}
@@ -1,50 +1,418 @@
+use crate::chains::{Chain, ChainRegistry, Ens};
+use crate::evm::abi::{self, Token};
+use crate::evm::ens;
+use crate::evm::rpc::Receipt;
+use crate::evm::signer::Signer;
+use crate::evm::tx::Eip1559Transaction;
+use crate::evm::{keccak256, Address};
 use crate::models::MintResult;
-use anyhow::{anyhow, Result};
-use reqwest::Client;
-use std::env;
+use anyhow::{anyhow, Context, Result};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
 
-/// Mint a token on-chain (or mock). Returns tx hash and optional token id.
-pub async fn mint_token(metadata_url: &str, recipient: &str) -> Result<MintResult> {
-    if let Ok(rpc) = env::var("BLOCKCHAIN_RPC") {
-        tracing::info!(rpc = %rpc, "calling configured blockchain RPC");
-        let client = Client::new();
-        // For simplicity we POST a JSON body {metadata_url, recipient}
-        let body = serde_json::json!({"metadata_url": metadata_url, "recipient": recipient});
-        let resp = client
-            .post(&rpc)
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| anyhow!("rpc request failed: {}", e))?;
+/// ERC-721 mint function called on each chain's contract, e.g. OpenZeppelin's
+/// `ERC721URIStorage` with an owner-only `safeMint`.
+const MINT_SIGNATURE: &str = "safeMint(address,string)";
+
+/// ERC-721 event emitted with `from = 0` when a token is minted.
+const TRANSFER_EVENT: &str = "Transfer(address,address,uint256)";
+
+/// How often `eth_getTransactionReceipt` is polled while waiting for confirmations.
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Who a token is minted to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Recipient {
+    Address(Address),
+    /// A `.eth` name, normalized; resolved through ENS when the mint is sent
+    Ens(String),
+}
+
+/// Parses a recipient: a hex address (checked against its EIP-55 checksum when
+/// mixed-case) or a `.eth` name.
+pub fn parse_recipient(value: &str) -> Result<Recipient> {
+    if ens::is_ens_name(value) {
+        return Ok(Recipient::Ens(ens::normalize(value)?));
+    }
+    value
+        .parse()
+        .map(Recipient::Address)
+        .map_err(|e| anyhow!("invalid recipient address {}: {}", value, e))
+}
+
+/// A signed mint transaction that may not have been sent yet.
+pub struct SignedMint {
+    /// The mint, with the hash of the signed transaction but no token ID yet
+    pub mint: MintResult,
+    /// The transaction for `eth_sendRawTransaction`; `None` for mocked mints
+    pub raw_tx: Option<Vec<u8>>,
+}
+
+/// Sign a mint transaction (or mock) without sending it.
+///
+/// On a configured chain (the default one unless `chain` names another), signs an
+/// EIP-1559 `safeMint(recipient, metadata_url)` transaction from
+/// `WALLET_PRIVATE_KEY` to the chain's contract. Without a recipient, the token is
+/// minted to the wallet itself. ENS names are resolved on the registry's ENS chain
+/// before signing.
+///
+/// The transaction hash is computed from the signed bytes, so the caller can save
+/// the transaction before [`broadcast_mint`] sends it.
+pub async fn sign_mint(
+    chains: &ChainRegistry,
+    chain: Option<&str>,
+    metadata_url: &str,
+    recipient: Option<&str>,
+) -> Result<SignedMint> {
+    match chains.get(chain)? {
+        Some(chain) => {
+            tracing::info!(chain = %chain.name, "minting via Ethereum JSON-RPC");
+            let (mint, raw_tx) = sign_transaction(
+                chain,
+                chains.signer()?,
+                chains.ens()?,
+                metadata_url,
+                recipient,
+            )
+            .await?;
+            Ok(SignedMint {
+                mint,
+                raw_tx: Some(raw_tx),
+            })
+        }
+        None => {
+            // Mock path
+            let tx_hash = format!("0x{}", Uuid::new_v4().simple());
+            tracing::warn!(tx_hash = %tx_hash, "no chain configured - returning mock transaction");
+            Ok(SignedMint {
+                mint: MintResult {
+                    tx_hash,
+                    token_id: None,
+                    block_number: None,
+                    chain_id: None,
+                    explorer_url: None,
+                },
+                raw_tx: None,
+            })
+        }
+    }
+}
+
+/// Send a transaction signed by [`sign_mint`].
+///
+/// Sending the same transaction again is safe: if the node rejects it because it
+/// already knows it (pending or mined), that counts as sent.
+pub async fn broadcast_mint(
+    chains: &ChainRegistry,
+    chain: Option<&str>,
+    raw_tx: &[u8],
+    tx_hash: &str,
+) -> Result<()> {
+    let Some(chain) = chains.get(chain)? else {
+        return Ok(());
+    };
+    match chain.rpc.send_raw_transaction(raw_tx).await {
+        Ok(sent) => {
+            if !sent.eq_ignore_ascii_case(tx_hash) {
+                tracing::warn!(expected = %tx_hash, returned = %sent, "node returned another transaction hash");
+            }
+            tracing::info!(tx_hash = %tx_hash, chain = %chain.name, "mint transaction sent");
+            Ok(())
+        }
+        Err(e) => {
+            if chain.rpc.transaction_by_hash(tx_hash).await?.is_some() {
+                tracing::info!(tx_hash = %tx_hash, error = %e, "mint transaction already known to the node");
+                Ok(())
+            } else {
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Outcome of waiting for a mint transaction.
+#[derive(Debug)]
+pub enum Confirmation {
+    /// Mined with the chain's `confirmations`
+    Confirmed(MintResult),
+    /// Mined but reverted, so the mint failed for good
+    Reverted(String),
+    /// Not mined with enough confirmations within the chain's `receipt_timeout`;
+    /// it may still be
+    NotYet(String),
+}
+
+/// Wait for a mint transaction (or mock).
+///
+/// On-chain, returns once the transaction has the chain's `confirmations`, with the
+/// token ID from its `Transfer` event, once it reverted, or when the chain's
+/// `receipt_timeout` has passed. Errors are those of the node, after which the
+/// transaction may still be mined.
+pub async fn confirm_mint(
+    chains: &ChainRegistry,
+    chain: Option<&str>,
+    tx_hash: &str,
+) -> Result<Confirmation> {
+    match chains.get(chain)? {
+        Some(chain) => {
+            let Some(receipt) = wait_for_confirmations(chain, tx_hash).await? else {
+                return Ok(Confirmation::NotYet(format!(
+                    "transaction {} not confirmed after {}s",
+                    tx_hash,
+                    chain.receipt_timeout.as_secs()
+                )));
+            };
+            if !receipt.succeeded() {
+                return Ok(Confirmation::Reverted(format!(
+                    "transaction {} reverted in block {}",
+                    tx_hash,
+                    receipt.block_number()?
+                )));
+            }
+
+            let token_id = minted_token_id(&receipt, chain.contract);
+            if token_id.is_none() {
+                tracing::warn!(tx_hash = %tx_hash, "no Transfer event from the contract in receipt");
+            }
+            Ok(Confirmation::Confirmed(MintResult {
+                tx_hash: tx_hash.to_string(),
+                token_id,
+                block_number: Some(receipt.block_number()?),
+                chain_id: Some(chain.chain_id().await?),
+                explorer_url: chain.explorer_tx_url(tx_hash),
+            }))
+        }
+        None => {
+            // Mock path
+            let token_id = Some(format!("{}", Uuid::new_v4().simple()));
+            tracing::warn!(tx_hash = %tx_hash, "no chain configured - returning mock mint result");
+            Ok(Confirmation::Confirmed(MintResult {
+                tx_hash: tx_hash.to_string(),
+                token_id,
+                block_number: None,
+                chain_id: None,
+                explorer_url: None,
+            }))
+        }
+    }
+}
+
+/// Builds and signs the mint transaction.
+///
+/// Returns the mint with the transaction's hash, `keccak256` of the signed
+/// envelope, and the raw transaction.
+async fn sign_transaction(
+    chain: &Chain,
+    signer: &Signer,
+    ens: &Ens,
+    metadata_url: &str,
+    recipient: Option<&str>,
+) -> Result<(MintResult, Vec<u8>)> {
+    let from = signer.address();
+    let to = match recipient.map(parse_recipient).transpose()? {
+        Some(Recipient::Address(address)) => address,
+        Some(Recipient::Ens(name)) => {
+            let address = ens::resolve(&ens.rpc, ens.registry, &name)
+                .await
+                .with_context(|| format!("failed to resolve recipient {}", name))?;
+            tracing::info!(name = %name, address = %address, "resolved ENS recipient");
+            address
+        }
+        None => from,
+    };
+    let data = abi::encode_call(
+        MINT_SIGNATURE,
+        &[Token::Address(to), Token::String(metadata_url.to_string())],
+    );
+
+    let chain_id = chain.chain_id().await?;
+    let nonce = chain.rpc.pending_nonce(from).await?;
+    let base_fee = chain.rpc.base_fee().await?;
+    let priority_fee = chain.rpc.max_priority_fee().await?;
+    // Estimation also surfaces reverts (e.g. the wallet is not the contract owner)
+    let gas = chain.rpc.estimate_gas(from, chain.contract, &data).await?;
+
+    let tx = Eip1559Transaction {
+        chain_id,
+        nonce,
+        max_priority_fee_per_gas: priority_fee,
+        // Room for the base fee to double before the transaction is priced out
+        max_fee_per_gas: base_fee * 2 + priority_fee,
+        gas_limit: gas + gas / 5,
+        to: chain.contract,
+        value: 0,
+        data,
+    };
+    let raw = tx.sign(signer)?;
+    let tx_hash = format!("0x{}", hex::encode(keccak256(&raw)));
+
+    tracing::info!(
+        tx_hash = %tx_hash,
+        from = %from,
+        to = %to,
+        contract = %chain.contract,
+        chain = %chain.name,
+        chain_id,
+        nonce,
+        "mint transaction signed"
+    );
+    let mint = MintResult {
+        explorer_url: chain.explorer_tx_url(&tx_hash),
+        tx_hash,
+        token_id: None,
+        block_number: None,
+        chain_id: Some(chain_id),
+    };
+    Ok((mint, raw))
+}
+
+/// Polls for the receipt of `tx_hash` until it has enough confirmations, or
+/// reverted.
+///
+/// Returns `None` if the transaction is not confirmed within the timeout.
+async fn wait_for_confirmations(chain: &Chain, tx_hash: &str) -> Result<Option<Receipt>> {
+    let deadline = Instant::now() + chain.receipt_timeout;
+    loop {
+        if let Some(receipt) = chain.rpc.transaction_receipt(tx_hash).await? {
+            let mined_in = receipt.block_number()?;
+            if !receipt.succeeded() {
+                return Ok(Some(receipt));
+            }
+            let head = chain.rpc.block_number().await?;
+            let confirmations = head.saturating_sub(mined_in) + 1;
+            if confirmations >= chain.confirmations {
+                tracing::info!(tx_hash = %tx_hash, block = mined_in, confirmations, "mint confirmed");
+                return Ok(Some(receipt));
+            }
+        }
+
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+    }
+}
+
+/// Token ID of the `Transfer(0, to, tokenId)` event emitted by `contract`, in decimal.
+fn minted_token_id(receipt: &Receipt, contract: Address) -> Option<String> {
+    let transfer = abi::event_topic(TRANSFER_EVENT);
+    receipt
+        .logs
+        .iter()
+        .filter(|log| log.address.parse::<Address>().ok() == Some(contract))
+        .filter_map(|log| {
+            log.topics
+                .iter()
+                .map(|t| abi::parse_word(t))
+                .collect::<Option<Vec<_>>>()
+        })
+        // ERC-721 indexes all three arguments, unlike ERC-20's Transfer
+        .find(|topics| topics.len() == 4 && topics[0] == transfer && topics[1] == [0u8; 32])
+        .map(|topics| abi::uint256_to_decimal(&topics[3]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_recipient() {
+        assert_eq!(
+            parse_recipient("0x70997970C51812dc3A010C7d01b50e0d17dc79C8").unwrap(),
+            Recipient::Address(
+                "0x70997970c51812dc3a010c7d01b50e0d17dc79c8"
+                    .parse()
+                    .unwrap()
+            )
+        );
+        assert_eq!(
+            parse_recipient("Valet.eth").unwrap(),
+            Recipient::Ens("valet.eth".to_string())
+        );
+        assert!(parse_recipient("default-recipient-address").is_err());
+        // Checksum with one letter's case flipped
+        assert!(parse_recipient("0x70997970c51812dc3A010C7d01b50e0d17dc79C8").is_err());
+    }
+
+    #[test]
+    fn test_minted_token_id_from_receipt() {
+        let contract: Address = "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+            .parse()
+            .unwrap();
+        let receipt: Receipt = serde_json::from_value(serde_json::json!({
+            "status": "0x1",
+            "blockNumber": "0x2",
+            "logs": [
+                {
+                    // Same event from another contract
+                    "address": "0x0000000000000000000000000000000000000001",
+                    "topics": [
+                        "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                        "0x0000000000000000000000000000000000000000000000000000000000000000",
+                        "0x00000000000000000000000070997970c51812dc3a010c7d01b50e0d17dc79c8",
+                        "0x0000000000000000000000000000000000000000000000000000000000000063"
+                    ]
+                },
+                {
+                    "address": "0x5fbdb2315678afecb367f032d93f642f64180aa3",
+                    "topics": [
+                        "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                        "0x0000000000000000000000000000000000000000000000000000000000000000",
+                        "0x00000000000000000000000070997970c51812dc3a010c7d01b50e0d17dc79c8",
+                        "0x0000000000000000000000000000000000000000000000000000000000000007"
+                    ]
+                }
+            ]
+        }))
+        .unwrap();
 
-        let status = resp.status();
-        if !status.is_success() {
-            let text = resp.text().await.unwrap_or_default();
-            return Err(anyhow!("rpc call failed: {} - {}", status, text));
+        assert!(receipt.succeeded());
+        assert_eq!(receipt.block_number().unwrap(), 2);
+        assert_eq!(minted_token_id(&receipt, contract).as_deref(), Some("7"));
+    }
+
+    /// Mints against a local node. Start `anvil`, deploy an ERC-721 with an owner-only
+    /// `safeMint(address,string)` from the first anvil account, then run
+    /// `BLOCKCHAIN_RPC=http://127.0.0.1:8545 CONTRACT_ADDRESS=0x... cargo test -- --ignored`.
+    #[tokio::test]
+    #[ignore = "requires a local anvil node with a deployed ERC-721 contract"]
+    async fn test_mint_on_anvil() {
+        use std::env;
+
+        env::var("BLOCKCHAIN_RPC").expect("BLOCKCHAIN_RPC not set");
+        if env::var("WALLET_PRIVATE_KEY").is_err() {
+            env::set_var(
+                "WALLET_PRIVATE_KEY",
+                "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80",
+            );
         }
+        let chains = ChainRegistry::from_env(&reqwest::Client::new()).unwrap();
+        let chain = chains.get(None).unwrap().unwrap();
 
-        // Attempt to parse a tx hash and token id
-        let json: serde_json::Value = resp
-            .json()
+        let signed = sign_mint(
+            &chains,
+            None,
+            "ipfs://bafytest",
+            Some("0x70997970C51812dc3A010C7d01b50e0d17dc79C8"),
+        )
+        .await
+        .unwrap();
+        let submitted = signed.mint;
+        let raw_tx = signed.raw_tx.unwrap();
+        broadcast_mint(&chains, None, &raw_tx, &submitted.tx_hash)
             .await
-            .map_err(|e| anyhow!("failed to parse response: {}", e))?;
-        let tx_hash = json
-            .get("tx_hash")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string())
-            .unwrap_or_else(|| format!("0x{}", Uuid::new_v4().simple()));
-        let token_id = json
-            .get("token_id")
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-
-        Ok(MintResult { tx_hash, token_id })
-    } else {
-        // Mock path
-        let tx_hash = format!("0x{}", Uuid::new_v4().simple());
-        let token_id = Some(format!("{}", Uuid::new_v4().simple()));
-        tracing::warn!(tx_hash = %tx_hash, "BLOCKCHAIN_RPC not set - returning mock mint result");
-        Ok(MintResult { tx_hash, token_id })
+            .unwrap();
+        // Sending it again is not an error, and does not mint twice
+        broadcast_mint(&chains, None, &raw_tx, &submitted.tx_hash)
+            .await
+            .unwrap();
+        let receipt = wait_for_confirmations(chain, &submitted.tx_hash)
+            .await
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(submitted.tx_hash.len(), 66);
+        assert_eq!(submitted.chain_id, Some(31337));
+        assert!(minted_token_id(&receipt, chain.contract).is_some());
     }
 }
@@ -0,0 +1,488 @@
+//! Registry of the EVM chains mints can be sent to.
+//!
+//! Chains are read from the JSON file at `CHAINS_CONFIG`:
+//!
+//! ```json
+//! {
+//!   "default": "sepolia",
+//!   "chains": [
+//!     {
+//!       "name": "sepolia",
+//!       "chain_id": 11155111,
+//!       "rpc_urls": ["https://rpc.sepolia.org", "https://ethereum-sepolia-rpc.publicnode.com"],
+//!       "contract_address": "0x...",
+//!       "explorer_url": "https://sepolia.etherscan.io",
+//!       "confirmations": 2
+//!     }
+//!   ],
+//!   "ens": {
+//!     "rpc_urls": ["https://ethereum-rpc.publicnode.com"]
+//!   }
+//! }
+//! ```
+//!
+//! Without it, `BLOCKCHAIN_RPC` and the related variables configure a single chain
+//! named `default`, and with neither, mints are mocked. All chains sign with
+//! `WALLET_PRIVATE_KEY`.
+//!
+//! `.eth` recipients are resolved on one ENS chain, Ethereum mainnet unless `ens`
+//! (or `ENS_RPC_URL` and `ENS_REGISTRY`) points elsewhere, whichever chain the token
+//! is minted on.
+
+use crate::evm::ens;
+use crate::evm::rpc::EthRpc;
+use crate::evm::signer::Signer;
+use crate::evm::Address;
+use anyhow::{anyhow, bail, Context, Result};
+use reqwest::Client;
+use serde::Deserialize;
+use std::env;
+use std::time::Duration;
+
+/// Name of the chain configured from `BLOCKCHAIN_RPC`.
+const ENV_CHAIN_NAME: &str = "default";
+
+/// Ethereum mainnet endpoint ENS names are resolved on when none is configured.
+const DEFAULT_ENS_RPC: &str = "https://ethereum-rpc.publicnode.com";
+
+/// A chain as written in the `CHAINS_CONFIG` file.
+#[derive(Debug, Deserialize)]
+struct ChainConfig {
+    /// Name clients pass as `chain`, e.g. `sepolia` or `base`
+    name: String,
+    /// Chain ID for signing; queried with `eth_chainId` when not set
+    #[serde(default)]
+    chain_id: Option<u64>,
+    /// JSON-RPC endpoints, tried in order
+    rpc_urls: Vec<String>,
+    /// ERC-721 contract exposing `safeMint(address,string)`
+    contract_address: String,
+    /// Block explorer, e.g. `https://sepolia.etherscan.io`
+    #[serde(default)]
+    explorer_url: Option<String>,
+    /// Blocks (including its own) a mint must be buried under
+    #[serde(default = "default_confirmations")]
+    confirmations: u64,
+    /// How long to wait for those confirmations
+    #[serde(default = "default_receipt_timeout_secs")]
+    receipt_timeout_secs: u64,
+}
+
+fn default_confirmations() -> u64 {
+    1
+}
+
+fn default_receipt_timeout_secs() -> u64 {
+    120
+}
+
+/// The `CHAINS_CONFIG` file.
+#[derive(Debug, Deserialize)]
+struct ChainsFile {
+    /// Chain used when a request names none; the first chain when not set
+    #[serde(default)]
+    default: Option<String>,
+    chains: Vec<ChainConfig>,
+    /// Where `.eth` recipients are resolved
+    #[serde(default)]
+    ens: EnsConfig,
+}
+
+/// The `ens` section of the `CHAINS_CONFIG` file.
+#[derive(Debug, Default, Deserialize)]
+struct EnsConfig {
+    /// JSON-RPC endpoints of the ENS chain, tried in order; mainnet when empty
+    #[serde(default)]
+    rpc_urls: Vec<String>,
+    /// ENS registry on that chain
+    #[serde(default)]
+    registry: Option<String>,
+}
+
+/// A chain mints can be sent to.
+pub struct Chain {
+    pub name: String,
+    pub rpc: EthRpc,
+    /// `None` to ask the node
+    chain_id: Option<u64>,
+    pub contract: Address,
+    explorer_url: Option<String>,
+    pub confirmations: u64,
+    pub receipt_timeout: Duration,
+}
+
+impl Chain {
+    fn from_config(client: &Client, config: ChainConfig) -> Result<Self> {
+        if config.rpc_urls.is_empty() {
+            bail!("no rpc_urls");
+        }
+        Ok(Self {
+            rpc: EthRpc::with_fallbacks(client.clone(), config.rpc_urls),
+            chain_id: config.chain_id,
+            contract: config
+                .contract_address
+                .parse()
+                .map_err(|e| anyhow!("invalid contract_address: {}", e))?,
+            explorer_url: config
+                .explorer_url
+                .map(|url| url.trim_end_matches('/').to_string()),
+            confirmations: config.confirmations,
+            receipt_timeout: Duration::from_secs(config.receipt_timeout_secs),
+            name: config.name,
+        })
+    }
+
+    /// The chain ID, as configured or reported by the node.
+    pub async fn chain_id(&self) -> Result<u64> {
+        match self.chain_id {
+            Some(id) => Ok(id),
+            None => self.rpc.chain_id().await,
+        }
+    }
+
+    /// Link to a transaction on the chain's block explorer.
+    pub fn explorer_tx_url(&self, tx_hash: &str) -> Option<String> {
+        self.explorer_url
+            .as_ref()
+            .map(|url| format!("{}/tx/{}", url, tx_hash))
+    }
+}
+
+/// The chain `.eth` recipients are resolved on.
+pub struct Ens {
+    pub rpc: EthRpc,
+    pub registry: Address,
+}
+
+impl Ens {
+    fn from_config(client: &Client, config: EnsConfig) -> Result<Self> {
+        let rpc_urls = if config.rpc_urls.is_empty() {
+            vec![DEFAULT_ENS_RPC.to_string()]
+        } else {
+            config.rpc_urls
+        };
+        let registry = config.registry.unwrap_or_else(|| ens::REGISTRY.to_string());
+        Ok(Self {
+            rpc: EthRpc::with_fallbacks(client.clone(), rpc_urls),
+            registry: registry
+                .parse()
+                .map_err(|e| anyhow!("invalid ENS registry: {}", e))?,
+        })
+    }
+}
+
+/// The configured chains and the wallet that mints on them. Empty when mints are mocked.
+#[derive(Default)]
+pub struct ChainRegistry {
+    chains: Vec<Chain>,
+    /// Index of the default chain
+    default: usize,
+    signer: Option<Signer>,
+    ens: Option<Ens>,
+}
+
+impl ChainRegistry {
+    /// Loads the chains from `CHAINS_CONFIG`, or the single chain at `BLOCKCHAIN_RPC`.
+    pub fn from_env(client: &Client) -> Result<Self> {
+        let mut file: ChainsFile = match env::var("CHAINS_CONFIG") {
+            Ok(path) => {
+                let json = std::fs::read_to_string(&path)
+                    .with_context(|| format!("failed to read CHAINS_CONFIG {}", path))?;
+                serde_json::from_str(&json)
+                    .with_context(|| format!("invalid CHAINS_CONFIG {}", path))?
+            }
+            Err(_) => match env::var("BLOCKCHAIN_RPC") {
+                Ok(rpc) => env_chains_file(rpc)?,
+                Err(_) => return Ok(Self::default()),
+            },
+        };
+
+        let key = env::var("WALLET_PRIVATE_KEY")
+            .context("WALLET_PRIVATE_KEY must be set when chains are configured")?;
+        let signer = Signer::from_hex(&key).context("invalid WALLET_PRIVATE_KEY")?;
+
+        if file.ens.rpc_urls.is_empty() {
+            if let Ok(urls) = env::var("ENS_RPC_URL") {
+                file.ens.rpc_urls = urls.split(',').map(|url| url.trim().to_string()).collect();
+            }
+        }
+        if file.ens.registry.is_none() {
+            file.ens.registry = env::var("ENS_REGISTRY").ok();
+        }
+        Self::new(client, file, signer)
+    }
+
+    fn new(client: &Client, file: ChainsFile, signer: Signer) -> Result<Self> {
+        if file.chains.is_empty() {
+            bail!("no chains configured");
+        }
+        let mut chains: Vec<Chain> = Vec::new();
+        for config in file.chains {
+            if chains.iter().any(|c| c.name == config.name) {
+                bail!("chain {} is configured twice", config.name);
+            }
+            let name = config.name.clone();
+            chains.push(
+                Chain::from_config(client, config)
+                    .with_context(|| format!("invalid chain {}", name))?,
+            );
+        }
+        let default = match file.default {
+            Some(name) => chains
+                .iter()
+                .position(|c| c.name == name)
+                .ok_or_else(|| anyhow!("default chain {} is not configured", name))?,
+            None => 0,
+        };
+
+        let ens = Ens::from_config(client, file.ens).context("invalid ens")?;
+
+        Ok(Self {
+            chains,
+            default,
+            signer: Some(signer),
+            ens: Some(ens),
+        })
+    }
+
+    /// A registry with one chain, `test`, at `rpc_url`, signing with the first
+    /// anvil development key. Receipts are polled once, without waiting.
+    #[cfg(test)]
+    pub fn for_tests(rpc_url: &str) -> Self {
+        let file: ChainsFile = serde_json::from_value(serde_json::json!({
+            "chains": [{
+                "name": "test",
+                "chain_id": 31337,
+                "rpc_urls": [rpc_url],
+                "contract_address": "0x5FbDB2315678afecb367f032d93F642f64180aa3",
+                "receipt_timeout_secs": 0
+            }]
+        }))
+        .unwrap();
+        let signer =
+            Signer::from_hex("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80")
+                .unwrap();
+        Self::new(&Client::new(), file, signer).unwrap()
+    }
+
+    /// Resolves ENS names on the node at `rpc_url` instead of mainnet.
+    #[cfg(test)]
+    pub fn with_ens_rpc(mut self, rpc_url: &str) -> Self {
+        let config = EnsConfig {
+            rpc_urls: vec![rpc_url.to_string()],
+            registry: None,
+        };
+        self.ens = Some(Ens::from_config(&Client::new(), config).unwrap());
+        self
+    }
+
+    /// Whether mints are mocked because no chain is configured.
+    pub fn is_mock(&self) -> bool {
+        self.chains.is_empty()
+    }
+
+    /// Names of the configured chains, the default first.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.chains.iter().map(|c| c.name.as_str()).collect();
+        if !names.is_empty() {
+            names.swap(0, self.default);
+        }
+        names
+    }
+
+    /// Looks up the chain a request names, or the default chain.
+    ///
+    /// Returns `Ok(None)` when mints are mocked and no chain is named, and an error
+    /// for chains that are not configured.
+    pub fn get(&self, name: Option<&str>) -> Result<Option<&Chain>> {
+        match name {
+            None => Ok(self.chains.get(self.default)),
+            Some(name) => self
+                .chains
+                .iter()
+                .find(|c| c.name == name)
+                .map(Some)
+                .ok_or_else(|| {
+                    if self.is_mock() {
+                        anyhow!("unknown chain {:?}; no chains are configured", name)
+                    } else {
+                        anyhow!(
+                            "unknown chain {:?}; configured chains: [{}]",
+                            name,
+                            self.names().join(", ")
+                        )
+                    }
+                }),
+        }
+    }
+
+    /// The chain ENS names are resolved on; set whenever a chain is configured.
+    pub fn ens(&self) -> Result<&Ens> {
+        self.ens
+            .as_ref()
+            .ok_or_else(|| anyhow!("no ENS chain configured"))
+    }
+
+    /// The wallet that signs mints; set whenever a chain is configured.
+    pub fn signer(&self) -> Result<&Signer> {
+        self.signer
+            .as_ref()
+            .ok_or_else(|| anyhow!("no wallet configured"))
+    }
+}
+
+/// The single chain configured by `BLOCKCHAIN_RPC`, `CONTRACT_ADDRESS`, `CHAIN_ID`,
+/// `EXPLORER_URL`, `MINT_CONFIRMATIONS` and `MINT_RECEIPT_TIMEOUT_SECS`.
+fn env_chains_file(rpc: String) -> Result<ChainsFile> {
+    let contract_address = env::var("CONTRACT_ADDRESS")
+        .context("CONTRACT_ADDRESS must be set when BLOCKCHAIN_RPC is set")?;
+    let chain_id = match env::var("CHAIN_ID") {
+        Ok(id) => Some(id.parse().context("CHAIN_ID must be a number")?),
+        Err(_) => None,
+    };
+    let confirmations = match env::var("MINT_CONFIRMATIONS") {
+        Ok(n) => n.parse().context("MINT_CONFIRMATIONS must be a number")?,
+        Err(_) => default_confirmations(),
+    };
+    let receipt_timeout_secs = match env::var("MINT_RECEIPT_TIMEOUT_SECS") {
+        Ok(secs) => secs
+            .parse()
+            .context("MINT_RECEIPT_TIMEOUT_SECS must be a number")?,
+        Err(_) => default_receipt_timeout_secs(),
+    };
+
+    Ok(ChainsFile {
+        default: None,
+        chains: vec![ChainConfig {
+            name: ENV_CHAIN_NAME.to_string(),
+            chain_id,
+            rpc_urls: vec![rpc],
+            contract_address,
+            explorer_url: env::var("EXPLORER_URL").ok(),
+            confirmations,
+            receipt_timeout_secs,
+        }],
+        ens: EnsConfig::default(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// First anvil development key.
+    const TEST_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    fn registry(json: serde_json::Value) -> Result<ChainRegistry> {
+        let file: ChainsFile = serde_json::from_value(json)?;
+        ChainRegistry::new(&Client::new(), file, Signer::from_hex(TEST_KEY).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_registry_lookup() {
+        let chains = registry(serde_json::json!({
+            "default": "base-sepolia",
+            "chains": [
+                {
+                    "name": "anvil",
+                    "rpc_urls": ["http://127.0.0.1:8545"],
+                    "contract_address": "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+                },
+                {
+                    "name": "base-sepolia",
+                    "chain_id": 84532,
+                    "rpc_urls": ["https://sepolia.base.org"],
+                    "contract_address": "0x5FbDB2315678afecb367f032d93F642f64180aa3",
+                    "explorer_url": "https://sepolia.basescan.org/",
+                    "confirmations": 3
+                }
+            ]
+        }))
+        .unwrap();
+
+        assert!(!chains.is_mock());
+        assert_eq!(chains.names(), ["base-sepolia", "anvil"]);
+
+        let default = chains.get(None).unwrap().unwrap();
+        assert_eq!(default.name, "base-sepolia");
+        assert_eq!(default.chain_id().await.unwrap(), 84532);
+        assert_eq!(default.confirmations, 3);
+        assert_eq!(
+            default.explorer_tx_url("0xabc").as_deref(),
+            Some("https://sepolia.basescan.org/tx/0xabc")
+        );
+
+        let anvil = chains.get(Some("anvil")).unwrap().unwrap();
+        assert_eq!(anvil.confirmations, 1);
+        assert_eq!(anvil.explorer_tx_url("0xabc"), None);
+
+        let err = chains.get(Some("mainnet")).err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "unknown chain \"mainnet\"; configured chains: [base-sepolia, anvil]"
+        );
+    }
+
+    #[test]
+    fn test_ens_chain() {
+        let chain = serde_json::json!({
+            "name": "base-sepolia",
+            "rpc_urls": ["https://sepolia.base.org"],
+            "contract_address": "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+        });
+
+        // Mainnet unless configured, whichever chain mints
+        let chains = registry(serde_json::json!({ "chains": [chain] })).unwrap();
+        let ens = chains.ens().unwrap();
+        assert_eq!(ens.rpc.urls(), [DEFAULT_ENS_RPC]);
+        assert_eq!(ens.registry, ens::REGISTRY.parse().unwrap());
+
+        let chains = registry(serde_json::json!({
+            "chains": [chain],
+            "ens": {
+                "rpc_urls": ["https://ethereum-sepolia-rpc.publicnode.com"],
+                "registry": "0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e"
+            }
+        }))
+        .unwrap();
+        assert_eq!(
+            chains.ens().unwrap().rpc.urls(),
+            ["https://ethereum-sepolia-rpc.publicnode.com"]
+        );
+
+        let invalid = serde_json::json!({ "chains": [chain], "ens": { "registry": "0x12" } });
+        assert!(registry(invalid).is_err());
+        assert!(ChainRegistry::default().ens().is_err());
+    }
+
+    #[test]
+    fn test_example_config() {
+        let chains =
+            registry(serde_json::from_str(include_str!("../chains.example.json")).unwrap())
+                .unwrap();
+        assert_eq!(chains.names(), ["anvil", "sepolia", "base-sepolia"]);
+    }
+
+    #[test]
+    fn test_invalid_registries() {
+        let chain = serde_json::json!({
+            "name": "anvil",
+            "rpc_urls": ["http://127.0.0.1:8545"],
+            "contract_address": "0x5FbDB2315678afecb367f032d93F642f64180aa3"
+        });
+
+        assert!(registry(serde_json::json!({ "chains": [] })).is_err());
+        assert!(registry(serde_json::json!({ "chains": [chain, chain] })).is_err());
+        assert!(registry(serde_json::json!({ "default": "base", "chains": [chain] })).is_err());
+
+        let mut no_rpc = chain.clone();
+        no_rpc["rpc_urls"] = serde_json::json!([]);
+        assert!(registry(serde_json::json!({ "chains": [no_rpc] })).is_err());
+
+        // Mocked mints know no chains
+        let mock = ChainRegistry::default();
+        assert!(mock.is_mock());
+        assert!(mock.get(None).unwrap().is_none());
+        assert!(mock.get(Some("anvil")).is_err());
+    }
+}
@@ -0,0 +1,178 @@
+//! Solidity ABI encoding of contract calls.
+
+use super::{keccak256, Address};
+
+/// A value passed to a contract function.
+#[derive(Debug, Clone)]
+pub enum Token {
+    Address(Address),
+    /// A `bytes32` value, e.g. an ENS node
+    FixedBytes([u8; 32]),
+    String(String),
+}
+
+/// Returns the 4-byte selector of a function signature such as
+/// `safeMint(address,string)`.
+pub fn selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// Returns the topic identifying an event, e.g. `Transfer(address,address,uint256)`.
+pub fn event_topic(signature: &str) -> [u8; 32] {
+    keccak256(signature.as_bytes())
+}
+
+/// Parses a `0x`-prefixed 32-byte word, such as a log topic.
+pub fn parse_word(value: &str) -> Option<[u8; 32]> {
+    let mut out = [0u8; 32];
+    hex::decode_to_slice(value.strip_prefix("0x")?, &mut out).ok()?;
+    Some(out)
+}
+
+/// Decodes an `address` returned by a contract call: the last 20 bytes of the first
+/// word, whose other bytes must be zero.
+pub fn decode_address(data: &[u8]) -> Option<Address> {
+    let word = data.get(..32)?;
+    if word[..12].iter().any(|b| *b != 0) {
+        return None;
+    }
+    let mut bytes = [0u8; 20];
+    bytes.copy_from_slice(&word[12..]);
+    Some(Address(bytes))
+}
+
+/// Formats a 32-byte big-endian `uint256` as a decimal string.
+pub fn uint256_to_decimal(word: &[u8; 32]) -> String {
+    let mut value = *word;
+    let mut digits = Vec::new();
+    loop {
+        // Divide the whole number by 10, keeping the remainder as the next digit
+        let mut remainder = 0u32;
+        for byte in value.iter_mut() {
+            let current = (remainder << 8) | u32::from(*byte);
+            *byte = (current / 10) as u8;
+            remainder = current % 10;
+        }
+        digits.push(b'0' + remainder as u8);
+        if value.iter().all(|b| *b == 0) {
+            break;
+        }
+    }
+    digits.reverse();
+    String::from_utf8(digits).unwrap()
+}
+
+/// Encodes a call to `signature` with the given arguments as transaction data.
+pub fn encode_call(signature: &str, args: &[Token]) -> Vec<u8> {
+    let mut out = selector(signature).to_vec();
+    out.extend(encode_args(args));
+    out
+}
+
+/// Encodes arguments as a tuple: static values and offsets in the head, dynamic
+/// values in the tail.
+fn encode_args(args: &[Token]) -> Vec<u8> {
+    let mut head = Vec::with_capacity(args.len() * 32);
+    let mut tail = Vec::new();
+
+    for arg in args {
+        match arg {
+            Token::Address(address) => {
+                head.extend_from_slice(&[0u8; 12]);
+                head.extend_from_slice(&address.0);
+            }
+            Token::FixedBytes(bytes) => head.extend_from_slice(bytes),
+            Token::String(value) => {
+                head.extend(word((args.len() * 32 + tail.len()) as u128));
+                tail.extend(word(value.len() as u128));
+                tail.extend_from_slice(value.as_bytes());
+                let padding = (32 - value.len() % 32) % 32;
+                tail.extend(std::iter::repeat_n(0u8, padding));
+            }
+        }
+    }
+
+    head.extend(tail);
+    head
+}
+
+/// A 32-byte big-endian word.
+fn word(value: u128) -> [u8; 32] {
+    let mut out = [0u8; 32];
+    out[16..].copy_from_slice(&value.to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_selectors() {
+        assert_eq!(
+            selector("transfer(address,uint256)"),
+            [0xa9, 0x05, 0x9c, 0xbb]
+        );
+        assert_eq!(
+            selector("safeMint(address,string)"),
+            [0xd2, 0x04, 0xc4, 0x5e]
+        );
+        assert_eq!(selector("resolver(bytes32)"), [0x01, 0x78, 0xb8, 0xbf]);
+        assert_eq!(selector("addr(bytes32)"), [0x3b, 0x3b, 0x57, 0xde]);
+    }
+
+    #[test]
+    fn test_decode_address() {
+        let mut word = [0u8; 32];
+        word[31] = 1;
+        assert_eq!(
+            decode_address(&word).unwrap().to_string(),
+            "0x0000000000000000000000000000000000000001"
+        );
+        assert!(decode_address(&word[..31]).is_none());
+        word[0] = 1;
+        assert!(decode_address(&word).is_none());
+    }
+
+    #[test]
+    fn test_transfer_topic() {
+        assert_eq!(
+            hex::encode(event_topic("Transfer(address,address,uint256)")),
+            "ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"
+        );
+    }
+
+    #[test]
+    fn test_uint256_to_decimal() {
+        let mut word = [0u8; 32];
+        assert_eq!(uint256_to_decimal(&word), "0");
+        word[31] = 42;
+        assert_eq!(uint256_to_decimal(&word), "42");
+        assert_eq!(
+            uint256_to_decimal(&[0xff; 32]),
+            "115792089237316195423570985008687907853269984665640564039457584007913129639935"
+        );
+        assert!(parse_word("0x2a").is_none());
+    }
+
+    #[test]
+    fn test_encode_safe_mint() {
+        let to: Address = "0x70997970C51812dc3A010C7d01b50e0d17dc79C8"
+            .parse()
+            .unwrap();
+        let data = encode_call(
+            "safeMint(address,string)",
+            &[Token::Address(to), Token::String("ipfs://bafy".to_string())],
+        );
+
+        let expected = concat!(
+            "d204c45e",
+            "00000000000000000000000070997970c51812dc3a010c7d01b50e0d17dc79c8",
+            "0000000000000000000000000000000000000000000000000000000000000040",
+            "000000000000000000000000000000000000000000000000000000000000000b",
+            "697066733a2f2f62616679000000000000000000000000000000000000000000",
+        );
+        assert_eq!(hex::encode(data), expected);
+    }
+}
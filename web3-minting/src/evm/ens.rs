@@ -0,0 +1,165 @@
+//! Resolution of ENS names such as `vitalik.eth` to addresses, by reading the ENS
+//! registry and the name's resolver with `eth_call`.
+
+use super::abi::{self, Token};
+use super::rpc::EthRpc;
+use super::{keccak256, Address};
+use anyhow::{anyhow, bail, Result};
+
+/// The ENS registry, at the same address on mainnet, Sepolia and Holesky.
+pub const REGISTRY: &str = "0x00000000000C2E074eC69A0dFb2997BA6C7d2e1e";
+
+/// Whether a recipient is meant as an ENS name rather than an address.
+pub fn is_ens_name(value: &str) -> bool {
+    value.to_ascii_lowercase().ends_with(".eth")
+}
+
+/// Normalizes a `.eth` name to lowercase.
+///
+/// Full ENS normalization (UTS-46) is not implemented, so only ASCII letters, digits,
+/// `-` and `_` are accepted in labels.
+pub fn normalize(name: &str) -> Result<String> {
+    let name = name.to_ascii_lowercase();
+    if !name.ends_with(".eth") {
+        bail!("ENS name must end in .eth, got {:?}", name);
+    }
+    for label in name.split('.') {
+        if label.is_empty() {
+            bail!("ENS name {:?} has an empty label", name);
+        }
+        if !label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            bail!(
+                "ENS name {:?} has unsupported characters; only ASCII names are resolved",
+                name
+            );
+        }
+    }
+    Ok(name)
+}
+
+/// The ENS `namehash` of a normalized name: the node identifying it in the registry.
+pub fn namehash(name: &str) -> [u8; 32] {
+    let mut node = [0u8; 32];
+    if name.is_empty() {
+        return node;
+    }
+    for label in name.rsplit('.') {
+        let mut buf = [0u8; 64];
+        buf[..32].copy_from_slice(&node);
+        buf[32..].copy_from_slice(&keccak256(label.as_bytes()));
+        node = keccak256(&buf);
+    }
+    node
+}
+
+/// Resolves a `.eth` name to the address its resolver returns for `addr(node)`.
+///
+/// Fails if the name is not normalizable, has no resolver, or resolves to the zero
+/// address.
+pub async fn resolve(rpc: &EthRpc, registry: Address, name: &str) -> Result<Address> {
+    let name = normalize(name)?;
+    let node = namehash(&name);
+
+    let output = rpc
+        .eth_call(
+            registry,
+            &abi::encode_call("resolver(bytes32)", &[Token::FixedBytes(node)]),
+        )
+        .await?;
+    if output.is_empty() {
+        bail!(
+            "ENS registry {} returned no data; is ENS deployed on this chain?",
+            registry
+        );
+    }
+    let resolver = abi::decode_address(&output)
+        .ok_or_else(|| anyhow!("invalid resolver() result for {}", name))?;
+    if resolver == Address([0u8; 20]) {
+        bail!("ENS name {} has no resolver", name);
+    }
+
+    let output = rpc
+        .eth_call(
+            resolver,
+            &abi::encode_call("addr(bytes32)", &[Token::FixedBytes(node)]),
+        )
+        .await?;
+    let address = abi::decode_address(&output)
+        .ok_or_else(|| anyhow!("invalid addr() result for {}", name))?;
+    if address == Address([0u8; 20]) {
+        bail!("ENS name {} does not resolve to an address", name);
+    }
+    Ok(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use reqwest::Client;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn test_namehash() {
+        assert_eq!(namehash(""), [0u8; 32]);
+        assert_eq!(
+            hex::encode(namehash("eth")),
+            "93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"
+        );
+        assert_eq!(
+            hex::encode(namehash("foo.eth")),
+            "de9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f"
+        );
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Valet.ETH").unwrap(), "valet.eth");
+        assert!(is_ens_name("Valet.ETH"));
+        assert!(!is_ens_name("0x70997970C51812dc3A010C7d01b50e0d17dc79C8"));
+        assert!(normalize("valet..eth").is_err());
+        assert!(normalize("välet.eth").is_err());
+        assert!(normalize("valet.xyz").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_resolve_against_fake_node() {
+        let registry: Address = REGISTRY.parse().unwrap();
+        let resolver = "0x4976fb03c32e5b8cfe2b6ccb31c09ba78ebaba41";
+        let owner = "0x70997970c51812dc3a010c7d01b50e0d17dc79c8";
+
+        // Answers eth_call like a registry and resolver that only know valet.eth
+        let node = hex::encode(namehash("valet.eth"));
+        let app = Router::new().route(
+            "/",
+            post(move |Json(body): Json<Value>| {
+                let node = node.clone();
+                async move {
+                    let call = &body["params"][0];
+                    let to = call["to"].as_str().unwrap().to_string();
+                    let data = call["data"].as_str().unwrap().to_string();
+                    let known = data.ends_with(&node);
+                    let result = match (to.as_str(), known) {
+                        (_, false) => format!("0x{}", "0".repeat(64)),
+                        (t, true) if t == resolver => format!("0x{:0>64}", &owner[2..]),
+                        _ => format!("0x{:0>64}", &resolver[2..]),
+                    };
+                    Json(json!({ "jsonrpc": "2.0", "id": 1, "result": result }))
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let rpc = EthRpc::new(Client::new(), format!("http://{}", addr));
+        let address = resolve(&rpc, registry, "Valet.eth").await.unwrap();
+        assert_eq!(address.to_string(), owner);
+
+        let err = resolve(&rpc, registry, "unknown.eth").await.unwrap_err();
+        assert_eq!(err.to_string(), "ENS name unknown.eth has no resolver");
+    }
+}
@@ -0,0 +1,130 @@
+//! Minimal Ethereum support for minting: ABI encoding, RLP, EIP-1559 transactions,
+//! secp256k1 signing and the JSON-RPC calls needed to send a transaction.
+
+pub mod abi;
+pub mod ens;
+pub mod rlp;
+pub mod rpc;
+pub mod signer;
+pub mod tx;
+
+use anyhow::{anyhow, Result};
+use sha3::{Digest, Keccak256};
+use std::fmt;
+use std::str::FromStr;
+
+/// A 20-byte Ethereum account or contract address.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Address(pub [u8; 20]);
+
+impl FromStr for Address {
+    type Err = anyhow::Error;
+
+    /// Parses a `0x`-prefixed (or bare) 40-digit hex address.
+    ///
+    /// Mixed-case addresses must carry a valid EIP-55 checksum; all-lowercase and
+    /// all-uppercase addresses have none to check.
+    fn from_str(s: &str) -> Result<Self> {
+        let digits = s.strip_prefix("0x").unwrap_or(s);
+        if digits.len() != 40 {
+            return Err(anyhow!("expected 40 hex digits, got {}", digits.len()));
+        }
+        let mut bytes = [0u8; 20];
+        hex::decode_to_slice(digits, &mut bytes).map_err(|e| anyhow!("invalid hex: {}", e))?;
+        let address = Address(bytes);
+
+        let has_lower = digits.chars().any(|c| c.is_ascii_lowercase());
+        let has_upper = digits.chars().any(|c| c.is_ascii_uppercase());
+        if has_lower && has_upper && address.checksummed()[2..] != *digits {
+            return Err(anyhow!(
+                "invalid EIP-55 checksum (expected {})",
+                address.checksummed()
+            ));
+        }
+        Ok(address)
+    }
+}
+
+impl Address {
+    /// The EIP-55 mixed-case form: a letter is uppercased when the matching nibble of
+    /// the keccak-256 hash of the lowercase hex is 8 or more.
+    pub fn checksummed(&self) -> String {
+        let lower = hex::encode(self.0);
+        let hash = keccak256(lower.as_bytes());
+        let digits: String = lower
+            .chars()
+            .enumerate()
+            .map(|(i, c)| {
+                let nibble = (hash[i / 2] >> (4 * (1 - i % 2))) & 0x0f;
+                if nibble >= 8 {
+                    c.to_ascii_uppercase()
+                } else {
+                    c
+                }
+            })
+            .collect();
+        format!("0x{}", digits)
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x{}", hex::encode(self.0))
+    }
+}
+
+/// Keccak-256 hash, as used throughout Ethereum.
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    Keccak256::digest(data).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_round_trip() {
+        let a: Address = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+            .parse()
+            .unwrap();
+        assert_eq!(a.to_string(), "0xf39fd6e51aad88f6f4ce6ab8827279cfffb92266");
+        assert!("0x1234".parse::<Address>().is_err());
+        assert!("default-recipient-address".parse::<Address>().is_err());
+    }
+
+    #[test]
+    fn test_eip55_checksum() {
+        // Test vectors from EIP-55
+        for checksummed in [
+            "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+            "0xdbF03B407c01E7cD3CBea99509d93f8DDDC8C6FB",
+            "0xD1220A0cf47c7B9Be7A2E6BA89F429762e7b9aDb",
+        ] {
+            let a: Address = checksummed.parse().unwrap();
+            assert_eq!(a.checksummed(), checksummed);
+
+            // No checksum to verify in single-case addresses
+            assert!(checksummed.to_lowercase().parse::<Address>().is_ok());
+            assert!(format!("0x{}", checksummed[2..].to_uppercase())
+                .parse::<Address>()
+                .is_ok());
+        }
+
+        let err = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAeD"
+            .parse::<Address>()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid EIP-55 checksum (expected 0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed)"
+        );
+    }
+
+    #[test]
+    fn test_keccak256() {
+        assert_eq!(
+            hex::encode(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+//! Recursive Length Prefix encoding, used to serialize transactions.
+
+/// Encodes a byte string.
+pub fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    if bytes.len() == 1 && bytes[0] < 0x80 {
+        return bytes.to_vec();
+    }
+    let mut out = length_prefix(0x80, bytes.len());
+    out.extend_from_slice(bytes);
+    out
+}
+
+/// Encodes an unsigned integer as its minimal big-endian byte string.
+pub fn encode_uint(value: u128) -> Vec<u8> {
+    encode_uint_bytes(&value.to_be_bytes())
+}
+
+/// Encodes a big-endian unsigned integer of any width (e.g. a 32-byte signature
+/// component), dropping its leading zero bytes.
+pub fn encode_uint_bytes(be_bytes: &[u8]) -> Vec<u8> {
+    let first = be_bytes
+        .iter()
+        .position(|b| *b != 0)
+        .unwrap_or(be_bytes.len());
+    encode_bytes(&be_bytes[first..])
+}
+
+/// Encodes a list of already-encoded items.
+pub fn encode_list(items: &[Vec<u8>]) -> Vec<u8> {
+    let payload_len = items.iter().map(Vec::len).sum();
+    let mut out = length_prefix(0xc0, payload_len);
+    for item in items {
+        out.extend_from_slice(item);
+    }
+    out
+}
+
+fn length_prefix(offset: u8, len: usize) -> Vec<u8> {
+    if len < 56 {
+        return vec![offset + len as u8];
+    }
+    let len_bytes = len.to_be_bytes();
+    let first = len_bytes.iter().position(|b| *b != 0).unwrap_or(0);
+    let len_bytes = &len_bytes[first..];
+    let mut out = vec![offset + 55 + len_bytes.len() as u8];
+    out.extend_from_slice(len_bytes);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_examples() {
+        // Examples from the Ethereum RLP specification
+        assert_eq!(encode_bytes(b"dog"), b"\x83dog");
+        assert_eq!(encode_bytes(b""), vec![0x80]);
+        assert_eq!(encode_uint(0), vec![0x80]);
+        assert_eq!(encode_uint(15), vec![0x0f]);
+        assert_eq!(encode_uint(1024), vec![0x82, 0x04, 0x00]);
+        assert_eq!(
+            encode_list(&[encode_bytes(b"cat"), encode_bytes(b"dog")]),
+            b"\xc8\x83cat\x83dog"
+        );
+        assert_eq!(encode_list(&[]), vec![0xc0]);
+    }
+
+    #[test]
+    fn test_encode_long_string() {
+        let s = b"Lorem ipsum dolor sit amet, consectetur adipisicing elit";
+        let encoded = encode_bytes(s);
+        assert_eq!(&encoded[..2], &[0xb8, 0x38]);
+        assert_eq!(&encoded[2..], s);
+    }
+}
@@ -0,0 +1,256 @@
+//! The Ethereum JSON-RPC calls needed to send a transaction and track its receipt,
+//! plus `eth_call` for reading contracts such as ENS.
+
+use super::Address;
+use anyhow::{anyhow, Result};
+use reqwest::Client;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::{json, Value};
+
+/// A transaction receipt, as returned by `eth_getTransactionReceipt`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Receipt {
+    /// `0x1` if the transaction succeeded, `0x0` if it reverted
+    pub status: String,
+    /// Block the transaction was included in
+    pub block_number: String,
+    #[serde(default)]
+    pub logs: Vec<Log>,
+}
+
+impl Receipt {
+    pub fn succeeded(&self) -> bool {
+        self.status == "0x1"
+    }
+
+    pub fn block_number(&self) -> Result<u64> {
+        Ok(parse_quantity(&self.block_number)? as u64)
+    }
+}
+
+/// An event log emitted by a transaction.
+#[derive(Debug, Deserialize)]
+pub struct Log {
+    /// Contract that emitted the event
+    pub address: String,
+    /// Event signature hash followed by the indexed arguments
+    pub topics: Vec<String>,
+}
+
+/// Client for an Ethereum node's JSON-RPC endpoints.
+///
+/// With several URLs, a request that fails to reach a node (connection error or
+/// HTTP error status) is retried on the next one. Errors returned by the node
+/// itself are not retried.
+pub struct EthRpc {
+    client: Client,
+    urls: Vec<String>,
+}
+
+impl EthRpc {
+    pub fn new(client: Client, url: impl Into<String>) -> Self {
+        Self::with_fallbacks(client, vec![url.into()])
+    }
+
+    /// A client trying `urls` in order.
+    pub fn with_fallbacks(client: Client, urls: Vec<String>) -> Self {
+        Self { client, urls }
+    }
+
+    /// The endpoints, in the order they are tried.
+    pub fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    /// Calls `method` and returns its `result`, or the node's error as an `Err`.
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let body = json!({ "jsonrpc": "2.0", "method": method, "params": params, "id": 1 });
+
+        let mut last_error = anyhow!("no RPC URL configured");
+        let mut response = None;
+        for url in &self.urls {
+            match self.post(url, method, &body).await {
+                Ok(json) => {
+                    response = Some(json);
+                    break;
+                }
+                Err(e) => {
+                    if self.urls.len() > 1 {
+                        tracing::warn!(url = %url, error = %e, "rpc endpoint failed");
+                    }
+                    last_error = e;
+                }
+            }
+        }
+        let mut json = response.ok_or(last_error)?;
+
+        if let Some(error) = json.get("error") {
+            let message = error["message"].as_str().unwrap_or("unknown error");
+            return Err(anyhow!(
+                "{} failed: {} ({})",
+                method,
+                message,
+                error["code"]
+            ));
+        }
+        serde_json::from_value(json["result"].take())
+            .map_err(|e| anyhow!("unexpected {} result: {}", method, e))
+    }
+
+    /// Posts a request to one endpoint and returns the JSON-RPC response.
+    async fn post(&self, url: &str, method: &str, body: &Value) -> Result<Value> {
+        let resp = self
+            .client
+            .post(url)
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| anyhow!("rpc request failed: {}", e))?;
+
+        let status = resp.status();
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            return Err(anyhow!("{} failed: {} - {}", method, status, text));
+        }
+
+        resp.json()
+            .await
+            .map_err(|e| anyhow!("failed to parse response: {}", e))
+    }
+
+    /// `eth_chainId`
+    pub async fn chain_id(&self) -> Result<u64> {
+        let id: String = self.call("eth_chainId", json!([])).await?;
+        Ok(parse_quantity(&id)? as u64)
+    }
+
+    /// `eth_getTransactionCount` including pending transactions, i.e. the next nonce.
+    pub async fn pending_nonce(&self, address: Address) -> Result<u64> {
+        let count: String = self
+            .call(
+                "eth_getTransactionCount",
+                json!([address.to_string(), "pending"]),
+            )
+            .await?;
+        Ok(parse_quantity(&count)? as u64)
+    }
+
+    /// Base fee of the latest block.
+    pub async fn base_fee(&self) -> Result<u128> {
+        let block: Value = self
+            .call("eth_getBlockByNumber", json!(["latest", false]))
+            .await?;
+        let base_fee = block["baseFeePerGas"]
+            .as_str()
+            .ok_or_else(|| anyhow!("latest block has no baseFeePerGas (EIP-1559 not active?)"))?;
+        parse_quantity(base_fee)
+    }
+
+    /// `eth_maxPriorityFeePerGas`
+    pub async fn max_priority_fee(&self) -> Result<u128> {
+        let fee: String = self.call("eth_maxPriorityFeePerGas", json!([])).await?;
+        parse_quantity(&fee)
+    }
+
+    /// `eth_estimateGas` for a call from `from` to `to` with `data`.
+    pub async fn estimate_gas(&self, from: Address, to: Address, data: &[u8]) -> Result<u64> {
+        let call = json!({
+            "from": from.to_string(),
+            "to": to.to_string(),
+            "data": format!("0x{}", hex::encode(data)),
+        });
+        let gas: String = self.call("eth_estimateGas", json!([call])).await?;
+        Ok(parse_quantity(&gas)? as u64)
+    }
+
+    /// `eth_call` of `to` with `data` against the latest block; returns the output.
+    pub async fn eth_call(&self, to: Address, data: &[u8]) -> Result<Vec<u8>> {
+        let call = json!({
+            "to": to.to_string(),
+            "data": format!("0x{}", hex::encode(data)),
+        });
+        let output: String = self.call("eth_call", json!([call, "latest"])).await?;
+        let digits = output
+            .strip_prefix("0x")
+            .ok_or_else(|| anyhow!("eth_call output without 0x prefix: {}", output))?;
+        hex::decode(digits).map_err(|e| anyhow!("invalid eth_call output: {}", e))
+    }
+
+    /// `eth_blockNumber`
+    pub async fn block_number(&self) -> Result<u64> {
+        let number: String = self.call("eth_blockNumber", json!([])).await?;
+        Ok(parse_quantity(&number)? as u64)
+    }
+
+    /// `eth_getTransactionReceipt`; `None` while the transaction is still pending.
+    pub async fn transaction_receipt(&self, tx_hash: &str) -> Result<Option<Receipt>> {
+        self.call("eth_getTransactionReceipt", json!([tx_hash]))
+            .await
+    }
+
+    /// `eth_getTransactionByHash`; `None` if the node does not know the transaction.
+    pub async fn transaction_by_hash(&self, tx_hash: &str) -> Result<Option<Value>> {
+        self.call("eth_getTransactionByHash", json!([tx_hash]))
+            .await
+    }
+
+    /// `eth_sendRawTransaction`; returns the transaction hash.
+    pub async fn send_raw_transaction(&self, raw: &[u8]) -> Result<String> {
+        self.call(
+            "eth_sendRawTransaction",
+            json!([format!("0x{}", hex::encode(raw))]),
+        )
+        .await
+    }
+}
+
+/// Parses a hex-encoded JSON-RPC quantity such as `"0x7a69"`.
+pub fn parse_quantity(value: &str) -> Result<u128> {
+    let digits = value
+        .strip_prefix("0x")
+        .ok_or_else(|| anyhow!("quantity without 0x prefix: {}", value))?;
+    u128::from_str_radix(digits, 16).map_err(|e| anyhow!("invalid quantity {}: {}", value, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_quantity() {
+        assert_eq!(parse_quantity("0x7a69").unwrap(), 31337);
+        assert_eq!(parse_quantity("0x0").unwrap(), 0);
+        assert!(parse_quantity("1234").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_falls_back_to_next_url() {
+        use axum::{routing::post, Json, Router};
+
+        let app = Router::new().route(
+            "/",
+            post(|| async { Json(json!({ "jsonrpc": "2.0", "id": 1, "result": "0x7a69" })) }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        // A port nothing listens on anymore
+        let closed = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let closed_addr = closed.local_addr().unwrap();
+        drop(closed);
+
+        let rpc = EthRpc::with_fallbacks(
+            Client::new(),
+            vec![
+                format!("http://{}", closed_addr),
+                format!("http://{}", addr),
+            ],
+        );
+        assert_eq!(rpc.chain_id().await.unwrap(), 31337);
+
+        let rpc = EthRpc::new(Client::new(), format!("http://{}", closed_addr));
+        assert!(rpc.chain_id().await.is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! secp256k1 transaction signing with a locally held private key.
+
+use super::{keccak256, Address};
+use anyhow::{anyhow, Result};
+use k256::ecdsa::SigningKey;
+
+/// An ECDSA signature in the form transactions carry it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// Recovery ID (0 or 1)
+    pub y_parity: u8,
+    pub r: [u8; 32],
+    pub s: [u8; 32],
+}
+
+/// Signs with the wallet key. Deliberately not `Debug`, so the key cannot end up in logs.
+pub struct Signer {
+    key: SigningKey,
+    address: Address,
+}
+
+impl Signer {
+    /// Creates a signer from a hex private key, with or without `0x` prefix.
+    pub fn from_hex(private_key: &str) -> Result<Self> {
+        let digits = private_key
+            .trim()
+            .strip_prefix("0x")
+            .unwrap_or(private_key.trim());
+        let bytes = hex::decode(digits).map_err(|_| anyhow!("private key is not valid hex"))?;
+        let key = SigningKey::from_slice(&bytes)
+            .map_err(|_| anyhow!("private key is not a valid secp256k1 key"))?;
+
+        // The address is the last 20 bytes of the hash of the uncompressed public key
+        let public = key.verifying_key().to_encoded_point(false);
+        let hash = keccak256(&public.as_bytes()[1..]);
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&hash[12..]);
+
+        Ok(Self {
+            key,
+            address: Address(address),
+        })
+    }
+
+    /// The account address of this key.
+    pub fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Signs a 32-byte message hash.
+    pub fn sign_hash(&self, hash: &[u8; 32]) -> Result<Signature> {
+        let (signature, recovery_id) = self
+            .key
+            .sign_prehash_recoverable(hash)
+            .map_err(|e| anyhow!("signing failed: {}", e))?;
+
+        let bytes = signature.to_bytes();
+        let mut r = [0u8; 32];
+        let mut s = [0u8; 32];
+        r.copy_from_slice(&bytes[..32]);
+        s.copy_from_slice(&bytes[32..]);
+        Ok(Signature {
+            y_parity: recovery_id.to_byte(),
+            r,
+            s,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k256::ecdsa::{RecoveryId, VerifyingKey};
+
+    /// First development account of anvil and hardhat.
+    const ANVIL_KEY: &str = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+    #[test]
+    fn test_address_from_key() {
+        let signer = Signer::from_hex(ANVIL_KEY).unwrap();
+        assert_eq!(
+            signer.address(),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+                .parse()
+                .unwrap()
+        );
+        assert!(Signer::from_hex("not-a-key").is_err());
+    }
+
+    #[test]
+    fn test_signature_recovers_signer() {
+        let signer = Signer::from_hex(ANVIL_KEY).unwrap();
+        let hash = keccak256(b"mint");
+        let sig = signer.sign_hash(&hash).unwrap();
+
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&sig.r);
+        bytes[32..].copy_from_slice(&sig.s);
+        let signature = k256::ecdsa::Signature::from_slice(&bytes).unwrap();
+        let recovery_id = RecoveryId::from_byte(sig.y_parity).unwrap();
+        let recovered = VerifyingKey::recover_from_prehash(&hash, &signature, recovery_id).unwrap();
+
+        assert_eq!(&recovered, signer.key.verifying_key());
+    }
+}
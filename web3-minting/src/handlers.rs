@@ -1,52 +1,356 @@
-use crate::models::{ErrorResponse, Metadata, MintRequest, MintResponse};
-use axum::{http::StatusCode, response::IntoResponse, Json};
+use crate::models::{
+    AssetUploaded, ErrorResponse, InlineAsset, Metadata, MintAccepted, MintRequest,
+};
+use crate::AppState;
+use anyhow::{anyhow, bail};
+use axum::{
+    extract::{Multipart, Path, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use std::sync::Arc;
 
-pub async fn mint(Json(payload): Json<MintRequest>) -> impl IntoResponse {
+/// Queues a mint job and returns its ID right away; poll `GET /mint/:id` for progress.
+///
+/// An inline `asset` is uploaded before the job is queued, so the job only
+/// references it by URL; it is uploaded after every other check passes, so a
+/// rejected request never leaves an orphaned upload. A malformed `recipient` is
+/// rejected here; `.eth` names are resolved by the worker.
+pub async fn mint(
+    State(state): State<AppState>,
+    Json(mut payload): Json<MintRequest>,
+) -> impl IntoResponse {
     tracing::info!(request = ?payload, "/mint called");
 
-    // Build metadata
-    let metadata = Metadata {
-        name: payload.name.clone(),
-        description: payload.description.clone(),
-        asset_url: payload.asset_url.clone(),
+    let asset = match payload.asset.take() {
+        Some(asset) => match check_inline_asset(&payload, asset) {
+            Ok(asset) => Some(asset),
+            Err(e) => return error_response(StatusCode::BAD_REQUEST, e.to_string()),
+        },
+        None => None,
     };
 
-    // Upload metadata
-    let upload = match crate::storage::upload_metadata(&metadata).await {
-        Ok(u) => u,
+    if let Some(recipient) = payload.recipient.as_deref().filter(|r| !r.is_empty()) {
+        if let Err(e) = crate::blockchain::parse_recipient(recipient) {
+            return error_response(StatusCode::BAD_REQUEST, format!("{:#}", e));
+        }
+    }
+
+    if let Err(e) = state.chains.get(payload.chain.as_deref()) {
+        return error_response(StatusCode::BAD_REQUEST, e.to_string());
+    }
+
+    // The asset's URL comes from storage, so the rest of the metadata is validated without it
+    if let Err(e) = crate::metadata::validate(&Metadata::from(&payload)) {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            format!("invalid metadata: {:#}", e),
+        );
+    }
+
+    if let Some(asset) = asset {
+        if let Err(resp) = attach_asset(&state, &mut payload, asset).await {
+            return resp;
+        }
+    }
+
+    let job = match state.jobs.create(payload) {
+        Ok(job) => job,
         Err(e) => {
-            tracing::error!(error = %e, "metadata upload failed");
-            let body = ErrorResponse {
-                error: format!("upload error: {}", e),
-            };
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response();
+            tracing::error!(error = %e, "failed to create mint job");
+            return error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("job error: {}", e),
+            );
         }
     };
 
-    // Determine recipient
-    let recipient = payload
-        .recipient
-        .clone()
-        .unwrap_or_else(|| "default-recipient-address".to_string());
+    if state.queue.send(job.id.clone()).is_err() {
+        tracing::error!(job_id = %job.id, "mint worker is not running");
+        return error_response(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "mint worker is not running".to_string(),
+        );
+    }
+
+    tracing::info!(job_id = %job.id, "/mint queued");
+    let resp = MintAccepted {
+        job_id: job.id,
+        status: job.status,
+    };
+    (StatusCode::ACCEPTED, Json(resp)).into_response()
+}
 
-    // Mint token
-    let mint = match crate::blockchain::mint_token(&upload.url, &recipient).await {
-        Ok(m) => m,
+/// Returns a mint job with its status and, once available, upload and mint results.
+pub async fn get_mint(State(state): State<AppState>, Path(id): Path<String>) -> impl IntoResponse {
+    match state.jobs.get(&id) {
+        Ok(Some(job)) => (StatusCode::OK, Json(job)).into_response(),
+        Ok(None) => error_response(StatusCode::NOT_FOUND, format!("mint job {} not found", id)),
         Err(e) => {
-            tracing::error!(error = %e, "mint call failed");
-            let body = ErrorResponse {
-                error: format!("mint error: {}", e),
+            tracing::error!(job_id = %id, error = %e, "failed to load mint job");
+            error_response(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("job error: {}", e),
+            )
+        }
+    }
+}
+
+/// Uploads the multipart field `file` (an image, audio or video) to storage and
+/// returns its CID and URL, to be used as `image` or `animation_url` in a mint.
+pub async fn upload_asset(
+    State(state): State<AppState>,
+    mut multipart: Multipart,
+) -> impl IntoResponse {
+    let (filename, content_type, bytes) = loop {
+        match multipart.next_field().await {
+            Ok(Some(field)) if field.name() == Some("file") => {
+                let filename = field.file_name().unwrap_or("asset").to_string();
+                let content_type = field
+                    .content_type()
+                    .unwrap_or("application/octet-stream")
+                    .to_string();
+                match field.bytes().await {
+                    Ok(bytes) => break (filename, content_type, bytes.to_vec()),
+                    Err(e) => {
+                        return error_response(
+                            StatusCode::BAD_REQUEST,
+                            format!("invalid upload: {}", e),
+                        )
+                    }
+                }
+            }
+            Ok(Some(_)) => continue,
+            Ok(None) => {
+                return error_response(
+                    StatusCode::BAD_REQUEST,
+                    "missing multipart field \"file\"".to_string(),
+                )
+            }
+            Err(e) => {
+                return error_response(StatusCode::BAD_REQUEST, format!("invalid upload: {}", e))
+            }
+        }
+    };
+    tracing::info!(filename = %filename, content_type = %content_type, size = bytes.len(), "/assets called");
+
+    let size = bytes.len();
+    if let Err(e) = crate::storage::check_asset(&content_type, size) {
+        return error_response(StatusCode::BAD_REQUEST, e.to_string());
+    }
+    match crate::storage::upload_asset(state.storage.as_ref(), &filename, &content_type, bytes)
+        .await
+    {
+        Ok(upload) => {
+            let resp = AssetUploaded {
+                cid: upload.cid,
+                url: upload.url,
+                content_type,
+                size,
             };
-            return (StatusCode::INTERNAL_SERVER_ERROR, Json(body)).into_response();
+            (StatusCode::OK, Json(resp)).into_response()
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "asset upload failed");
+            error_response(StatusCode::BAD_GATEWAY, format!("upload error: {}", e))
         }
+    }
+}
+
+/// An inline asset that passed [`check_inline_asset`], ready to upload.
+struct CheckedAsset {
+    filename: String,
+    content_type: String,
+    bytes: Vec<u8>,
+}
+
+impl CheckedAsset {
+    /// Images become the token's `image`; audio and video its `animation_url`.
+    fn is_image(&self) -> bool {
+        self.content_type.starts_with("image/")
+    }
+}
+
+/// Decodes an inline asset and checks its type and size, and that it does not
+/// replace a URL the request already sets.
+fn check_inline_asset(request: &MintRequest, asset: InlineAsset) -> anyhow::Result<CheckedAsset> {
+    let bytes = BASE64
+        .decode(asset.data.as_bytes())
+        .map_err(|e| anyhow!("asset data is not valid base64: {}", e))?;
+    crate::storage::check_asset(&asset.content_type, bytes.len())?;
+
+    let asset = CheckedAsset {
+        filename: asset.filename.unwrap_or_else(|| "asset".to_string()),
+        content_type: asset.content_type,
+        bytes,
+    };
+    let (field, slot) = if asset.is_image() {
+        ("image", &request.image)
+    } else {
+        ("animation_url", &request.animation_url)
     };
+    if slot.is_some() {
+        bail!("asset would replace {}; send one or the other", field);
+    }
+    Ok(asset)
+}
 
-    let resp = MintResponse {
-        status: "success".to_string(),
-        upload,
-        mint,
+/// Uploads a checked inline asset and references it from the request as `image`
+/// (images) or `animation_url` (audio and video).
+async fn attach_asset(
+    state: &AppState,
+    request: &mut MintRequest,
+    asset: CheckedAsset,
+) -> Result<(), Response> {
+    let slot = if asset.is_image() {
+        &mut request.image
+    } else {
+        &mut request.animation_url
     };
+    let upload = crate::storage::upload_asset(
+        state.storage.as_ref(),
+        &asset.filename,
+        &asset.content_type,
+        asset.bytes,
+    )
+    .await
+    .map_err(|e| {
+        tracing::error!(error = %e, "asset upload failed");
+        error_response(StatusCode::BAD_GATEWAY, format!("upload error: {}", e))
+    })?;
+    *slot = Some(upload.url);
+    Ok(())
+}
+
+/// Lets a request through only if it carries the service token (`MINT_API_TOKEN`)
+/// as `Authorization: Bearer <token>`; the mint and asset routes are for the other
+/// services, not for browsers.
+pub async fn require_token(
+    State(token): State<Arc<str>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !is_authorized(request.headers(), &token) {
+        return error_response(
+            StatusCode::UNAUTHORIZED,
+            "invalid or missing service token".to_string(),
+        );
+    }
+    next.run(request).await
+}
+
+/// Checks the `Authorization: Bearer` header against the service token.
+fn is_authorized(headers: &HeaderMap, token: &str) -> bool {
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|provided| constant_time_eq(provided.as_bytes(), token.as_bytes()))
+}
+
+/// Compares two byte strings without exiting early on the first difference.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn error_response(status: StatusCode, error: String) -> Response {
+    (status, Json(ErrorResponse { error })).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chains::ChainRegistry;
+    use crate::jobs::JobStore;
+    use crate::models::UploadResult;
+    use crate::storage::StorageBackend;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::mpsc;
+
+    /// Counts uploads.
+    #[derive(Default)]
+    struct CountingBackend {
+        puts: AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl StorageBackend for CountingBackend {
+        fn name(&self) -> &'static str {
+            "counting"
+        }
+
+        async fn put(
+            &self,
+            _filename: &str,
+            _content_type: &str,
+            _bytes: Vec<u8>,
+        ) -> anyhow::Result<UploadResult> {
+            self.puts.fetch_add(1, Ordering::SeqCst);
+            Ok(UploadResult {
+                cid: "bafyasset".to_string(),
+                url: "ipfs://bafyasset".to_string(),
+            })
+        }
+    }
+
+    async fn post_mint(storage: Arc<CountingBackend>, request: serde_json::Value) -> StatusCode {
+        let (queue, _rx) = mpsc::unbounded_channel();
+        let state = AppState {
+            jobs: Arc::new(JobStore::open_in_memory().unwrap()),
+            queue,
+            storage,
+            chains: Arc::new(ChainRegistry::default()),
+        };
+        let request = serde_json::from_value(request).unwrap();
+        mint(State(state), Json(request))
+            .await
+            .into_response()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_asset_is_uploaded_only_after_the_request_is_validated() {
+        let asset = serde_json::json!({
+            "filename": "cover.png",
+            "content_type": "image/png",
+            "data": BASE64.encode(b"\x89PNG\r\n\x1a\n"),
+        });
+        let storage = Arc::new(CountingBackend::default());
+
+        for invalid in [
+            serde_json::json!({ "name": "Test", "recipient": "0x1234", "asset": asset }),
+            serde_json::json!({ "name": "Test", "chain": "no-such-chain", "asset": asset }),
+            serde_json::json!({ "name": "Test", "background_color": "#fff", "asset": asset }),
+        ] {
+            let status = post_mint(storage.clone(), invalid.clone()).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", invalid);
+        }
+        assert_eq!(storage.puts.load(Ordering::SeqCst), 0);
+
+        let valid = serde_json::json!({ "name": "Test", "asset": asset });
+        assert_eq!(
+            post_mint(storage.clone(), valid).await,
+            StatusCode::ACCEPTED
+        );
+        assert_eq!(storage.puts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_only_the_service_token_is_authorized() {
+        let headers = |value: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, value.parse().unwrap());
+            headers
+        };
 
-    tracing::info!(response = ?resp, "/mint completed");
-    (StatusCode::OK, Json(resp)).into_response()
+        assert!(is_authorized(&headers("Bearer s3cret"), "s3cret"));
+        assert!(!is_authorized(&headers("Bearer s3cre"), "s3cret"));
+        assert!(!is_authorized(&headers("Bearer s3cret!"), "s3cret"));
+        assert!(!is_authorized(&headers("s3cret"), "s3cret"));
+        assert!(!is_authorized(&HeaderMap::new(), "s3cret"));
+    }
 }
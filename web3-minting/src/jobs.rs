@@ -0,0 +1,226 @@
+//! SQLite persistence for mint jobs, so queued and in-flight mints survive restarts.
+
+use crate::models::{JobStatus, MintJob, MintRequest};
+use anyhow::{anyhow, Context, Result};
+use rusqlite::{params, Connection, OptionalExtension, Row};
+use std::path::Path;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+/// SQLite-backed store of mint jobs.
+///
+/// Request, upload and mint results are stored as JSON columns; jobs are only ever
+/// looked up by ID or status.
+pub struct JobStore {
+    conn: Mutex<Connection>,
+}
+
+impl JobStore {
+    /// Opens (or creates) the job database at the given path.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let conn = Connection::open(path).context("failed to open job database")?;
+        Self::with_connection(conn)
+    }
+
+    /// Opens a store backed by a private in-memory database.
+    #[cfg(test)]
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(conn: Connection) -> Result<Self> {
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS mint_jobs (
+                id         TEXT PRIMARY KEY,
+                status     TEXT NOT NULL,
+                request    TEXT NOT NULL,
+                upload     TEXT,
+                mint       TEXT,
+                raw_tx     TEXT,
+                error      TEXT,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            );",
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Creates a pending job for a request.
+    pub fn create(&self, request: MintRequest) -> Result<MintJob> {
+        let now = chrono::Utc::now().to_rfc3339();
+        let job = MintJob {
+            id: Uuid::new_v4().to_string(),
+            status: JobStatus::Pending,
+            request,
+            upload: None,
+            mint: None,
+            raw_tx: None,
+            error: None,
+            created_at: now.clone(),
+            updated_at: now,
+        };
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO mint_jobs (id, status, request, created_at, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                job.id,
+                job.status.as_str(),
+                serde_json::to_string(&job.request)?,
+                job.created_at,
+                job.updated_at
+            ],
+        )?;
+        Ok(job)
+    }
+
+    /// Returns a job by ID.
+    pub fn get(&self, id: &str) -> Result<Option<MintJob>> {
+        let conn = self.conn.lock().unwrap();
+        let row = conn
+            .query_row(
+                "SELECT id, status, request, upload, mint, raw_tx, error, created_at, updated_at
+                 FROM mint_jobs WHERE id = ?1",
+                params![id],
+                read_row,
+            )
+            .optional()?;
+        row.map(into_job).transpose()
+    }
+
+    /// Returns the jobs that are not finished, oldest first.
+    pub fn unfinished(&self) -> Result<Vec<MintJob>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT id, status, request, upload, mint, raw_tx, error, created_at, updated_at
+             FROM mint_jobs WHERE status NOT IN ('confirmed', 'failed')
+             ORDER BY created_at",
+        )?;
+        let rows = stmt.query_map([], read_row)?;
+        rows.map(|row| into_job(row?)).collect()
+    }
+
+    /// Saves the progress of a job and bumps its `updated_at`.
+    pub fn save(&self, job: &mut MintJob) -> Result<()> {
+        job.updated_at = chrono::Utc::now().to_rfc3339();
+        let upload = job.upload.as_ref().map(serde_json::to_string).transpose()?;
+        let mint = job.mint.as_ref().map(serde_json::to_string).transpose()?;
+        let raw_tx = job.raw_tx.as_ref().map(hex::encode);
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE mint_jobs SET status = ?2, upload = ?3, mint = ?4, raw_tx = ?5, error = ?6,
+             updated_at = ?7 WHERE id = ?1",
+            params![
+                job.id,
+                job.status.as_str(),
+                upload,
+                mint,
+                raw_tx,
+                job.error,
+                job.updated_at
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+/// Columns of a `mint_jobs` row, before JSON decoding.
+type JobRow = (
+    String,
+    String,
+    String,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    String,
+    String,
+);
+
+fn read_row(row: &Row) -> rusqlite::Result<JobRow> {
+    Ok((
+        row.get(0)?,
+        row.get(1)?,
+        row.get(2)?,
+        row.get(3)?,
+        row.get(4)?,
+        row.get(5)?,
+        row.get(6)?,
+        row.get(7)?,
+        row.get(8)?,
+    ))
+}
+
+fn into_job(row: JobRow) -> Result<MintJob> {
+    let (id, status, request, upload, mint, raw_tx, error, created_at, updated_at) = row;
+    Ok(MintJob {
+        status: JobStatus::parse(&status)
+            .ok_or_else(|| anyhow!("job {} has unknown status {}", id, status))?,
+        request: serde_json::from_str(&request)?,
+        upload: upload.as_deref().map(serde_json::from_str).transpose()?,
+        mint: mint.as_deref().map(serde_json::from_str).transpose()?,
+        raw_tx: raw_tx.as_deref().map(hex::decode).transpose()?,
+        error,
+        created_at,
+        updated_at,
+        id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MintResult;
+
+    fn request() -> MintRequest {
+        MintRequest {
+            name: "Test".to_string(),
+            description: None,
+            image: None,
+            animation_url: None,
+            external_url: None,
+            attributes: Vec::new(),
+            background_color: None,
+            recipient: None,
+            chain: None,
+            asset: None,
+        }
+    }
+
+    #[test]
+    fn test_jobs_round_trip() {
+        let store = JobStore::open_in_memory().unwrap();
+        let mut job = store.create(request()).unwrap();
+        let done = store.create(request()).unwrap();
+        assert_eq!(store.unfinished().unwrap().len(), 2);
+
+        job.status = JobStatus::Submitted;
+        job.mint = Some(MintResult {
+            tx_hash: "0xabc".to_string(),
+            token_id: None,
+            block_number: None,
+            chain_id: Some(31337),
+            explorer_url: None,
+        });
+        job.raw_tx = Some(vec![0x02, 0xf8]);
+        store.save(&mut job).unwrap();
+
+        let mut done = store.get(&done.id).unwrap().unwrap();
+        done.status = JobStatus::Failed;
+        done.error = Some("boom".to_string());
+        store.save(&mut done).unwrap();
+
+        let loaded = store.get(&job.id).unwrap().unwrap();
+        assert_eq!(loaded.status, JobStatus::Submitted);
+        assert_eq!(loaded.mint.unwrap().tx_hash, "0xabc");
+        assert_eq!(loaded.raw_tx, Some(vec![0x02, 0xf8]));
+        assert_eq!(loaded.request.name, "Test");
+
+        let unfinished = store.unfinished().unwrap();
+        assert_eq!(unfinished.len(), 1);
+        assert_eq!(unfinished[0].id, job.id);
+        assert!(store.get("missing").unwrap().is_none());
+    }
+}
@@ -1,23 +1,104 @@
 use std::net::SocketAddr;
+use std::sync::Arc;
 
 mod blockchain;
+mod chains;
+mod evm;
 mod handlers;
+mod jobs;
+mod metadata;
 mod models;
 mod storage;
+mod worker;
 
-use axum::{routing::post, Router};
+use axum::{
+    extract::DefaultBodyLimit,
+    http::{header, HeaderValue, Method},
+    middleware,
+    routing::{get, post},
+    Router,
+};
+use jobs::JobStore;
+use tokio::sync::mpsc;
+use tower_http::cors::CorsLayer;
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
+/// Shared state of the request handlers.
+#[derive(Clone)]
+pub struct AppState {
+    /// Persistent mint jobs
+    pub jobs: Arc<JobStore>,
+    /// Queue of job IDs for the mint worker
+    pub queue: mpsc::UnboundedSender<String>,
+    /// Where assets and metadata are uploaded
+    pub storage: Arc<dyn storage::StorageBackend>,
+    /// Chains mints can be sent to
+    pub chains: Arc<chains::ChainRegistry>,
+}
+
 #[tokio::main]
 async fn main() {
+    // Load CHAINS_CONFIG, WALLET_PRIVATE_KEY etc. from .env if present
+    dotenv::dotenv().ok();
+
     // Initialize tracing subscriber
     tracing_subscriber::registry()
         .with(fmt::layer())
         .with(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
         .init();
 
-    // Build our application with routes
-    let app = Router::new().route("/mint", post(handlers::mint));
+    // The mint and asset routes only answer the services holding this token
+    let api_token: Arc<str> = std::env::var("MINT_API_TOKEN")
+        .ok()
+        .filter(|token| !token.is_empty())
+        .expect("MINT_API_TOKEN must be set")
+        .into();
+
+    // Open the job database and start the worker, resuming unfinished jobs
+    let db_path = std::env::var("MINT_JOBS_DB_PATH").unwrap_or_else(|_| "mint_jobs.db".to_string());
+    let jobs = Arc::new(JobStore::open(&db_path).expect("Failed to open job database"));
+    let storage =
+        storage::from_env(&reqwest::Client::new()).expect("Invalid storage configuration");
+    tracing::info!(
+        backend = storage.name(),
+        "asset and metadata storage configured"
+    );
+    let chains = Arc::new(
+        chains::ChainRegistry::from_env(&reqwest::Client::new())
+            .expect("Invalid chain configuration"),
+    );
+    if chains.is_mock() {
+        tracing::warn!("no chain configured - mints are mocked");
+    } else {
+        tracing::info!(chains = ?chains.names(), "chains configured");
+    }
+    let queue = worker::spawn(jobs.clone(), storage.clone(), chains.clone())
+        .expect("Failed to load unfinished mint jobs");
+
+    // Build our application with routes; mcp-api and mcp-server call them with the token
+    let state = AppState {
+        jobs,
+        queue,
+        storage: storage.clone(),
+        chains,
+    };
+    let mut app = Router::new()
+        .route("/mint", post(handlers::mint))
+        .route("/mint/:id", get(handlers::get_mint))
+        .route("/assets", post(handlers::upload_asset))
+        .route_layer(middleware::from_fn_with_state(
+            api_token,
+            handlers::require_token,
+        ))
+        // Room for the largest asset, base64-encoded when sent inline with /mint
+        .layer(DefaultBodyLimit::max(
+            storage::MAX_ASSET_SIZE / 3 * 4 + 64 * 1024,
+        ))
+        .with_state(state);
+    if let Some(files) = storage.router() {
+        app = app.merge(files);
+    }
+    let app = app.layer(cors_from_env());
 
     // Run on 0.0.0.0:8081
     let addr = SocketAddr::from(([0, 0, 0, 0], 8081));
@@ -29,3 +110,32 @@ async fn main() {
 
     axum::serve(listener, app).await.expect("Server failed");
 }
+
+/// Builds the CORS layer from the `CORS_ORIGINS` environment variable.
+///
+/// Only browsers need CORS, and they only load stored files; the default allows
+/// the frontend's dev server.
+///
+/// # Environment Variables
+///
+/// - `CORS_ORIGINS` - Comma-separated origins (optional, defaults to "http://localhost:5173")
+///
+/// # Panics
+///
+/// Panics if an origin is not a valid header value.
+fn cors_from_env() -> CorsLayer {
+    let origins =
+        std::env::var("CORS_ORIGINS").unwrap_or_else(|_| "http://localhost:5173".to_string());
+    let allowed: Vec<HeaderValue> = origins
+        .split(',')
+        .map(str::trim)
+        .filter(|origin| !origin.is_empty())
+        .map(|origin| origin.parse().expect("Invalid origin in CORS_ORIGINS"))
+        .collect();
+    tracing::info!("Allowing CORS from: {}", origins);
+
+    CorsLayer::new()
+        .allow_origin(allowed)
+        .allow_methods([Method::GET, Method::POST])
+        .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE])
+}
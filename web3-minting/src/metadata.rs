@@ -0,0 +1,205 @@
+//! Validation of token metadata against the ERC-721 metadata JSON schema, as
+//! extended by OpenSea (`animation_url`, `external_url`, `attributes`, `background_color`).
+
+use crate::models::{Attribute, Metadata};
+use anyhow::{bail, Result};
+
+/// URL schemes marketplaces resolve for `image` and `animation_url`.
+const MEDIA_SCHEMES: &[&str] = &["https://", "http://", "ipfs://", "ar://"];
+
+/// Display types of numeric traits.
+const DISPLAY_TYPES: &[&str] = &["number", "boost_number", "boost_percentage", "date"];
+
+/// Checks metadata before it is uploaded; the error names the first invalid field.
+pub fn validate(metadata: &Metadata) -> Result<()> {
+    if metadata.name.trim().is_empty() {
+        bail!("name must not be empty");
+    }
+
+    if let Some(image) = &metadata.image {
+        // Images may also be inlined, e.g. SVGs
+        if !image.starts_with("data:image/") {
+            check_url("image", image, MEDIA_SCHEMES)?;
+        }
+    }
+    if let Some(url) = &metadata.animation_url {
+        check_url("animation_url", url, MEDIA_SCHEMES)?;
+    }
+    if let Some(url) = &metadata.external_url {
+        check_url("external_url", url, &["https://", "http://"])?;
+    }
+
+    if let Some(color) = &metadata.background_color {
+        if color.len() != 6 || !color.chars().all(|c| c.is_ascii_hexdigit()) {
+            bail!(
+                "background_color must be six hex digits without '#', got {:?}",
+                color
+            );
+        }
+    }
+
+    for (i, attribute) in metadata.attributes.iter().enumerate() {
+        check_attribute(attribute).map_err(|e| e.context(format!("attributes[{}]", i)))?;
+    }
+    Ok(())
+}
+
+fn check_url(field: &str, url: &str, schemes: &[&str]) -> Result<()> {
+    let valid = schemes
+        .iter()
+        .any(|scheme| url.len() > scheme.len() && url.starts_with(scheme));
+    if !valid {
+        bail!(
+            "{} must be a URL starting with one of {}, got {:?}",
+            field,
+            schemes.join(", "),
+            url
+        );
+    }
+    Ok(())
+}
+
+fn check_attribute(attribute: &Attribute) -> Result<()> {
+    if let Some(trait_type) = &attribute.trait_type {
+        if trait_type.trim().is_empty() {
+            bail!("trait_type must not be empty");
+        }
+    }
+
+    match &attribute.display_type {
+        Some(display_type) => {
+            if !DISPLAY_TYPES.contains(&display_type.as_str()) {
+                bail!(
+                    "display_type must be one of {}, got {:?}",
+                    DISPLAY_TYPES.join(", "),
+                    display_type
+                );
+            }
+            // `date` values are unix timestamps
+            if !attribute.value.is_number() {
+                bail!("value must be a number for display_type {}", display_type);
+            }
+        }
+        None => {
+            if !attribute.value.is_string() && !attribute.value.is_number() {
+                bail!("value must be a string or a number");
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::MintRequest;
+    use serde_json::json;
+
+    fn metadata() -> Metadata {
+        Metadata {
+            name: "Conversation #1".to_string(),
+            description: Some("A chat with the concierge".to_string()),
+            image: Some("ipfs://bafkreiabc".to_string()),
+            animation_url: Some("https://example.com/reply.mp3".to_string()),
+            external_url: Some("https://example.com/conversations/1".to_string()),
+            attributes: vec![
+                Attribute {
+                    trait_type: Some("Agent".to_string()),
+                    value: json!("Concierge"),
+                    display_type: None,
+                },
+                Attribute {
+                    trait_type: Some("Turns".to_string()),
+                    value: json!(4),
+                    display_type: Some("number".to_string()),
+                },
+            ],
+            background_color: Some("1F2937".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_serializes_marketplace_fields() {
+        let value = serde_json::to_value(metadata()).unwrap();
+        assert_eq!(value["image"], "ipfs://bafkreiabc");
+        assert_eq!(
+            value["attributes"][0],
+            json!({ "trait_type": "Agent", "value": "Concierge" })
+        );
+        assert_eq!(
+            value["attributes"][1],
+            json!({ "trait_type": "Turns", "value": 4, "display_type": "number" })
+        );
+
+        // Unset fields are left out rather than null
+        let request: MintRequest = serde_json::from_value(json!({ "name": "Minimal" })).unwrap();
+        assert_eq!(
+            serde_json::to_value(Metadata::from(&request)).unwrap(),
+            json!({ "name": "Minimal" })
+        );
+
+        // Older clients send the image as `asset_url`
+        let request: MintRequest = serde_json::from_value(
+            json!({ "name": "Old", "asset_url": "https://example.com/a.png" }),
+        )
+        .unwrap();
+        assert_eq!(request.image.as_deref(), Some("https://example.com/a.png"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&metadata()).is_ok());
+
+        let invalid = [
+            Metadata {
+                name: " ".to_string(),
+                ..metadata()
+            },
+            Metadata {
+                image: Some("/tmp/a.png".to_string()),
+                ..metadata()
+            },
+            Metadata {
+                external_url: Some("ipfs://bafy".to_string()),
+                ..metadata()
+            },
+            Metadata {
+                background_color: Some("#1F2937".to_string()),
+                ..metadata()
+            },
+            Metadata {
+                attributes: vec![Attribute {
+                    trait_type: Some("Level".to_string()),
+                    value: json!("high"),
+                    display_type: Some("boost_number".to_string()),
+                }],
+                ..metadata()
+            },
+            Metadata {
+                attributes: vec![Attribute {
+                    trait_type: None,
+                    value: json!(true),
+                    display_type: None,
+                }],
+                ..metadata()
+            },
+        ];
+        for m in invalid {
+            assert!(validate(&m).is_err(), "should be invalid: {:?}", m);
+        }
+
+        let err = validate(&Metadata {
+            attributes: vec![Attribute {
+                trait_type: Some("Mood".to_string()),
+                value: json!(null),
+                display_type: None,
+            }],
+            ..metadata()
+        })
+        .unwrap_err();
+        assert_eq!(
+            format!("{:#}", err),
+            "attributes[0]: value must be a string or a number"
+        );
+    }
+}
@@ -1,27 +1,119 @@
 use serde::{Deserialize, Serialize};
 
 /// Request payload sent by front-end to trigger a mint.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MintRequest {
     /// Human-friendly name/title
     pub name: String,
     /// Description or transcript
     pub description: Option<String>,
-    /// Link to uploaded asset (image/audio) on your storage (optional)
-    pub asset_url: Option<String>,
-    /// Recipient address for token (optional; can be assigned server-side)
+    /// Image of the token (`asset_url` is accepted for older clients)
+    #[serde(default, alias = "asset_url")]
+    pub image: Option<String>,
+    /// Audio, video or HTML version of the token, e.g. the conversation audio
+    #[serde(default)]
+    pub animation_url: Option<String>,
+    /// Page about the token on your site
+    #[serde(default)]
+    pub external_url: Option<String>,
+    /// Traits shown by marketplaces
+    #[serde(default)]
+    pub attributes: Vec<Attribute>,
+    /// Background color as six hex digits, without `#`
+    #[serde(default)]
+    pub background_color: Option<String>,
+    /// Recipient address (EIP-55 checksummed if mixed-case) or `.eth` name; the
+    /// minting wallet when omitted
     pub recipient: Option<String>,
+    /// Name of the configured chain to mint on; the default chain when omitted
+    #[serde(default)]
+    pub chain: Option<String>,
+    /// File to upload before minting; it becomes the `image` (images) or the
+    /// `animation_url` (audio and video). Never stored with the job.
+    #[serde(default, skip_serializing)]
+    pub asset: Option<InlineAsset>,
 }
 
-/// Internal metadata object that will be uploaded to storage (IPFS etc.)
-#[derive(Debug, Serialize)]
+/// A file sent inline with a mint request.
+#[derive(Clone, Deserialize)]
+pub struct InlineAsset {
+    /// File name, e.g. `reply.mp3`
+    #[serde(default)]
+    pub filename: Option<String>,
+    /// Media type, e.g. `audio/mpeg`
+    pub content_type: String,
+    /// Base64-encoded file contents
+    pub data: String,
+}
+
+impl std::fmt::Debug for InlineAsset {
+    // Requests are logged, so leave out the contents
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("InlineAsset")
+            .field("filename", &self.filename)
+            .field("content_type", &self.content_type)
+            .field("data_len", &self.data.len())
+            .finish()
+    }
+}
+
+/// A token trait, e.g. `{"trait_type": "Agent", "value": "Concierge"}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Attribute {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trait_type: Option<String>,
+    /// A string or a number
+    pub value: serde_json::Value,
+    /// How numeric traits are displayed: `number`, `boost_number`, `boost_percentage` or `date`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_type: Option<String>,
+}
+
+/// Token metadata uploaded to storage, in the ERC-721 metadata JSON format with the
+/// fields OpenSea and other marketplaces read.
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct Metadata {
     pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
-    pub asset_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub animation_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub external_url: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<Attribute>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background_color: Option<String>,
+}
+
+impl From<&MintRequest> for Metadata {
+    fn from(request: &MintRequest) -> Self {
+        Metadata {
+            name: request.name.clone(),
+            description: request.description.clone(),
+            image: request.image.clone(),
+            animation_url: request.animation_url.clone(),
+            external_url: request.external_url.clone(),
+            attributes: request.attributes.clone(),
+            background_color: request.background_color.clone(),
+        }
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Response of `POST /assets`.
+#[derive(Debug, Serialize)]
+pub struct AssetUploaded {
+    /// Content identifier (CID) or equivalent from storage
+    pub cid: String,
+    /// A full gateway URL to retrieve the asset; use it as `image` or `animation_url`
+    pub url: String,
+    pub content_type: String,
+    pub size: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UploadResult {
     /// Content identifier (CID) or equivalent from storage
     pub cid: String,
@@ -29,19 +121,93 @@ pub struct UploadResult {
     pub url: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MintResult {
     /// Blockchain transaction hash
     pub tx_hash: String,
     /// Token ID minted (if available)
     pub token_id: Option<String>,
+    /// Block the mint transaction was included in (on-chain mints only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    /// Chain the token was minted on (on-chain mints only)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    /// The transaction on the chain's block explorer, if one is configured
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explorer_url: Option<String>,
+}
+
+/// Progress of a mint job.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobStatus {
+    /// Queued, not started yet
+    Pending,
+    /// Uploading the metadata to storage
+    Uploading,
+    /// Mint transaction sent, waiting for confirmations
+    Submitted,
+    /// Minted (or mocked, when no blockchain is configured)
+    Confirmed,
+    /// Upload or mint failed; see the job's `error`
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Pending => "pending",
+            JobStatus::Uploading => "uploading",
+            JobStatus::Submitted => "submitted",
+            JobStatus::Confirmed => "confirmed",
+            JobStatus::Failed => "failed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "pending" => Some(JobStatus::Pending),
+            "uploading" => Some(JobStatus::Uploading),
+            "submitted" => Some(JobStatus::Submitted),
+            "confirmed" => Some(JobStatus::Confirmed),
+            "failed" => Some(JobStatus::Failed),
+            _ => None,
+        }
+    }
+
+    /// Whether the job is finished and will not change anymore.
+    pub fn is_final(&self) -> bool {
+        matches!(self, JobStatus::Confirmed | JobStatus::Failed)
+    }
+}
+
+/// A mint request and its progress, as returned by `GET /mint/:id`.
+#[derive(Debug, Clone, Serialize)]
+pub struct MintJob {
+    pub id: String,
+    pub status: JobStatus,
+    pub request: MintRequest,
+    /// Metadata location, once uploaded
+    pub upload: Option<UploadResult>,
+    /// Transaction hash once submitted; token ID and block once confirmed
+    pub mint: Option<MintResult>,
+    /// The signed mint transaction, saved before it is sent so a restarted worker
+    /// sends the same one again
+    #[serde(skip)]
+    pub raw_tx: Option<Vec<u8>>,
+    /// Why the job failed
+    pub error: Option<String>,
+    /// RFC 3339 timestamps
+    pub created_at: String,
+    pub updated_at: String,
 }
 
+/// Response of `POST /mint`: the job to poll.
 #[derive(Debug, Serialize)]
-pub struct MintResponse {
-    pub status: String,
-    pub upload: UploadResult,
-    pub mint: MintResult,
+pub struct MintAccepted {
+    pub job_id: String,
+    pub status: JobStatus,
 }
 
 #[derive(Debug, Serialize)]
@@ -0,0 +1,689 @@
+//! Arweave backend: files are stored as signed format 2 data transactions.
+//!
+//! Transactions are signed with an RSA wallet (a JWK keyfile as exported by
+//! arweave.app or `arweave-js`), the same way `arweave-js` does: RSA-PSS over the
+//! SHA-384 "deep hash" of the transaction fields. Small files are carried inline in
+//! the transaction; larger ones are posted to `/chunk` after it, one chunk at a time
+//! with its Merkle proof, since gateways reject large inline data.
+
+use super::StorageBackend;
+use crate::models::UploadResult;
+use anyhow::{anyhow, Context, Result};
+use async_trait::async_trait;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand_core::OsRng;
+use reqwest::Client;
+use rsa::traits::PublicKeyParts;
+use rsa::{BigUint, Pss, RsaPrivateKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256, Sha384};
+use std::ops::Range;
+
+/// Public gateway, also accepting transactions.
+pub const DEFAULT_ARWEAVE_URL: &str = "https://arweave.net";
+
+/// Data is split into chunks of this size for the data root.
+const MAX_CHUNK_SIZE: usize = 256 * 1024;
+/// A last chunk smaller than this is avoided by splitting the previous one in half.
+const MIN_CHUNK_SIZE: usize = 32 * 1024;
+/// Largest file sent inline in `POST /tx`; gateways reject inline data above about
+/// 12 MiB.
+const MAX_INLINE_SIZE: usize = 10 * 1024 * 1024;
+
+/// Posts data transactions to an Arweave node or gateway (or arlocal, for testing).
+pub struct ArweaveBackend {
+    client: Client,
+    url: String,
+    key: RsaPrivateKey,
+    /// Files larger than this are uploaded in chunks
+    inline_limit: usize,
+}
+
+impl ArweaveBackend {
+    pub fn new(client: Client, url: impl Into<String>, key: RsaPrivateKey) -> Self {
+        Self {
+            client,
+            url: url.into().trim_end_matches('/').to_string(),
+            key,
+            inline_limit: MAX_INLINE_SIZE,
+        }
+    }
+
+    /// Wallet address: base64url SHA-256 of the public key modulus.
+    pub fn address(&self) -> String {
+        b64(&Sha256::digest(self.key.n().to_bytes_be()))
+    }
+
+    async fn get_text(&self, path: &str) -> Result<String> {
+        let resp = self
+            .client
+            .get(format!("{}{}", self.url, path))
+            .send()
+            .await
+            .map_err(|e| anyhow!("arweave request failed: {}", e))?;
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(anyhow!(
+                "arweave GET {} failed: {} - {}",
+                path,
+                status,
+                text
+            ));
+        }
+        Ok(text.trim().to_string())
+    }
+
+    async fn post_json(&self, path: &str, body: &impl Serialize) -> Result<()> {
+        let resp = self
+            .client
+            .post(format!("{}{}", self.url, path))
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| anyhow!("arweave request failed: {}", e))?;
+        let status = resp.status();
+        if !status.is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            return Err(anyhow!(
+                "arweave POST {} rejected: {} - {}",
+                path,
+                status,
+                text
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StorageBackend for ArweaveBackend {
+    fn name(&self) -> &'static str {
+        "arweave"
+    }
+
+    async fn put(
+        &self,
+        _filename: &str,
+        content_type: &str,
+        bytes: Vec<u8>,
+    ) -> Result<UploadResult> {
+        let last_tx = self.get_text("/tx_anchor").await?;
+        let reward = self.get_text(&format!("/price/{}", bytes.len())).await?;
+
+        let mut tx = Transaction::new(
+            &self.key,
+            &last_tx,
+            reward,
+            vec![("Content-Type", content_type)],
+            &bytes,
+        )?;
+
+        if bytes.len() <= self.inline_limit {
+            self.post_json("/tx", &tx).await?;
+        } else {
+            // The data is not signed, only its root, so the header can go without it
+            tx.data = String::new();
+            self.post_json("/tx", &tx).await?;
+            let (_, proofs) = merkle_tree(&bytes).context("no chunks to upload")?;
+            let count = proofs.len();
+            for (i, proof) in proofs.into_iter().enumerate() {
+                let chunk = Chunk {
+                    data_root: tx.data_root.clone(),
+                    data_size: tx.data_size.clone(),
+                    data_path: b64(&proof.data_path),
+                    offset: (proof.range.end - 1).to_string(),
+                    chunk: b64(&bytes[proof.range]),
+                };
+                self.post_json("/chunk", &chunk)
+                    .await
+                    .with_context(|| format!("failed to upload chunk {} of {}", i + 1, count))?;
+            }
+        }
+
+        let url = format!("{}/{}", self.url, tx.id);
+        Ok(UploadResult { cid: tx.id, url })
+    }
+}
+
+/// Reads a wallet from its JWK keyfile contents.
+pub fn key_from_jwk(jwk: &str) -> Result<RsaPrivateKey> {
+    #[derive(Deserialize)]
+    struct Jwk {
+        n: String,
+        e: String,
+        d: String,
+        p: String,
+        q: String,
+    }
+
+    let jwk: Jwk = serde_json::from_str(jwk).context("invalid Arweave JWK")?;
+    let int = |field: &str, value: &str| -> Result<BigUint> {
+        let bytes = URL_SAFE_NO_PAD
+            .decode(value)
+            .with_context(|| format!("invalid base64url in JWK field {}", field))?;
+        Ok(BigUint::from_bytes_be(&bytes))
+    };
+    let key = RsaPrivateKey::from_components(
+        int("n", &jwk.n)?,
+        int("e", &jwk.e)?,
+        int("d", &jwk.d)?,
+        vec![int("p", &jwk.p)?, int("q", &jwk.q)?],
+    )
+    .context("invalid Arweave JWK")?;
+    Ok(key)
+}
+
+/// A format 2 transaction as posted to `/tx`; binary fields are base64url.
+#[derive(Debug, Serialize, Deserialize)]
+struct Transaction {
+    format: u8,
+    id: String,
+    last_tx: String,
+    owner: String,
+    tags: Vec<Tag>,
+    target: String,
+    quantity: String,
+    data: String,
+    data_size: String,
+    data_root: String,
+    reward: String,
+    signature: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Tag {
+    name: String,
+    value: String,
+}
+
+/// A chunk of a transaction's data as posted to `/chunk`; binary fields are base64url.
+#[derive(Debug, Serialize, Deserialize)]
+struct Chunk {
+    data_root: String,
+    data_size: String,
+    /// Merkle proof from the data root to the chunk
+    data_path: String,
+    /// Offset of the chunk's last byte in the data
+    offset: String,
+    chunk: String,
+}
+
+impl Transaction {
+    /// Builds and signs a data transaction carrying `data` inline.
+    fn new(
+        key: &RsaPrivateKey,
+        last_tx: &str,
+        reward: String,
+        tags: Vec<(&str, &str)>,
+        data: &[u8],
+    ) -> Result<Self> {
+        let mut tx = Transaction {
+            format: 2,
+            id: String::new(),
+            last_tx: last_tx.to_string(),
+            owner: b64(&key.n().to_bytes_be()),
+            tags: tags
+                .into_iter()
+                .map(|(name, value)| Tag {
+                    name: b64(name.as_bytes()),
+                    value: b64(value.as_bytes()),
+                })
+                .collect(),
+            target: String::new(),
+            quantity: "0".to_string(),
+            data: b64(data),
+            data_size: data.len().to_string(),
+            data_root: data_root(data).map(|root| b64(&root)).unwrap_or_default(),
+            reward,
+            signature: String::new(),
+        };
+
+        let hashed = Sha256::digest(tx.signature_data()?);
+        let signature = key
+            .sign_with_rng(&mut OsRng, Pss::new::<Sha256>(), &hashed)
+            .map_err(|e| anyhow!("failed to sign arweave transaction: {}", e))?;
+
+        // The transaction ID is the hash of its signature
+        tx.id = b64(&Sha256::digest(&signature));
+        tx.signature = b64(&signature);
+        Ok(tx)
+    }
+
+    /// The deep hash of the signed fields.
+    fn signature_data(&self) -> Result<[u8; 48]> {
+        let decode = |value: &str| {
+            URL_SAFE_NO_PAD
+                .decode(value)
+                .map_err(|e| anyhow!("invalid base64url in transaction: {}", e))
+        };
+        let tags = self
+            .tags
+            .iter()
+            .map(|tag| {
+                Ok(DeepHash::List(vec![
+                    DeepHash::Blob(decode(&tag.name)?),
+                    DeepHash::Blob(decode(&tag.value)?),
+                ]))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(DeepHash::List(vec![
+            DeepHash::Blob(self.format.to_string().into_bytes()),
+            DeepHash::Blob(decode(&self.owner)?),
+            DeepHash::Blob(decode(&self.target)?),
+            DeepHash::Blob(self.quantity.clone().into_bytes()),
+            DeepHash::Blob(self.reward.clone().into_bytes()),
+            DeepHash::Blob(decode(&self.last_tx)?),
+            DeepHash::List(tags),
+            DeepHash::Blob(self.data_size.clone().into_bytes()),
+            DeepHash::Blob(decode(&self.data_root)?),
+        ])
+        .hash())
+    }
+}
+
+/// Arweave's structured hash over nested lists of byte strings.
+enum DeepHash {
+    Blob(Vec<u8>),
+    List(Vec<DeepHash>),
+}
+
+impl DeepHash {
+    fn hash(&self) -> [u8; 48] {
+        match self {
+            DeepHash::Blob(bytes) => {
+                let tag = Sha384::digest(format!("blob{}", bytes.len()));
+                Sha384::new()
+                    .chain_update(tag)
+                    .chain_update(Sha384::digest(bytes))
+                    .finalize()
+                    .into()
+            }
+            DeepHash::List(items) => {
+                let tag: [u8; 48] = Sha384::digest(format!("list{}", items.len())).into();
+                items.iter().fold(tag, |acc, item| {
+                    Sha384::new()
+                        .chain_update(acc)
+                        .chain_update(item.hash())
+                        .finalize()
+                        .into()
+                })
+            }
+        }
+    }
+}
+
+/// Byte ranges of the chunks the data is split into.
+fn chunk_ranges(len: usize) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while len - start >= MAX_CHUNK_SIZE {
+        let rest = len - start;
+        let mut size = MAX_CHUNK_SIZE;
+        if rest > MAX_CHUNK_SIZE && rest - MAX_CHUNK_SIZE < MIN_CHUNK_SIZE {
+            size = rest.div_ceil(2);
+        }
+        ranges.push((start, start + size));
+        start += size;
+    }
+    ranges.push((start, len));
+    ranges
+}
+
+/// Root of the Merkle tree over the data chunks, or `None` for empty data.
+fn data_root(data: &[u8]) -> Option<[u8; 32]> {
+    merkle_tree(data).map(|(root, _)| root)
+}
+
+/// A data chunk and the Merkle proof of it.
+struct ChunkProof {
+    range: Range<usize>,
+    /// Proofs of the branches from the root down, then of the leaf
+    data_path: Vec<u8>,
+}
+
+/// Root of the Merkle tree over the data chunks and the proof of each chunk, or
+/// `None` for empty data.
+fn merkle_tree(data: &[u8]) -> Option<([u8; 32], Vec<ChunkProof>)> {
+    if data.is_empty() {
+        return None;
+    }
+
+    // Leaves commit to a chunk and its end offset; branches to their children and
+    // the offset where the left one ends. A node's proof is what it commits to.
+    let mut proofs = Vec::new();
+    let mut nodes: Vec<([u8; 32], usize, Range<usize>)> = chunk_ranges(data.len())
+        .into_iter()
+        .enumerate()
+        .map(|(i, (start, end))| {
+            let data_hash = Sha256::digest(&data[start..end]);
+            proofs.push(ChunkProof {
+                range: start..end,
+                data_path: [&data_hash[..], &note(end)].concat(),
+            });
+            (
+                hash_all(&[&Sha256::digest(data_hash), &Sha256::digest(note(end))]),
+                end,
+                i..i + 1,
+            )
+        })
+        .collect();
+
+    while nodes.len() > 1 {
+        nodes = nodes
+            .chunks(2)
+            .map(|pair| match pair {
+                [(left, left_end, left_leaves), (right, right_end, right_leaves)] => {
+                    let leaves = left_leaves.start..right_leaves.end;
+                    let proof = [&left[..], &right[..], &note(*left_end)].concat();
+                    for leaf in &mut proofs[leaves.clone()] {
+                        leaf.data_path.splice(0..0, proof.iter().copied());
+                    }
+                    (
+                        hash_all(&[
+                            &Sha256::digest(left),
+                            &Sha256::digest(right),
+                            &Sha256::digest(note(*left_end)),
+                        ]),
+                        *right_end,
+                        leaves,
+                    )
+                }
+                [node] => node.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+    }
+    Some((nodes[0].0, proofs))
+}
+
+/// An offset as a 32-byte big-endian integer.
+fn note(offset: usize) -> [u8; 32] {
+    let mut note = [0u8; 32];
+    note[24..].copy_from_slice(&(offset as u64).to_be_bytes());
+    note
+}
+
+fn hash_all(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+fn b64(bytes: &[u8]) -> String {
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::json_response;
+    use axum::{extract::Path, routing::get, routing::post, Json, Router};
+    use rsa::traits::PrivateKeyParts;
+    use rsa::RsaPublicKey;
+    use std::sync::{Arc, Mutex};
+
+    fn test_key() -> RsaPrivateKey {
+        RsaPrivateKey::new(&mut OsRng, 1024).unwrap()
+    }
+
+    fn verify(tx: &Transaction) -> bool {
+        let owner = URL_SAFE_NO_PAD.decode(&tx.owner).unwrap();
+        let key =
+            RsaPublicKey::new(BigUint::from_bytes_be(&owner), BigUint::from(65537u32)).unwrap();
+        let signature = URL_SAFE_NO_PAD.decode(&tx.signature).unwrap();
+        let hashed = Sha256::digest(tx.signature_data().unwrap());
+        key.verify(Pss::new::<Sha256>(), &hashed, &signature)
+            .is_ok()
+            && tx.id == b64(&Sha256::digest(&signature))
+    }
+
+    #[test]
+    fn test_chunk_ranges() {
+        assert_eq!(chunk_ranges(10), vec![(0, 10)]);
+        assert_eq!(
+            chunk_ranges(MAX_CHUNK_SIZE),
+            vec![(0, MAX_CHUNK_SIZE), (MAX_CHUNK_SIZE, MAX_CHUNK_SIZE)]
+        );
+        // A tiny last chunk is avoided by splitting the data in half
+        let len = MAX_CHUNK_SIZE + 10;
+        assert_eq!(chunk_ranges(len), vec![(0, len / 2), (len / 2, len)]);
+        let len = MAX_CHUNK_SIZE + MIN_CHUNK_SIZE;
+        assert_eq!(
+            chunk_ranges(len),
+            vec![(0, MAX_CHUNK_SIZE), (MAX_CHUNK_SIZE, len)]
+        );
+    }
+
+    /// Follows a chunk's proof from the data root as a node does, and returns the
+    /// hash of the chunk it proves, or `None` if the proof is invalid.
+    fn validate_path(root: &[u8], offset: usize, path: &[u8]) -> Option<Vec<u8>> {
+        let mut id = root.to_vec();
+        let mut path = path;
+        while path.len() > 64 {
+            let (left, right, boundary) = (&path[..32], &path[32..64], &path[64..96]);
+            let expected = hash_all(&[
+                &Sha256::digest(left),
+                &Sha256::digest(right),
+                &Sha256::digest(boundary),
+            ]);
+            if id != expected {
+                return None;
+            }
+            let boundary = u64::from_be_bytes(boundary[24..].try_into().unwrap()) as usize;
+            id = if offset < boundary { left } else { right }.to_vec();
+            path = &path[96..];
+        }
+        let (data_hash, end) = (&path[..32], &path[32..64]);
+        let expected = hash_all(&[&Sha256::digest(data_hash), &Sha256::digest(end)]);
+        (id == expected).then(|| data_hash.to_vec())
+    }
+
+    #[test]
+    fn test_chunk_proofs() {
+        let data: Vec<u8> = (0..3 * MAX_CHUNK_SIZE + 1000).map(|i| i as u8).collect();
+        let (root, proofs) = merkle_tree(&data).unwrap();
+        assert_eq!(Some(root), data_root(&data));
+        assert_eq!(proofs.len(), 4);
+        for proof in &proofs {
+            let data_hash = validate_path(&root, proof.range.end - 1, &proof.data_path);
+            assert_eq!(
+                data_hash.as_deref(),
+                Some(&Sha256::digest(&data[proof.range.clone()])[..])
+            );
+        }
+        // A proof is only valid for its own chunk
+        assert!(validate_path(&root, 0, &proofs[3].data_path).is_none());
+    }
+
+    #[test]
+    fn test_signed_transaction_verifies() {
+        let key = test_key();
+        let tx = Transaction::new(
+            &key,
+            "",
+            "1000".to_string(),
+            vec![("Content-Type", "application/json")],
+            b"{}",
+        )
+        .unwrap();
+        assert!(verify(&tx));
+        assert_eq!(tx.data_size, "2");
+        assert_eq!(tx.data_root.len(), 43);
+
+        // Changing any signed field breaks the signature
+        let mut tampered = Transaction::new(&key, "", "1000".to_string(), vec![], b"{}").unwrap();
+        tampered.reward = "1".to_string();
+        assert!(!verify(&tampered));
+    }
+
+    #[test]
+    fn test_key_from_jwk() {
+        let key = test_key();
+        let int = |n: &BigUint| b64(&n.to_bytes_be());
+        let jwk = serde_json::json!({
+            "kty": "RSA",
+            "n": int(key.n()),
+            "e": int(key.e()),
+            "d": int(key.d()),
+            "p": int(&key.primes()[0]),
+            "q": int(&key.primes()[1]),
+        });
+        let parsed = key_from_jwk(&jwk.to_string()).unwrap();
+        assert_eq!(parsed, key);
+        assert!(key_from_jwk("{}").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_put_posts_signed_transaction() {
+        let app = Router::new()
+            .route("/tx_anchor", get(|| async { "bm90LWEtcmVhbC1hbmNob3I" }))
+            .route(
+                "/price/:size",
+                get(|Path(size): Path<u64>| async move { (size * 10).to_string() }),
+            )
+            .route(
+                "/tx",
+                post(|Json(tx): Json<Transaction>| async move {
+                    assert!(verify(&tx));
+                    assert_eq!(tx.reward, "20");
+                    assert_eq!(tx.last_tx, "bm90LWEtcmVhbC1hbmNob3I");
+                    assert_eq!(URL_SAFE_NO_PAD.decode(&tx.data).unwrap(), b"{}");
+                    "OK"
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let url = format!("http://{}", addr);
+        let backend = ArweaveBackend::new(Client::new(), url.clone(), test_key());
+        let r = backend
+            .put("metadata.json", "application/json", b"{}".to_vec())
+            .await
+            .unwrap();
+
+        assert_eq!(r.cid.len(), 43);
+        assert_eq!(r.url, format!("{}/{}", url, r.cid));
+    }
+
+    #[tokio::test]
+    async fn test_put_uploads_large_files_in_chunks() {
+        let txs = Arc::new(Mutex::new(Vec::new()));
+        let chunks = Arc::new(Mutex::new(Vec::new()));
+        let (tx_log, chunk_log) = (txs.clone(), chunks.clone());
+        let app = Router::new()
+            .route("/tx_anchor", get(|| async { "bm90LWEtcmVhbC1hbmNob3I" }))
+            .route("/price/:size", get(|| async { "1000" }))
+            .route(
+                "/tx",
+                post(move |Json(tx): Json<Transaction>| async move {
+                    tx_log.lock().unwrap().push(tx);
+                    "OK"
+                }),
+            )
+            .route(
+                "/chunk",
+                post(move |Json(chunk): Json<Chunk>| async move {
+                    chunk_log.lock().unwrap().push(chunk);
+                    "OK"
+                }),
+            );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let mut backend =
+            ArweaveBackend::new(Client::new(), format!("http://{}", addr), test_key());
+        backend.inline_limit = MAX_CHUNK_SIZE;
+        let data: Vec<u8> = (0..2 * MAX_CHUNK_SIZE + 1000).map(|i| i as u8).collect();
+        let r = backend
+            .put("song.mp3", "audio/mpeg", data.clone())
+            .await
+            .unwrap();
+
+        let txs = txs.lock().unwrap();
+        assert_eq!(txs.len(), 1);
+        let tx = &txs[0];
+        assert!(verify(tx));
+        assert_eq!(tx.id, r.cid);
+        assert_eq!(tx.data, "");
+        assert_eq!(tx.data_size, data.len().to_string());
+
+        let root = URL_SAFE_NO_PAD.decode(&tx.data_root).unwrap();
+        let mut uploaded = Vec::new();
+        for chunk in chunks.lock().unwrap().iter() {
+            assert_eq!(chunk.data_root, tx.data_root);
+            assert_eq!(chunk.data_size, tx.data_size);
+            let bytes = URL_SAFE_NO_PAD.decode(&chunk.chunk).unwrap();
+            let path = URL_SAFE_NO_PAD.decode(&chunk.data_path).unwrap();
+            let offset: usize = chunk.offset.parse().unwrap();
+            assert_eq!(offset, uploaded.len() + bytes.len() - 1);
+            assert_eq!(
+                validate_path(&root, offset, &path).as_deref(),
+                Some(&Sha256::digest(&bytes)[..])
+            );
+            uploaded.extend(bytes);
+        }
+        assert_eq!(uploaded, data);
+    }
+
+    /// Posts a transaction to arlocal (`npx arlocal`), mines it and reads the data back.
+    #[tokio::test]
+    #[ignore = "requires arlocal on 127.0.0.1:1984"]
+    async fn test_put_on_arlocal() {
+        let url = std::env::var("ARWEAVE_URL").unwrap_or_else(|_| "http://127.0.0.1:1984".into());
+        let client = Client::new();
+        let backend = ArweaveBackend::new(
+            client.clone(),
+            url.clone(),
+            RsaPrivateKey::new(&mut OsRng, 2048).unwrap(),
+        );
+
+        // Fund the wallet so it can pay the reward
+        backend
+            .get_text(&format!("/mint/{}/1000000000000", backend.address()))
+            .await
+            .unwrap();
+
+        let r = backend
+            .put(
+                "metadata.json",
+                "application/json",
+                br#"{"name":"arweave test"}"#.to_vec(),
+            )
+            .await
+            .unwrap();
+        backend.get_text("/mine").await.unwrap();
+
+        let status = json_response(
+            client
+                .get(format!("{}/tx/{}/status", url, r.cid))
+                .send()
+                .await
+                .unwrap(),
+            "status",
+        )
+        .await
+        .unwrap();
+        assert!(
+            status["number_of_confirmations"].as_u64().is_some(),
+            "status: {}",
+            status
+        );
+
+        let content = client
+            .get(&r.url)
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(content, r#"{"name":"arweave test"}"#);
+    }
+}
@@ -0,0 +1,133 @@
+//! Kubo (go-ipfs) HTTP RPC API backend.
+
+use super::{json_response, IpfsGateway, StorageBackend};
+use crate::models::UploadResult;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::{multipart, Client};
+
+/// Adds and pins files on a Kubo node, e.g. a local daemon at `http://127.0.0.1:5001`.
+pub struct KuboBackend {
+    client: Client,
+    api_url: String,
+    gateway: IpfsGateway,
+}
+
+impl KuboBackend {
+    /// `api_url` is the node's RPC address; a full `.../api/v0/add` URL is accepted too.
+    pub fn new(client: Client, api_url: impl Into<String>, gateway: IpfsGateway) -> Self {
+        let api_url = api_url.into();
+        let api_url = api_url
+            .trim_end_matches('/')
+            .trim_end_matches("/api/v0/add")
+            .to_string();
+        Self {
+            client,
+            api_url,
+            gateway,
+        }
+    }
+}
+
+#[async_trait]
+impl StorageBackend for KuboBackend {
+    fn name(&self) -> &'static str {
+        "kubo"
+    }
+
+    async fn put(
+        &self,
+        filename: &str,
+        content_type: &str,
+        bytes: Vec<u8>,
+    ) -> Result<UploadResult> {
+        let part = multipart::Part::bytes(bytes)
+            .file_name(filename.to_string())
+            .mime_str(content_type)?;
+        let form = multipart::Form::new().part("file", part);
+
+        // `pin=true` pins the content on the node so it is not garbage collected;
+        // CIDv1 gives the `bafy...` form that gateways and marketplaces expect
+        let resp = self
+            .client
+            .post(format!("{}/api/v0/add", self.api_url))
+            .query(&[("pin", "true"), ("cid-version", "1")])
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| anyhow!("ipfs request failed: {}", e))?;
+        let json = json_response(resp, "ipfs add").await?;
+
+        let cid = json["Hash"]
+            .as_str()
+            .ok_or_else(|| anyhow!("ipfs add response has no Hash: {}", json))?
+            .to_string();
+        Ok(self.gateway.upload_result(cid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{extract::RawQuery, http::HeaderMap, routing::post, Json, Router};
+
+    #[tokio::test]
+    async fn test_add_is_multipart_and_pinned() {
+        let app = Router::new().route(
+            "/api/v0/add",
+            post(|RawQuery(query): RawQuery, headers: HeaderMap| async move {
+                let content_type = headers["content-type"].to_str().unwrap().to_string();
+                assert!(content_type.starts_with("multipart/form-data"));
+                assert_eq!(query.as_deref(), Some("pin=true&cid-version=1"));
+                Json(serde_json::json!({ "Name": "metadata.json", "Hash": "bafykubo", "Size": "10" }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let backend = KuboBackend::new(
+            Client::new(),
+            format!("http://{}/api/v0/add", addr),
+            IpfsGateway::new("http://gateway.test/ipfs"),
+        );
+        let r = backend
+            .put("metadata.json", "application/json", b"{}".to_vec())
+            .await
+            .unwrap();
+
+        assert_eq!(r.cid, "bafykubo");
+        assert_eq!(r.url, "http://gateway.test/ipfs/bafykubo");
+    }
+
+    /// Adds a file to a local Kubo daemon (`ipfs daemon`) and reads it back.
+    #[tokio::test]
+    #[ignore = "requires a local Kubo daemon on 127.0.0.1:5001"]
+    async fn test_add_on_local_daemon() {
+        let api = std::env::var("IPFS_URL").unwrap_or_else(|_| "http://127.0.0.1:5001".into());
+        let backend = KuboBackend::new(
+            Client::new(),
+            api.clone(),
+            IpfsGateway::new("http://127.0.0.1:8080/ipfs"),
+        );
+        let r = backend
+            .put(
+                "metadata.json",
+                "application/json",
+                br#"{"name":"kubo test"}"#.to_vec(),
+            )
+            .await
+            .unwrap();
+
+        let content = Client::new()
+            .post(format!("{}/api/v0/cat", backend.api_url))
+            .query(&[("arg", &r.cid)])
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(content, r#"{"name":"kubo test"}"#);
+    }
+}
@@ -0,0 +1,187 @@
+//! Local filesystem backend for development.
+//!
+//! Files are stored under their CID, so the same content always gets the same
+//! identifier, and are served by this service at `GET /ipfs/:cid`.
+
+use super::{IpfsGateway, StorageBackend};
+use crate::models::UploadResult;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Multicodec code of raw binary content.
+const RAW_CODEC: u8 = 0x55;
+/// Multihash code of SHA-256.
+const SHA2_256: u8 = 0x12;
+
+/// Stores files in a directory, named by their CID.
+pub struct LocalBackend {
+    dir: PathBuf,
+    gateway: IpfsGateway,
+}
+
+impl LocalBackend {
+    /// `base_url` is where this service serves the files, e.g. `http://localhost:8081/ipfs`.
+    pub fn new(dir: impl Into<PathBuf>, base_url: impl Into<String>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create storage directory {}", dir.display()))?;
+        Ok(Self {
+            dir,
+            gateway: IpfsGateway::new(base_url),
+        })
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalBackend {
+    fn name(&self) -> &'static str {
+        "local"
+    }
+
+    async fn put(
+        &self,
+        _filename: &str,
+        content_type: &str,
+        bytes: Vec<u8>,
+    ) -> Result<UploadResult> {
+        let cid = cid_v1_raw(&bytes);
+        let path = self.dir.join(&cid);
+        tokio::fs::write(&path, &bytes)
+            .await
+            .with_context(|| format!("failed to write {}", path.display()))?;
+        // The content type is kept next to the file, for serving it
+        tokio::fs::write(self.dir.join(format!("{}.type", cid)), content_type).await?;
+        Ok(self.gateway.upload_result(cid))
+    }
+
+    fn router(&self) -> Option<Router> {
+        Some(
+            Router::new()
+                .route("/ipfs/:cid", get(serve))
+                .with_state(Arc::new(self.dir.clone())),
+        )
+    }
+}
+
+/// Serves a stored file; content-addressed files never change, so they are cached forever.
+async fn serve(State(dir): State<Arc<PathBuf>>, Path(cid): Path<String>) -> Response {
+    // Only CIDs as created by `cid_v1_raw`, so the path can't leave the directory
+    let valid = cid.starts_with('b')
+        && cid
+            .bytes()
+            .all(|c| c.is_ascii_lowercase() || (b'2'..=b'7').contains(&c));
+    if !valid {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    let bytes = match tokio::fs::read(dir.join(&cid)).await {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return StatusCode::NOT_FOUND.into_response()
+        }
+        Err(e) => {
+            tracing::error!(cid = %cid, error = %e, "failed to read stored file");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let content_type = tokio::fs::read_to_string(dir.join(format!("{}.type", cid)))
+        .await
+        .unwrap_or_else(|_| "application/octet-stream".to_string());
+
+    (
+        [
+            (header::CONTENT_TYPE, content_type),
+            (
+                header::CACHE_CONTROL,
+                "public, max-age=31536000, immutable".to_string(),
+            ),
+        ],
+        bytes,
+    )
+        .into_response()
+}
+
+/// CIDv1 of raw content with a SHA-256 multihash, in base32 (`bafkrei...`).
+///
+/// This is the CID `ipfs add --cid-version=1 --raw-leaves` gives files that fit in a
+/// single block (256 KiB), so small files can be moved to a real IPFS node unchanged.
+pub fn cid_v1_raw(bytes: &[u8]) -> String {
+    let mut cid = vec![0x01, RAW_CODEC, SHA2_256, 32];
+    cid.extend_from_slice(&Sha256::digest(bytes));
+    format!("b{}", base32_lower(&cid))
+}
+
+/// RFC 4648 base32, lowercase and without padding, as used by multibase `b`.
+fn base32_lower(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+    let mut out = String::with_capacity(bytes.len().div_ceil(5) * 8);
+    let mut buffer = 0u16;
+    let mut bits = 0;
+    for &byte in bytes {
+        buffer = (buffer << 8) | byte as u16;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(ALPHABET[((buffer >> bits) & 31) as usize] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(ALPHABET[((buffer << (5 - bits)) & 31) as usize] as char);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cid_matches_ipfs() {
+        // `echo -n "hello world" | ipfs add --cid-version=1 --raw-leaves`
+        assert_eq!(
+            cid_v1_raw(b"hello world"),
+            "bafkreifzjut3te2nhyekklss27nh3k72ysco7y32koao5eei66wof36n5e"
+        );
+        assert_eq!(base32_lower(b"foobar"), "mzxw6ytboi");
+    }
+
+    #[tokio::test]
+    async fn test_put_and_serve() {
+        let dir = std::env::temp_dir().join(format!("web3-minting-{}", uuid::Uuid::new_v4()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}/ipfs", listener.local_addr().unwrap());
+        let backend = LocalBackend::new(&dir, base.clone()).unwrap();
+        let app = backend.router().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let r = backend
+            .put("metadata.json", "application/json", b"hello world".to_vec())
+            .await
+            .unwrap();
+        assert_eq!(r.cid, cid_v1_raw(b"hello world"));
+        assert_eq!(r.url, format!("{}/{}", base, r.cid));
+
+        let resp = reqwest::get(&r.url).await.unwrap();
+        assert_eq!(resp.headers()["content-type"], "application/json");
+        assert_eq!(resp.text().await.unwrap(), "hello world");
+
+        let missing = reqwest::get(format!("{}/{}", base, cid_v1_raw(b"other")))
+            .await
+            .unwrap();
+        assert_eq!(missing.status(), 404);
+        let invalid = reqwest::get(format!("{}/..%2Fsecret", base)).await.unwrap();
+        assert_eq!(invalid.status(), 404);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,277 @@
+//! Storage backends for NFT metadata.
+//!
+//! The backend is selected with `STORAGE_BACKEND`:
+//! - `kubo` - a Kubo (go-ipfs) node's HTTP RPC API at `IPFS_URL`
+//! - `pinata` - Pinata's pinning API, authenticated with `PINATA_JWT`
+//! - `web3storage` - a web3.storage-style upload API (`POST /upload` with a bearer token)
+//! - `arweave` - Arweave data transactions at `ARWEAVE_URL`, signed with the `ARWEAVE_WALLET` keyfile
+//! - `local` - files in `LOCAL_STORAGE_DIR`, named by real CIDs and served by this service
+//! - `mock` - fake CIDs, for local development
+//!
+//! When `STORAGE_BACKEND` is not set, `kubo` is used if `IPFS_URL` is set and `mock`
+//! otherwise. IPFS backends return URLs on `IPFS_GATEWAY_URL` (default `https://ipfs.io/ipfs`).
+
+mod arweave;
+mod kubo;
+mod local;
+mod pinata;
+mod web3storage;
+
+pub use arweave::ArweaveBackend;
+pub use kubo::KuboBackend;
+pub use local::LocalBackend;
+pub use pinata::PinataBackend;
+pub use web3storage::Web3StorageBackend;
+
+use crate::models::{Metadata, UploadResult};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use axum::Router;
+use reqwest::Client;
+use std::env;
+use std::sync::Arc;
+use uuid::Uuid;
+
+/// Largest asset accepted for upload.
+pub const MAX_ASSET_SIZE: usize = 25 * 1024 * 1024;
+/// Media types accepted as token assets.
+const ASSET_TYPES: &[&str] = &["image/", "audio/", "video/"];
+
+/// Default public gateway for IPFS content.
+const DEFAULT_IPFS_GATEWAY: &str = "https://ipfs.io/ipfs";
+/// Default directory of the local backend.
+const DEFAULT_LOCAL_STORAGE_DIR: &str = "local_storage";
+/// Default URL the local backend's files are served at.
+const DEFAULT_LOCAL_STORAGE_URL: &str = "http://localhost:8081/ipfs";
+
+/// A place to store files, such as NFT metadata.
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Backend name, for logs.
+    fn name(&self) -> &'static str;
+
+    /// Stores a file and returns its content identifier and a URL to retrieve it.
+    async fn put(&self, filename: &str, content_type: &str, bytes: Vec<u8>)
+        -> Result<UploadResult>;
+
+    /// Routes serving the stored files, for backends that serve them from this service.
+    fn router(&self) -> Option<Router> {
+        None
+    }
+}
+
+/// Builds the backend configured by `STORAGE_BACKEND` (see the module docs).
+pub fn from_env(client: &Client) -> Result<Arc<dyn StorageBackend>> {
+    let gateway = IpfsGateway::from_env();
+    let backend: Arc<dyn StorageBackend> = match env::var("STORAGE_BACKEND").ok().as_deref() {
+        Some("kubo") => {
+            let url = env::var("IPFS_URL")
+                .map_err(|_| anyhow!("IPFS_URL must be set for the kubo backend"))?;
+            Arc::new(KuboBackend::new(client.clone(), url, gateway))
+        }
+        Some("pinata") => {
+            let jwt = env::var("PINATA_JWT")
+                .map_err(|_| anyhow!("PINATA_JWT must be set for the pinata backend"))?;
+            let mut backend = PinataBackend::new(client.clone(), jwt, gateway);
+            if let Ok(url) = env::var("PINATA_API_URL") {
+                backend = backend.with_api_url(url);
+            }
+            Arc::new(backend)
+        }
+        Some("web3storage") => {
+            let token = env::var("WEB3_STORAGE_TOKEN").map_err(|_| {
+                anyhow!("WEB3_STORAGE_TOKEN must be set for the web3storage backend")
+            })?;
+            let mut backend = Web3StorageBackend::new(client.clone(), token, gateway);
+            if let Ok(url) = env::var("WEB3_STORAGE_URL") {
+                backend = backend.with_api_url(url);
+            }
+            Arc::new(backend)
+        }
+        Some("arweave") => {
+            let path = env::var("ARWEAVE_WALLET")
+                .map_err(|_| anyhow!("ARWEAVE_WALLET must be set for the arweave backend"))?;
+            let jwk = std::fs::read_to_string(&path)
+                .map_err(|e| anyhow!("failed to read ARWEAVE_WALLET {}: {}", path, e))?;
+            let url = env::var("ARWEAVE_URL")
+                .unwrap_or_else(|_| arweave::DEFAULT_ARWEAVE_URL.to_string());
+            let backend = ArweaveBackend::new(client.clone(), url, arweave::key_from_jwk(&jwk)?);
+            // The wallet pays for storage, so it needs AR
+            tracing::info!(address = %backend.address(), "arweave wallet loaded");
+            Arc::new(backend)
+        }
+        Some("local") => {
+            let dir = env::var("LOCAL_STORAGE_DIR")
+                .unwrap_or_else(|_| DEFAULT_LOCAL_STORAGE_DIR.to_string());
+            let url = env::var("LOCAL_STORAGE_URL")
+                .unwrap_or_else(|_| DEFAULT_LOCAL_STORAGE_URL.to_string());
+            Arc::new(LocalBackend::new(dir, url)?)
+        }
+        Some("mock") => Arc::new(MockBackend { gateway }),
+        Some(other) => return Err(anyhow!("unknown STORAGE_BACKEND: {}", other)),
+        None => match env::var("IPFS_URL") {
+            Ok(url) => Arc::new(KuboBackend::new(client.clone(), url, gateway)),
+            Err(_) => Arc::new(MockBackend { gateway }),
+        },
+    };
+    Ok(backend)
+}
+
+/// Upload metadata as JSON. Returns CID and a URL to retrieve it.
+pub async fn upload_metadata(
+    backend: &dyn StorageBackend,
+    metadata: &Metadata,
+) -> Result<UploadResult> {
+    let bytes = serde_json::to_vec(metadata)?;
+    let upload = backend
+        .put("metadata.json", "application/json", bytes)
+        .await?;
+    tracing::info!(backend = backend.name(), cid = %upload.cid, url = %upload.url, "metadata uploaded");
+    Ok(upload)
+}
+
+/// Checks that a file can be used as a token asset: an image, audio or video of at
+/// most `MAX_ASSET_SIZE` bytes.
+pub fn check_asset(content_type: &str, size: usize) -> Result<()> {
+    if !ASSET_TYPES
+        .iter()
+        .any(|prefix| content_type.starts_with(prefix))
+    {
+        return Err(anyhow!(
+            "unsupported asset type {:?}; expected an image, audio or video file",
+            content_type
+        ));
+    }
+    if size == 0 {
+        return Err(anyhow!("asset is empty"));
+    }
+    if size > MAX_ASSET_SIZE {
+        return Err(anyhow!(
+            "asset is {} bytes; the limit is {} bytes",
+            size,
+            MAX_ASSET_SIZE
+        ));
+    }
+    Ok(())
+}
+
+/// Upload a token asset checked with `check_asset`. Returns CID and a URL to retrieve it.
+pub async fn upload_asset(
+    backend: &dyn StorageBackend,
+    filename: &str,
+    content_type: &str,
+    bytes: Vec<u8>,
+) -> Result<UploadResult> {
+    let size = bytes.len();
+    let upload = backend.put(filename, content_type, bytes).await?;
+    tracing::info!(backend = backend.name(), cid = %upload.cid, size, content_type, "asset uploaded");
+    Ok(upload)
+}
+
+/// Base URL of the HTTP gateway that IPFS content is linked through.
+#[derive(Debug, Clone)]
+pub struct IpfsGateway {
+    base: String,
+}
+
+impl IpfsGateway {
+    pub fn new(base: impl Into<String>) -> Self {
+        Self {
+            base: base.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Reads `IPFS_GATEWAY_URL`, defaulting to the public ipfs.io gateway.
+    pub fn from_env() -> Self {
+        Self::new(env::var("IPFS_GATEWAY_URL").unwrap_or_else(|_| DEFAULT_IPFS_GATEWAY.to_string()))
+    }
+
+    /// Upload result for a CID on this gateway.
+    pub fn upload_result(&self, cid: String) -> UploadResult {
+        let url = format!("{}/{}", self.base, cid);
+        UploadResult { cid, url }
+    }
+}
+
+/// Returns fake CIDs without storing anything.
+struct MockBackend {
+    gateway: IpfsGateway,
+}
+
+#[async_trait]
+impl StorageBackend for MockBackend {
+    fn name(&self) -> &'static str {
+        "mock"
+    }
+
+    async fn put(
+        &self,
+        _filename: &str,
+        _content_type: &str,
+        _bytes: Vec<u8>,
+    ) -> Result<UploadResult> {
+        // Deterministic-ish CID and gateway URL for local dev and testing.
+        let cid = format!("bafy{}", Uuid::new_v4().simple());
+        tracing::warn!(cid = %cid, "no storage backend configured - returning mock upload result");
+        Ok(self.gateway.upload_result(cid))
+    }
+}
+
+/// Reads a JSON response, turning non-success statuses into errors.
+async fn json_response(resp: reqwest::Response, what: &str) -> Result<serde_json::Value> {
+    let status = resp.status();
+    if !status.is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        return Err(anyhow!("{} failed: {} - {}", what, status, text));
+    }
+    resp.json()
+        .await
+        .map_err(|e| anyhow!("failed to parse response: {}", e))
+}
+
+/// Mock backend for tests in this module and in the worker.
+#[cfg(test)]
+pub fn mock() -> Arc<dyn StorageBackend> {
+    Arc::new(MockBackend {
+        gateway: IpfsGateway::new(DEFAULT_IPFS_GATEWAY),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_upload_metadata_mock() {
+        let m = Metadata {
+            name: "Test".to_string(),
+            description: Some("desc".to_string()),
+            image: Some("https://example.com/a.png".to_string()),
+            animation_url: None,
+            external_url: None,
+            attributes: Vec::new(),
+            background_color: None,
+        };
+        let r = upload_metadata(mock().as_ref(), &m)
+            .await
+            .expect("upload should succeed");
+        assert!(r.cid.starts_with("bafy") || !r.cid.is_empty());
+        assert!(r.url.contains(&r.cid));
+    }
+
+    #[test]
+    fn test_check_asset() {
+        assert!(check_asset("audio/mpeg", 1024).is_ok());
+        assert!(check_asset("image/png", MAX_ASSET_SIZE).is_ok());
+        assert!(check_asset("application/pdf", 1024).is_err());
+        assert!(check_asset("audio/mpeg", 0).is_err());
+        assert!(check_asset("video/mp4", MAX_ASSET_SIZE + 1).is_err());
+    }
+
+    #[test]
+    fn test_gateway_urls() {
+        let gateway = IpfsGateway::new("http://127.0.0.1:8080/ipfs/");
+        let r = gateway.upload_result("bafyabc".to_string());
+        assert_eq!(r.url, "http://127.0.0.1:8080/ipfs/bafyabc");
+    }
+}
@@ -0,0 +1,109 @@
+//! Pinata pinning service backend.
+
+use super::{json_response, IpfsGateway, StorageBackend};
+use crate::models::UploadResult;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::{multipart, Client};
+
+const DEFAULT_API_URL: &str = "https://api.pinata.cloud";
+
+/// Pins files with Pinata's `pinFileToIPFS`, authenticated with a JWT.
+pub struct PinataBackend {
+    client: Client,
+    api_url: String,
+    jwt: String,
+    gateway: IpfsGateway,
+}
+
+impl PinataBackend {
+    pub fn new(client: Client, jwt: impl Into<String>, gateway: IpfsGateway) -> Self {
+        Self {
+            client,
+            api_url: DEFAULT_API_URL.to_string(),
+            jwt: jwt.into(),
+            gateway,
+        }
+    }
+
+    /// Overrides the API base URL (`PINATA_API_URL`).
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into().trim_end_matches('/').to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl StorageBackend for PinataBackend {
+    fn name(&self) -> &'static str {
+        "pinata"
+    }
+
+    async fn put(
+        &self,
+        filename: &str,
+        content_type: &str,
+        bytes: Vec<u8>,
+    ) -> Result<UploadResult> {
+        let part = multipart::Part::bytes(bytes)
+            .file_name(filename.to_string())
+            .mime_str(content_type)?;
+        let form = multipart::Form::new()
+            .part("file", part)
+            .text("pinataOptions", r#"{"cidVersion":1}"#);
+
+        let resp = self
+            .client
+            .post(format!("{}/pinning/pinFileToIPFS", self.api_url))
+            .bearer_auth(&self.jwt)
+            .multipart(form)
+            .send()
+            .await
+            .map_err(|e| anyhow!("pinata request failed: {}", e))?;
+        let json = json_response(resp, "pinata upload").await?;
+
+        let cid = json["IpfsHash"]
+            .as_str()
+            .ok_or_else(|| anyhow!("pinata response has no IpfsHash: {}", json))?
+            .to_string();
+        Ok(self.gateway.upload_result(cid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::HeaderMap, routing::post, Json, Router};
+
+    #[tokio::test]
+    async fn test_pin_file_with_jwt() {
+        let app = Router::new().route(
+            "/pinning/pinFileToIPFS",
+            post(|headers: HeaderMap| async move {
+                assert_eq!(headers["authorization"], "Bearer test-jwt");
+                Json(serde_json::json!({
+                    "IpfsHash": "bafypinata",
+                    "PinSize": 10,
+                    "Timestamp": "2024-01-01T00:00:00Z"
+                }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let backend = PinataBackend::new(
+            Client::new(),
+            "test-jwt",
+            IpfsGateway::new("https://gateway.pinata.cloud/ipfs"),
+        )
+        .with_api_url(format!("http://{}", addr));
+        let r = backend
+            .put("metadata.json", "application/json", b"{}".to_vec())
+            .await
+            .unwrap();
+
+        assert_eq!(r.cid, "bafypinata");
+        assert_eq!(r.url, "https://gateway.pinata.cloud/ipfs/bafypinata");
+    }
+}
@@ -0,0 +1,102 @@
+//! web3.storage-style upload API backend.
+
+use super::{json_response, IpfsGateway, StorageBackend};
+use crate::models::UploadResult;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use reqwest::Client;
+
+const DEFAULT_API_URL: &str = "https://api.web3.storage";
+
+/// Uploads files as the raw request body to `POST {api}/upload` with a bearer token,
+/// and reads the `cid` from the response. Several pinning services expose this API.
+pub struct Web3StorageBackend {
+    client: Client,
+    api_url: String,
+    token: String,
+    gateway: IpfsGateway,
+}
+
+impl Web3StorageBackend {
+    pub fn new(client: Client, token: impl Into<String>, gateway: IpfsGateway) -> Self {
+        Self {
+            client,
+            api_url: DEFAULT_API_URL.to_string(),
+            token: token.into(),
+            gateway,
+        }
+    }
+
+    /// Overrides the API base URL (`WEB3_STORAGE_URL`).
+    pub fn with_api_url(mut self, api_url: impl Into<String>) -> Self {
+        self.api_url = api_url.into().trim_end_matches('/').to_string();
+        self
+    }
+}
+
+#[async_trait]
+impl StorageBackend for Web3StorageBackend {
+    fn name(&self) -> &'static str {
+        "web3storage"
+    }
+
+    async fn put(
+        &self,
+        filename: &str,
+        content_type: &str,
+        bytes: Vec<u8>,
+    ) -> Result<UploadResult> {
+        let resp = self
+            .client
+            .post(format!("{}/upload", self.api_url))
+            .bearer_auth(&self.token)
+            .header("Content-Type", content_type)
+            .header("X-Name", filename)
+            .body(bytes)
+            .send()
+            .await
+            .map_err(|e| anyhow!("web3.storage request failed: {}", e))?;
+        let json = json_response(resp, "web3.storage upload").await?;
+
+        let cid = json["cid"]
+            .as_str()
+            .ok_or_else(|| anyhow!("web3.storage response has no cid: {}", json))?
+            .to_string();
+        Ok(self.gateway.upload_result(cid))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Bytes, http::HeaderMap, routing::post, Json, Router};
+
+    #[tokio::test]
+    async fn test_upload_raw_body() {
+        let app = Router::new().route(
+            "/upload",
+            post(|headers: HeaderMap, body: Bytes| async move {
+                assert_eq!(headers["authorization"], "Bearer test-token");
+                assert_eq!(headers["x-name"], "metadata.json");
+                assert_eq!(&body[..], b"{}");
+                Json(serde_json::json!({ "cid": "bafyw3s" }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let backend = Web3StorageBackend::new(
+            Client::new(),
+            "test-token",
+            IpfsGateway::new("https://w3s.link/ipfs"),
+        )
+        .with_api_url(format!("http://{}", addr));
+        let r = backend
+            .put("metadata.json", "application/json", b"{}".to_vec())
+            .await
+            .unwrap();
+
+        assert_eq!(r.url, "https://w3s.link/ipfs/bafyw3s");
+    }
+}
@@ -0,0 +1,600 @@
+//! Background worker that runs mint jobs.
+//!
+//! Jobs are uploaded, signed and sent one at a time, in the order they were queued.
+//! Besides keeping the load on storage and RPC providers predictable, this means
+//! mint transactions from the wallet never race for the same nonce. Confirmations
+//! are awaited in a task per job, outside the queue, so a slow block does not hold
+//! up the jobs behind it.
+
+use crate::blockchain::Confirmation;
+use crate::chains::ChainRegistry;
+use crate::jobs::JobStore;
+use crate::models::{JobStatus, Metadata, MintJob};
+use crate::storage::StorageBackend;
+use anyhow::Result;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// How long a submitted job whose mint could not be confirmed waits before it is
+/// queued again.
+const RECHECK_DELAY: Duration = Duration::from_secs(60);
+
+/// Starts the worker and returns the queue to send new job IDs to.
+///
+/// Jobs left unfinished by a previous run are queued first. A job's transaction is
+/// saved as soon as it is signed, before it is sent, so a job that was already
+/// `submitted` sends that same transaction again and waits for it; a second one is
+/// never signed, so a token is never minted twice.
+pub fn spawn(
+    store: Arc<JobStore>,
+    storage: Arc<dyn StorageBackend>,
+    chains: Arc<ChainRegistry>,
+) -> Result<mpsc::UnboundedSender<String>> {
+    start(store, storage, chains, RECHECK_DELAY)
+}
+
+fn start(
+    store: Arc<JobStore>,
+    storage: Arc<dyn StorageBackend>,
+    chains: Arc<ChainRegistry>,
+    recheck_delay: Duration,
+) -> Result<mpsc::UnboundedSender<String>> {
+    let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+
+    let resumed = store.unfinished()?;
+    if !resumed.is_empty() {
+        tracing::info!(count = resumed.len(), "resuming unfinished mint jobs");
+    }
+    for job in resumed {
+        let _ = tx.send(job.id);
+    }
+
+    let queue = tx.clone();
+    tokio::spawn(async move {
+        while let Some(id) = rx.recv().await {
+            match store.get(&id) {
+                Ok(Some(job)) => {
+                    if let Some(job) = run(&store, storage.as_ref(), &chains, job).await {
+                        tokio::spawn(confirm(
+                            store.clone(),
+                            chains.clone(),
+                            queue.clone(),
+                            recheck_delay,
+                            job,
+                        ));
+                    }
+                }
+                Ok(None) => tracing::warn!(job_id = %id, "queued mint job not found"),
+                Err(e) => tracing::error!(job_id = %id, error = %e, "failed to load mint job"),
+            }
+        }
+    });
+
+    Ok(tx)
+}
+
+/// Runs a job until its transaction is sent, recording each step and any failure.
+///
+/// # Returns
+///
+/// The job if it is `submitted` and its confirmation should be awaited
+async fn run(
+    store: &JobStore,
+    storage: &dyn StorageBackend,
+    chains: &ChainRegistry,
+    mut job: MintJob,
+) -> Option<MintJob> {
+    tracing::info!(job_id = %job.id, status = job.status.as_str(), "running mint job");
+
+    match advance(store, storage, chains, &mut job).await {
+        Ok(()) => Some(job),
+        // The transaction was signed and may have reached a node, so it may still be
+        // mined: the job stays submitted
+        Err(e) if job.status == JobStatus::Submitted => {
+            tracing::warn!(job_id = %job.id, error = %e, "failed to send mint transaction");
+            job.error = Some(e.to_string());
+            save(store, &mut job);
+            Some(job)
+        }
+        Err(e) => {
+            tracing::error!(job_id = %job.id, error = %e, "mint job failed");
+            job.status = JobStatus::Failed;
+            job.error = Some(e.to_string());
+            save(store, &mut job);
+            None
+        }
+    }
+}
+
+/// Waits for a submitted job's transaction and records the outcome.
+///
+/// Only a reverted transaction fails the job. If the transaction is not confirmed
+/// in time or the node cannot be reached, it may still be mined, so the job stays
+/// `submitted` with the problem in its `error` and is queued again after
+/// `recheck_delay`, which sends the saved transaction again and waits anew.
+async fn confirm(
+    store: Arc<JobStore>,
+    chains: Arc<ChainRegistry>,
+    queue: mpsc::UnboundedSender<String>,
+    recheck_delay: Duration,
+    mut job: MintJob,
+) {
+    let Some(tx_hash) = job.mint.as_ref().map(|mint| mint.tx_hash.clone()) else {
+        return;
+    };
+
+    let problem = match crate::blockchain::confirm_mint(
+        &chains,
+        job.request.chain.as_deref(),
+        &tx_hash,
+    )
+    .await
+    {
+        Ok(Confirmation::Confirmed(mint)) => {
+            job.mint = Some(mint);
+            job.status = JobStatus::Confirmed;
+            job.error = None;
+            save(&store, &mut job);
+            tracing::info!(job = ?job, "mint job confirmed");
+            return;
+        }
+        Ok(Confirmation::Reverted(reason)) => {
+            tracing::error!(job_id = %job.id, error = %reason, "mint job failed");
+            job.status = JobStatus::Failed;
+            job.error = Some(format!("mint error: {}", reason));
+            save(&store, &mut job);
+            return;
+        }
+        Ok(Confirmation::NotYet(reason)) => reason,
+        Err(e) => format!("mint error: {}", e),
+    };
+
+    tracing::warn!(
+        job_id = %job.id,
+        error = %problem,
+        retry_in_secs = recheck_delay.as_secs(),
+        "mint not confirmed yet"
+    );
+    job.error = Some(problem);
+    save(&store, &mut job);
+    tokio::time::sleep(recheck_delay).await;
+    let _ = queue.send(job.id);
+}
+
+fn save(store: &JobStore, job: &mut MintJob) {
+    if let Err(e) = store.save(job) {
+        tracing::error!(job_id = %job.id, error = %e, "failed to save mint job");
+    }
+}
+
+async fn advance(
+    store: &JobStore,
+    storage: &dyn StorageBackend,
+    chains: &ChainRegistry,
+    job: &mut MintJob,
+) -> Result<()> {
+    // Upload metadata
+    let upload = match job.upload.clone() {
+        Some(upload) => upload,
+        None => {
+            job.status = JobStatus::Uploading;
+            store.save(job)?;
+
+            let metadata = Metadata::from(&job.request);
+            crate::metadata::validate(&metadata).map_err(|e| e.context("invalid metadata"))?;
+            let upload = crate::storage::upload_metadata(storage, &metadata)
+                .await
+                .map_err(|e| e.context("upload error"))?;
+            job.upload = Some(upload.clone());
+            upload
+        }
+    };
+
+    // Sign the mint transaction, unless a previous run already did, and save it
+    // before sending it
+    let tx_hash = match &job.mint {
+        Some(mint) => mint.tx_hash.clone(),
+        None => {
+            // Determine recipient (the minting wallet when none is given)
+            let recipient = job.request.recipient.as_deref().filter(|r| !r.is_empty());
+            let signed = crate::blockchain::sign_mint(
+                chains,
+                job.request.chain.as_deref(),
+                &upload.url,
+                recipient,
+            )
+            .await
+            .map_err(|e| e.context("mint error"))?;
+            let tx_hash = signed.mint.tx_hash.clone();
+            job.mint = Some(signed.mint);
+            job.raw_tx = signed.raw_tx;
+            job.status = JobStatus::Submitted;
+            store.save(job)?;
+            tx_hash
+        }
+    };
+
+    // Send it; after a restart this sends the saved transaction again, in case the
+    // previous run stopped before it reached the node
+    if let Some(raw_tx) = &job.raw_tx {
+        crate::blockchain::broadcast_mint(chains, job.request.chain.as_deref(), raw_tx, &tx_hash)
+            .await
+            .map_err(|e| e.context("mint error"))?;
+    }
+    job.status = JobStatus::Submitted;
+    store.save(job)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::evm::keccak256;
+    use crate::models::{MintRequest, MintResult, UploadResult};
+    use serde_json::{json, Value};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn request() -> MintRequest {
+        MintRequest {
+            name: "Test".to_string(),
+            description: Some("desc".to_string()),
+            image: None,
+            animation_url: None,
+            external_url: None,
+            attributes: Vec::new(),
+            background_color: None,
+            recipient: None,
+            chain: None,
+            asset: None,
+        }
+    }
+
+    async fn wait_until_final(store: &JobStore, id: &str) -> MintJob {
+        for _ in 0..100 {
+            let job = store.get(id).unwrap().unwrap();
+            if job.status.is_final() {
+                return job;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("job {} did not finish", id);
+    }
+
+    /// How the fake node answers.
+    #[derive(Clone, Copy, Default)]
+    struct Node {
+        /// Sending a transaction fails as it does on a node that already has it
+        already_known: bool,
+        /// Receipt requests answered with `null` before the transaction is mined
+        unmined_polls: usize,
+        /// The transaction reverts
+        reverts: bool,
+    }
+
+    /// Starts a node answering the calls of a mint, and returns its URL.
+    ///
+    /// Each call's method is recorded; raw transactions are recorded with whether
+    /// a job had saved them before they were sent.
+    async fn fake_node(store: Arc<JobStore>, calls: Arc<Mutex<Vec<String>>>, node: Node) -> String {
+        use axum::{routing::post, Json, Router};
+
+        let app = Router::new().route(
+            "/",
+            post(move |Json(body): Json<Value>| async move {
+                let method = body["method"].as_str().unwrap().to_string();
+                let result = match method.as_str() {
+                    "eth_getTransactionCount" => json!("0x0"),
+                    "eth_getBlockByNumber" => json!({ "baseFeePerGas": "0x3b9aca00" }),
+                    "eth_maxPriorityFeePerGas" => json!("0x3b9aca00"),
+                    "eth_estimateGas" => json!("0x30000"),
+                    "eth_sendRawTransaction" => {
+                        let raw = body["params"][0].as_str().unwrap();
+                        let raw = hex::decode(raw.trim_start_matches("0x")).unwrap();
+                        let saved = store
+                            .unfinished()
+                            .unwrap()
+                            .iter()
+                            .any(|job| job.raw_tx.as_ref() == Some(&raw));
+                        calls
+                            .lock()
+                            .unwrap()
+                            .push(format!("eth_sendRawTransaction saved={}", saved));
+                        if node.already_known {
+                            return Json(json!({
+                                "jsonrpc": "2.0", "id": 1,
+                                "error": { "code": -32000, "message": "already known" }
+                            }));
+                        }
+                        json!(format!("0x{}", hex::encode(keccak256(&raw))))
+                    }
+                    "eth_getTransactionByHash" => json!({ "hash": body["params"][0] }),
+                    "eth_getTransactionReceipt" => {
+                        let polls = calls
+                            .lock()
+                            .unwrap()
+                            .iter()
+                            .filter(|c| *c == "eth_getTransactionReceipt")
+                            .count();
+                        if polls < node.unmined_polls {
+                            calls.lock().unwrap().push(method);
+                            return Json(json!({ "jsonrpc": "2.0", "id": 1, "result": null }));
+                        }
+                        receipt(if node.reverts { "0x0" } else { "0x1" })
+                    }
+                    "eth_blockNumber" => json!("0x1"),
+                    other => panic!("unexpected call {}", other),
+                };
+                if method != "eth_sendRawTransaction" {
+                    calls.lock().unwrap().push(method);
+                }
+                Json(json!({ "jsonrpc": "2.0", "id": 1, "result": result }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}", addr)
+    }
+
+    /// A receipt of the mint of token 7 with the given status.
+    fn receipt(status: &str) -> Value {
+        json!({
+            "status": status,
+            "blockNumber": "0x1",
+            "logs": [{
+                "address": "0x5fbdb2315678afecb367f032d93f642f64180aa3",
+                "topics": [
+                    "0xddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef",
+                    "0x0000000000000000000000000000000000000000000000000000000000000000",
+                    "0x000000000000000000000000f39fd6e51aad88f6f4ce6ab8827279cfffb92266",
+                    "0x0000000000000000000000000000000000000000000000000000000000000007"
+                ]
+            }]
+        })
+    }
+
+    #[tokio::test]
+    async fn test_transaction_is_saved_before_it_is_sent() {
+        let store = Arc::new(JobStore::open_in_memory().unwrap());
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let url = fake_node(store.clone(), calls.clone(), Node::default()).await;
+        let queue = spawn(
+            store.clone(),
+            crate::storage::mock(),
+            Arc::new(ChainRegistry::for_tests(&url)),
+        )
+        .unwrap();
+
+        let job = store.create(request()).unwrap();
+        queue.send(job.id.clone()).unwrap();
+        let job = wait_until_final(&store, &job.id).await;
+
+        assert_eq!(
+            job.status,
+            JobStatus::Confirmed,
+            "job error: {:?}",
+            job.error
+        );
+        let raw_tx = job.raw_tx.unwrap();
+        let mint = job.mint.unwrap();
+        assert_eq!(
+            mint.tx_hash,
+            format!("0x{}", hex::encode(keccak256(&raw_tx)))
+        );
+        assert_eq!(mint.token_id.as_deref(), Some("7"));
+        let calls = calls.lock().unwrap();
+        let sent: Vec<_> = calls
+            .iter()
+            .filter(|c| c.starts_with("eth_sendRawTransaction"))
+            .collect();
+        assert_eq!(sent, ["eth_sendRawTransaction saved=true"]);
+    }
+
+    #[tokio::test]
+    async fn test_unconfirmed_mint_stays_submitted_and_is_checked_again() {
+        let store = Arc::new(JobStore::open_in_memory().unwrap());
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let node = Node {
+            unmined_polls: 1,
+            ..Node::default()
+        };
+        let url = fake_node(store.clone(), calls.clone(), node).await;
+        let queue = start(
+            store.clone(),
+            crate::storage::mock(),
+            Arc::new(ChainRegistry::for_tests(&url)),
+            Duration::from_millis(300),
+        )
+        .unwrap();
+
+        let job = store.create(request()).unwrap();
+        queue.send(job.id.clone()).unwrap();
+
+        // Not mined within the receipt timeout: still submitted, with the reason
+        let mut waiting = None;
+        for _ in 0..50 {
+            let job = store.get(&job.id).unwrap().unwrap();
+            if job.error.is_some() {
+                waiting = Some(job);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let waiting = waiting.expect("job was not checked");
+        assert_eq!(waiting.status, JobStatus::Submitted);
+        assert!(waiting.error.unwrap().contains("not confirmed"));
+
+        // Queued again, the same transaction is sent again and then mined
+        let job = wait_until_final(&store, &job.id).await;
+        assert_eq!(job.status, JobStatus::Confirmed);
+        assert_eq!(job.error, None);
+        assert_eq!(job.mint.unwrap().token_id.as_deref(), Some("7"));
+        let calls = calls.lock().unwrap();
+        let sent: Vec<_> = calls
+            .iter()
+            .filter(|c| c.starts_with("eth_sendRawTransaction"))
+            .collect();
+        assert_eq!(sent.len(), 2);
+        assert_eq!(
+            calls
+                .iter()
+                .filter(|c| *c == "eth_getTransactionCount")
+                .count(),
+            1
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reverted_mint_fails_the_job() {
+        let store = Arc::new(JobStore::open_in_memory().unwrap());
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let node = Node {
+            reverts: true,
+            ..Node::default()
+        };
+        let url = fake_node(store.clone(), calls.clone(), node).await;
+        let queue = spawn(
+            store.clone(),
+            crate::storage::mock(),
+            Arc::new(ChainRegistry::for_tests(&url)),
+        )
+        .unwrap();
+
+        let job = store.create(request()).unwrap();
+        queue.send(job.id.clone()).unwrap();
+        let job = wait_until_final(&store, &job.id).await;
+
+        assert_eq!(job.status, JobStatus::Failed);
+        assert!(job.error.unwrap().contains("reverted in block 1"));
+    }
+
+    #[tokio::test]
+    async fn test_resumed_job_resends_the_saved_transaction() {
+        let store = Arc::new(JobStore::open_in_memory().unwrap());
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let url = fake_node(
+            store.clone(),
+            calls.clone(),
+            Node {
+                already_known: true,
+                ..Node::default()
+            },
+        )
+        .await;
+
+        // A job whose transaction was signed and saved when the worker stopped
+        let raw_tx = vec![0x02, 0xf8, 0x01];
+        let mut job = store.create(request()).unwrap();
+        job.status = JobStatus::Submitted;
+        job.upload = Some(UploadResult {
+            cid: "bafytest".to_string(),
+            url: "ipfs://bafytest".to_string(),
+        });
+        job.mint = Some(MintResult {
+            tx_hash: format!("0x{}", hex::encode(keccak256(&raw_tx))),
+            token_id: None,
+            block_number: None,
+            chain_id: Some(31337),
+            explorer_url: None,
+        });
+        job.raw_tx = Some(raw_tx);
+        store.save(&mut job).unwrap();
+
+        spawn(
+            store.clone(),
+            crate::storage::mock(),
+            Arc::new(ChainRegistry::for_tests(&url)),
+        )
+        .unwrap();
+        let job = wait_until_final(&store, &job.id).await;
+
+        assert_eq!(
+            job.status,
+            JobStatus::Confirmed,
+            "job error: {:?}",
+            job.error
+        );
+        assert_eq!(job.mint.unwrap().token_id.as_deref(), Some("7"));
+        // The node already had it, and no new transaction was signed
+        let calls = calls.lock().unwrap();
+        assert!(calls.contains(&"eth_sendRawTransaction saved=true".to_string()));
+        assert!(calls.contains(&"eth_getTransactionByHash".to_string()));
+        assert!(!calls.contains(&"eth_getTransactionCount".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_ens_recipient_is_resolved_on_the_ens_chain() {
+        use axum::{routing::post, Json, Router};
+
+        let store = Arc::new(JobStore::open_in_memory().unwrap());
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        // The minting chain's node panics on eth_call, so ENS must be looked up elsewhere
+        let url = fake_node(store.clone(), calls.clone(), Node::default()).await;
+
+        // Registry and resolver both answer with the recipient's address
+        let lookups = Arc::new(Mutex::new(0));
+        let ens_calls = lookups.clone();
+        let app = Router::new().route(
+            "/",
+            post(move |Json(body): Json<Value>| async move {
+                assert_eq!(body["method"], "eth_call");
+                *ens_calls.lock().unwrap() += 1;
+                let owner = "70997970c51812dc3a010c7d01b50e0d17dc79c8";
+                Json(json!({ "jsonrpc": "2.0", "id": 1, "result": format!("0x{:0>64}", owner) }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ens_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let queue = spawn(
+            store.clone(),
+            crate::storage::mock(),
+            Arc::new(ChainRegistry::for_tests(&url).with_ens_rpc(&ens_url)),
+        )
+        .unwrap();
+        let mut request = request();
+        request.recipient = Some("valet.eth".to_string());
+        let job = store.create(request).unwrap();
+        queue.send(job.id.clone()).unwrap();
+        let job = wait_until_final(&store, &job.id).await;
+
+        assert_eq!(
+            job.status,
+            JobStatus::Confirmed,
+            "job error: {:?}",
+            job.error
+        );
+        assert_eq!(*lookups.lock().unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_job_runs_to_confirmation_with_mocks() {
+        let store = Arc::new(JobStore::open_in_memory().unwrap());
+        let queue = spawn(
+            store.clone(),
+            crate::storage::mock(),
+            Arc::new(ChainRegistry::default()),
+        )
+        .unwrap();
+
+        let job = store.create(request()).unwrap();
+        queue.send(job.id.clone()).unwrap();
+
+        let mut status = JobStatus::Pending;
+        for _ in 0..50 {
+            status = store.get(&job.id).unwrap().unwrap().status;
+            if status.is_final() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let job = store.get(&job.id).unwrap().unwrap();
+        assert_eq!(status, JobStatus::Confirmed, "job error: {:?}", job.error);
+        assert!(job.upload.is_some());
+        assert!(job.mint.unwrap().token_id.is_some());
+    }
+}